@@ -0,0 +1,83 @@
+use crate::generate::PipelineArgs;
+use cargo_about::run::baseline::Baseline;
+use krates::Utf8PathBuf as PathBuf;
+
+#[derive(clap::Parser, Debug)]
+pub struct CommonArgs {
+    #[clap(flatten)]
+    pipeline: PipelineArgs,
+    /// The path of the baseline file
+    ///
+    /// Defaults to `<manifest_root>/about-baseline.toml` if not specified
+    #[clap(long)]
+    baseline: Option<PathBuf>,
+}
+
+#[derive(clap::Subcommand, Debug)]
+pub enum Command {
+    /// Writes the licenses of every crate to the baseline, replacing any
+    /// existing baseline
+    Write(CommonArgs),
+    /// Fails if any crate uses licenses that are not accepted, unless the
+    /// crate is in the baseline with the same version and licenses
+    Check(CommonArgs),
+}
+
+#[derive(clap::Parser, Debug)]
+pub struct Args {
+    #[clap(subcommand)]
+    cmd: Command,
+}
+
+pub fn cmd(args: Args, color: crate::Color) -> anyhow::Result<()> {
+    let (mut common, write) = match args.cmd {
+        Command::Write(common) => (common, true),
+        Command::Check(common) => (common, false),
+    };
+
+    // Violations are only failures if they are not in the baseline, so they
+    // need to be included in the output rather than failing immediately
+    common.pipeline.continue_on_error = true;
+    let pipeline = common.pipeline.pipeline(color)?;
+
+    let baseline_path = common.baseline.unwrap_or_else(|| {
+        pipeline.manifest_path().parent().map_or_else(
+            || "about-baseline.toml".into(),
+            |root| root.join("about-baseline.toml"),
+        )
+    });
+
+    let prepared = pipeline.prepare()?;
+    let input = pipeline.generate(&prepared)?;
+
+    if write {
+        let baseline = Baseline::new(&input);
+        baseline.write(&baseline_path)?;
+        log::info!(
+            "wrote {} crates to baseline '{baseline_path}'",
+            baseline.crates.len()
+        );
+        return Ok(());
+    }
+
+    let baseline = Baseline::load(&baseline_path)?;
+    let deviations = baseline.check(&input);
+
+    for entry in &deviations.stale {
+        log::warn!("baseline entry '{entry}' no longer matches any crate");
+    }
+
+    for entry in &deviations.violations {
+        log::error!(
+            "crate '{entry}' has licenses that are not accepted, and is not in the baseline"
+        );
+    }
+
+    anyhow::ensure!(
+        deviations.violations.is_empty(),
+        "{} crate(s) deviate from the baseline '{baseline_path}'",
+        deviations.violations.len()
+    );
+
+    Ok(())
+}
@@ -0,0 +1,163 @@
+use anyhow::Context as _;
+use cargo_about::run;
+use krates::{Utf8Path as Path, Utf8PathBuf as PathBuf};
+
+#[derive(clap::Subcommand, Debug)]
+pub enum Subcommand {
+    /// Prints the location of each cache
+    Dir,
+    /// Removes every entry from the caches
+    Clean,
+    /// Prints the number of entries in each cache and their total size
+    Stats,
+}
+
+#[derive(clap::Parser, Debug)]
+pub struct Args {
+    /// Path to the config to use, which can change the location of the
+    /// caches with `http-cache-dir`
+    ///
+    /// Defaults to `<manifest_root>/about.toml` if not specified
+    #[clap(short, long)]
+    config: Option<PathBuf>,
+    /// The path of the Cargo.toml for the root crate.
+    ///
+    /// Defaults to the current crate or workspace in the current working directory
+    #[clap(short, long)]
+    manifest_path: Option<PathBuf>,
+    #[clap(subcommand)]
+    cmd: Subcommand,
+}
+
+/// The number of entries in a cache and the total size of its files
+#[derive(Default)]
+struct Stats {
+    entries: usize,
+    bytes: u64,
+}
+
+impl Stats {
+    /// Reads the entries in the cache directory. Subdirectories, eg. the `git`
+    /// cache nested in a configured `http-cache-dir`, are separate caches, and
+    /// the locks and temporary files next to entries are not entries
+    /// themselves, but still take up space
+    fn read(dir: &Path) -> anyhow::Result<Self> {
+        let mut stats = Self::default();
+
+        let entries = match std::fs::read_dir(dir) {
+            Ok(entries) => entries,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(stats),
+            Err(err) => return Err(err).with_context(|| format!("failed to read '{dir}'")),
+        };
+
+        for entry in entries {
+            let entry = entry.with_context(|| format!("failed to read '{dir}'"))?;
+            let md = entry.metadata()?;
+            if !md.is_file() {
+                continue;
+            }
+
+            stats.bytes += md.len();
+
+            let name = entry.file_name();
+            let name = name.to_string_lossy();
+            if !name.ends_with(".lock") && !name.contains(".tmp") {
+                stats.entries += 1;
+            }
+        }
+
+        Ok(stats)
+    }
+}
+
+/// Formats a size in bytes with a binary unit, eg. `1.5 MiB`
+fn format_size(bytes: u64) -> String {
+    const UNITS: &[&str] = &["KiB", "MiB", "GiB"];
+
+    if bytes < 1024 {
+        return format!("{bytes} B");
+    }
+
+    let mut size = bytes as f64 / 1024.0;
+    let mut unit = UNITS[0];
+    for next in &UNITS[1..] {
+        if size < 1024.0 {
+            break;
+        }
+        size /= 1024.0;
+        unit = *next;
+    }
+
+    format!("{size:.1} {unit}")
+}
+
+pub fn cmd(args: Args) -> anyhow::Result<()> {
+    let manifest_path = if let Some(mp) = args.manifest_path {
+        mp
+    } else {
+        let cwd =
+            std::env::current_dir().context("unable to determine current working directory")?;
+        PathBuf::from_path_buf(cwd)
+            .map_err(|pb| {
+                anyhow::anyhow!(
+                    "current working directory '{}' is not a utf-8 path",
+                    pb.display()
+                )
+            })?
+            .join("Cargo.toml")
+    };
+
+    let mut cfg = run::load_config(&manifest_path, args.config.as_deref())?;
+
+    // The caches can still be inspected and cleaned when caching is disabled
+    cfg.no_http_cache = false;
+
+    let caches = [
+        ("http", cfg.http_cache_root()),
+        ("git", cfg.git_cache_root()),
+    ]
+    .into_iter()
+    .map(|(name, root)| Ok((name, root.context("unable to find CARGO_HOME directory")?)))
+    .collect::<anyhow::Result<Vec<_>>>()?;
+
+    match args.cmd {
+        Subcommand::Dir => {
+            for (name, root) in &caches {
+                println!("{name}: {root}");
+            }
+        }
+        Subcommand::Stats => {
+            for (name, root) in &caches {
+                let stats = Stats::read(root)?;
+                println!(
+                    "{name}: {} entries, {} in '{root}'",
+                    stats.entries,
+                    format_size(stats.bytes)
+                );
+            }
+        }
+        Subcommand::Clean => {
+            // The stats are read up front, as the git cache is nested in a
+            // configured `http-cache-dir`, and is removed along with it
+            let stats = caches
+                .iter()
+                .map(|(_, root)| Stats::read(root))
+                .collect::<anyhow::Result<Vec<_>>>()?;
+
+            for ((name, root), stats) in caches.iter().zip(stats) {
+                if root.exists() {
+                    std::fs::remove_dir_all(root)
+                        .with_context(|| format!("failed to remove '{root}'"))?;
+                }
+
+                println!(
+                    "{name}: removed {} entries, {} from '{root}'",
+                    stats.entries,
+                    format_size(stats.bytes)
+                );
+            }
+        }
+    }
+
+    Ok(())
+}
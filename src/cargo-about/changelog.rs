@@ -0,0 +1,56 @@
+use crate::generate::PipelineArgs;
+use anyhow::Context as _;
+use cargo_about::run::changelog;
+use krates::Utf8PathBuf as PathBuf;
+
+#[derive(clap::Parser, Debug)]
+pub struct Args {
+    #[clap(flatten)]
+    pipeline: PipelineArgs,
+    /// The git revision of the previous release, eg. a tag, to compare the
+    /// current `Cargo.lock` against
+    #[clap(long, value_name = "GIT-REV")]
+    since: String,
+    /// A file to write the markdown section to, rather than stdout
+    #[clap(short, long)]
+    output_file: Option<PathBuf>,
+}
+
+pub fn cmd(args: Args, color: crate::Color) -> anyhow::Result<()> {
+    let pipeline = args.pipeline.pipeline(color)?;
+
+    let lockfile = changelog::find_lockfile(pipeline.manifest_path())?;
+    let old = changelog::lockfile_at(&lockfile, &args.since)?;
+    let new = std::fs::read_to_string(&lockfile)
+        .with_context(|| format!("failed to read '{lockfile}'"))?;
+
+    let changes = changelog::diff(&old, &new)?;
+    log::info!(
+        "{} crate(s) were added or updated since '{}'",
+        changes.len(),
+        args.since
+    );
+
+    let markdown = if changes.is_empty() {
+        changelog::unchanged()
+    } else {
+        // Only the licenses of the changed crates need to be gathered
+        let mut names: Vec<_> = changes.iter().map(|change| change.name.clone()).collect();
+        names.dedup();
+
+        let pipeline = pipeline.only(names);
+        let prepared = pipeline.prepare()?;
+        let input = pipeline.generate(&prepared)?;
+
+        changelog::render(&input, &changes)
+    };
+
+    match args.output_file {
+        Some(path) => {
+            std::fs::write(&path, markdown).with_context(|| format!("failed to write '{path}'"))?;
+        }
+        None => print!("{markdown}"),
+    }
+
+    Ok(())
+}
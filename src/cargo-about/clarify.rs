@@ -1,14 +1,18 @@
 use anyhow::Context as _;
 use cargo_about::licenses::fetch::GitCache;
 use krates::Utf8PathBuf as PathBuf;
+use std::ops::Range;
 
-fn parse_subsection(s: &str) -> anyhow::Result<(Option<String>, Option<String>)> {
+/// The start and end text of a subsection
+type Subsection = (Option<String>, Option<String>);
+
+fn parse_subsection(s: &str) -> anyhow::Result<Subsection> {
     let pos = s
         .find("!!")
         .with_context(|| format!("unable to find '!!' in {s}"))?;
 
     let start = &s[..pos];
-    let end = &s[pos + 1..];
+    let end = &s[pos + 2..];
 
     Ok((
         (!start.is_empty()).then(|| start.to_owned()),
@@ -16,6 +20,127 @@ fn parse_subsection(s: &str) -> anyhow::Result<(Option<String>, Option<String>)>
     ))
 }
 
+/// Parses a 1-based range of lines, either `<first>..<end>`, which excludes
+/// the end line, or `<first>..=<last>`
+fn parse_lines(s: &str) -> anyhow::Result<(usize, usize)> {
+    let (first, last, inclusive) = if let Some((first, last)) = s.split_once("..=") {
+        (first, last, true)
+    } else if let Some((first, end)) = s.split_once("..") {
+        (first, end, false)
+    } else {
+        anyhow::bail!("expected a range of lines such as '10..120' in {s}");
+    };
+
+    let parse = |line: &str| {
+        line.trim()
+            .parse::<usize>()
+            .ok()
+            .filter(|line| *line > 0)
+            .with_context(|| format!("'{line}' is not a valid line number"))
+    };
+
+    let first = parse(first)?;
+    let last = if inclusive {
+        parse(last)?
+    } else {
+        parse(last)? - 1
+    };
+
+    anyhow::ensure!(first <= last, "the range of lines {s} is empty");
+    Ok((first, last))
+}
+
+/// Checks if the text only appears once in the haystack, including
+/// overlapping appearances
+fn is_unique(haystack: &str, text: &str) -> bool {
+    !text.is_empty() && haystack.find(text) == haystack.rfind(text)
+}
+
+/// Extends the start text of the subsection with the text that follows it, a
+/// line at a time, until it only appears once in the file, so that the
+/// clarification can't select a different region of the file
+fn unique_start(contents: &str, range: &Range<usize>, start: &str) -> anyhow::Result<String> {
+    let first_end = range.start + start.len();
+
+    std::iter::once(first_end)
+        .chain(
+            contents[range.clone()]
+                .match_indices('\n')
+                .map(|(i, _)| range.start + i)
+                .filter(|end| *end > first_end),
+        )
+        .chain(std::iter::once(range.end))
+        .map(|end| &contents[range.start..end])
+        .find(|text| is_unique(contents, text))
+        .map(String::from)
+        .with_context(|| {
+            format!(
+                "unable to find start text that uniquely identifies the subsection starting with '{start}'"
+            )
+        })
+}
+
+/// Extends the end text of the subsection with the text that precedes it, a
+/// line at a time, until it only appears once after the start of the
+/// subsection
+fn unique_end(contents: &str, range: &Range<usize>, end: &str) -> anyhow::Result<String> {
+    let rest = &contents[range.start..];
+    let first_start = range.end - end.len();
+
+    std::iter::once(first_start)
+        .chain(
+            contents[range.clone()]
+                .rmatch_indices('\n')
+                .map(|(i, _)| range.start + i + 1)
+                .filter(|start| *start < first_start),
+        )
+        .chain(std::iter::once(range.start))
+        .map(|start| &contents[start..range.end])
+        .find(|text| is_unique(rest, text))
+        .map(String::from)
+        .with_context(|| {
+            format!(
+                "unable to find end text that uniquely identifies the subsection ending with '{end}'"
+            )
+        })
+}
+
+/// Converts a range of lines into the equivalent subsection, with start and
+/// end text of whole lines that select exactly those lines when the
+/// clarification is verified
+fn line_subsection(
+    contents: &str,
+    (first, last): (usize, usize),
+) -> anyhow::Result<(Range<usize>, Subsection)> {
+    // The byte range of each line, excluding its line ending
+    let mut lines = Vec::new();
+    let mut offset = 0;
+    for line in contents.split_inclusive('\n') {
+        let text = line.trim_end_matches(['\r', '\n']);
+        lines.push(offset..offset + text.len());
+        offset += line.len();
+    }
+
+    anyhow::ensure!(
+        last <= lines.len(),
+        "line {last} is past the end of the file, which has {} lines",
+        lines.len()
+    );
+
+    let first_line = &lines[first - 1];
+    let last_line = &lines[last - 1];
+    let range = first_line.start..last_line.end;
+
+    let start = (range.start != 0)
+        .then(|| unique_start(contents, &range, &contents[first_line.clone()]))
+        .transpose()?;
+    let end = (range.end != contents.len())
+        .then(|| unique_end(contents, &range, &contents[last_line.clone()]))
+        .transpose()?;
+
+    Ok((range, (start, end)))
+}
+
 #[derive(clap::Subcommand, Debug)]
 pub enum Subcommand {
     /// Reads the license information from a path on disk
@@ -33,7 +158,9 @@ pub enum Subcommand {
     /// Retrieves the file from the git repository and commit associated with
     /// the specified crate and version
     Crate {
-        /// The crate's `<name>-<version>` spec to retrieve. The crate source must already be downloaded.
+        /// The crate's `<name>-<version>` spec to retrieve, or just its name
+        /// to use the version in the current workspace's `Cargo.lock`. The
+        /// crate source must already be downloaded.
         spec: String,
     },
 }
@@ -43,17 +170,135 @@ pub struct Args {
     /// One or more subsections in the file which is itself its own license.
     /// Uses `!!` as the separator between the start and end of the subsection
     #[clap(long, short, value_parser = parse_subsection)]
-    subsections: Vec<(Option<String>, Option<String>)>,
+    subsections: Vec<Subsection>,
+    /// One or more ranges of lines in the file which are themselves their own
+    /// license, eg. `10..120`, or `10..=120` to include line 120. The
+    /// clarification uses the equivalent start and end text of the lines
+    #[clap(long, value_parser = parse_lines)]
+    lines: Vec<(usize, usize)>,
     /// The minimum confidence score a license must have
     #[clap(long, default_value = "0.8")]
     threshold: f32,
+    /// The name of the crate being clarified, which outputs a complete
+    /// `[<name>.clarify]` section ready to be pasted into an about.toml.
+    ///
+    /// Inferred from the spec if using the `crate` subcommand.
+    #[clap(long = "crate")]
+    krate: Option<String>,
+    /// Writes the `[<name>.clarify]` section to the config, replacing any
+    /// existing clarification for the crate, rather than printing it
+    #[clap(long)]
+    write: bool,
+    /// Path to the config to write to
+    #[clap(short, long, default_value = "about.toml")]
+    config: PathBuf,
+    /// The algorithm used to calculate checksums.
+    ///
+    /// Defaults to the `checksum-algorithm` of the config, or `sha256` if the
+    /// config doesn't exist
+    #[clap(long)]
+    checksum_algorithm: Option<cargo_about::ChecksumAlgorithm>,
     /// The relative file path from the root of the source
     path: PathBuf,
     #[clap(subcommand)]
     cmd: Subcommand,
 }
 
+/// Splits a `<name>-<version>` spec, returning `None` for the version if the
+/// spec is just the crate's name
+fn split_spec(spec: &str) -> (&str, Option<semver::Version>) {
+    spec.match_indices('-')
+        .find_map(|(i, _)| {
+            let version = spec[i + 1..].parse().ok()?;
+            Some((&spec[..i], Some(version)))
+        })
+        .unwrap_or((spec, None))
+}
+
+/// Resolves the version of the crate in the `Cargo.lock` of the workspace in
+/// the current working directory
+fn locked_version(name: &str) -> anyhow::Result<semver::Version> {
+    let cwd = std::env::current_dir().context("unable to determine current working directory")?;
+    let cwd = PathBuf::from_path_buf(cwd).map_err(|pb| {
+        anyhow::anyhow!(
+            "current working directory '{}' is not a utf-8 path",
+            pb.display()
+        )
+    })?;
+
+    let lockfile = cargo_about::run::changelog::find_lockfile(&cwd.join("Cargo.toml"))?;
+    let contents = std::fs::read_to_string(&lockfile)
+        .with_context(|| format!("failed to read '{lockfile}'"))?;
+
+    let versions = cargo_about::run::changelog::third_party(&contents)?
+        .remove(name)
+        .with_context(|| format!("crate '{name}' is not in '{lockfile}'"))?;
+
+    anyhow::ensure!(
+        versions.len() == 1,
+        "multiple versions of crate '{name}' are in '{lockfile}', specify one of {}",
+        versions
+            .iter()
+            .map(|vers| format!("'{name}-{vers}'"))
+            .collect::<Vec<_>>()
+            .join(", ")
+    );
+
+    Ok(versions.into_iter().next().unwrap())
+}
+
+/// Locates the downloaded source of the crate in any of the registries in
+/// `CARGO_HOME`, eg. the sparse and git indices of crates.io, or alternative
+/// registries
+fn crate_source(spec: &str) -> anyhow::Result<PathBuf> {
+    let (name, version) = split_spec(spec);
+    let version = match version {
+        Some(version) => version,
+        None => locked_version(name)?,
+    };
+
+    let src = PathBuf::from_path_buf(
+        home::cargo_home()
+            .context("unable to find CARGO_HOME directory")?
+            .join("registry/src"),
+    )
+    .map_err(|_e| anyhow::anyhow!("CARGO_HOME directory is not utf-8"))?;
+
+    // Each registry has its own directory, eg. `index.crates.io-<hash>`
+    let mut registries: Vec<_> = match std::fs::read_dir(&src) {
+        Ok(entries) => entries
+            .filter_map(|entry| Some(src.join(entry.ok()?.file_name().to_str()?)))
+            .collect(),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+        Err(err) => return Err(err).with_context(|| format!("failed to read '{src}'")),
+    };
+    registries.sort();
+
+    registries
+        .into_iter()
+        .map(|registry| registry.join(format!("{name}-{version}")))
+        .find(|path| path.join("Cargo.toml").exists())
+        .with_context(|| {
+            format!("unable to find the source of '{name}-{version}' in any registry in '{src}', it must be downloaded first, eg. with `cargo fetch`")
+        })
+}
+
 pub fn cmd(args: Args) -> anyhow::Result<()> {
+    let mut krate_name = args.krate;
+    let from_git = !matches!(args.cmd, Subcommand::Path { .. });
+
+    // The clarification is written into the config with toml_edit, so that
+    // its formatting and comments are kept, which isn't possible for the
+    // other formats
+    anyhow::ensure!(
+        !args.write || !matches!(args.config.extension(), Some("yaml" | "yml" | "json")),
+        "--write can only update a TOML config, '{}' must be updated manually",
+        args.config
+    );
+
+    let cfg = load_config(&args.config)?;
+    let checksum_algorithm = args.checksum_algorithm.unwrap_or(cfg.checksum_algorithm);
+
     let contents = match args.cmd {
         Subcommand::Path { root } => {
             let full_path = root.join(&args.path);
@@ -61,29 +306,20 @@ pub fn cmd(args: Args) -> anyhow::Result<()> {
                 .with_context(|| format!("unable to read file '{full_path}'"))?
         }
         Subcommand::Repo { rev, repo } => {
-            let gc = GitCache::online();
+            let gc = GitCache::online(&cfg)?;
 
             gc.retrieve_remote(repo.as_str(), &rev, &args.path)
                 .context("failed to retrieve remote file")?
         }
         Subcommand::Crate { spec } => {
-            // Just hardcoding to the typical because I can't be bothered
-            let root = PathBuf::from_path_buf(
-                home::cargo_home()
-                    .context("unable to find CARGO_HOME directory")?
-                    .join("registry/src/index.crates.io-6f17d22bba15001f"),
-            )
-            .map_err(|_e| anyhow::anyhow!("CARGO_HOME directory is not utf-8"))?;
-
-            let crate_path = root.join(spec);
-
-            anyhow::ensure!(crate_path.exists(), "unable to find crate source");
+            let crate_path = crate_source(&spec)?;
 
             let manifest = std::fs::read_to_string(crate_path.join("Cargo.toml"))
                 .context("failed to read Cargo.toml")?;
 
             #[derive(serde::Deserialize)]
             struct Pkg {
+                name: String,
                 repository: String,
             }
 
@@ -95,7 +331,9 @@ pub fn cmd(args: Args) -> anyhow::Result<()> {
             let pkg: MinPkg =
                 toml::from_str(&manifest).context("failed to deserialize Cargo.toml")?;
 
-            let gc = GitCache::online();
+            krate_name.get_or_insert(pkg.package.name);
+
+            let gc = GitCache::online(&cfg)?;
             let vcs_info = GitCache::parse_vcs_info(&crate_path.join(".cargo_vcs_info.json"))
                 .context("failed to read sha1")?;
 
@@ -104,10 +342,10 @@ pub fn cmd(args: Args) -> anyhow::Result<()> {
         }
     };
 
-    let subsections = if args.subsections.is_empty() {
+    let subsections = if args.subsections.is_empty() && args.lines.is_empty() {
         vec![(0..contents.len(), (None, None))]
     } else {
-        let mut subs = Vec::with_capacity(args.subsections.len());
+        let mut subs = Vec::with_capacity(args.subsections.len() + args.lines.len());
 
         for (start, end) in args.subsections {
             let start_ind = match &start {
@@ -125,10 +363,25 @@ pub fn cmd(args: Args) -> anyhow::Result<()> {
                         + start_ind
                         + end.len()
                 }
-                None => 0,
+                None => contents.len(),
             };
 
-            subs.push((start_ind..end_ind, (start, end)));
+            // The start and end text are extended until they are unique, as
+            // text that also appears elsewhere in the file is easily matched
+            // to the wrong region if the file changes
+            let range = start_ind..end_ind;
+            let start = start
+                .map(|start| unique_start(&contents, &range, &start))
+                .transpose()?;
+            let end = end
+                .map(|end| unique_end(&contents, &range, &end))
+                .transpose()?;
+
+            subs.push((range, (start, end)));
+        }
+
+        for lines in args.lines {
+            subs.push(line_subsection(&contents, lines)?);
         }
 
         subs
@@ -156,16 +409,7 @@ pub fn cmd(args: Args) -> anyhow::Result<()> {
     for (ind, (subrange, (start, end))) in subsections.into_iter().enumerate() {
         let subsection = &contents[subrange];
 
-        let mut ctx = ring::digest::Context::new(&ring::digest::SHA256);
-
-        ctx.update(subsection.as_bytes());
-
-        // TODO: Warn on carriage returns?
-        // for line in subsection.split('\r') {
-        //     ctx.update(line.as_bytes());
-        // }
-
-        let checksum = ctx.finish();
+        let checksum = checksum_algorithm.checksum(subsection);
 
         let text = askalono::TextData::new(subsection);
         let scan_result = strategy
@@ -208,7 +452,7 @@ pub fn cmd(args: Args) -> anyhow::Result<()> {
             license: Some(spdx::Expression::parse(license.name).with_context(|| {
                 format!("failed to parse license {} as an expression", license.name)
             })?),
-            checksum: cargo_about::to_hex(checksum.as_ref()),
+            checksum,
             start,
             end,
         });
@@ -220,17 +464,117 @@ pub fn cmd(args: Args) -> anyhow::Result<()> {
         )
     })?;
 
+    // Files retrieved from a remote repository are verified against the
+    // repository, not the crate source
+    let (files, git) = if from_git {
+        (Vec::new(), files)
+    } else {
+        (files, Vec::new())
+    };
+
     let clarification = Clarification {
         license: overall_expression,
         override_git_commit: None,
+        version_req: None,
         files,
-        git: Vec::new(),
+        git,
     };
 
-    let clar_toml =
-        toml::to_string_pretty(&clarification).context("failed to serialize to toml")?;
+    let Some(krate_name) = krate_name else {
+        anyhow::ensure!(
+            !args.write,
+            "the name of the crate must be specified with --crate to write the clarification"
+        );
 
-    println!("{clar_toml}");
+        let clar_toml =
+            toml::to_string_pretty(&clarification).context("failed to serialize to toml")?;
+
+        println!("{clar_toml}");
+        return Ok(());
+    };
+
+    let path = &args.config;
+    let mut doc = if args.write && path.exists() {
+        std::fs::read_to_string(path)
+            .with_context(|| format!("unable to read '{path}'"))?
+            .parse::<toml_edit::DocumentMut>()
+            .with_context(|| format!("failed to parse '{path}'"))?
+    } else {
+        toml_edit::DocumentMut::new()
+    };
+
+    let krate_table = doc
+        .entry(&krate_name)
+        .or_insert_with(|| {
+            let mut table = toml_edit::Table::new();
+            table.set_implicit(true);
+            toml_edit::Item::Table(table)
+        })
+        .as_table_mut()
+        .with_context(|| format!("'{krate_name}' is not a table"))?;
+
+    krate_table.insert(
+        "clarify",
+        toml_edit::Item::Table(clarify_table(&clarification)),
+    );
+
+    if args.write {
+        std::fs::write(path, doc.to_string())
+            .with_context(|| format!("failed to write '{path}'"))?;
+        log::info!("wrote clarification for '{krate_name}' to '{path}'");
+    } else {
+        println!("{doc}");
+    }
 
     Ok(())
 }
+
+/// Loads the config, or the default configuration if it doesn't exist
+fn load_config(path: &krates::Utf8Path) -> anyhow::Result<cargo_about::licenses::config::Config> {
+    if !path.exists() {
+        return Ok(Default::default());
+    }
+
+    cargo_about::run::load_config(path, Some(path))
+}
+
+/// Converts a clarification into the table used in an about.toml
+pub fn clarify_table(
+    clarification: &cargo_about::licenses::config::Clarification,
+) -> toml_edit::Table {
+    use toml_edit::value;
+
+    let mut table = toml_edit::Table::new();
+    table.insert("license", value(clarification.license.as_ref()));
+
+    if let Some(commit) = &clarification.override_git_commit {
+        table.insert("override-git-commit", value(commit));
+    }
+
+    for (key, files) in [("files", &clarification.files), ("git", &clarification.git)] {
+        if files.is_empty() {
+            continue;
+        }
+
+        let mut aot = toml_edit::ArrayOfTables::new();
+        for file in files {
+            let mut ft = toml_edit::Table::new();
+            ft.insert("path", value(file.path.as_str()));
+            ft.insert("checksum", value(&file.checksum));
+            if let Some(license) = &file.license {
+                ft.insert("license", value(license.as_ref()));
+            }
+            if let Some(start) = &file.start {
+                ft.insert("start", value(start));
+            }
+            if let Some(end) = &file.end {
+                ft.insert("end", value(end));
+            }
+            aot.push(ft);
+        }
+
+        table.insert(key, toml_edit::Item::ArrayOfTables(aot));
+    }
+
+    table
+}
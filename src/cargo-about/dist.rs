@@ -0,0 +1,106 @@
+use crate::generate::PipelineArgs;
+use anyhow::Context as _;
+use cargo_about::run::{self, output::OutputFormatter as _};
+use krates::Utf8PathBuf as PathBuf;
+
+#[derive(clap::Parser, Debug)]
+pub struct Args {
+    #[clap(flatten)]
+    pipeline: PipelineArgs,
+    /// The directory to write the artifacts to, the artifact for each target
+    /// is written to `<out-dir>/<target>/<artifact-name>`
+    #[clap(long)]
+    out_dir: PathBuf,
+    /// The file name of each artifact, defaults to `THIRD-PARTY-LICENSES.html`,
+    /// or `THIRD-PARTY-LICENSES.json` if no templates are specified.
+    ///
+    /// `{target}` is replaced with the target triple, eg. so that the
+    /// artifacts of every target can be uploaded side by side
+    #[clap(long)]
+    artifact_name: Option<String>,
+    /// The name of the template to use when rendering.
+    ///
+    /// If only passing a single template file to `templates` this is not used.
+    #[clap(short, long)]
+    name: Option<String>,
+    /// The handlebars template(s) or template directory to use.
+    ///
+    /// If not specified, each artifact is the JSON output of `generate`
+    templates: Option<PathBuf>,
+}
+
+pub fn cmd(mut args: Args, color: crate::Color) -> anyhow::Result<()> {
+    let targets = std::mem::take(&mut args.pipeline.target);
+    let mut pipeline = args.pipeline.pipeline(color)?;
+
+    let targets = if targets.is_empty() {
+        pipeline.config().targets.clone()
+    } else {
+        targets
+    };
+    anyhow::ensure!(
+        !targets.is_empty(),
+        "at least one target must be specified with --target, or with `targets` in the configuration"
+    );
+
+    let templates = args
+        .templates
+        .as_deref()
+        .map(|template_path| {
+            let templates = run::Templates::load(
+                template_path,
+                args.name.clone(),
+                run::TemplateEngine::Handlebars,
+            )?;
+            templates.check(None)?;
+            anyhow::Ok(templates)
+        })
+        .transpose()?;
+
+    let artifact_name = args.artifact_name.unwrap_or_else(|| {
+        if templates.is_some() {
+            "THIRD-PARTY-LICENSES.html".to_owned()
+        } else {
+            "THIRD-PARTY-LICENSES.json".to_owned()
+        }
+    });
+
+    let mut artifacts = Vec::with_capacity(targets.len());
+
+    // Each target is resolved separately, as the crates, and thus the
+    // licenses, that are built for each target can differ
+    for target in targets {
+        pipeline = pipeline.targets(vec![target.clone()]);
+
+        let prepared = pipeline.prepare()?;
+        let input = pipeline
+            .generate(&prepared)
+            .with_context(|| format!("failed to generate licenses for target '{target}'"))?;
+
+        let output = if let Some(templates) = &templates {
+            templates.format(&input)?
+        } else {
+            run::output::Json::default().format(&input)?
+        };
+
+        let dir = args.out_dir.join(&target);
+        std::fs::create_dir_all(&dir).with_context(|| format!("failed to create '{dir}'"))?;
+
+        let path = dir.join(artifact_name.replace("{target}", &target));
+        std::fs::write(&path, output)
+            .with_context(|| format!("artifact {path} could not be written"))?;
+
+        artifacts.push(serde_json::json!({
+            "target": target,
+            "path": path,
+            "crates": input.crates.len(),
+            "licenses": input.overview.iter().map(|ls| ls.id.as_str()).collect::<Vec<_>>(),
+        }));
+    }
+
+    // The manifest of the written artifacts is the contract with release
+    // tooling, so it is always a single line of JSON on stdout
+    println!("{}", serde_json::json!({ "artifacts": artifacts }));
+
+    Ok(())
+}
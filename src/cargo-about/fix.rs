@@ -0,0 +1,326 @@
+use crate::generate::PipelineArgs;
+use anyhow::Context as _;
+use cargo_about::licenses::{
+    config::{Clarification, ClarificationFile},
+    resolution::{self, Severity},
+    KrateLicense, LicenseFileKind,
+};
+use krates::{Utf8Path as Path, Utf8PathBuf as PathBuf};
+use std::io::BufRead as _;
+
+#[derive(clap::Parser, Debug)]
+pub struct Args {
+    #[clap(flatten)]
+    pipeline: PipelineArgs,
+}
+
+/// An action that resolves the failure of a crate
+enum Action {
+    /// Appends the licensee to `accepted`
+    Accept(spdx::Licensee),
+    /// Clarifies the crate's license with the license files that were found
+    Clarify(Box<Clarification>),
+    /// Ignores the crate by adding its name to `private.names`
+    Ignore,
+    Skip,
+}
+
+/// Builds a clarification of the crate's license from the license texts that
+/// were found in it, with the checksum of each file
+fn clarification(
+    kl: &KrateLicense<'_>,
+    algorithm: cargo_about::ChecksumAlgorithm,
+) -> Option<Clarification> {
+    let root = kl.krate.manifest_path.parent()?;
+
+    let mut files: Vec<(PathBuf, Vec<String>)> = Vec::new();
+    for lf in &kl.license_files {
+        if !matches!(lf.kind, LicenseFileKind::Text(_)) {
+            continue;
+        }
+
+        let expr = lf.license_expr.to_string();
+        match files.iter_mut().find(|(path, _)| *path == lf.path) {
+            Some((_, exprs)) if !exprs.contains(&expr) => exprs.push(expr),
+            Some(_) => {}
+            None => files.push((lf.path.clone(), vec![expr])),
+        }
+    }
+
+    let mut license = Vec::new();
+    let mut clarified = Vec::with_capacity(files.len());
+    for (path, exprs) in files {
+        let contents = std::fs::read_to_string(&path).ok()?;
+        let expr = exprs.join(" AND ");
+
+        clarified.push(ClarificationFile {
+            path: path.strip_prefix(root).unwrap_or(&path).to_owned(),
+            license: Some(spdx::Expression::parse(&expr).ok()?),
+            checksum: algorithm.checksum(&contents),
+            start: None,
+            end: None,
+        });
+
+        for expr in exprs {
+            if !license.contains(&expr) {
+                license.push(expr);
+            }
+        }
+    }
+
+    if clarified.is_empty() {
+        return None;
+    }
+
+    Some(Clarification {
+        license: spdx::Expression::parse(&license.join(" AND ")).ok()?,
+        override_git_commit: None,
+        version_req: None,
+        files: clarified,
+        git: Vec::new(),
+    })
+}
+
+/// Shows the detected license information for the crate, and prompts for the
+/// action to take, returning `None` if there is no more input
+fn prompt(
+    kl: &KrateLicense<'_>,
+    resolved: &resolution::Resolved,
+    candidates: Vec<spdx::Licensee>,
+    clarification: Option<Clarification>,
+    input: &mut impl Iterator<Item = std::io::Result<String>>,
+) -> anyhow::Result<Option<Action>> {
+    let krate = kl.krate;
+
+    eprintln!(
+        "crate '{} {}' failed license resolution",
+        krate.name, krate.version
+    );
+    eprintln!(
+        "  declared license: {}",
+        krate.license.as_deref().unwrap_or("none")
+    );
+    for diag in &resolved.diagnostics {
+        if diag.severity >= Severity::Error {
+            eprintln!("  error: {}", diag.message);
+        }
+    }
+
+    let root = krate.manifest_path.parent();
+    for lf in &kl.license_files {
+        if matches!(lf.kind, LicenseFileKind::Notice(_)) {
+            continue;
+        }
+
+        let path = root
+            .and_then(|root| lf.path.strip_prefix(root).ok())
+            .unwrap_or(&lf.path);
+        eprintln!(
+            "  license file: {path} ({}, confidence {:.2})",
+            lf.license_expr, lf.confidence
+        );
+    }
+
+    let mut actions = Vec::new();
+    for licensee in candidates {
+        actions.push((format!("accept '{licensee}'"), Action::Accept(licensee)));
+    }
+    if let Some(clarification) = clarification {
+        actions.push((
+            format!(
+                "clarify the license as '{}' with the license files",
+                clarification.license
+            ),
+            Action::Clarify(Box::new(clarification)),
+        ));
+    }
+
+    for (i, (description, _)) in actions.iter().enumerate() {
+        eprintln!("  [{}] {description}", i + 1);
+    }
+    eprintln!("  [i] ignore the crate");
+    eprintln!("  [s] skip");
+
+    loop {
+        eprint!("> ");
+
+        let Some(line) = input.next() else {
+            eprintln!();
+            return Ok(None);
+        };
+        let line = line.context("failed to read from stdin")?;
+
+        match line.trim() {
+            "i" => return Ok(Some(Action::Ignore)),
+            "s" | "" => return Ok(Some(Action::Skip)),
+            choice => {
+                if let Some(i) = choice
+                    .parse::<usize>()
+                    .ok()
+                    .filter(|i| (1..=actions.len()).contains(i))
+                {
+                    return Ok(Some(actions.swap_remove(i - 1).1));
+                }
+
+                eprintln!("unknown action '{choice}'");
+            }
+        }
+    }
+}
+
+/// Appends the value to the array, creating the array if needed
+fn push_unique(item: &mut toml_edit::Item, key: &str, value: &str) -> anyhow::Result<()> {
+    let array = item
+        .or_insert(toml_edit::value(toml_edit::Array::new()))
+        .as_array_mut()
+        .with_context(|| format!("'{key}' is not an array"))?;
+
+    if !array.iter().any(|v| v.as_str() == Some(value)) {
+        array.push(value);
+    }
+
+    Ok(())
+}
+
+/// The configuration to write the fixes to
+fn config_path(pipeline: &cargo_about::run::Pipeline) -> anyhow::Result<PathBuf> {
+    match &pipeline.config().config_path {
+        Some(path) => {
+            anyhow::ensure!(
+                path.file_name() != Some("Cargo.toml"),
+                "fixes can't be written to `workspace.metadata.about` in '{path}', move the configuration to an about.toml"
+            );
+            anyhow::ensure!(
+                !matches!(path.extension(), Some("yaml" | "yml" | "json")),
+                "fixes can only be written to TOML configurations, '{path}' must be converted to an about.toml"
+            );
+            Ok(path.clone())
+        }
+        None => Ok(pipeline
+            .manifest_path()
+            .parent()
+            .unwrap_or(Path::new("."))
+            .join("about.toml")),
+    }
+}
+
+pub fn cmd(args: Args, color: crate::Color) -> anyhow::Result<()> {
+    let pipeline = args.pipeline.pipeline(color)?;
+    let path = config_path(&pipeline)?;
+    let cfg = pipeline.config();
+
+    let prepared = pipeline.prepare()?;
+    let summary = pipeline.gather(&prepared)?;
+
+    // Crates without a license are failures rather than warnings, as they
+    // can be fixed by a clarification too
+    let (_files, resolved) = resolution::resolve(
+        &summary,
+        &cfg.accepted,
+        &cfg.denied,
+        &cfg.crates,
+        &cfg.exceptions,
+        true,
+        false,
+    );
+
+    let failed: Vec<_> = summary
+        .iter()
+        .zip(resolved.iter())
+        .filter_map(|(kl, res)| {
+            let res = res.as_ref()?;
+            res.diagnostics
+                .iter()
+                .any(|diag| diag.severity >= Severity::Error)
+                .then_some((kl, res))
+        })
+        .collect();
+
+    if failed.is_empty() {
+        eprintln!("every crate's license was resolved, there is nothing to fix");
+        return Ok(());
+    }
+
+    let mut doc = if path.exists() {
+        std::fs::read_to_string(&path)
+            .with_context(|| format!("unable to read '{path}'"))?
+            .parse::<toml_edit::DocumentMut>()
+            .with_context(|| format!("failed to parse '{path}'"))?
+    } else {
+        toml_edit::DocumentMut::new()
+    };
+
+    let mut input = std::io::stdin().lock().lines();
+    let mut accepted = Vec::new();
+    let mut fixed = 0;
+
+    for (kl, res) in failed {
+        // Accepting a licensee for a previous crate can also resolve this one
+        if res
+            .unsatisfied
+            .as_ref()
+            .is_some_and(|us| !accepted.is_empty() && us.is_satisfied_by(&accepted))
+        {
+            continue;
+        }
+
+        let mut candidates: Vec<_> = res
+            .unsatisfied
+            .iter()
+            .flat_map(|us| us.candidates())
+            .collect();
+        candidates.sort();
+        candidates.dedup();
+
+        let clarification = clarification(kl, cfg.checksum_algorithm);
+
+        let Some(action) = prompt(kl, res, candidates, clarification, &mut input)? else {
+            break;
+        };
+
+        let name = &kl.krate.name;
+        match action {
+            Action::Accept(licensee) => {
+                push_unique(&mut doc["accepted"], "accepted", &licensee.to_string())?;
+                accepted.push(licensee);
+            }
+            Action::Clarify(clarification) => {
+                let krate_table = doc
+                    .entry(name)
+                    .or_insert_with(|| {
+                        let mut table = toml_edit::Table::new();
+                        table.set_implicit(true);
+                        toml_edit::Item::Table(table)
+                    })
+                    .as_table_mut()
+                    .with_context(|| format!("'{name}' is not a table"))?;
+
+                krate_table.insert(
+                    "clarify",
+                    toml_edit::Item::Table(crate::clarify::clarify_table(&clarification)),
+                );
+            }
+            Action::Ignore => {
+                let private = doc
+                    .entry("private")
+                    .or_insert(toml_edit::table())
+                    .as_table_mut()
+                    .context("'private' is not a table")?;
+                private.insert("ignore", toml_edit::value(true));
+                push_unique(&mut private["names"], "private.names", name)?;
+            }
+            Action::Skip => continue,
+        }
+
+        fixed += 1;
+    }
+
+    if fixed == 0 {
+        return Ok(());
+    }
+
+    std::fs::write(&path, doc.to_string()).with_context(|| format!("failed to write '{path}'"))?;
+    log::info!("wrote {fixed} fix(es) to '{path}'");
+
+    Ok(())
+}
@@ -1,32 +1,99 @@
 use anyhow::Context as _;
-use cargo_about::licenses;
-use cargo_about::licenses::LicenseInfo;
+use cargo_about::run::{self, output::OutputFormatter as _, Pipeline};
 use codespan_reporting::term;
-use krates::cm::Package;
 use krates::{Utf8Path as Path, Utf8PathBuf as PathBuf};
-use serde::Serialize;
-use std::{collections::BTreeMap, fmt};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt,
+};
 
 #[derive(clap::ValueEnum, Copy, Clone, Debug, Default)]
 pub enum OutputFormat {
     /// Uses one or more handlebars templates to transform JSON to the output
     #[default]
     Handlebars,
+    /// Uses one or more jinja templates to transform JSON to the output
+    #[value(alias = "tera")]
+    Jinja,
     /// Outputs the raw JSON of the discovered licenses
     Json,
+    /// Outputs one JSON object per line for each crate, with the full text of
+    /// the licenses that apply to it
+    JsonLines,
+    /// Appends the results to the sqlite database specified by `-o, --output-file`
+    Sqlite,
+    /// Outputs a summary of the licenses as a JSON object of OCI image
+    /// annotations
+    OciAnnotations,
+    /// Outputs a row of comma separated values for each crate, eg. for
+    /// spreadsheets
+    Csv,
+    /// Outputs a row of tab separated values for each crate
+    Tsv,
 }
 
 impl fmt::Display for OutputFormat {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             Self::Handlebars => f.write_str("handlebars"),
+            Self::Jinja => f.write_str("jinja"),
             Self::Json => f.write_str("json"),
+            Self::JsonLines => f.write_str("json-lines"),
+            Self::Sqlite => f.write_str("sqlite"),
+            Self::OciAnnotations => f.write_str("oci-annotations"),
+            Self::Csv => f.write_str("csv"),
+            Self::Tsv => f.write_str("tsv"),
         }
     }
 }
 
-#[derive(clap::Parser, Debug)]
-pub struct Args {
+#[derive(clap::ValueEnum, Copy, Clone, Debug, Default)]
+pub enum Encoding {
+    /// UTF-8 without a byte order mark
+    #[default]
+    Utf8,
+    /// UTF-8 with a byte order mark
+    Utf8Bom,
+    /// UTF-16 little endian with a byte order mark
+    Utf16le,
+}
+
+impl Encoding {
+    fn encode(self, s: &str) -> Vec<u8> {
+        match self {
+            Self::Utf8 => s.as_bytes().to_vec(),
+            Self::Utf8Bom => {
+                let mut v = Vec::with_capacity(s.len() + 3);
+                v.extend_from_slice(&[0xef, 0xbb, 0xbf]);
+                v.extend_from_slice(s.as_bytes());
+                v
+            }
+            Self::Utf16le => {
+                let mut v = Vec::with_capacity(s.len() * 2 + 2);
+                v.extend_from_slice(&[0xff, 0xfe]);
+                for cu in s.encode_utf16() {
+                    v.extend_from_slice(&cu.to_le_bytes());
+                }
+                v
+            }
+        }
+    }
+}
+
+impl fmt::Display for Encoding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Utf8 => f.write_str("utf8"),
+            Self::Utf8Bom => f.write_str("utf8-bom"),
+            Self::Utf16le => f.write_str("utf16le"),
+        }
+    }
+}
+
+/// The arguments used to build the crate graph and gather licenses, shared by
+/// all subcommands that run the pipeline
+#[derive(clap::Args, Debug)]
+pub struct PipelineArgs {
     /// Path to the config to use
     ///
     /// Defaults to `<manifest_root>/about.toml` if not specified
@@ -35,14 +102,6 @@ pub struct Args {
     /// The confidence threshold required for license files to be positively identified: 0.0 - 1.0
     #[clap(long, default_value = "0.8")]
     threshold: f32,
-    /// The name of the template to use when rendering.
-    ///
-    /// If only passing a single template file to `templates` this is not used.
-    #[clap(short, long)]
-    name: Option<String>,
-    /// A file to write the generated output to, typically an .html file.
-    #[clap(short, long)]
-    output_file: Option<PathBuf>,
     /// Space-separated list of features to activate
     #[clap(long)]
     features: Vec<String>,
@@ -57,17 +116,47 @@ pub struct Args {
     /// Overrides the `targets` configuration value, and note that unlike cargo
     /// itself this can take multiple targets instead of just one.
     #[clap(long)]
-    target: Vec<String>,
+    pub target: Vec<String>,
+    /// Only gathers and outputs licenses for the specified crate(s), which
+    /// can contain `*` and `?` wildcards.
+    ///
+    /// Overrides the `only` configuration value, and is useful when iterating
+    /// on the configuration for a single crate.
+    #[clap(long)]
+    only: Vec<String>,
+    /// The locale of the `strings` passed to templates, eg. `de`.
+    ///
+    /// Overrides the `locale` configuration value
+    #[clap(long)]
+    locale: Option<String>,
+    /// A file of additional accepted licenses, with one SPDX licensee per
+    /// line, which are merged with the `accepted` configuration value.
+    ///
+    /// Allows the accepted licenses to be maintained outside of the
+    /// repository, eg. by a legal team.
+    #[clap(long)]
+    accepted_file: Option<PathBuf>,
+    /// An additional accepted licensee, which is appended to the `accepted`
+    /// configuration value.
+    ///
+    /// May be specified multiple times, eg. to experiment with accepting a
+    /// license without editing the configuration
+    #[clap(long, value_name = "LICENSEE")]
+    accept: Vec<String>,
+    /// Only accepts the licensees specified with `--accept`, ignoring the
+    /// `accepted` configuration value and `--accepted-file`
+    #[clap(long, requires = "accept")]
+    accept_only: bool,
     /// Run without accessing the network.
     ///
     /// In addition to cargo not fetching crates, this will mean that only
     /// local files will be crawled for license information.
     /// 1. clearlydefined.io will not be used, so some more ambiguous/complicated
-    ///     license files might be ignored
+    ///    license files might be ignored
     /// 2. Crates that are improperly packaged and don't contain their LICENSE
-    ///     file(s) will fallback to the default license file, missing eg.
-    ///     copyright information in the license that would be retrieved from
-    ///     the original git repo for the crate in question
+    ///    file(s) will fallback to the default license file, missing eg.
+    ///    copyright information in the license that would be retrieved from
+    ///    the original git repo for the crate in question
     #[arg(long)]
     offline: bool,
     /// Assert that `Cargo.lock` will remain unchanged
@@ -84,450 +173,712 @@ pub struct Args {
     /// Scan licenses for the entire workspace, not just the active package
     #[clap(long)]
     workspace: bool,
+    /// Only includes the specified workspace member(s) and their dependencies
+    /// in the crate graph.
+    ///
+    /// May be specified multiple times, eg. to generate a separate attribution
+    /// file for each binary shipped from a workspace.
+    #[clap(short, long = "package", alias = "workspace-member")]
+    package: Vec<String>,
+    /// Only includes the dependencies of the specified binary target(s) in
+    /// the crate graph.
+    ///
+    /// Dev dependencies are never linked into a binary, so they are excluded.
+    #[clap(long)]
+    bin: Vec<String>,
     /// Exit with a non-zero exit code when failing to read, synthesize, or
     /// clarify a license expression for a crate
     #[clap(long)]
     fail: bool,
+    /// Exit with a non-zero exit code when the canonical text of a license is
+    /// used because a crate doesn't include its license text
+    ///
+    /// Equivalent to setting `fail-on-missing-license-text = true` in the
+    /// configuration
+    #[clap(long)]
+    fail_on_missing_license_text: bool,
+    /// Includes crates whose licenses are not accepted in the output, with
+    /// `accepted` set to false, rather than failing
+    ///
+    /// Equivalent to setting `include-unaccepted = true` in the configuration
+    #[clap(long)]
+    pub continue_on_error: bool,
+    /// The format license resolution diagnostics are written to stderr in.
+    ///
+    /// `json` writes one JSON object per line for each diagnostic, with the
+    /// crate, severity, message, and the failed license requirements
+    #[clap(long, default_value = "human")]
+    message_format: run::MessageFormat,
+    /// Emits license resolution diagnostics for each crate, rather than
+    /// aggregating identical diagnostics for multiple crates into one
+    #[clap(long)]
+    verbose_diagnostics: bool,
+    /// A directory of unpacked crate sources that is used as the registry,
+    /// rather than crates.io, so that the output can be generated without
+    /// network access, eg. in tests.
+    ///
+    /// Files that would be retrieved from a crate's git repository are read
+    /// from `.repos/<host>/<org>/<repo>/<rev>/<path>` in the directory
+    #[clap(long)]
+    registry_dir: Option<PathBuf>,
+    /// Displays a progress bar while gathering licenses.
+    ///
+    /// Enabled by default when stderr is a terminal
+    #[clap(long)]
+    progress: bool,
+    /// Generates output that is identical across runs and machines, so that
+    /// checked in attribution files only change when the licenses do.
+    ///
+    /// The generation time is omitted unless `SOURCE_DATE_EPOCH` is set, and
+    /// local paths are output relative to placeholders such as `$CARGO_HOME`
+    #[clap(long)]
+    reproducible: bool,
+}
+
+impl PipelineArgs {
+    /// Loads the configuration and creates the pipeline
+    pub fn pipeline(self, color: crate::Color) -> anyhow::Result<Pipeline> {
+        let manifest_path = if let Some(mp) = self.manifest_path {
+            mp
+        } else {
+            let cwd =
+                std::env::current_dir().context("unable to determine current working directory")?;
+            let mut cwd = PathBuf::from_path_buf(cwd).map_err(|pb| {
+                anyhow::anyhow!(
+                    "current working directory '{}' is not a utf-8 path",
+                    pb.display()
+                )
+            })?;
+
+            cwd.push("Cargo.toml");
+            cwd
+        };
+
+        anyhow::ensure!(
+            manifest_path.exists(),
+            "cargo manifest path '{manifest_path}' does not exist"
+        );
+
+        let mut cfg = run::load_config(&manifest_path, self.config.as_deref())?;
+
+        if !self.only.is_empty() {
+            cfg.only = self.only;
+        }
+
+        if self.locale.is_some() {
+            cfg.locale = self.locale;
+        }
+
+        if self.accept_only {
+            cfg.accepted.clear();
+        } else if let Some(accepted_file) = &self.accepted_file {
+            cfg.add_accepted_file(accepted_file)?;
+        }
+
+        for accept in &self.accept {
+            let licensee = spdx::Licensee::parse(accept).map_err(|err| {
+                anyhow::anyhow!("'{accept}' passed to --accept is not a valid SPDX licensee: {err}")
+            })?;
+
+            if !cfg.accepted.contains(&licensee) {
+                cfg.accepted.push(licensee);
+            }
+        }
+
+        cfg.include_unaccepted |= self.continue_on_error;
+        cfg.fail_on_missing_license_text |= self.fail_on_missing_license_text;
+
+        use std::io::IsTerminal;
+        use term::termcolor::ColorChoice;
+
+        Ok(Pipeline::new(manifest_path, cfg)
+            .features(self.features)
+            .all_features(self.all_features)
+            .no_default_features(self.no_default_features)
+            .workspace(self.workspace)
+            .packages(self.package)
+            .bins(self.bin)
+            .lock_options(krates::LockOptions {
+                frozen: self.frozen,
+                locked: self.locked,
+                offline: self.offline,
+            })
+            .targets(self.target)
+            .threshold(self.threshold)
+            .fail_on_missing(self.fail)
+            .message_format(self.message_format)
+            .verbose_diagnostics(self.verbose_diagnostics)
+            .progress(self.progress || std::io::stderr().is_terminal())
+            .registry_dir(self.registry_dir)
+            .reproducible(self.reproducible)
+            .ascii_diagnostics(matches!(color, crate::Color::Ascii))
+            .color(match color {
+                crate::Color::Auto => {
+                    // The termcolor crate doesn't check the stream to see if it's a TTY
+                    // which doesn't really fit with how the rest of the coloring works
+                    if std::io::stderr().is_terminal() {
+                        ColorChoice::Auto
+                    } else {
+                        ColorChoice::Never
+                    }
+                }
+                crate::Color::Always => ColorChoice::Always,
+                crate::Color::Never | crate::Color::Ascii => ColorChoice::Never,
+            }))
+    }
+}
+
+#[derive(clap::Parser, Debug)]
+pub struct Args {
+    #[clap(flatten)]
+    pipeline: PipelineArgs,
+    /// The name of the template to use when rendering.
+    ///
+    /// If only passing a single template file to `templates` this is not used.
+    #[clap(short, long)]
+    name: Option<String>,
+    /// A file to write the generated output to, typically an .html file.
+    ///
+    /// May be specified multiple times in the form `<name>=<path>` to render
+    /// each named template in the templates directory to its own file, in
+    /// which case the `outputs` configuration value is ignored.
+    #[clap(short, long, value_parser = parse_output_file)]
+    output_file: Vec<(Option<String>, PathBuf)>,
+    /// The encoding used when writing output files, output to stdout is
+    /// always UTF-8
+    #[clap(long, default_value_t)]
+    encoding: Encoding,
+    /// How the metadata supplied by crates, eg. descriptions and authors, is
+    /// sanitized before it is output, so that a crate can't inject markup
+    /// into the output
+    #[clap(long, value_enum, default_value = "auto")]
+    sanitize: run::sanitize::Sanitize,
+    /// Renders the template once for each crate, with only the crate and the
+    /// licenses it uses, to `<dir>/<name>-<version>.html`, along with an
+    /// `index.html` linking to each of them
+    #[clap(long)]
+    per_crate_output: Option<PathBuf>,
+    /// Writes a Makefile-style dependency file to the path, listing every
+    /// file consulted to generate the output, so that build systems only
+    /// rerun generation when one of them changes
+    #[clap(long)]
+    emit_depinfo: Option<PathBuf>,
+    /// Gathers and resolves licenses and renders the output as normal, but
+    /// only prints a summary and what would be written, rather than writing
+    /// any output
+    #[clap(long)]
+    dry_run: bool,
+    /// Prints every URL that would be contacted for the current configuration,
+    /// without contacting any of them, rather than writing any output
+    ///
+    /// Cargo is run offline, so the crate graph must be resolvable without
+    /// network access
+    #[clap(long)]
+    no_network_audit: bool,
+    /// Writes a separate output for each target, with only the crates that
+    /// are built for the target, to `<output>-<target>.<ext>`.
+    ///
+    /// The targets are specified with `--target`, or the `targets`
+    /// configuration value. The licenses of the crates are only gathered once
+    /// for all of the targets
+    #[clap(long)]
+    split_by_target: bool,
     /// The format of the output, defaults to `handlebars`.
     #[clap(long, default_value_t)]
     format: OutputFormat,
+    /// The full attribution document, eg. rendered HTML, to reference by its
+    /// name and digest in the annotations output with `--format oci-annotations`
+    #[clap(long, value_name = "PATH")]
+    oci_document: Option<PathBuf>,
     /// The template(s) or template directory to use.
     ///
-    /// Must either be a `.hbs` file, or have at least one `.hbs` file in it if
-    /// it is a directory.
+    /// Must either be a template file, or have at least one `.hbs` file (or
+    /// `.jinja` or `.j2` file for `jinja`) in it if it is a directory.
     ///
-    /// Required if `--format` is not `json`
+    /// Required if `--format` is not `json` or `json-lines`
     templates: Option<PathBuf>,
 }
 
-fn load_config(manifest_path: &Path) -> anyhow::Result<cargo_about::licenses::config::Config> {
-    let mut parent = manifest_path.parent();
+fn parse_output_file(s: &str) -> anyhow::Result<(Option<String>, PathBuf)> {
+    Ok(match s.split_once('=') {
+        Some((name, path)) => {
+            anyhow::ensure!(!name.is_empty(), "template name for output '{s}' is empty");
+            anyhow::ensure!(
+                !path.is_empty(),
+                "output path for template '{name}' is empty"
+            );
+            (Some(name.to_owned()), path.into())
+        }
+        None => (None, s.into()),
+    })
+}
+
+/// Gathers licenses without network access, printing the sources cargo would
+/// retrieve crates from, and every HTTP request that would have been sent
+fn network_audit(pipeline: Pipeline) -> anyhow::Result<()> {
+    let audit = cargo_about::licenses::http_cache::NetworkAudit::default();
+    let pipeline = pipeline.network_audit(audit.clone());
 
-    // Move up directories until we find an about.toml, to handle
-    // cases where eg in a workspace there is a top-level about.toml
-    // but the user is only getting a listing for a particular crate from it
-    while let Some(p) = parent {
-        // We _could_ limit ourselves to only directories that also have a Cargo.toml
-        // in them, but there could be cases where someone has multiple
-        // rust projects in subdirectories with a single top level about.toml that is
-        // used across all of them, we could also introduce a metadata entry for the
-        // relative path of the about.toml to use for the crate/workspace
+    let prepared = pipeline.prepare()?;
 
-        // if !p.join("Cargo.toml").exists() {
-        //     parent = p.parent();
-        //     continue;
-        // }
+    let sources: BTreeSet<_> = prepared
+        .krates
+        .krates()
+        .filter_map(|krate| krate.source.as_ref().map(|src| src.repr.as_str()))
+        .collect();
+    for source in sources {
+        println!("cargo {source}");
+    }
 
-        let about_toml = p.join("about.toml");
+    // Print the requests even if license resolution fails, as they are still
+    // the requests that would have been sent
+    let generated = pipeline.generate(&prepared);
 
-        if about_toml.exists() {
-            let contents = std::fs::read_to_string(&about_toml)?;
-            let cfg = toml::from_str(&contents)?;
+    for request in audit.requests() {
+        println!("{request}");
+    }
 
-            log::info!("loaded config from '{about_toml}'");
-            return Ok(cfg);
-        }
+    generated.map(|_input| ())
+}
 
-        parent = p.parent();
+/// The name of the file the output for a single crate is written to
+fn per_crate_filename(pl: &run::PackageLicense<'_>, ext: &str) -> String {
+    format!("{}-{}.{ext}", pl.package.name, pl.package.version)
+}
+
+/// Writes a Makefile-style depfile, with the outputs as the targets and every
+/// file consulted to generate them as the prerequisites
+fn write_depinfo(path: &Path, targets: &[PathBuf], deps: &BTreeSet<PathBuf>) -> anyhow::Result<()> {
+    use std::fmt::Write as _;
+
+    fn escape(path: &Path) -> String {
+        path.as_str().replace(' ', "\\ ").replace('$', "$$")
     }
 
-    log::warn!("no 'about.toml' found, falling back to default configuration");
-    Ok(cargo_about::licenses::config::Config::default())
+    let mut depinfo = targets
+        .iter()
+        .map(|target| escape(target))
+        .collect::<Vec<_>>()
+        .join(" ");
+    depinfo.push(':');
+
+    for dep in deps {
+        let _ = write!(depinfo, " \\\n  {}", escape(dep));
+    }
+    depinfo.push('\n');
+
+    std::fs::write(path, depinfo).with_context(|| format!("failed to write depinfo '{path}'"))
+}
+
+/// Inserts the target before the extension of an output path, eg.
+/// `about.html` becomes `about-x86_64-pc-windows-msvc.html`
+fn target_path(path: &Path, target: &str) -> PathBuf {
+    match (path.file_stem(), path.extension()) {
+        (Some(stem), Some(ext)) => path.with_file_name(format!("{stem}-{target}.{ext}")),
+        _ => format!("{path}-{target}").into(),
+    }
 }
 
 pub fn cmd(args: Args, color: crate::Color) -> anyhow::Result<()> {
-    let manifest_path = if let Some(mp) = args.manifest_path.clone() {
-        mp
-    } else {
-        let cwd =
-            std::env::current_dir().context("unable to determine current working directory")?;
-        let mut cwd = PathBuf::from_path_buf(cwd).map_err(|pb| {
-            anyhow::anyhow!(
-                "current working directory '{}' is not a utf-8 path",
-                pb.display()
-            )
-        })?;
-
-        cwd.push("Cargo.toml");
-        cwd
-    };
+    let targets = args.pipeline.target.clone();
+    let pipeline = args.pipeline.pipeline(color)?;
+
+    if args.no_network_audit {
+        return network_audit(pipeline);
+    }
 
     anyhow::ensure!(
-        manifest_path.exists(),
-        "cargo manifest path '{manifest_path}' does not exist"
+        matches!(
+            args.format,
+            OutputFormat::Json
+                | OutputFormat::JsonLines
+                | OutputFormat::Sqlite
+                | OutputFormat::OciAnnotations
+                | OutputFormat::Csv
+                | OutputFormat::Tsv
+        ) || args.templates.is_some(),
+        "{0} template(s) must be specified when using {0} output format",
+        args.format
+    );
+    anyhow::ensure!(
+        !matches!(args.format, OutputFormat::Sqlite) || args.templates.is_none(),
+        "templates can't be used with sqlite output format, which writes a database rather than rendering a template"
     );
 
-    let cfg = match &args.config {
-        Some(cfg_path) => {
-            let cfg_str = std::fs::read_to_string(cfg_path)
-                .with_context(|| format!("unable to read '{cfg_path}'"))?;
-            toml::from_str(&cfg_str)
-                .with_context(|| format!("unable to deserialize config from '{cfg_path}'"))?
+    // Outputs specified on the command line take precedence over the ones in
+    // the config, and named outputs render a specific template to a specific path
+    let mut output_file = None;
+    let mut named_outputs = BTreeMap::new();
+    let templates_dir = args.templates.as_deref().is_some_and(|tp| tp.is_dir());
+    if args.output_file.is_empty() {
+        // The configured outputs only apply when rendering a templates
+        // directory, other formats are written to stdout as usual
+        if templates_dir {
+            named_outputs.extend(pipeline.config().outputs.clone());
         }
-        None => load_config(&manifest_path)?,
-    };
+    } else {
+        for (name, path) in args.output_file {
+            if let Some(name) = name {
+                named_outputs.insert(name, path);
+            } else {
+                anyhow::ensure!(
+                    output_file.is_none(),
+                    "only one output file without a template name can be specified"
+                );
+                output_file = Some(path);
+            }
+        }
+    }
 
-    let mut all_crates = None;
-    let mut store = None;
-    let mut templates = None;
+    anyhow::ensure!(
+        named_outputs.is_empty() || templates_dir,
+        "rendering named templates to multiple output files requires a templates directory"
+    );
 
     anyhow::ensure!(
-        matches!(args.format, OutputFormat::Json) || args.templates.is_some(),
-        "handlebars template(s) must be specified when using handlebars output format"
+        args.per_crate_output.is_none() || args.templates.is_some(),
+        "rendering an output per crate requires a template"
     );
 
+    // A database can't be written to stdout, so the output file is the path
+    // of the database instead
+    let sqlite_path = if matches!(args.format, OutputFormat::Sqlite) {
+        let path = output_file.take().filter(|path| path != "-").context(
+            "the path of the database must be specified with -o, --output-file when using sqlite output format",
+        )?;
+        anyhow::ensure!(
+            cfg!(feature = "sqlite"),
+            "sqlite output format requires cargo-about to be built with the `sqlite` feature"
+        );
+        Some(path)
+    } else {
+        None
+    };
+
+    // The graph is prepared for every target, and each target's crates are
+    // then resolved separately
+    let split_targets = if args.split_by_target {
+        let targets = if targets.is_empty() {
+            pipeline.config().targets.clone()
+        } else {
+            targets
+        };
+
+        anyhow::ensure!(
+            !targets.is_empty(),
+            "at least one target must be specified with --target, or with `targets` in the configuration, to split the output by target"
+        );
+        anyhow::ensure!(
+            output_file.as_ref().is_some_and(|path| path != "-") || !named_outputs.is_empty(),
+            "splitting the output by target requires output files, specified with -o, --output-file or the `outputs` configuration value"
+        );
+        anyhow::ensure!(
+            sqlite_path.is_none() && args.per_crate_output.is_none(),
+            "the output can't be split by target when using sqlite output format or --per-crate-output"
+        );
+
+        Some(targets)
+    } else {
+        None
+    };
+
     // Check if the parent process is powershell, if it is, assume that it will
     // screw up the output https://github.com/EmbarkStudios/cargo-about/issues/198
     // and inform the user about the -o, --output-file option
-    let redirect_stdout =
-        args.output_file.is_none() || args.output_file.as_deref() == Some(Path::new("-"));
+    let redirect_stdout = match &output_file {
+        Some(path) => path == "-",
+        None => {
+            named_outputs.is_empty() && sqlite_path.is_none() && args.per_crate_output.is_none()
+        }
+    };
     if redirect_stdout {
         anyhow::ensure!(!cargo_about::is_powershell_parent(), "cargo-about should not redirect its output in powershell, please use the -o, --output-file option to redirect to a file to avoid powershell encoding issues");
     }
 
-    rayon::scope(|s| {
-        s.spawn(|_| {
-            log::info!("gathering crates for {manifest_path}");
-            all_crates = Some(cargo_about::get_all_crates(
-                &manifest_path,
-                args.no_default_features,
-                args.all_features,
-                args.features.clone(),
-                args.workspace,
-                krates::LockOptions {
-                    frozen: args.frozen,
-                    locked: args.locked,
-                    offline: args.offline,
-                },
-                &cfg,
-                &args.target,
-            ));
-        });
-        s.spawn(|_| {
-            log::info!("loading license store");
-            store = Some(cargo_about::licenses::store_from_cache());
-        });
-        s.spawn(|_| {
-            let Some(template_path) = args.templates.as_ref() else {
-                return;
+    // Load and check the templates before gathering, which can take a long
+    // time, so that any mistakes in them are reported immediately
+    let templates = args
+        .templates
+        .as_deref()
+        .map(|template_path| {
+            let engine = match args.format {
+                OutputFormat::Jinja => run::TemplateEngine::Jinja,
+                OutputFormat::Handlebars
+                | OutputFormat::Json
+                | OutputFormat::JsonLines
+                | OutputFormat::Sqlite
+                | OutputFormat::OciAnnotations
+                | OutputFormat::Csv
+                | OutputFormat::Tsv => run::TemplateEngine::Handlebars,
             };
+            run::Templates::load(template_path, args.name.clone(), engine)
+                .map(|templates| templates.sanitize(args.sanitize))
+        })
+        .transpose()?;
 
-            let load_templates = || -> anyhow::Result<_> {
-                let mut reg = Handlebars::new();
-
-                anyhow::ensure!(template_path.exists(), "template(s) path '{template_path}' does not exist");
-
-                use handlebars::*;
-
-                reg.register_helper(
-                    "json",
-                    Box::new(
-                        |h: &Helper<'_, >,
-                         _r: &Handlebars<'_>,
-                         _c: &Context,
-                         _rc: &mut RenderContext<'_, '_>,
-                         out: &mut dyn Output|
-                         -> HelperResult {
-                            let param = h
-                                .param(0)
-                                .ok_or_else(|| RenderErrorReason::ParamNotFoundForIndex("json", 0))?;
-
-                            match serde_json::to_string_pretty(param.value()) {
-                                Ok(json) => Ok(out.write(&json)?),
-                                Err(err) => {
-                                    Err(RenderErrorReason::Other(err.to_string()).into())
-                                }
-                            }
-                        },
-                    ),
-                );
-
-                if template_path.is_dir() {
-                    reg.register_templates_directory( template_path, handlebars::DirectorySourceOptions::default())?;
-
-                    anyhow::ensure!(!reg.get_templates().is_empty(), "template path '{template_path}' did not contain any hbs files");
-
-                    Ok((reg, args.name.context("specified a directory for templates, but did not provide the name of the template to use")?))
-                } else {
-                    // Ignore the extension, if the user says they want to use a specific file, that's on them
-                    reg.register_template_file("tmpl", template_path)?;
-
-                    Ok((reg, "tmpl".to_owned()))
-                }
-            };
+    if let Some(templates) = &templates {
+        for name in named_outputs.keys() {
+            templates.check(Some(name))?;
+        }
 
-            templates = Some(load_templates());
-        });
-    });
+        if redirect_stdout || output_file.is_some() || args.per_crate_output.is_some() {
+            templates.check(None)?;
+        }
 
-    let krates = all_crates.unwrap()?;
-    let store = store.unwrap()?;
+        if let Some(name) = templates
+            .index_template()
+            .filter(|_| args.per_crate_output.is_some())
+        {
+            templates.check(Some(name))?;
+        }
+    }
 
-    log::info!("gathered {} crates", krates.len());
+    let template_paths: Vec<_> = templates
+        .iter()
+        .flat_map(|templates| templates.source_paths())
+        .collect();
+    let static_files = match &templates {
+        Some(templates) => templates.static_files()?,
+        None => Vec::new(),
+    };
 
-    let client = if !args.offline && !args.frozen {
-        Some(reqwest::blocking::ClientBuilder::new().build()?)
-    } else {
-        None
+    let prepared = pipeline.prepare()?;
+    let inputs = match &split_targets {
+        Some(targets) => pipeline
+            .generate_by_target(&prepared, targets)?
+            .into_iter()
+            .map(|(target, input)| (Some(target), input))
+            .collect(),
+        None => vec![(None, pipeline.generate(&prepared)?)],
     };
 
-    let summary = licenses::Gatherer::with_store(std::sync::Arc::new(store))
-        .with_confidence_threshold(args.threshold)
-        .with_max_depth(cfg.max_depth.map(|md| md as _))
-        .gather(&krates, &cfg, client);
+    let formatters = if templates.is_none() && sqlite_path.is_none() {
+        use run::output::{Formatters, Json, OciAnnotations};
 
-    let (files, resolved) =
-        licenses::resolution::resolve(&summary, &cfg.accepted, &cfg.crates, args.fail);
+        let mut formatters = Formatters::default();
 
-    use term::termcolor::ColorChoice;
+        if let Some(path) = &args.oci_document {
+            let contents =
+                std::fs::read(path).with_context(|| format!("failed to read '{path}'"))?;
+            let name = path.file_name().unwrap_or(path.as_str());
+            formatters.register(
+                "oci-annotations",
+                OciAnnotations::default().document(name, &contents),
+            );
+        }
 
-    let stream = term::termcolor::StandardStream::stderr(match color {
-        crate::Color::Auto => {
-            // The termcolor crate doesn't check the stream to see if it's a TTY
-            // which doesn't really fit with how the rest of the coloring works
-            use std::io::IsTerminal;
-            if std::io::stderr().is_terminal() {
-                ColorChoice::Auto
-            } else {
-                ColorChoice::Never
-            }
+        // JSON is only sanitized when explicitly requested, as it isn't
+        // known how it will be consumed
+        if args.sanitize == run::sanitize::Sanitize::Html {
+            formatters
+                .register("json", Json::default().sanitize_html(true))
+                .register("json-lines", Json::lines().sanitize_html(true));
         }
-        crate::Color::Always => ColorChoice::Always,
-        crate::Color::Never => ColorChoice::Never,
-    });
-
-    let output = if let Some(templates) = templates {
-        let (registry, template_name) = templates?;
-        let input = generate(&summary, &resolved, &files, stream)?;
-        registry.render(&template_name, &input)?
-    } else {
-        let input = generate(&summary, &resolved, &files, stream)?;
-        serde_json::to_string(&input)?
-    };
 
-    if let Some(path) = &args.output_file.filter(|_| !redirect_stdout) {
-        std::fs::write(path, output)
-            .with_context(|| format!("output file {path} could not be written"))?;
+        Some(formatters)
     } else {
-        println!("{output}");
-    }
-
-    Ok(())
-}
+        None
+    };
 
-#[derive(Clone, Serialize)]
-struct UsedBy<'a> {
-    #[serde(rename = "crate")]
-    krate: &'a krates::cm::Package,
-    path: Option<PathBuf>,
-}
+    // Render everything up front, so that a dry run can report exactly what
+    // would be written
+    let mut outputs = Vec::new();
 
-#[derive(Clone, Serialize)]
-struct License<'a> {
-    /// The full name of the license
-    name: String,
-    /// The SPDX short identifier for the license
-    id: String,
-    /// True if this is the first license of its kind in the flat array
-    first_of_kind: bool,
-    /// The full license text
-    text: String,
-    /// The path where the license text was sourced from
-    source_path: Option<PathBuf>,
-    /// The list of crates this license was applied to
-    used_by: Vec<UsedBy<'a>>,
-}
+    for (target, input) in &inputs {
+        let output_path = |path: &PathBuf| match target {
+            Some(target) => target_path(path, target),
+            None => path.clone(),
+        };
 
-#[derive(Serialize)]
-struct LicenseSet {
-    count: usize,
-    name: String,
-    id: String,
-    indices: Vec<usize>,
-    text: String,
-}
+        let output = if let Some(templates) = &templates {
+            for (name, path) in &named_outputs {
+                let output = templates.render_named(name, input)?;
+                outputs.push((Some(output_path(path)), args.encoding.encode(&output)));
+            }
 
-#[derive(Serialize)]
-struct Input<'a> {
-    overview: Vec<LicenseSet>,
-    licenses: Vec<License<'a>>,
-    crates: Vec<PackageLicense<'a>>,
-}
+            if let Some(dir) = &args.per_crate_output {
+                // The output is typically HTML, but the template can output
+                // anything, eg. markdown for `about.md.hbs`
+                let ext = output_file
+                    .as_ref()
+                    .and_then(|path| path.extension())
+                    .map(str::to_owned)
+                    .or_else(|| templates.output_extension(None))
+                    .unwrap_or_else(|| "html".to_owned());
+
+                let mut filenames = Vec::with_capacity(input.crates.len());
+                for (i, pl) in input.crates.iter().enumerate() {
+                    let output = templates.render(&input.for_crate(i))?;
+                    let filename = per_crate_filename(pl, &ext);
+                    outputs.push((Some(dir.join(&filename)), args.encoding.encode(&output)));
+                    filenames.push(filename);
+                }
 
-fn generate<'kl>(
-    nfos: &[licenses::KrateLicense<'kl>],
-    resolved: &[Option<licenses::Resolved>],
-    files: &licenses::resolution::Files,
-    stream: term::termcolor::StandardStream,
-) -> anyhow::Result<Input<'kl>> {
-    use cargo_about::licenses::resolution::Severity;
+                // The default index is HTML, but an index template outputs
+                // the same kind of file as the template for each crate, unless
+                // its name says otherwise
+                let index_ext = match templates.index_template() {
+                    Some(name) => templates
+                        .output_extension(Some(name))
+                        .unwrap_or_else(|| ext.clone()),
+                    None => "html".to_owned(),
+                };
 
-    let mut num_errors = 0;
+                let index = templates.render_index(input, &filenames)?;
+                outputs.push((
+                    Some(dir.join(format!("index.{index_ext}"))),
+                    args.encoding.encode(&index),
+                ));
+            }
 
-    let diag_cfg = term::Config::default();
+            (redirect_stdout || output_file.is_some())
+                .then(|| templates.format(input))
+                .transpose()?
+        } else if let Some(formatters) = &formatters {
+            Some(formatters.get(&args.format.to_string())?.format(input)?)
+        } else {
+            None
+        };
+
+        if let Some(output) = output {
+            if let Some(path) = output_file.as_ref().filter(|_| !redirect_stdout) {
+                outputs.push((Some(output_path(path)), args.encoding.encode(&output)));
+            } else {
+                let mut output = output.into_bytes();
+                output.push(b'\n');
+                outputs.push((None, output));
+            }
+        }
+    }
 
-    let mut licenses = {
-        let mut licenses = BTreeMap::new();
-        for (krate_license, resolved) in nfos
+    // The static files of the templates, eg. stylesheets, are copied next to
+    // every output file, so that multi-file HTML output works as is
+    let mut static_outputs = Vec::new();
+    if !static_files.is_empty() {
+        let dirs: BTreeSet<_> = outputs
             .iter()
-            .zip(resolved.iter())
-            .filter_map(|(kl, res)| res.as_ref().map(|res| (kl, res)))
-        {
-            if !resolved.diagnostics.is_empty() {
-                let mut streaml = stream.lock();
+            .filter_map(|(path, _)| path.as_deref()?.parent())
+            .map(|dir| dir.to_owned())
+            .collect();
 
-                for diag in &resolved.diagnostics {
-                    if diag.severity >= Severity::Error {
-                        num_errors += 1;
-                    }
+        for (src, rel_path) in &static_files {
+            let contents = std::fs::read(src).with_context(|| format!("failed to read '{src}'"))?;
+            for dir in &dirs {
+                static_outputs.push((dir.join(rel_path), contents.clone()));
+            }
+        }
+    }
 
-                    term::emit(&mut streaml, &diag_cfg, files, diag)?;
-                }
+    if args.dry_run {
+        for (target, input) in &inputs {
+            if let Some(target) = target {
+                print!("{target}: ");
+            }
+            println!(
+                "{} crates, {} licenses",
+                input.crates.len(),
+                input.overview.len()
+            );
+            for ls in &input.overview {
+                println!("  {} {}", ls.count, ls.id);
             }
+        }
 
-            let license_iter = resolved.licenses.iter().flat_map(|license| {
-                let mut license_texts = Vec::new();
-                match license.license {
-                    spdx::LicenseItem::Spdx { id, .. } => {
-                        // Attempt to retrieve the actual license file from the crate, note that in some cases
-                        // _sigh_ there are actually multiple license texts for the same license with different
-                        // copyright holders/authors/attribution so we can't just return 1
-                        license_texts.extend(krate_license
-                            .license_files
-                            .iter()
-                            .filter_map(|lf| {
-                                // Check if this is the actual license file we want
-                                if !lf
-                                    .license_expr
-                                    .evaluate(|ereq| ereq.license.id() == Some(id))
-                                {
-                                    return None;
-                                }
-
-                                match &lf.kind {
-                                    licenses::LicenseFileKind::Text(text)
-                                    | licenses::LicenseFileKind::AddendumText(text, _) => {
-                                        let license = License {
-                                            name: id.full_name.to_owned(),
-                                            id: id.name.to_owned(),
-                                            text: text.clone(),
-                                            source_path: Some(lf.path.clone()),
-                                            used_by: Vec::new(),
-                                            first_of_kind: false,
-                                        };
-                                        Some(license)
-                                    }
-                                    licenses::LicenseFileKind::Header => None,
-                                }
-                            }));
-
-                        if license_texts.is_empty() {
-                            log::debug!(
-                                "unable to find text for license '{license}' for crate '{}', falling back to canonical text",
-                                krate_license.krate
-                            );
-
-                            // If the crate doesn't have the actual license file,
-                            // fallback to the canonical license text and emit a warning
-                            license_texts.push(License {
-                                name: id.full_name.to_owned(),
-                                id: id.name.to_owned(),
-                                text: id.text().to_owned(),
-                                source_path: None,
-                                used_by: Vec::new(),
-                                first_of_kind: false,
-                            });
-                        }
-                    }
-                    spdx::LicenseItem::Other { .. } => {
-                        log::warn!(
-                            "{license} has no license file for crate '{}'",
-                            krate_license.krate
-                        );
-                    }
-                }
+        for (path, output) in &outputs {
+            match path {
+                Some(path) => println!("would write {} bytes to '{path}'", output.len()),
+                None => println!("would write {} bytes to stdout", output.len()),
+            }
+        }
 
-                license_texts
-            });
+        for (path, contents) in &static_outputs {
+            println!("would copy {} bytes to '{path}'", contents.len());
+        }
 
-            for license in license_iter {
-                let entry = licenses
-                    .entry(license.name.clone())
-                    .or_insert_with(BTreeMap::new);
+        if let Some(path) = &sqlite_path {
+            println!("would append run to database '{path}'");
+        }
 
-                let lic = entry.entry(license.text.clone()).or_insert_with(|| license);
-                lic.used_by.push(UsedBy {
-                    krate: krate_license.krate,
-                    path: None,
-                });
-            }
+        return Ok(());
+    }
+
+    #[cfg(feature = "sqlite")]
+    if let Some(path) = &sqlite_path {
+        for (_, input) in &inputs {
+            run::sqlite::export(input, pipeline.manifest_path(), path)
+                .with_context(|| format!("failed to export to database '{path}'"))?;
         }
+    }
 
-        let mut licenses: Vec<_> = licenses
-            .into_iter()
-            .flat_map(|(_, v)| v.into_values())
+    if let Some(dir) = &args.per_crate_output {
+        std::fs::create_dir_all(dir).with_context(|| format!("failed to create '{dir}'"))?;
+    }
+
+    if let Some(depinfo_path) = &args.emit_depinfo {
+        let mut targets: Vec<_> = outputs
+            .iter()
+            .filter_map(|(path, _)| path.clone())
+            .chain(static_outputs.iter().map(|(path, _)| path.clone()))
+            .chain(sqlite_path.clone())
             .collect();
 
-        // Sort the krates that use a license lexicographically
-        for lic in &mut licenses {
-            lic.used_by.sort_by(|a, b| a.krate.id.cmp(&b.krate.id));
+        // Output written to stdout is redirected to a file we don't know, so
+        // the depfile itself is the target instead
+        if targets.is_empty() {
+            targets.push(depinfo_path.clone());
         }
 
-        licenses.sort_by(|a, b| a.id.cmp(&b.id));
-        licenses
-    };
-
-    if num_errors > 0 {
-        anyhow::bail!(
-            "encountered {num_errors} errors resolving licenses, unable to generate output"
+        let mut deps = BTreeSet::new();
+        deps.insert(pipeline.manifest_path().to_owned());
+        deps.extend(pipeline.config().loaded_files.iter().cloned());
+        deps.extend(template_paths);
+        deps.extend(static_files.iter().map(|(src, _)| src.clone()));
+        deps.extend(
+            prepared
+                .krates
+                .krates()
+                .map(|krate| krate.manifest_path.clone()),
+        );
+        deps.insert(prepared.krates.workspace_root().join("Cargo.lock"));
+        // The paths in the output may be relative, logical, redacted, or
+        // remapped, so the gathered paths on disk are used instead
+        deps.extend(
+            inputs
+                .iter()
+                .flat_map(|(_, input)| input.license_files.iter().cloned()),
         );
-    }
 
-    let mut overview: Vec<LicenseSet> = Vec::with_capacity(256);
+        // Files retrieved from remote git hosts for clarifications don't
+        // exist locally
+        deps.retain(|path| path.is_file());
 
-    for (ndx, lic) in licenses.iter_mut().enumerate() {
-        match overview.binary_search_by(|i| i.id.cmp(&lic.id)) {
-            Ok(i) => {
-                let ov = &mut overview[i];
-                ov.indices.push(ndx);
-                ov.count += lic.used_by.len();
-            }
-            Err(i) => {
-                let mut ls = LicenseSet {
-                    count: lic.used_by.len(),
-                    name: lic.name.clone(),
-                    id: lic.id.clone(),
-                    indices: Vec::with_capacity(10),
-                    text: lic.text.clone(),
-                };
+        write_depinfo(depinfo_path, &targets, &deps)?;
+    }
 
-                ls.indices.push(ndx);
-                overview.insert(i, ls);
-                lic.first_of_kind = true;
-            }
+    for (path, output) in outputs {
+        if let Some(path) = path {
+            std::fs::write(&path, output)
+                .with_context(|| format!("output file {path} could not be written"))?;
+        } else {
+            // Write the raw UTF-8 bytes rather than going through the formatting
+            // machinery, so that nothing between us and the stream can transcode it
+            use std::io::Write as _;
+            let mut stdout = std::io::stdout().lock();
+            stdout
+                .write_all(&output)
+                .and_then(|()| stdout.flush())
+                .context("failed to write output to stdout")?;
         }
     }
 
-    // Show the most used licenses first
-    overview.sort_by(|a, b| b.count.cmp(&a.count));
-
-    let crates = nfos
-        .iter()
-        .filter(|nfo| !matches!(nfo.lic_info, LicenseInfo::Ignore))
-        .map(|nfo| PackageLicense {
-            package: &nfo.krate.0,
-            license: nfo.lic_info.to_string(),
-        })
-        .collect();
-    Ok(Input {
-        overview,
-        licenses,
-        crates,
-    })
-}
+    for (path, contents) in static_outputs {
+        if let Some(parent) = path.parent().filter(|parent| !parent.as_str().is_empty()) {
+            std::fs::create_dir_all(parent)
+                .with_context(|| format!("failed to create '{parent}'"))?;
+        }
+        std::fs::write(&path, contents)
+            .with_context(|| format!("static file {path} could not be written"))?;
+    }
 
-#[derive(Serialize)]
-struct PackageLicense<'a> {
-    package: &'a Package,
-    license: String,
+    Ok(())
 }
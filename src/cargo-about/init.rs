@@ -3,6 +3,10 @@ use std::fs;
 static DEFAULT_CONFIG: &str = include_str!("../../resources/about.toml");
 static DEFAULT_HBS: &str = include_str!("../../resources/default.hbs");
 
+/// The intro text in the default template that is replaced with the
+/// localized intro for the project
+const DEFAULT_INTRO: &str = "<p>{{strings.intro}}</p>";
+
 #[derive(clap::Parser, Debug)]
 pub struct Args {
     /// Disables the handlebars generation
@@ -11,24 +15,93 @@ pub struct Args {
     /// Forces cargo-about to overwrite the local config file
     #[clap(long)]
     overwrite: bool,
+    /// Adds a copyright notice for the project to the generated template,
+    /// with the specified year, or the current year if not specified
+    #[clap(
+        long,
+        value_name = "YEAR",
+        num_args = 0..=1,
+        default_missing_value = "",
+        value_parser = parse_year
+    )]
+    copyright_year: Option<i32>,
+    /// The locale of the generated template, eg. `de`, which is also set as
+    /// the `locale` in the generated config
+    #[clap(long)]
+    locale: Option<String>,
+}
+
+fn parse_year(s: &str) -> anyhow::Result<i32> {
+    if s.is_empty() {
+        Ok(time::OffsetDateTime::now_utc().year())
+    } else {
+        s.parse()
+            .map_err(|_err| anyhow::anyhow!("'{s}' is not a valid year"))
+    }
+}
+
+/// Escapes the characters that have special meaning in HTML
+fn escape_html(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
 }
 
 pub fn cmd(args: Args) -> anyhow::Result<()> {
-    let root_path = krates::cm::MetadataCommand::new().exec()?.workspace_root;
+    let metadata = krates::cm::MetadataCommand::new().exec()?;
+    let root_path = &metadata.workspace_root;
     let with_handlebars = !args.no_handlebars;
 
     if with_handlebars {
         let handlebars_path = root_path.join("about.hbs");
         let write_handlebars = !handlebars_path.is_file() || args.overwrite;
         if write_handlebars {
-            fs::write(handlebars_path, DEFAULT_HBS)?;
+            // Use the name of the root package, falling back to the name of
+            // the workspace directory for virtual manifests
+            let name = metadata
+                .root_package()
+                .map(|pkg| pkg.name.as_str())
+                .or_else(|| root_path.file_name())
+                .unwrap_or("this project");
+
+            let cfg = cargo_about::licenses::config::Config {
+                locale: args.locale.clone(),
+                ..Default::default()
+            };
+            let strings = cargo_about::run::strings::resolve(&cfg, name)?;
+            let name = escape_html(name);
+
+            let mut intro = format!("<p>{}</p>", escape_html(&strings["intro"]));
+            if let Some(year) = args.copyright_year {
+                intro.push_str(&format!(
+                    "\n            <p>Copyright &copy; {year} {name}</p>"
+                ));
+            }
+
+            fs::write(handlebars_path, DEFAULT_HBS.replace(DEFAULT_INTRO, &intro))?;
         }
     }
 
     let config_path = root_path.join("about.toml");
     let write_config = !config_path.exists() || args.overwrite;
     if write_config {
-        fs::write(config_path, DEFAULT_CONFIG)?;
+        let mut config = DEFAULT_CONFIG.to_owned();
+        if let Some(locale) = &args.locale {
+            config.push_str(&format!(
+                "\nlocale = {}\n",
+                toml::Value::String(locale.clone())
+            ));
+        }
+
+        fs::write(config_path, config)?;
     }
 
     Ok(())
@@ -0,0 +1,166 @@
+use crate::generate::PipelineArgs;
+use cargo_about::licenses::{KrateLicense, RegistryCheck};
+use serde::Serialize;
+
+#[derive(clap::ValueEnum, Copy, Clone, Debug)]
+pub enum Format {
+    /// A table aligned for reading in a terminal
+    Table,
+    /// A JSON array with an object for each crate
+    Json,
+    /// Comma separated values, with a row for each license file
+    Csv,
+}
+
+#[derive(clap::Parser, Debug)]
+pub struct Args {
+    #[clap(flatten)]
+    pipeline: PipelineArgs,
+    /// The format to list the licenses in
+    #[clap(long, value_enum, default_value = "table")]
+    format: Format,
+}
+
+#[derive(Serialize)]
+struct Crate<'a> {
+    name: &'a str,
+    version: String,
+    /// The license expression cargo-about uses for the crate, `Unknown` if it
+    /// has to be synthesized from the license files, or `Ignore`
+    license: String,
+    files: Vec<File>,
+    /// Whether the license texts found in the crate agree with the license
+    /// crates.io lists for it
+    registry: RegistryCheck,
+}
+
+#[derive(Serialize)]
+struct File {
+    /// The path of the file, relative to the crate's root
+    path: String,
+    license: String,
+    confidence: f32,
+}
+
+impl<'a> Crate<'a> {
+    fn new(kl: &'a KrateLicense<'_>) -> Self {
+        let root = kl.krate.manifest_path.parent();
+
+        Self {
+            name: &kl.krate.name,
+            version: kl.krate.version.to_string(),
+            license: kl.lic_info.to_string(),
+            files: kl
+                .license_files
+                .iter()
+                .map(|lf| File {
+                    path: root
+                        .and_then(|root| lf.path.strip_prefix(root).ok())
+                        .unwrap_or(&lf.path)
+                        .to_string(),
+                    license: lf.license_expr.to_string(),
+                    confidence: lf.confidence,
+                })
+                .collect(),
+            registry: kl.registry_check(),
+        }
+    }
+
+    /// The rows for the crate, one per license file, or a single row without
+    /// a file if none were found
+    fn rows(&self) -> Vec<[String; 7]> {
+        let row = |file: Option<&File>| {
+            [
+                self.name.to_owned(),
+                self.version.clone(),
+                self.license.clone(),
+                file.map_or_else(String::new, |f| f.path.clone()),
+                file.map_or_else(String::new, |f| f.license.clone()),
+                file.map_or_else(String::new, |f| format!("{:.2}", f.confidence)),
+                self.registry.to_string(),
+            ]
+        };
+
+        if self.files.is_empty() {
+            vec![row(None)]
+        } else {
+            self.files.iter().map(|f| row(Some(f))).collect()
+        }
+    }
+}
+
+const HEADER: [&str; 7] = [
+    "crate",
+    "version",
+    "license",
+    "file",
+    "file license",
+    "confidence",
+    "registry",
+];
+
+fn print_table(crates: &[Crate<'_>]) {
+    let rows: Vec<_> = crates.iter().flat_map(|c| c.rows()).collect();
+
+    let mut widths = HEADER.map(str::len);
+    for row in &rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+
+    let print_row = |row: [&str; 7]| {
+        let mut line = String::new();
+        for (i, (cell, width)) in row.iter().zip(widths).enumerate() {
+            if i > 0 {
+                line.push_str("  ");
+            }
+            line.push_str(cell);
+            line.extend(std::iter::repeat(' ').take(width - cell.chars().count()));
+        }
+        println!("{}", line.trim_end());
+    };
+
+    print_row(HEADER);
+    for row in &rows {
+        print_row(row.each_ref().map(String::as_str));
+    }
+}
+
+/// Quotes the field if it contains characters that are special in CSV
+fn csv_field(field: &str) -> std::borrow::Cow<'_, str> {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\"")).into()
+    } else {
+        field.into()
+    }
+}
+
+fn print_csv(crates: &[Crate<'_>]) {
+    let print_row = |row: [&str; 7]| {
+        let row = row.map(csv_field);
+        println!("{}", row.join(","));
+    };
+
+    print_row(HEADER);
+    for row in crates.iter().flat_map(|c| c.rows()) {
+        print_row(row.each_ref().map(String::as_str));
+    }
+}
+
+pub fn cmd(args: Args, color: crate::Color) -> anyhow::Result<()> {
+    let pipeline = args.pipeline.pipeline(color)?;
+
+    let prepared = pipeline.prepare()?;
+    let summary = pipeline.gather(&prepared)?;
+
+    let crates: Vec<_> = summary.iter().map(Crate::new).collect();
+
+    match args.format {
+        Format::Table => print_table(&crates),
+        Format::Json => println!("{}", serde_json::to_string_pretty(&crates)?),
+        Format::Csv => print_csv(&crates),
+    }
+
+    Ok(())
+}
@@ -1,9 +1,22 @@
 #![doc = include_str!("../../README.md")]
 use anyhow::Context as _;
 
+mod baseline;
+mod cache;
+mod changelog;
 mod clarify;
+mod dist;
+mod fix;
 mod generate;
 mod init;
+mod licenses;
+mod merge;
+#[cfg(feature = "sqlite")]
+mod record;
+mod schema;
+#[cfg(feature = "sqlite")]
+mod trend;
+mod validate_config;
 
 #[global_allocator]
 static ALLOC: mimalloc::MiMalloc = mimalloc::MiMalloc;
@@ -16,13 +29,75 @@ enum Command {
     Init(init::Args),
     /// Computes a clarification for a file
     Clarify(clarify::Args),
+    /// Outputs the JSON schema for the JSON output of `generate`
+    Schema(schema::Args),
+    /// Appends a summary of the licenses in use to a history database
+    #[cfg(feature = "sqlite")]
+    Record(record::Args),
+    /// Shows how the licenses in use changed over the recorded history
+    #[cfg(feature = "sqlite")]
+    Trend(trend::Args),
+    /// Writes or checks a baseline of the licenses used by each crate
+    Baseline(baseline::Args),
+    /// Lists the license information detected for each crate, without
+    /// resolving or rendering it
+    Licenses(licenses::Args),
+    /// Merges the JSON outputs of multiple `generate` runs, eg. of separate
+    /// workspaces, and renders them as a single output
+    Merge(merge::Args),
+    /// Validates the configuration against the crate graph, reporting crate
+    /// configurations and workarounds that don't apply to any crate, and
+    /// clarification files that don't exist
+    ValidateConfig(validate_config::Args),
+    /// Writes the output for each target to its own directory, and prints a
+    /// JSON manifest of the written artifacts, for use by release tooling
+    /// such as cargo-dist
+    Dist(dist::Args),
+    /// Interactively resolves the crates whose licenses fail to resolve, by
+    /// accepting a licensee, clarifying the crate's license, or ignoring the
+    /// crate, and writes the fixes to the configuration
+    Fix(fix::Args),
+    /// Shows the location of the caches of responses from clearlydefined.io
+    /// and files retrieved from git hosts, reports their sizes, or cleans them
+    Cache(cache::Args),
+    /// Lists the crates that were added or updated since a git revision, and
+    /// their licenses, as a markdown section for release notes
+    Changelog(changelog::Args),
 }
 
 #[derive(clap::ValueEnum, Copy, Clone, Debug)]
 pub enum Color {
+    /// Colors output written to a terminal, unless `NO_COLOR` is set
     Auto,
     Always,
     Never,
+    /// Never colors output, and only uses ASCII characters when rendering
+    /// diagnostics, rather than unicode box drawing characters
+    Ascii,
+}
+
+impl Color {
+    /// Resolves `auto` to `never` if the [`NO_COLOR`](https://no-color.org)
+    /// environment variable is set to a non-empty value
+    fn resolve(self) -> Self {
+        match self {
+            Self::Auto if std::env::var_os("NO_COLOR").is_some_and(|nc| !nc.is_empty()) => {
+                Self::Never
+            }
+            color => color,
+        }
+    }
+
+    /// Whether output written to stderr is colored
+    fn is_colored(self) -> bool {
+        use std::io::IsTerminal as _;
+
+        match self {
+            Self::Auto => std::io::stderr().is_terminal(),
+            Self::Always => true,
+            Self::Never | Self::Ascii => false,
+        }
+    }
 }
 
 impl std::str::FromStr for Color {
@@ -35,6 +110,7 @@ impl std::str::FromStr for Color {
             "auto" => Self::Auto,
             "always" => Self::Always,
             "never" => Self::Never,
+            "ascii" => Self::Ascii,
             _ => anyhow::bail!("unknown color option '{s}' specified"),
         })
     }
@@ -71,7 +147,7 @@ Possible values:
     cmd: Command,
 }
 
-fn setup_logger(level: log::LevelFilter) -> Result<(), fern::InitError> {
+fn setup_logger(level: log::LevelFilter, colored: bool) -> Result<(), fern::InitError> {
     use log::Level as Lvl;
     use nu_ansi_term::Color;
 
@@ -79,10 +155,19 @@ fn setup_logger(level: log::LevelFilter) -> Result<(), fern::InitError> {
         .level(log::LevelFilter::Warn)
         .level_for("cargo_about", level)
         .format(move |out, message, record| {
+            let level = record.level();
+            if !colored {
+                out.finish(format_args!(
+                    "{date} [{level}] {message}",
+                    date = time::OffsetDateTime::now_utc(),
+                ));
+                return;
+            }
+
             out.finish(format_args!(
                 "{date} [{level}] {message}\x1B[0m",
                 date = time::OffsetDateTime::now_utc(),
-                level = match record.level() {
+                level = match level {
                     Lvl::Error => Color::Red.paint("ERROR"),
                     Lvl::Warn => Color::Yellow.paint("WARN"),
                     Lvl::Info => Color::Green.paint("INFO"),
@@ -92,7 +177,10 @@ fn setup_logger(level: log::LevelFilter) -> Result<(), fern::InitError> {
                 message = message,
             ));
         })
-        .chain(std::io::stderr())
+        // Log messages are written around the progress bar, if there is one
+        .chain(fern::Output::call(|record| {
+            cargo_about::progress::write_log(record.args());
+        }))
         .apply()?;
     Ok(())
 }
@@ -110,12 +198,26 @@ fn real_main() -> anyhow::Result<()> {
         })
     });
 
-    setup_logger(args.log_level)?;
+    let color = args.color.resolve();
+    setup_logger(args.log_level, color.is_colored())?;
 
     match args.cmd {
-        Command::Generate(gen) => generate::cmd(gen, args.color),
+        Command::Generate(gen) => generate::cmd(gen, color),
         Command::Init(init) => init::cmd(init),
         Command::Clarify(clarify) => clarify::cmd(clarify),
+        Command::Schema(schema) => schema::cmd(schema),
+        #[cfg(feature = "sqlite")]
+        Command::Record(record) => record::cmd(record, color),
+        #[cfg(feature = "sqlite")]
+        Command::Trend(trend) => trend::cmd(trend),
+        Command::Baseline(baseline) => baseline::cmd(baseline, color),
+        Command::Licenses(licenses) => licenses::cmd(licenses, color),
+        Command::Merge(merge) => merge::cmd(merge),
+        Command::ValidateConfig(validate) => validate_config::cmd(validate, color),
+        Command::Dist(dist) => dist::cmd(dist, color),
+        Command::Fix(fix) => fix::cmd(fix, color),
+        Command::Cache(cache) => cache::cmd(cache),
+        Command::Changelog(changelog) => changelog::cmd(changelog, color),
     }
 }
 
@@ -0,0 +1,93 @@
+use anyhow::Context as _;
+use cargo_about::run::{self, merge::Merger};
+use krates::Utf8PathBuf as PathBuf;
+
+#[derive(clap::ValueEnum, Copy, Clone, Debug, Default)]
+pub enum MergeFormat {
+    /// Uses a handlebars template to transform the merged JSON to the output
+    #[default]
+    Handlebars,
+    /// Uses a jinja template to transform the merged JSON to the output
+    #[value(alias = "tera")]
+    Jinja,
+    /// Outputs the merged JSON
+    Json,
+}
+
+#[derive(clap::Parser, Debug)]
+pub struct Args {
+    /// The name of the template to use when rendering.
+    ///
+    /// If only passing a single template file to `templates` this is not used.
+    #[clap(short, long)]
+    name: Option<String>,
+    /// A file to write the merged output to, defaults to stdout
+    #[clap(short, long)]
+    output_file: Option<PathBuf>,
+    /// How the metadata supplied by crates, eg. descriptions and authors, is
+    /// sanitized before it is rendered
+    #[clap(long, value_enum, default_value = "auto")]
+    sanitize: run::sanitize::Sanitize,
+    /// The format of the output, defaults to `handlebars`.
+    #[clap(long, value_enum, default_value_t)]
+    format: MergeFormat,
+    /// The template(s) or template directory to render the merged output with.
+    ///
+    /// Required if `--format` is not `json`
+    #[clap(short, long)]
+    templates: Option<PathBuf>,
+    /// The JSON outputs of `generate --format json` to merge
+    #[clap(required = true)]
+    inputs: Vec<PathBuf>,
+}
+
+pub fn cmd(args: Args) -> anyhow::Result<()> {
+    let engine = match args.format {
+        MergeFormat::Handlebars => Some(run::TemplateEngine::Handlebars),
+        MergeFormat::Jinja => Some(run::TemplateEngine::Jinja),
+        MergeFormat::Json => None,
+    };
+
+    // Load the templates before merging so mistakes are reported immediately
+    let templates = match (engine, &args.templates) {
+        (Some(engine), Some(template_path)) => {
+            Some(run::Templates::load(template_path, args.name, engine)?.sanitize(args.sanitize))
+        }
+        (Some(_), None) => {
+            anyhow::bail!("template(s) must be specified unless using json output format")
+        }
+        (None, _) => None,
+    };
+
+    if let Some(templates) = &templates {
+        templates.check(None)?;
+    }
+
+    let mut merger = Merger::default();
+    for path in &args.inputs {
+        let contents =
+            std::fs::read_to_string(path).with_context(|| format!("failed to read '{path}'"))?;
+        let output = serde_json::from_str(&contents)
+            .with_context(|| format!("failed to deserialize '{path}'"))?;
+        merger
+            .add(output)
+            .with_context(|| format!("failed to merge '{path}'"))?;
+    }
+
+    let merged = merger.finish();
+
+    let output = match &templates {
+        Some(templates) => templates.render_value(merged)?,
+        None => serde_json::to_string(&merged)?,
+    };
+
+    if let Some(path) = args.output_file.filter(|path| path != "-") {
+        std::fs::write(&path, output)
+            .with_context(|| format!("output file {path} could not be written"))?;
+    } else {
+        anyhow::ensure!(!cargo_about::is_powershell_parent(), "cargo-about should not redirect its output in powershell, please use the -o, --output-file option to redirect to a file to avoid powershell encoding issues");
+        println!("{output}");
+    }
+
+    Ok(())
+}
@@ -0,0 +1,36 @@
+use crate::generate::PipelineArgs;
+use anyhow::Context as _;
+use cargo_about::run::history;
+use krates::Utf8PathBuf as PathBuf;
+
+#[derive(clap::Parser, Debug)]
+pub struct Args {
+    #[clap(flatten)]
+    pipeline: PipelineArgs,
+    /// The path of the history database to append the summary of this run to,
+    /// created if it doesn't exist
+    #[clap(long)]
+    db: PathBuf,
+}
+
+pub fn cmd(args: Args, color: crate::Color) -> anyhow::Result<()> {
+    let pipeline = args.pipeline.pipeline(color)?;
+
+    let prepared = pipeline.prepare()?;
+    let input = pipeline.generate(&prepared)?;
+
+    let manifest_path = pipeline.manifest_path();
+    let git_rev = history::git_rev(manifest_path);
+
+    history::record(&input, manifest_path, git_rev.as_deref(), &args.db)
+        .with_context(|| format!("failed to record run to '{}'", args.db))?;
+
+    log::info!(
+        "recorded {} crates, {} licenses to '{}'",
+        input.crates.len(),
+        input.overview.len(),
+        args.db
+    );
+
+    Ok(())
+}
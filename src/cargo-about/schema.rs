@@ -0,0 +1,7 @@
+#[derive(clap::Parser, Debug)]
+pub struct Args {}
+
+pub fn cmd(_args: Args) -> anyhow::Result<()> {
+    println!("{}", cargo_about::run::SCHEMA);
+    Ok(())
+}
@@ -0,0 +1,77 @@
+use cargo_about::run::history;
+use krates::Utf8PathBuf as PathBuf;
+use std::collections::BTreeSet;
+
+#[derive(clap::Parser, Debug)]
+pub struct Args {
+    /// The path of the history database written by `record`
+    #[clap(long)]
+    db: PathBuf,
+    /// Only shows the most recent N records
+    #[clap(long)]
+    limit: Option<usize>,
+}
+
+/// Formats the change from the previous value, if there is one
+fn delta(previous: Option<usize>, current: usize) -> String {
+    match previous {
+        Some(previous) if previous != current => {
+            format!(" ({:+})", current as i64 - previous as i64)
+        }
+        _ => String::new(),
+    }
+}
+
+pub fn cmd(args: Args) -> anyhow::Result<()> {
+    let records = history::load(&args.db)?;
+
+    if records.is_empty() {
+        println!("no runs have been recorded in '{}'", args.db);
+        return Ok(());
+    }
+
+    // Keep the record before the first shown one, so the deltas are still
+    // relative to the previous run
+    let skip = args
+        .limit
+        .map_or(0, |limit| records.len().saturating_sub(limit));
+
+    let mut previous = skip.checked_sub(1).map(|i| &records[i]);
+
+    for record in &records[skip..] {
+        println!(
+            "{} {} {} crates{}, {} licenses{}, {} failures{}",
+            record.timestamp,
+            record
+                .git_rev
+                .as_deref()
+                .map_or("<no rev>", |rev| &rev[..rev.len().min(10)]),
+            record.crates,
+            delta(previous.map(|p| p.crates), record.crates),
+            record.licenses.len(),
+            delta(previous.map(|p| p.licenses.len()), record.licenses.len()),
+            record.failures,
+            delta(previous.map(|p| p.failures), record.failures),
+        );
+
+        // Only show the licenses that changed, unless this is the first record
+        let ids: BTreeSet<_> = record
+            .licenses
+            .keys()
+            .chain(previous.iter().flat_map(|p| p.licenses.keys()))
+            .collect();
+
+        for id in ids {
+            let before = previous.map(|p| p.licenses.get(id).copied().unwrap_or_default());
+            let after = record.licenses.get(id).copied().unwrap_or_default();
+
+            if before != Some(after) {
+                println!("  {after} {id}{}", delta(before, after));
+            }
+        }
+
+        previous = Some(record);
+    }
+
+    Ok(())
+}
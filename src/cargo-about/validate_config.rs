@@ -0,0 +1,20 @@
+use crate::generate::PipelineArgs;
+
+#[derive(clap::Parser, Debug)]
+pub struct Args {
+    #[clap(flatten)]
+    pipeline: PipelineArgs,
+}
+
+pub fn cmd(args: Args, color: crate::Color) -> anyhow::Result<()> {
+    let pipeline = args.pipeline.pipeline(color)?;
+    let prepared = pipeline.prepare()?;
+
+    pipeline.validate_config(&prepared)?;
+    log::info!(
+        "configuration is valid for {} crates",
+        prepared.krates.len()
+    );
+
+    Ok(())
+}
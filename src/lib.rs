@@ -4,6 +4,8 @@ use krates::cm;
 use std::{cmp, fmt};
 
 pub mod licenses;
+pub mod progress;
+pub mod run;
 
 pub struct Krate(pub cm::Package);
 
@@ -24,7 +26,11 @@ impl Krate {
                 }
             }
         } else {
-            log::warn!("crate '{self}' doesn't have a license field");
+            // Crates can specify a license file instead, which is scanned when
+            // gathering license files
+            if self.0.license_file.is_none() {
+                log::warn!("crate '{self}' doesn't have a license field");
+            }
             licenses::LicenseInfo::Unknown
         }
     }
@@ -105,9 +111,85 @@ pub fn get_all_crates(
     cfg: &licenses::config::Config,
     target_overrdes: &[String],
 ) -> anyhow::Result<Krates> {
+    let mut graphs = get_crates_by_targets(
+        cargo_toml,
+        no_default_features,
+        all_features,
+        features,
+        workspace,
+        &[],
+        &[],
+        lock_opts,
+        None,
+        cfg,
+        &[target_overrdes],
+    )?;
+
+    Ok(graphs.remove(0))
+}
+
+/// Gathers a crate graph for each of the sets of targets, the same as
+/// [`get_all_crates`], but only runs `cargo metadata` once for all of them.
+///
+/// The graph can also be restricted to the specified workspace `packages`, or
+/// the packages of the specified `bins`, and crates.io can be replaced with a
+/// `registry_dir` of unpacked crate sources
+#[allow(clippy::too_many_arguments)]
+pub fn get_crates_by_targets(
+    cargo_toml: &krates::Utf8Path,
+    no_default_features: bool,
+    all_features: bool,
+    features: Vec<String>,
+    workspace: bool,
+    packages: &[String],
+    bins: &[String],
+    lock_opts: krates::LockOptions,
+    registry_dir: Option<&krates::Utf8Path>,
+    cfg: &licenses::config::Config,
+    target_sets: &[&[String]],
+) -> anyhow::Result<Vec<Krates>> {
+    let md = crates_metadata(
+        cargo_toml,
+        no_default_features,
+        all_features,
+        features,
+        lock_opts,
+        registry_dir,
+    )?;
+
+    graphs_from_metadata(&md, workspace, packages, bins, cfg, target_sets)
+}
+
+/// Runs `cargo metadata` for the workspace, which the crate graph of any
+/// target can then be built from, see [`graphs_from_metadata`]
+pub(crate) fn crates_metadata(
+    cargo_toml: &krates::Utf8Path,
+    no_default_features: bool,
+    all_features: bool,
+    features: Vec<String>,
+    lock_opts: krates::LockOptions,
+    registry_dir: Option<&krates::Utf8Path>,
+) -> anyhow::Result<cm::Metadata> {
     let mut mdc = krates::Cmd::new();
     mdc.manifest_path(cargo_toml);
-    mdc.lock_opts(lock_opts);
+
+    if let Some(registry_dir) = registry_dir {
+        // Replaces crates.io with a directory source, so crates are resolved
+        // from the unpacked sources in the directory without network access
+        let directory = toml::Value::String(registry_dir.to_string());
+        mdc.other_options([
+            "--config".to_owned(),
+            "source.crates-io.replace-with=\"cargo-about-registry-dir\"".to_owned(),
+            "--config".to_owned(),
+            format!("source.cargo-about-registry-dir.directory={directory}"),
+        ]);
+        mdc.lock_opts(krates::LockOptions {
+            offline: true,
+            ..lock_opts
+        });
+    } else {
+        mdc.lock_opts(lock_opts);
+    }
 
     // The metadata command builder is weird and only allows you to specify
     // one of these, but really you might need to do multiple of them
@@ -121,37 +203,47 @@ pub fn get_all_crates(
 
     mdc.features(features);
 
-    let mut builder = krates::Builder::new();
-
-    if workspace {
-        builder.workspace(true);
-    }
-
-    if cfg.ignore_build_dependencies {
-        builder.ignore_kind(krates::DepKind::Build, krates::Scope::All);
-    }
+    Ok(cm::MetadataCommand::from(mdc).exec()?)
+}
 
-    if cfg.ignore_dev_dependencies {
-        builder.ignore_kind(krates::DepKind::Dev, krates::Scope::All);
-    }
+/// Builds a crate graph for each of the sets of targets from the metadata of
+/// the workspace
+pub(crate) fn graphs_from_metadata(
+    md: &cm::Metadata,
+    workspace: bool,
+    packages: &[String],
+    bins: &[String],
+    cfg: &licenses::config::Config,
+    target_sets: &[&[String]],
+) -> anyhow::Result<Vec<Krates>> {
+    // krates can only restrict the workspace roots by manifest path, so the
+    // package and binary names are mapped to their manifests
+    let mut manifests = Vec::with_capacity(packages.len() + bins.len());
+    for name in packages {
+        let Some(pkg) = md
+            .workspace_packages()
+            .into_iter()
+            .find(|pkg| pkg.name == *name)
+        else {
+            anyhow::bail!("package '{name}' is not a member of the workspace");
+        };
 
-    if cfg.ignore_transitive_dependencies {
-        builder.ignore_kind(krates::DepKind::Normal, krates::Scope::NonWorkspace);
-        builder.ignore_kind(krates::DepKind::Dev, krates::Scope::NonWorkspace);
-        builder.ignore_kind(krates::DepKind::Build, krates::Scope::NonWorkspace);
+        manifests.push(pkg.manifest_path.clone());
     }
 
-    if target_overrdes.is_empty() {
-        builder.include_targets(cfg.targets.iter().map(|triple| (triple.as_str(), vec![])));
-    } else {
-        builder.include_targets(
-            target_overrdes
+    for bin in bins {
+        let Some(pkg) = md.workspace_packages().into_iter().find(|pkg| {
+            pkg.targets
                 .iter()
-                .map(|triple| (triple.as_str(), vec![])),
-        );
+                .any(|target| target.name == *bin && target.is_bin())
+        }) else {
+            anyhow::bail!("no binary target named '{bin}' in the workspace");
+        };
+
+        manifests.push(pkg.manifest_path.clone());
     }
 
-    let graph = builder.build(mdc, |filtered: cm::Package| {
+    let on_filter = |filtered: cm::Package| {
         if let Some(src) = filtered.source {
             if src.is_crates_io() {
                 log::debug!("filtered {} {}", filtered.name, filtered.version);
@@ -161,9 +253,50 @@ pub fn get_all_crates(
         } else {
             log::debug!("filtered crate {} {}", filtered.name, filtered.version);
         }
-    })?;
+    };
+
+    target_sets
+        .iter()
+        .map(|targets| {
+            let mut builder = krates::Builder::new();
 
-    Ok(graph)
+            if workspace {
+                builder.workspace(true);
+            }
+
+            if cfg.ignore_build_dependencies {
+                builder.ignore_kind(krates::DepKind::Build, krates::Scope::All);
+            }
+
+            if cfg.ignore_dev_dependencies {
+                builder.ignore_kind(krates::DepKind::Dev, krates::Scope::All);
+            }
+
+            if cfg.ignore_transitive_dependencies {
+                builder.ignore_kind(krates::DepKind::Normal, krates::Scope::NonWorkspace);
+                builder.ignore_kind(krates::DepKind::Dev, krates::Scope::NonWorkspace);
+                builder.ignore_kind(krates::DepKind::Build, krates::Scope::NonWorkspace);
+            }
+
+            if targets.is_empty() {
+                builder.include_targets(cfg.targets.iter().map(|triple| (triple.as_str(), vec![])));
+            } else {
+                builder.include_targets(targets.iter().map(|triple| (triple.as_str(), vec![])));
+            }
+
+            if !manifests.is_empty() {
+                // Dev dependencies are never linked into a binary
+                if !bins.is_empty() {
+                    builder.ignore_kind(krates::DepKind::Dev, krates::Scope::All);
+                }
+
+                builder.workspace(true);
+                builder.include_workspace_crates(manifests.clone());
+            }
+
+            Ok(builder.build_with_metadata(md.clone(), on_filter)?)
+        })
+        .collect()
 }
 
 #[inline]
@@ -179,6 +312,100 @@ pub fn to_hex(bytes: &[u8]) -> String {
     s
 }
 
+/// The algorithm used to calculate the checksum of a clarified file
+#[derive(
+    clap::ValueEnum,
+    serde::Serialize,
+    serde::Deserialize,
+    Copy,
+    Clone,
+    Debug,
+    Default,
+    PartialEq,
+    Eq,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum ChecksumAlgorithm {
+    /// SHA-256, checksums without a prefix use this algorithm
+    #[default]
+    Sha256,
+    /// SHA-512, prefixed with `sha512:`
+    Sha512,
+    /// BLAKE3, prefixed with `blake3:`
+    Blake3,
+}
+
+impl ChecksumAlgorithm {
+    /// The prefix of checksums calculated with the algorithm
+    pub fn prefix(self) -> &'static str {
+        match self {
+            Self::Sha256 => "sha256:",
+            Self::Sha512 => "sha512:",
+            Self::Blake3 => "blake3:",
+        }
+    }
+
+    /// Calculates the checksum of the buffer, in hex, prefixed with the
+    /// algorithm, except for SHA-256, which is left bare for compatibility
+    pub fn checksum(self, buffer: &str) -> String {
+        match self {
+            Self::Sha256 => {
+                to_hex(ring::digest::digest(&ring::digest::SHA256, buffer.as_bytes()).as_ref())
+            }
+            Self::Sha512 => format!(
+                "{}{}",
+                self.prefix(),
+                to_hex(ring::digest::digest(&ring::digest::SHA512, buffer.as_bytes()).as_ref())
+            ),
+            Self::Blake3 => format!(
+                "{}{}",
+                self.prefix(),
+                to_hex(blake3::hash(buffer.as_bytes()).as_bytes())
+            ),
+        }
+    }
+}
+
+/// Validates the checksum of the buffer, which is either a bare SHA-256
+/// checksum, or one prefixed with its [`ChecksumAlgorithm`], eg. `blake3:<hex>`
+pub fn validate_checksum(buffer: &str, expected: &str) -> anyhow::Result<()> {
+    let (algorithm, hex) = [
+        ChecksumAlgorithm::Sha256,
+        ChecksumAlgorithm::Sha512,
+        ChecksumAlgorithm::Blake3,
+    ]
+    .into_iter()
+    .find_map(|algo| expected.strip_prefix(algo.prefix()).map(|hex| (algo, hex)))
+    .unwrap_or((ChecksumAlgorithm::Sha256, expected));
+
+    if algorithm == ChecksumAlgorithm::Sha256 {
+        return validate_sha256(buffer, hex);
+    }
+
+    let expected_len = if algorithm == ChecksumAlgorithm::Sha512 {
+        128
+    } else {
+        64
+    };
+    anyhow::ensure!(
+        hex.len() == expected_len,
+        "checksum '{expected}' length is {} instead of expected {expected_len}",
+        hex.len()
+    );
+    anyhow::ensure!(
+        hex.bytes().all(|b| b.is_ascii_hexdigit()),
+        "invalid byte in checksum '{expected}'"
+    );
+
+    let actual = algorithm.checksum(buffer);
+    anyhow::ensure!(
+        actual[algorithm.prefix().len()..].eq_ignore_ascii_case(hex),
+        "checksum mismatch, expected '{expected}'"
+    );
+
+    Ok(())
+}
+
 pub fn validate_sha256(buffer: &str, expected: &str) -> anyhow::Result<()> {
     anyhow::ensure!(
         expected.len() == 64,
@@ -246,7 +473,7 @@ pub fn is_powershell_parent() -> bool {
         let Some(proc) = cmd
             .split('\0')
             .next()
-            .and_then(|path| path.split('/').last())
+            .and_then(|path| path.split('/').next_back())
         else {
             break;
         };
@@ -397,4 +624,24 @@ mod test {
     fn is_powershell_false() {
         assert!(!super::is_powershell_parent());
     }
+
+    #[test]
+    fn validates_prefixed_checksums() {
+        use super::{validate_checksum, ChecksumAlgorithm};
+
+        let text = "Permission is hereby granted, free of charge";
+
+        for algorithm in [
+            ChecksumAlgorithm::Sha256,
+            ChecksumAlgorithm::Sha512,
+            ChecksumAlgorithm::Blake3,
+        ] {
+            let checksum = algorithm.checksum(text);
+            validate_checksum(text, &checksum).unwrap();
+            validate_checksum(&text[1..], &checksum).unwrap_err();
+        }
+
+        assert!(!ChecksumAlgorithm::Sha256.checksum(text).contains(':'));
+        validate_checksum(text, "blake3:1234").unwrap_err();
+    }
 }
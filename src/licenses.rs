@@ -1,15 +1,20 @@
+mod archive;
+mod cache_dir;
 pub mod config;
+mod external;
 pub mod fetch;
+pub mod http_cache;
+pub mod obligations;
 pub mod resolution;
 mod scan;
-mod workarounds;
+pub(crate) mod workarounds;
 
 use crate::{Krate, Krates};
 use anyhow::Context as _;
-use krates::{KrateMatch, Utf8PathBuf as PathBuf};
+use krates::{KrateMatch, Utf8Path as Path, Utf8PathBuf as PathBuf};
 use rayon::prelude::*;
 pub use resolution::Resolved;
-use std::{cmp, fmt, sync::Arc};
+use std::{cmp, fmt, sync::Arc, time::Duration};
 
 const LICENSE_CACHE: &[u8] = include_bytes!("../spdx_cache.bin.zstd");
 
@@ -20,12 +25,26 @@ pub fn store_from_cache() -> anyhow::Result<LicenseStore> {
     askalono::Store::from_cache(LICENSE_CACHE).context("failed to load license store")
 }
 
-#[derive(Debug)]
+/// The date the embedded license store was generated, as the store itself
+/// doesn't carry any version information. This must be updated whenever
+/// `spdx_cache.bin.zstd` is regenerated
+pub const STORE_DATE: &str = "2024-11-18";
+
+/// A digest of the embedded license store, which changes whenever the store is
+/// regenerated, even if [`STORE_DATE`] wasn't updated
+pub fn store_digest() -> String {
+    use std::hash::Hasher;
+    let mut hasher = twox_hash::XxHash64::default();
+    hasher.write(LICENSE_CACHE);
+    format!("{:016x}", hasher.finish())
+}
+
+#[derive(Clone, Debug)]
 #[allow(clippy::large_enum_variant)]
 pub enum LicenseInfo {
     Expr(spdx::Expression),
     Unknown,
-    Ignore,
+    Ignore(IgnoreReason),
 }
 
 impl fmt::Display for LicenseInfo {
@@ -33,12 +52,46 @@ impl fmt::Display for LicenseInfo {
         match self {
             LicenseInfo::Expr(expr) => write!(f, "{expr}"),
             LicenseInfo::Unknown => write!(f, "Unknown"),
-            LicenseInfo::Ignore => write!(f, "Ignore"),
+            LicenseInfo::Ignore(_) => write!(f, "Ignore"),
         }
     }
 }
 
+/// The reason a crate was ignored, and its license not gathered
+#[derive(Copy, Clone, Debug, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum IgnoreReason {
+    /// The crate is not published, or is only published to private registries
+    Unpublished,
+    /// The crate is a workspace member or path dependency, and
+    /// `private.path-dependencies` is enabled
+    PathDependency,
+    /// The crate is from a git repository, and `private.git-dependencies` is
+    /// enabled
+    GitDependency,
+    /// The crate's name matches one of the `private.names` patterns
+    PrivateName,
+    /// The crate is in one of the `private.directories`
+    PrivateDirectory,
+    /// The crate's name doesn't match any of the `only` patterns
+    NotOnly,
+}
+
+impl fmt::Display for IgnoreReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Unpublished => "unpublished",
+            Self::PathDependency => "path-dependency",
+            Self::GitDependency => "git-dependency",
+            Self::PrivateName => "private-name",
+            Self::PrivateDirectory => "private-directory",
+            Self::NotOnly => "not-only",
+        })
+    }
+}
+
 /// The contents of a file with license info in it
+#[derive(Clone)]
 pub enum LicenseFileKind {
     /// The license file is the canonical text of the license
     Text(String),
@@ -48,8 +101,13 @@ pub enum LicenseFileKind {
     /// The file just has a license header, and presumably
     /// also contains other text in it (like, you know, code)
     Header,
+    /// The file is a NOTICE file, which isn't a license itself, but whose
+    /// contents are required to be reproduced by the license(s) it applies
+    /// to, eg. Apache-2.0
+    Notice(String),
 }
 
+#[derive(Clone)]
 pub struct LicenseFile {
     /// The SPDX requirement expression detected for the file
     pub license_expr: spdx::Expression,
@@ -91,10 +149,136 @@ impl PartialEq for LicenseFile {
 
 impl Eq for LicenseFile {}
 
+/// The overall outcome of license detection for a single crate
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Detection {
+    /// License information was gathered without any problems
+    #[default]
+    Found,
+    /// License information was gathered, but one or more files could not be
+    /// scanned, see [`KrateLicense::notes`] for the details
+    Partial,
+    /// License information could not be gathered for the crate
+    Failed,
+    /// The crate's sources were scanned without any problems, but they don't
+    /// contain any license files
+    #[serde(rename = "not-found")]
+    NotFound,
+    /// The crate's sources were not scanned, as the license store could not
+    /// be loaded, so only its declared license expression is known
+    Declared,
+}
+
+impl fmt::Display for Detection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Found => "found",
+            Self::Partial => "partial",
+            Self::Failed => "failed",
+            Self::NotFound => "not-found",
+            Self::Declared => "declared",
+        })
+    }
+}
+
+/// Whether the licenses of the texts found in a crate agree with the license
+/// declared in its manifest, which is the license crates.io lists for it
+#[derive(Copy, Clone, Debug, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RegistryCheck {
+    /// Every license text found in the crate is declared
+    Agrees,
+    /// One or more license texts found in the crate are for licenses that
+    /// aren't declared
+    Disagrees,
+    /// The crate isn't from crates.io, doesn't declare a license, or no
+    /// license texts were found in it
+    Unverified,
+}
+
+impl fmt::Display for RegistryCheck {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Agrees => "agrees",
+            Self::Disagrees => "disagrees",
+            Self::Unverified => "unverified",
+        })
+    }
+}
+
+#[derive(Clone)]
 pub struct KrateLicense<'krate> {
     pub krate: &'krate Krate,
     pub lic_info: LicenseInfo,
     pub license_files: Vec<LicenseFile>,
+    /// The overall state of license detection for the crate
+    pub detection: Detection,
+    /// Non-fatal problems encountered while gathering license information
+    /// for the crate, eg. individual files that could not be read
+    pub notes: Vec<String>,
+}
+
+impl<'krate> KrateLicense<'krate> {
+    /// Creates a crate license where detection succeeded without any notes
+    pub fn new(
+        krate: &'krate Krate,
+        lic_info: LicenseInfo,
+        license_files: Vec<LicenseFile>,
+    ) -> Self {
+        Self {
+            krate,
+            lic_info,
+            license_files,
+            detection: Detection::Found,
+            notes: Vec::new(),
+        }
+    }
+}
+
+impl KrateLicense<'_> {
+    /// Cross-checks the licenses of the texts found in the crate against the
+    /// license declared in its manifest, which is what crates.io lists for
+    /// it, as a lightweight signal that the packaged contents match the
+    /// registry's claim
+    pub fn registry_check(&self) -> RegistryCheck {
+        let declared = self
+            .krate
+            .license
+            .as_deref()
+            .filter(|_| {
+                self.krate
+                    .source
+                    .as_ref()
+                    .is_some_and(|src| src.is_crates_io())
+            })
+            .and_then(|license| spdx::Expression::parse_mode(license, spdx::ParseMode::LAX).ok());
+
+        let Some(declared) = declared else {
+            return RegistryCheck::Unverified;
+        };
+
+        let mut found = self
+            .license_files
+            .iter()
+            .filter(|lf| matches!(lf.kind, LicenseFileKind::Text(_)))
+            .flat_map(|lf| lf.license_expr.requirements())
+            .peekable();
+
+        if found.peek().is_none() {
+            return RegistryCheck::Unverified;
+        }
+
+        if found.all(|er| {
+            declared
+                .requirements()
+                .any(|der| der.req.license == er.req.license)
+        }) {
+            RegistryCheck::Agrees
+        } else {
+            RegistryCheck::Disagrees
+        }
+    }
 }
 
 impl Ord for KrateLicense<'_> {
@@ -121,17 +305,40 @@ impl PartialEq for KrateLicense<'_> {
 impl Eq for KrateLicense<'_> {}
 
 pub struct Gatherer {
-    store: Arc<LicenseStore>,
+    store: Option<Arc<LicenseStore>>,
     threshold: f32,
     max_depth: Option<usize>,
+    scan_timeout: Option<Duration>,
+    network_audit: Option<http_cache::NetworkAudit>,
+    progress: bool,
+    registry_dir: Option<PathBuf>,
 }
 
 impl Gatherer {
     pub fn with_store(store: Arc<LicenseStore>) -> Self {
         Self {
-            store,
+            store: Some(store),
             threshold: 0.8,
             max_depth: None,
+            scan_timeout: None,
+            network_audit: None,
+            progress: false,
+            registry_dir: None,
+        }
+    }
+
+    /// Creates a gatherer without a license store, meaning license files can't
+    /// be identified, so only license expressions from crate manifests,
+    /// clarifications, and workarounds are used
+    pub fn expression_only() -> Self {
+        Self {
+            store: None,
+            threshold: 0.8,
+            max_depth: None,
+            scan_timeout: None,
+            network_audit: None,
+            progress: false,
+            registry_dir: None,
         }
     }
 
@@ -145,6 +352,36 @@ impl Gatherer {
         self
     }
 
+    /// Sets the maximum amount of time spent scanning a single crate for
+    /// license files, after which only the crate's declared license expression
+    /// is used
+    pub fn with_scan_timeout(mut self, scan_timeout: Option<Duration>) -> Self {
+        self.scan_timeout = scan_timeout;
+        self
+    }
+
+    /// Records the HTTP requests that would be sent in the audit, rather than
+    /// sending them
+    pub fn with_network_audit(mut self, network_audit: Option<http_cache::NetworkAudit>) -> Self {
+        self.network_audit = network_audit;
+        self
+    }
+
+    /// Displays a progress bar of the crates that have been gathered, see
+    /// [`crate::progress`]
+    pub fn with_progress(mut self, progress: bool) -> Self {
+        self.progress = progress;
+        self
+    }
+
+    /// Retrieves the files of clarifications from the `.repos` directory of
+    /// the registry directory rather than remote git hosts, see
+    /// [`fetch::GitCache::with_registry_dir`]
+    pub fn with_registry_dir(mut self, registry_dir: Option<PathBuf>) -> Self {
+        self.registry_dir = registry_dir;
+        self
+    }
+
     pub fn gather<'krate>(
         self,
         krates: &'krate Krates,
@@ -152,44 +389,95 @@ impl Gatherer {
         client: Option<reqwest::blocking::Client>,
     ) -> Vec<KrateLicense<'krate>> {
         let mut licensed_krates = Vec::with_capacity(krates.len());
-
-        let threshold = self.threshold;
-        let min_threshold = threshold - 0.5;
-
-        let strategy = askalono::ScanStrategy::new(&self.store)
-            .mode(askalono::ScanMode::Elimination)
-            .confidence_threshold(if min_threshold < 0.1 {
-                0.1
-            } else {
-                min_threshold
-            })
-            .optimize(false)
-            .max_passes(1);
+        let progress = self
+            .progress
+            .then(|| crate::progress::Progress::new(krates.len()));
+
+        // The strategy is shared by every crate, so it must report matches
+        // for the lowest threshold configured for any of them
+        let lowest_threshold = cfg
+            .crates
+            .values()
+            .filter_map(|kc| kc.threshold)
+            .fold(self.threshold, |min, threshold| {
+                min.min(threshold.clamp(0.0, 1.0))
+            });
+        let min_threshold = lowest_threshold - 0.5;
+
+        let strategy = self.store.as_deref().map(|store| {
+            askalono::ScanStrategy::new(store)
+                .mode(askalono::ScanMode::Elimination)
+                .confidence_threshold(if min_threshold < 0.1 {
+                    0.1
+                } else {
+                    min_threshold
+                })
+                .optimize(false)
+                .max_passes(1)
+        });
 
         let is_offline = client.is_none();
-        let git_cache = fetch::GitCache::maybe_offline(client);
+        let cache_root = cfg.http_cache_root();
+        let git_cache = fetch::GitCache::maybe_offline(
+            client.map(|client| {
+                http_cache::HttpCache::new(client, cache_root.clone())
+                    .with_audit(self.network_audit.clone())
+            }),
+            // Files retrieved from the registry directory are never persisted,
+            // so that fixtures can't be affected by previous runs
+            if self.registry_dir.is_some() {
+                None
+            } else {
+                cfg.git_cache_root()
+            },
+        )
+        .with_registry_dir(self.registry_dir.clone())
+        .with_concurrency(cfg.fetch_concurrency.unwrap_or(fetch::DEFAULT_CONCURRENCY))
+        .with_retries(cfg.fetch_retries.unwrap_or(fetch::DEFAULT_RETRIES));
 
         // If we're ignoring crates that are private, just add them
         // to the list so all of the following gathers ignore them
         if cfg.private.ignore {
             for krate in krates.krates() {
-                if let Some(publish) = &krate.publish {
-                    if publish.is_empty()
-                        || publish
-                            .iter()
-                            .all(|reg| cfg.private.registries.contains(reg))
+                if let Some(reason) = cfg.private.private_reason(krate, krates.workspace_root()) {
+                    log::debug!("ignoring private crate '{krate}' ({reason})");
+                    licensed_krates.push(KrateLicense::new(
+                        krate,
+                        LicenseInfo::Ignore(reason),
+                        Vec::new(),
+                    ));
+                }
+            }
+
+            licensed_krates.sort();
+        }
+
+        // Likewise, if the user only wants a subset of crates, ignore all of
+        // the others so we don't waste time gathering their licenses
+        if !cfg.only.is_empty() {
+            for krate in krates.krates() {
+                if let Err(i) = binary_search(&licensed_krates, krate) {
+                    if !cfg
+                        .only
+                        .iter()
+                        .any(|pattern| config::matches_name(pattern, &krate.name))
                     {
-                        log::debug!("ignoring private crate '{krate}'");
-                        licensed_krates.push(KrateLicense {
-                            krate,
-                            lic_info: LicenseInfo::Ignore,
-                            license_files: Vec::new(),
-                        });
+                        licensed_krates.insert(
+                            i,
+                            KrateLicense::new(
+                                krate,
+                                LicenseInfo::Ignore(IgnoreReason::NotOnly),
+                                Vec::new(),
+                            ),
+                        );
                     }
                 }
             }
+        }
 
-            licensed_krates.sort();
+        if let Some(progress) = &progress {
+            progress.stage("clarifying");
+            progress.set_gathered(licensed_krates.len());
         }
 
         // Workarounds are built-in to cargo-about to deal with issues that certain
@@ -197,16 +485,22 @@ impl Gatherer {
         workarounds::apply_workarounds(krates, cfg, &git_cache, &mut licensed_krates);
 
         // Clarifications are user supplied and thus take precedence over any
-        // machine gathered data
-        self.gather_clarified(krates, cfg, &git_cache, &mut licensed_krates);
+        // machine gathered data, followed by the clarifications crates embed
+        // in their own manifests
+        let trusted = self.gather_clarified(krates, cfg, &git_cache, &mut licensed_krates);
 
         // Attempt to gather license information from clearly-defined.io so we
         // can get previously gathered license information + any possible
         // curations so that we only need to fallback to scanning local crate
         // sources if it's not already in clearly-defined
         if !is_offline && !cfg.no_clearly_defined {
-            match reqwest::blocking::ClientBuilder::new()
-                .timeout(std::time::Duration::from_secs(
+            if let Some(progress) = &progress {
+                progress.stage("fetching");
+                progress.set_gathered(licensed_krates.len());
+            }
+
+            match http_cache::client_builder(cfg.user_agent_suffix.as_deref())
+                .timeout(Duration::from_secs(
                     cfg.clearly_defined_timeout_secs.unwrap_or(30),
                 ))
                 .build()
@@ -215,8 +509,9 @@ impl Gatherer {
                     self.gather_clearly_defined(
                         krates,
                         cfg,
-                        client.into(),
-                        &strategy,
+                        &http_cache::HttpCache::new(client, cache_root)
+                            .with_audit(self.network_audit.clone()),
+                        strategy.as_ref(),
                         &mut licensed_krates,
                     );
                 }
@@ -226,13 +521,42 @@ impl Gatherer {
             }
         }
 
+        if let Some(progress) = &progress {
+            progress.stage("scanning");
+            progress.set_gathered(licensed_krates.len());
+        }
+
         // Finally, crawl the crate sources on disk to try and determine licenses
-        self.gather_file_system(krates, &strategy, &mut licensed_krates);
+        self.gather_file_system(
+            krates,
+            &cfg.crates,
+            &cfg.scan,
+            strategy.as_ref(),
+            is_offline
+                .then_some(cfg.license_overrides_dir.as_deref())
+                .flatten(),
+            progress.as_ref(),
+            &mut licensed_krates,
+        );
 
+        drop(progress);
         licensed_krates.sort();
+
+        apply_trusted_clarifications(trusted, &mut licensed_krates);
+
+        // Give the user's own resolver the final say on what the license for
+        // each crate actually is
+        if let Some(resolver) = &cfg.resolver {
+            external::apply(resolver, &mut licensed_krates);
+        }
+
         licensed_krates
     }
 
+    /// Applies the clarifications whose files match their checksums,
+    /// returning the clarifications that are trusted for a version
+    /// requirement instead, as they can only be applied once the crate has
+    /// been scanned, see [`apply_trusted_clarifications`]
     #[allow(clippy::unused_self)]
     fn gather_clarified<'k>(
         &self,
@@ -240,15 +564,53 @@ impl Gatherer {
         cfg: &config::Config,
         gc: &fetch::GitCache,
         licensed_krates: &mut Vec<KrateLicense<'k>>,
-    ) {
-        for (krate, clarification) in krates.krates().filter_map(|krate| {
-            cfg.crates
-                .get(&krate.name)
-                .and_then(|kc| kc.clarify.as_ref())
-                .map(|cl| (krate, cl))
-        }) {
+    ) -> Vec<(&'k Krate, spdx::Expression)> {
+        let configured = |krate: &Krate| cfg.krate_config(krate).and_then(|kc| kc.clarify.as_ref());
+
+        let embedded: Vec<_> = krates
+            .krates()
+            .filter(|krate| configured(krate).is_none())
+            .filter_map(|krate| embedded_clarification(krate).map(|cl| (krate, cl)))
+            .collect();
+
+        // Clarifications can require retrieving files from remote git hosts,
+        // so they are applied concurrently
+        let clarifications: Vec<_> = krates
+            .krates()
+            .filter_map(|krate| configured(krate).map(|cl| (krate, cl)))
+            .chain(embedded.iter().map(|(krate, cl)| (*krate, cl)))
+            .filter(|(krate, _)| binary_search(licensed_krates, krate).is_err())
+            .filter(|(krate, cl)| match &cl.version_req {
+                Some(req) if !req.matches(&krate.version) => {
+                    log::warn!(
+                        "clarification for crate '{krate}' does not apply, its version does not match '{req}'"
+                    );
+                    false
+                }
+                _ => true,
+            })
+            .collect();
+
+        let (trusted, clarifications): (Vec<_>, Vec<_>) =
+            clarifications.into_iter().partition(|(_, cl)| {
+                cl.version_req.is_some() && cl.files.is_empty() && cl.git.is_empty()
+            });
+        let trusted = trusted
+            .into_iter()
+            .map(|(krate, cl)| (krate, cl.license.clone()))
+            .collect();
+
+        let applied = gc.map_concurrent(clarifications, |(krate, clarification)| {
+            (
+                krate,
+                clarification,
+                apply_clarification(gc, krate, clarification),
+            )
+        });
+
+        for (krate, clarification, lic_files) in applied {
             if let Err(i) = binary_search(licensed_krates, krate) {
-                match apply_clarification(gc, krate, clarification) {
+                match lic_files {
                     Ok(lic_files) => {
                         log::debug!(
                             "applying clarification expression '{}' to crate {krate}",
@@ -256,11 +618,11 @@ impl Gatherer {
                         );
                         licensed_krates.insert(
                             i,
-                            KrateLicense {
+                            KrateLicense::new(
                                 krate,
-                                lic_info: LicenseInfo::Expr(clarification.license.clone()),
-                                license_files: lic_files,
-                            },
+                                LicenseInfo::Expr(clarification.license.clone()),
+                                lic_files,
+                            ),
                         );
                     }
                     Err(e) => {
@@ -269,14 +631,16 @@ impl Gatherer {
                 }
             }
         }
+
+        trusted
     }
 
     fn gather_clearly_defined<'k>(
         &self,
         krates: &'k Krates,
         cfg: &config::Config,
-        client: cd::client::Client,
-        strategy: &askalono::ScanStrategy<'_>,
+        client: &http_cache::HttpCache,
+        strategy: Option<&askalono::ScanStrategy<'_>>,
         licensed_krates: &mut Vec<KrateLicense<'k>>,
     ) {
         if cfg.no_clearly_defined {
@@ -291,11 +655,7 @@ impl Gatherer {
                 }
 
                 // Ignore local and git sources in favor of scanning those on the local disk
-                if krate
-                    .source
-                    .as_ref()
-                    .map_or(false, |src| src.is_crates_io())
-                {
+                if krate.source.as_ref().is_some_and(|src| src.is_crates_io()) {
                     Some(cd::Coordinate {
                         shape: cd::Shape::Crate,
                         provider: cd::Provider::CratesIo,
@@ -313,7 +673,7 @@ impl Gatherer {
         );
 
         let collected: Vec<_> = reqs.par_bridge().filter_map(|req| {
-            match client.execute::<cd::definitions::GetResponse>(req) {
+            match execute_cd_request(client, req) {
                 Ok(response) => {
                     Some(response.definitions.into_iter().filter_map(|def| {
                         if def.described.is_none() {
@@ -389,32 +749,39 @@ impl Gatherer {
                                             }
                                         };
 
-                                        Some(LicenseFile {
+                                        Some(vec![LicenseFile {
                                             license_expr,
                                             path,
                                             confidence,
                                             kind: license_text.map_or(LicenseFileKind::Header, LicenseFileKind::Text),
-                                        })
+                                        }])
                                     }
                                     (None, Some(license_text)) => {
+                                        let Some(strategy) = strategy else {
+                                            log::debug!("unable to identify license in '{path}' for crate '{krate}' without a license store");
+                                            return None;
+                                        };
+
                                         // For some reason, clearlydefined will correctly identify text as being a
                                         // license but won't give it an expression, so we have to figure out what it
                                         // is, but at least have high confidence that it will result in a match
-                                        scan::check_is_license_file(path.clone(), license_text, strategy, self.threshold)
-                                            .or_else(|| {
+                                        match scan::check_is_license_file(path.clone(), license_text, strategy, self.threshold) {
+                                            Ok(lfs) if !lfs.is_empty() => Some(lfs),
+                                            Ok(_) => {
                                                 log::warn!("clearlydefined detected license in '{path}' for crate '{krate}', but we failed to determine what its license was");
                                                 None
-                                            })
+                                            }
+                                            Err(note) => {
+                                                log::warn!("clearlydefined detected license in '{path}' for crate '{krate}', but we failed to determine what its license was: {note}");
+                                                None
+                                            }
+                                        }
                                     }
                                     _ => None,
                                 }
-                            }).collect();
+                            }).flatten().collect();
 
-                            KrateLicense {
-                                krate,
-                                lic_info: info,
-                                license_files,
-                            }
+                            KrateLicense::new(krate, info, license_files)
                         })
                     }).collect::<Vec<_>>())
                 }
@@ -431,14 +798,19 @@ impl Gatherer {
         licensed_krates.sort();
     }
 
+    #[allow(clippy::too_many_arguments)]
     fn gather_file_system<'k>(
         &self,
         krates: &'k Krates,
-        strategy: &askalono::ScanStrategy<'_>,
+        krate_cfgs: &std::collections::BTreeMap<String, config::KrateConfig>,
+        scan_cfg: &config::Scan,
+        strategy: Option<&askalono::ScanStrategy<'_>>,
+        overrides_dir: Option<&Path>,
+        progress: Option<&crate::progress::Progress>,
         licensed_krates: &mut Vec<KrateLicense<'k>>,
     ) {
-        let threshold = self.threshold;
         let max_depth = self.max_depth;
+        let scan_timeout = self.scan_timeout;
 
         let mut gathered: Vec<_> = krates
             .krates()
@@ -449,30 +821,168 @@ impl Gatherer {
                     return None;
                 }
 
-                let info = krate.get_license_expression();
+                if let Some(progress) = progress {
+                    progress.inc();
+                }
+
+                let mut info = krate.get_license_expression();
 
                 let root_path = krate.manifest_path.parent().unwrap();
 
-                let mut license_files =
-                    match scan::scan_files(root_path, strategy, threshold, max_depth) {
-                        Ok(files) => files,
-                        Err(err) => {
-                            log::error!(
-                                "unable to scan for license files for crate '{} - {}': {err}",
-                                krate.name,
-                                krate.version,
-                            );
-
-                            Vec::new()
+                let krate_cfg = config::find_krate_config(krate_cfgs, &krate.name, &krate.version);
+                let threshold = krate_cfg
+                    .and_then(|kc| kc.threshold)
+                    .map_or(self.threshold, |threshold| threshold.clamp(0.0, 1.0));
+                let deep_scan = krate_cfg.is_some_and(|kc| kc.deep_scan);
+
+                // Without a license store we can't identify any license files,
+                // so the only information we have is the crate's license expression
+                let Some(strategy) = strategy else {
+                    return Some(KrateLicense {
+                        detection: Detection::Declared,
+                        ..KrateLicense::new(krate, info, Vec::new())
+                    });
+                };
+
+                // If the crate doesn't have a license expression but specifies
+                // a license file, that file is scanned regardless of its name,
+                // as it won't necessarily be found when crawling the crate's
+                // files, and the expression is synthesized from it
+                let mut manifest_license_file = None;
+                if let (LicenseInfo::Unknown, Some(license_file)) = (&info, &krate.license_file) {
+                    match scan::scan_license_file(root_path, license_file, strategy, threshold) {
+                        Ok(lfs) => {
+                            // A file containing multiple license texts requires
+                            // all of them to be followed
+                            let expr = lfs
+                                .iter()
+                                .map(|lf| lf.license_expr.to_string())
+                                .collect::<Vec<_>>()
+                                .join(" AND ");
+
+                            match spdx::Expression::parse(&expr) {
+                                Ok(expr) => {
+                                    info = LicenseInfo::Expr(expr);
+                                    manifest_license_file = Some(lfs);
+                                }
+                                Err(err) => {
+                                    log::warn!("crate '{krate}': failed to parse license expression '{expr}': {err}");
+                                }
+                            }
+                        }
+                        Err(note) => {
+                            log::warn!("crate '{krate}': {note}");
                         }
+                    }
+                }
+
+                let (mut license_files, notices, notes, mut detection) =
+                    match scan::scan_files(root_path, strategy, threshold, max_depth, deep_scan, scan_cfg, scan_timeout)
+                    {
+                        Ok(scanned) => {
+                            // Individual files that couldn't be scanned are only
+                            // a problem if they prevented us from finding any
+                            // license files at all
+                            let detection = if scanned.license_files.is_empty() {
+                                if scanned.notes.is_empty() {
+                                    Detection::NotFound
+                                } else {
+                                    Detection::Failed
+                                }
+                            } else if scanned.notes.is_empty() {
+                                Detection::Found
+                            } else {
+                                Detection::Partial
+                            };
+
+                            (
+                                scanned.license_files,
+                                scanned.notices,
+                                scanned.notes,
+                                detection,
+                            )
+                        }
+                        Err(err) => (
+                            Vec::new(),
+                            Vec::new(),
+                            vec![format!("unable to scan for license files: {err:#}")],
+                            Detection::Failed,
+                        ),
                     };
 
+                match detection {
+                    Detection::Found | Detection::Declared => {}
+                    Detection::NotFound => {
+                        log::debug!("no license files were found for crate '{krate}'");
+                    }
+                    Detection::Partial => {
+                        for note in &notes {
+                            log::debug!("crate '{krate}': {note}");
+                        }
+                    }
+                    Detection::Failed => {
+                        log::error!(
+                            "failed to detect license files for crate '{krate}':\n  {}",
+                            notes.join("\n  ")
+                        );
+                    }
+                }
+
+                if let Some(lfs) = manifest_license_file.filter(|lfs| !lfs.is_empty()) {
+                    license_files.extend(lfs);
+                    if detection == Detection::NotFound {
+                        detection = Detection::Found;
+                    }
+                }
+
+                // When running offline, the license files of crates that
+                // don't contain any can't be retrieved from elsewhere, so
+                // they can be vendored in the overrides directory instead
+                let has_text = |lfs: &[LicenseFile]| {
+                    lfs.iter().any(|lf| {
+                        matches!(
+                            lf.kind,
+                            LicenseFileKind::Text(_) | LicenseFileKind::AddendumText(..)
+                        )
+                    })
+                };
+
+                if let Some(dir) = overrides_dir.filter(|_| !has_text(&license_files)) {
+                    let dir = dir.join(format!("{}-{}", krate.name, krate.version));
+
+                    if dir.is_dir() {
+                        match scan::scan_files(&dir, strategy, threshold, max_depth, false, scan_cfg, scan_timeout) {
+                            Ok(scanned) if has_text(&scanned.license_files) => {
+                                log::debug!("using license files from '{dir}' for crate '{krate}'");
+                                license_files.extend(scanned.license_files);
+                                detection = match detection {
+                                    Detection::Failed => Detection::Partial,
+                                    Detection::NotFound => Detection::Found,
+                                    detection => detection,
+                                };
+                            }
+                            Ok(_) => {
+                                log::warn!("no license files were found in '{dir}' for crate '{krate}'");
+                            }
+                            Err(err) => {
+                                log::warn!("unable to scan '{dir}' for license files for crate '{krate}': {err:#}");
+                            }
+                        }
+                    }
+                }
+
                 // Condense each license down to the best candidate if
                 // multiple are found
                 license_files.sort();
 
                 let mut expr = None;
                 license_files.retain(|lf| {
+                    // Each addendum text applies to its own subdirectory, so
+                    // they are never condensed
+                    if matches!(lf.kind, LicenseFileKind::AddendumText(..)) {
+                        return true;
+                    }
+
                     if let Some(cur) = &expr {
                         if *cur != lf.license_expr {
                             expr = Some(lf.license_expr.clone());
@@ -486,10 +996,33 @@ impl Gatherer {
                     }
                 });
 
+                // Notices apply to the crate's license(s), so if the crate
+                // doesn't declare its license we use the ones we detected
+                let notice_exprs: Vec<_> = match &info {
+                    LicenseInfo::Expr(expr) => vec![expr.clone()],
+                    _ => license_files
+                        .iter()
+                        .map(|lf| lf.license_expr.clone())
+                        .collect(),
+                };
+
+                for (path, text) in notices {
+                    for license_expr in &notice_exprs {
+                        license_files.push(LicenseFile {
+                            license_expr: license_expr.clone(),
+                            path: path.clone(),
+                            confidence: 1.0,
+                            kind: LicenseFileKind::Notice(text.clone()),
+                        });
+                    }
+                }
+
                 Some(KrateLicense {
                     krate,
                     lic_info: info,
                     license_files,
+                    detection,
+                    notes,
                 })
             })
             .collect();
@@ -498,6 +1031,42 @@ impl Gatherer {
     }
 }
 
+/// Executes a clearlydefined request, revalidating any cached response
+fn execute_cd_request(
+    client: &http_cache::HttpCache,
+    req: http::Request<bytes::Bytes>,
+) -> anyhow::Result<cd::definitions::GetResponse> {
+    let (parts, body) = req.into_parts();
+
+    let request = client
+        .client()
+        .request(parts.method, parts.uri.to_string())
+        .headers(parts.headers)
+        .body(body.to_vec())
+        .build()?;
+
+    let body = client.send(request)?;
+
+    let response = http::Response::builder()
+        .status(http::StatusCode::OK)
+        .body(bytes::Bytes::from(body))?;
+    Ok(cd::definitions::GetResponse::try_from(response)?)
+}
+
+/// Parses the clarification a crate embeds in its own manifest under
+/// `[package.metadata.about]`, if any
+fn embedded_clarification(krate: &Krate) -> Option<config::Clarification> {
+    let metadata = krate.metadata.get("about")?;
+
+    match serde_json::from_value(metadata.clone()) {
+        Ok(clarification) => Some(clarification),
+        Err(err) => {
+            log::warn!("failed to parse `package.metadata.about` for crate '{krate}': {err}");
+            None
+        }
+    }
+}
+
 pub(crate) fn apply_clarification(
     git_cache: &fetch::GitCache,
     krate: &crate::Krate,
@@ -505,7 +1074,7 @@ pub(crate) fn apply_clarification(
 ) -> anyhow::Result<Vec<LicenseFile>> {
     anyhow::ensure!(
         !clarification.files.is_empty() || !clarification.git.is_empty(),
-        "clarification for crate '{}' does not specify any valid LICENSE files to checksum",
+        "clarification for crate '{}' does not specify any valid LICENSE files to checksum, or a `version-req` to trust it for",
         krate.id
     );
 
@@ -519,26 +1088,62 @@ pub(crate) fn apply_clarification(
             "clarification file '{license_path}' is empty"
         );
 
+        // Start and end text that appear more than once can select the wrong
+        // region of the file, which is only known to be the right one if it
+        // matches the checksum
+        let mut ambiguous = Vec::new();
+
         let start = match &cf.start {
-            Some(starts) => contents.find(starts).with_context(|| {
-                format!("failed to find subsection starting with '{starts}' in {license_path}")
-            })?,
+            Some(starts) => {
+                let start = contents.find(starts).with_context(|| {
+                    format!("failed to find subsection starting with '{starts}' in {license_path}")
+                })?;
+
+                if contents.rfind(starts) != Some(start) {
+                    ambiguous.push(format!(
+                        "subsection start '{starts}' appears more than once in {license_path}"
+                    ));
+                }
+
+                start
+            }
             None => 0,
         };
 
         let end = match &cf.end {
             Some(ends) => {
-                contents[start..].find(ends).with_context(|| {
+                let rest = &contents[start..];
+                let end = rest.find(ends).with_context(|| {
                     format!("failed to find subsection ending with '{ends}' in {license_path}")
-                })? + start
-                    + ends.len()
+                })?;
+
+                if rest.rfind(ends) != Some(end) {
+                    ambiguous.push(format!(
+                        "subsection end '{ends}' appears more than once after the start in {license_path}"
+                    ));
+                }
+
+                start + end + ends.len()
             }
             None => contents.len(),
         };
 
         let text = &contents[start..end];
 
-        crate::validate_sha256(text, &cf.checksum)?;
+        if let Err(err) = crate::validate_checksum(text, &cf.checksum) {
+            if ambiguous.is_empty() {
+                return Err(err);
+            }
+
+            anyhow::bail!(
+                "{}, so the first appearance may have selected the wrong region: {err:#}, extend them until they are unique, eg. by running `cargo about clarify` again",
+                ambiguous.join(", and ")
+            );
+        }
+
+        for ambiguity in ambiguous {
+            log::warn!("{ambiguity}, extend it until it is unique, eg. by running `cargo about clarify` again");
+        }
 
         let text = text.to_owned();
 
@@ -581,6 +1186,61 @@ pub(crate) fn apply_clarification(
     Ok(lic_files)
 }
 
+/// Applies the clarifications that are trusted for a version requirement
+/// rather than verified with checksums, as long as every license found when
+/// gathering the crate is compatible with, ie. part of, the clarified
+/// expression. Otherwise the detected licenses are used, as the crate's
+/// licensing has presumably changed since it was clarified.
+fn apply_trusted_clarifications(
+    trusted: Vec<(&Krate, spdx::Expression)>,
+    licensed_krates: &mut [KrateLicense<'_>],
+) {
+    for (krate, license) in trusted {
+        let Ok((i, _)) = binary_search(licensed_krates, krate) else {
+            continue;
+        };
+        let kl = &mut licensed_krates[i];
+
+        let detected: Vec<_> = kl
+            .license_files
+            .iter()
+            .filter(|lf| !matches!(lf.kind, LicenseFileKind::Notice(_)))
+            .collect();
+
+        let has_text = detected.iter().any(|lf| {
+            matches!(
+                lf.kind,
+                LicenseFileKind::Text(_) | LicenseFileKind::AddendumText(..)
+            )
+        });
+        let divergent: Vec<_> = detected
+            .iter()
+            .filter(|lf| {
+                !lf.license_expr.requirements().all(|ereq| {
+                    license
+                        .requirements()
+                        .any(|clarified| clarified.req == ereq.req)
+                })
+            })
+            .map(|lf| format!("'{}' in '{}'", lf.license_expr, lf.path))
+            .collect();
+
+        if !has_text {
+            log::warn!(
+                "clarification for crate '{krate}' does not apply, no license files were found to compare against '{license}'"
+            );
+        } else if !divergent.is_empty() {
+            log::warn!(
+                "clarification for crate '{krate}' does not apply, the detected licenses diverge from '{license}': {}",
+                divergent.join(", ")
+            );
+        } else {
+            log::debug!("applying trusted clarification expression '{license}' to crate {krate}");
+            kl.lic_info = LicenseInfo::Expr(license);
+        }
+    }
+}
+
 #[inline]
 pub fn binary_search<'krate>(
     kl: &'krate [KrateLicense<'krate>],
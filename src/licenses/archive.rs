@@ -0,0 +1,465 @@
+//! Reads the entries of small tar and zip archives bundled in crates, eg. test
+//! data or vendored sources, which would otherwise hide the license files in
+//! them from the scanner entirely
+
+use krates::Utf8Path as Path;
+use std::io::Read as _;
+
+/// Archives larger than this are not read
+pub(crate) const MAX_ARCHIVE_SIZE: u64 = 10 * 1024 * 1024;
+/// Entries larger than this, once decompressed, are not read
+const MAX_ENTRY_SIZE: u64 = 1024 * 1024;
+
+/// The extensions of the archives that can be read
+pub(crate) const EXTENSIONS: &[&str] = &["tar", "tar.gz", "tgz", "zip"];
+
+/// Checks if the file is an archive that can be read
+pub(crate) fn is_archive(path: &Path) -> bool {
+    path.file_name().is_some_and(|name| {
+        let name = name.to_ascii_lowercase();
+        EXTENSIONS
+            .iter()
+            .any(|ext| name.ends_with(&format!(".{ext}")))
+    })
+}
+
+/// Reads the text of every file in the archive whose path matches the filter,
+/// along with its path in the archive. Entries that are too large or aren't
+/// valid utf-8 are skipped.
+pub(crate) fn entries(
+    path: &Path,
+    filter: impl Fn(&str) -> bool,
+) -> Result<Vec<(String, String)>, String> {
+    let data = std::fs::read(path).map_err(|e| format!("failed to read '{path}': {e}"))?;
+    let name = path.as_str().to_ascii_lowercase();
+
+    if name.ends_with(".zip") {
+        return zip_entries(&data, &filter).map_err(|e| format!("'{path}': {e}"));
+    }
+
+    if name.ends_with(".tar.gz") || name.ends_with(".tgz") {
+        let mut tar = Vec::new();
+        flate2::read::GzDecoder::new(data.as_slice())
+            .take(MAX_ARCHIVE_SIZE * 4)
+            .read_to_end(&mut tar)
+            .map_err(|e| format!("failed to decompress '{path}': {e}"))?;
+
+        return tar_entries(&tar, &filter).map_err(|e| format!("'{path}': {e}"));
+    }
+
+    tar_entries(&data, &filter).map_err(|e| format!("'{path}': {e}"))
+}
+
+fn tar_entries(
+    data: &[u8],
+    filter: &dyn Fn(&str) -> bool,
+) -> Result<Vec<(String, String)>, String> {
+    const BLOCK: usize = 512;
+
+    let mut entries = Vec::new();
+    let mut offset = 0;
+    // The name of the next entry, from a preceding GNU long name or pax
+    // extended header entry, used when the name doesn't fit in the header
+    let mut long_name = None;
+
+    while let Some(header) = data.get(offset..offset + BLOCK) {
+        // The archive ends with (at least) one empty block
+        if header.iter().all(|b| *b == 0) {
+            break;
+        }
+
+        let field = |start: usize, end: usize| {
+            let field = &header[start..end];
+            let len = field.iter().position(|b| *b == 0).unwrap_or(field.len());
+            &field[..len]
+        };
+
+        let size = std::str::from_utf8(field(124, 136))
+            .ok()
+            .and_then(|size| usize::from_str_radix(size.trim(), 8).ok())
+            .ok_or("invalid tar entry size")?;
+
+        let start = offset + BLOCK;
+        let contents = || data.get(start..start + size).ok_or("truncated tar archive");
+
+        match header[156] {
+            // GNU long name, the contents are the name of the next entry
+            b'L' => {
+                let name = contents()?;
+                let len = name.iter().position(|b| *b == 0).unwrap_or(name.len());
+                long_name = Some(String::from_utf8_lossy(&name[..len]).into_owned());
+            }
+            // pax extended header, with `<len> <key>=<value>\n` records
+            b'x' => {
+                let records = String::from_utf8_lossy(contents()?);
+                if let Some(path) = records
+                    .lines()
+                    .find_map(|record| record.split_once(' ')?.1.strip_prefix("path="))
+                {
+                    long_name = Some(path.to_owned());
+                }
+            }
+            kind => {
+                let name = long_name.take().unwrap_or_else(|| {
+                    let mut name = String::from_utf8_lossy(field(0, 100)).into_owned();
+                    if &header[257..262] == b"ustar" {
+                        let prefix = field(345, 500);
+                        if !prefix.is_empty() {
+                            name = format!("{}/{name}", String::from_utf8_lossy(prefix));
+                        }
+                    }
+                    name
+                });
+
+                let is_file = matches!(kind, b'0' | 0);
+
+                if is_file && size as u64 <= MAX_ENTRY_SIZE && filter(&name) {
+                    if let Ok(text) = std::str::from_utf8(contents()?) {
+                        entries.push((name, text.to_owned()));
+                    }
+                }
+            }
+        }
+
+        offset = start + size.div_ceil(BLOCK) * BLOCK;
+    }
+
+    Ok(entries)
+}
+
+fn zip_entries(
+    data: &[u8],
+    filter: &dyn Fn(&str) -> bool,
+) -> Result<Vec<(String, String)>, String> {
+    const EOCD: &[u8] = b"PK\x05\x06";
+    const ZIP64_EOCD: &[u8] = b"PK\x06\x06";
+    const ZIP64_LOCATOR: &[u8] = b"PK\x06\x07";
+    const CENTRAL_HEADER: &[u8] = b"PK\x01\x02";
+    /// The id of the extra field containing the zip64 sizes and offsets
+    const ZIP64_EXTRA: usize = 0x0001;
+
+    let u16_at = |pos: usize| {
+        data.get(pos..pos + 2)
+            .map(|b| u16::from_le_bytes([b[0], b[1]]) as usize)
+            .ok_or("truncated zip archive")
+    };
+    let u32_at = |pos: usize| {
+        data.get(pos..pos + 4)
+            .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]) as usize)
+            .ok_or("truncated zip archive")
+    };
+    let u64_at = |pos: usize| {
+        data.get(pos..pos + 8)
+            .and_then(|b| usize::try_from(u64::from_le_bytes(b.try_into().ok()?)).ok())
+            .ok_or("truncated zip archive")
+    };
+
+    // The end of central directory record is at the end of the archive,
+    // followed only by a comment of up to 64KiB
+    let eocd = (0..=data.len().saturating_sub(22))
+        .rev()
+        .take(u16::MAX as usize + 1)
+        .find(|pos| data[*pos..].starts_with(EOCD))
+        .ok_or("unable to find zip central directory")?;
+
+    let mut count = u16_at(eocd + 10)?;
+    let mut pos = u32_at(eocd + 16)?;
+
+    // Archives with too many entries, or that are too large, for the end of
+    // central directory record store them in the zip64 record instead, which
+    // is found via the locator immediately before it
+    if count == u16::MAX as usize || pos == u32::MAX as usize {
+        let locator = eocd
+            .checked_sub(20)
+            .filter(|locator| data[*locator..].starts_with(ZIP64_LOCATOR))
+            .ok_or("unable to find zip64 central directory")?;
+        let zip64_eocd = u64_at(locator + 8)?;
+
+        if !data
+            .get(zip64_eocd..)
+            .is_some_and(|record| record.starts_with(ZIP64_EOCD))
+        {
+            return Err("invalid zip64 central directory".to_owned());
+        }
+
+        count = u64_at(zip64_eocd + 32)?;
+        pos = u64_at(zip64_eocd + 48)?;
+    }
+
+    let mut entries = Vec::new();
+    for _ in 0..count {
+        if !data
+            .get(pos..)
+            .is_some_and(|header| header.starts_with(CENTRAL_HEADER))
+        {
+            return Err("invalid zip central directory".to_owned());
+        }
+
+        let method = u16_at(pos + 10)?;
+        let mut compressed_size = u32_at(pos + 20)?;
+        let mut size = u32_at(pos + 24)?;
+        let name_len = u16_at(pos + 28)?;
+        let extra_len = u16_at(pos + 30)?;
+        let comment_len = u16_at(pos + 32)?;
+        let mut local = u32_at(pos + 42)?;
+
+        let name = data
+            .get(pos + 46..pos + 46 + name_len)
+            .ok_or("truncated zip archive")?;
+        let name = String::from_utf8_lossy(name).into_owned();
+
+        // The zip64 extra field contains the values that don't fit in the
+        // header, in this order, but only the ones that don't fit
+        let mut extra = pos + 46 + name_len;
+        let extra_end = extra + extra_len;
+        while extra + 4 <= extra_end {
+            let id = u16_at(extra)?;
+            let len = u16_at(extra + 2)?;
+
+            if id == ZIP64_EXTRA {
+                let mut field = extra + 4;
+                for value in [&mut size, &mut compressed_size, &mut local] {
+                    if *value == u32::MAX as usize && field + 8 <= extra + 4 + len {
+                        *value = u64_at(field)?;
+                        field += 8;
+                    }
+                }
+            }
+
+            extra += 4 + len;
+        }
+
+        pos = extra_end + comment_len;
+
+        if name.ends_with('/') || size as u64 > MAX_ENTRY_SIZE || !filter(&name) {
+            continue;
+        }
+
+        let start = local + 30 + u16_at(local + 26)? + u16_at(local + 28)?;
+        let compressed = data
+            .get(start..start + compressed_size)
+            .ok_or("truncated zip archive")?;
+
+        let contents = match method {
+            // Stored
+            0 => compressed.to_vec(),
+            // Deflated
+            8 => {
+                let mut contents = Vec::with_capacity(size);
+                flate2::read::DeflateDecoder::new(compressed)
+                    .take(MAX_ENTRY_SIZE)
+                    .read_to_end(&mut contents)
+                    .map_err(|e| format!("failed to decompress '{name}': {e}"))?;
+                contents
+            }
+            _ => {
+                log::debug!("skipping '{name}' compressed with unsupported method {method}");
+                continue;
+            }
+        };
+
+        if let Ok(text) = String::from_utf8(contents) {
+            entries.push((name, text));
+        }
+    }
+
+    Ok(entries)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Builds a tar archive of the entries, with their name, type, and contents
+    fn tar(entries: &[(&str, u8, &[u8])]) -> Vec<u8> {
+        let mut tar = Vec::new();
+
+        for (name, kind, contents) in entries {
+            let mut header = [0u8; 512];
+            let name = &name.as_bytes()[..name.len().min(100)];
+            header[..name.len()].copy_from_slice(name);
+            header[124..135].copy_from_slice(format!("{:011o}", contents.len()).as_bytes());
+            header[156] = *kind;
+            header[257..263].copy_from_slice(b"ustar\0");
+
+            tar.extend_from_slice(&header);
+            tar.extend_from_slice(contents);
+            tar.resize(tar.len().div_ceil(512) * 512, 0);
+        }
+
+        tar.resize(tar.len() + 1024, 0);
+        tar
+    }
+
+    /// Builds a zip archive with a single stored entry, optionally with its
+    /// sizes, offset, and the number of entries in the zip64 records
+    fn zip(name: &str, contents: &[u8], zip64: bool) -> Vec<u8> {
+        let name = name.as_bytes();
+        let u32_or_max = |value: usize| if zip64 { u32::MAX } else { value as u32 };
+
+        let mut zip = Vec::new();
+        // Local file header
+        zip.extend_from_slice(b"PK\x03\x04");
+        zip.extend_from_slice(&[0; 14]);
+        zip.extend_from_slice(&(contents.len() as u32).to_le_bytes());
+        zip.extend_from_slice(&(contents.len() as u32).to_le_bytes());
+        zip.extend_from_slice(&(name.len() as u16).to_le_bytes());
+        zip.extend_from_slice(&[0; 2]);
+        zip.extend_from_slice(name);
+        zip.extend_from_slice(contents);
+
+        let mut extra = Vec::new();
+        if zip64 {
+            extra.extend_from_slice(&1u16.to_le_bytes());
+            extra.extend_from_slice(&24u16.to_le_bytes());
+            extra.extend_from_slice(&(contents.len() as u64).to_le_bytes());
+            extra.extend_from_slice(&(contents.len() as u64).to_le_bytes());
+            extra.extend_from_slice(&0u64.to_le_bytes());
+        }
+
+        // Central directory
+        let central = zip.len();
+        zip.extend_from_slice(b"PK\x01\x02");
+        zip.extend_from_slice(&[0; 16]);
+        zip.extend_from_slice(&u32_or_max(contents.len()).to_le_bytes());
+        zip.extend_from_slice(&u32_or_max(contents.len()).to_le_bytes());
+        zip.extend_from_slice(&(name.len() as u16).to_le_bytes());
+        zip.extend_from_slice(&(extra.len() as u16).to_le_bytes());
+        zip.extend_from_slice(&[0; 10]);
+        zip.extend_from_slice(&u32_or_max(0).to_le_bytes());
+        zip.extend_from_slice(name);
+        zip.extend_from_slice(&extra);
+        let central_len = zip.len() - central;
+
+        if zip64 {
+            // Zip64 end of central directory record
+            let record = zip.len();
+            zip.extend_from_slice(b"PK\x06\x06");
+            zip.extend_from_slice(&44u64.to_le_bytes());
+            zip.extend_from_slice(&[0; 12]);
+            zip.extend_from_slice(&1u64.to_le_bytes());
+            zip.extend_from_slice(&1u64.to_le_bytes());
+            zip.extend_from_slice(&(central_len as u64).to_le_bytes());
+            zip.extend_from_slice(&(central as u64).to_le_bytes());
+
+            // Zip64 end of central directory locator
+            zip.extend_from_slice(b"PK\x06\x07");
+            zip.extend_from_slice(&[0; 4]);
+            zip.extend_from_slice(&(record as u64).to_le_bytes());
+            zip.extend_from_slice(&1u32.to_le_bytes());
+        }
+
+        let count = if zip64 { u16::MAX } else { 1 };
+
+        // End of central directory
+        zip.extend_from_slice(b"PK\x05\x06");
+        zip.extend_from_slice(&[0; 4]);
+        zip.extend_from_slice(&count.to_le_bytes());
+        zip.extend_from_slice(&count.to_le_bytes());
+        zip.extend_from_slice(&(central_len as u32).to_le_bytes());
+        zip.extend_from_slice(&u32_or_max(central).to_le_bytes());
+        zip.extend_from_slice(&[0; 2]);
+
+        zip
+    }
+
+    #[test]
+    fn reads_stored_zip_entries() {
+        let zip = zip("LICENSE", b"license text", false);
+
+        assert_eq!(
+            zip_entries(&zip, &|_| true).unwrap(),
+            [("LICENSE".to_owned(), "license text".to_owned())]
+        );
+        assert!(zip_entries(&zip, &|name| name != "LICENSE")
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn reads_zip64_entries() {
+        let zip = zip("LICENSE", b"license text", true);
+
+        assert_eq!(
+            zip_entries(&zip, &|_| true).unwrap(),
+            [("LICENSE".to_owned(), "license text".to_owned())]
+        );
+    }
+
+    #[test]
+    fn rejects_truncated_zip_archives() {
+        let zip = zip("LICENSE", b"license text", false);
+
+        // Without the end of the central directory record
+        assert!(zip_entries(&zip[..zip.len() - 10], &|_| true).is_err());
+    }
+
+    #[test]
+    fn reads_tar_entries() {
+        let tar = tar(&[
+            ("vendor/", b'5', b""),
+            ("vendor/LICENSE", b'0', b"license text"),
+            ("vendor/binary", b'0', b"\xff\xfe"),
+            ("vendor/COPYING", b'2', b""),
+        ]);
+
+        assert_eq!(
+            tar_entries(&tar, &|_| true).unwrap(),
+            [("vendor/LICENSE".to_owned(), "license text".to_owned())]
+        );
+    }
+
+    #[test]
+    fn reads_gzipped_tar_entries() {
+        use std::io::Write as _;
+
+        let dir = assert_fs::TempDir::new().unwrap();
+        let path = Path::from_path(dir.path()).unwrap().join("vendor.tgz");
+
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder
+            .write_all(&tar(&[("LICENSE", b'0', b"license text")]))
+            .unwrap();
+        std::fs::write(&path, encoder.finish().unwrap()).unwrap();
+
+        assert_eq!(
+            entries(&path, |_| true).unwrap(),
+            [("LICENSE".to_owned(), "license text".to_owned())]
+        );
+    }
+
+    #[test]
+    fn reads_long_tar_entry_names() {
+        let long_name = format!("{}/LICENSE", "vendor".repeat(20));
+        let pax_name = format!("{}/COPYING", "vendor".repeat(20));
+        let pax_record = format!("{} path={pax_name}\n", pax_name.len() + 10);
+
+        let tar = tar(&[
+            ("././@LongLink", b'L', long_name.as_bytes()),
+            (&long_name, b'0', b"license text"),
+            ("PaxHeaders/COPYING", b'x', pax_record.as_bytes()),
+            (&pax_name, b'0', b"copying text"),
+            ("NOTICE", b'0', b"notice text"),
+        ]);
+
+        assert_eq!(
+            tar_entries(&tar, &|_| true).unwrap(),
+            [
+                (long_name, "license text".to_owned()),
+                (pax_name, "copying text".to_owned()),
+                ("NOTICE".to_owned(), "notice text".to_owned()),
+            ]
+        );
+    }
+
+    #[test]
+    fn rejects_truncated_tar_archives() {
+        let tar = tar(&[("LICENSE", b'0', &[b'a'; 1024])]);
+
+        assert_eq!(
+            tar_entries(&tar[..1024], &|_| true).unwrap_err(),
+            "truncated tar archive"
+        );
+        assert!(tar_entries(&tar[..600], &|_| true).is_err());
+    }
+}
@@ -0,0 +1,164 @@
+//! Helpers for the on-disk caches, which can be shared by multiple cargo-about
+//! processes at once, eg. by CI jobs that mount the same cache volume
+//!
+//! Entries are always written to a temporary file and renamed into place, so a
+//! reader never sees a partially written entry, and an entry can be locked
+//! while it is being retrieved, so that other processes wait for it rather
+//! than retrieving the same entry again.
+
+use anyhow::Context as _;
+use krates::Utf8Path as Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Writes the contents to a temporary file next to the path, and renames it
+/// into place
+pub(crate) fn write_atomic(path: &Path, contents: &[u8]) -> anyhow::Result<()> {
+    use std::io::Write as _;
+
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).with_context(|| format!("failed to create '{parent}'"))?;
+    }
+
+    // The same entry can be written by multiple threads, as well as multiple
+    // processes, at once
+    static NEXT: AtomicUsize = AtomicUsize::new(0);
+    let tmp = path.with_extension(format!(
+        "tmp{}-{}",
+        std::process::id(),
+        NEXT.fetch_add(1, Ordering::Relaxed)
+    ));
+
+    let write = || -> anyhow::Result<()> {
+        let mut file = std::fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&tmp)
+            .with_context(|| format!("failed to create '{tmp}'"))?;
+        file.write_all(contents)
+            .with_context(|| format!("failed to write '{tmp}'"))?;
+        // Ensure the contents are on disk before the rename makes them visible,
+        // otherwise a crash could leave an empty or truncated entry behind
+        file.sync_all()
+            .with_context(|| format!("failed to sync '{tmp}'"))?;
+        Ok(())
+    };
+
+    if let Err(err) = write() {
+        let _ = std::fs::remove_file(&tmp);
+        return Err(err);
+    }
+
+    if let Err(err) = std::fs::rename(&tmp, path) {
+        let _ = std::fs::remove_file(&tmp);
+
+        // On Windows, replacing a file that another process has open fails,
+        // in which case the existing entry is kept, as cache entries are
+        // either immutable or revalidated before use
+        if path.exists() {
+            log::debug!("'{path}' was written concurrently, keeping the existing entry: {err}");
+            return Ok(());
+        }
+
+        return Err(err).with_context(|| format!("failed to rename '{tmp}' => '{path}'"));
+    }
+
+    Ok(())
+}
+
+/// An exclusive advisory lock on a cache entry, which is released when dropped
+pub(crate) struct EntryLock {
+    _file: std::fs::File,
+}
+
+impl EntryLock {
+    /// Locks the entry at the specified path, waiting for any other process
+    /// or thread that holds the lock to release it
+    ///
+    /// Returns `None` if the lock can't be acquired, eg. because the cache is
+    /// on a filesystem that doesn't support locking, in which case the entry
+    /// is still safe to use, as it is written atomically, but may be retrieved
+    /// more than once.
+    pub(crate) fn acquire(path: &Path) -> Option<Self> {
+        let lock_path = path.with_extension("lock");
+
+        let lock = || -> anyhow::Result<std::fs::File> {
+            if let Some(parent) = lock_path.parent() {
+                std::fs::create_dir_all(parent)
+                    .with_context(|| format!("failed to create '{parent}'"))?;
+            }
+
+            let file = std::fs::OpenOptions::new()
+                .read(true)
+                .write(true)
+                .create(true)
+                .truncate(false)
+                .open(&lock_path)
+                .with_context(|| format!("failed to open '{lock_path}'"))?;
+
+            // Called via the trait, as newer versions of std have an inherent
+            // method with the same name
+            fs4::FileExt::lock(&file).with_context(|| format!("failed to lock '{lock_path}'"))?;
+            Ok(file)
+        };
+
+        match lock() {
+            Ok(file) => Some(Self { _file: file }),
+            Err(err) => {
+                log::debug!("continuing without a lock: {err:#}");
+                None
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn concurrent_writes_are_never_partial() {
+        let dir = assert_fs::TempDir::new().unwrap();
+        let path = Path::from_path(dir.path()).unwrap().join("entry");
+
+        let contents: Vec<Vec<u8>> = (0..8u8).map(|i| vec![i; 64 * 1024]).collect();
+
+        std::thread::scope(|s| {
+            for contents in &contents {
+                let path = &path;
+                s.spawn(move || {
+                    for _ in 0..8 {
+                        write_atomic(path, contents).unwrap();
+                        let read = std::fs::read(path).unwrap();
+                        assert_eq!(read.len(), 64 * 1024);
+                        assert!(read.iter().all(|b| *b == read[0]));
+                    }
+                });
+            }
+        });
+
+        // No temporary files are left behind
+        let entries = std::fs::read_dir(dir.path()).unwrap().count();
+        assert_eq!(entries, 1);
+    }
+
+    #[test]
+    fn lock_is_exclusive() {
+        let dir = assert_fs::TempDir::new().unwrap();
+        let path = Path::from_path(dir.path()).unwrap().join("entry");
+
+        let held = std::sync::atomic::AtomicBool::new(false);
+
+        std::thread::scope(|s| {
+            for _ in 0..4 {
+                s.spawn(|| {
+                    for _ in 0..8 {
+                        let _lock = EntryLock::acquire(&path).expect("failed to lock");
+                        assert!(!held.swap(true, Ordering::SeqCst));
+                        std::thread::sleep(std::time::Duration::from_millis(1));
+                        held.store(false, Ordering::SeqCst);
+                    }
+                });
+            }
+        });
+    }
+}
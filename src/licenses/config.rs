@@ -114,7 +114,8 @@ pub struct Ignore {
 pub struct ClarificationFile {
     /// The crate relative path to the file
     pub path: PathBuf,
-    /// The SHA-256 checksum of the file in hex
+    /// The checksum of the file in hex, either a bare SHA-256 checksum, or
+    /// one prefixed with its algorithm, eg. `blake3:<hex>`
     pub checksum: String,
     /// The license applied to the file. Defaults to the license of the parent
     /// clarification if not specified.
@@ -153,6 +154,17 @@ pub struct Clarification {
         skip_serializing_if = "Option::is_none"
     )]
     pub override_git_commit: Option<String>,
+    /// The versions of the crate the clarification applies to. If no `files`
+    /// or `git` files are specified, the clarification is trusted for these
+    /// versions without checksumming any files, as long as the licenses found
+    /// when scanning the crate are compatible with the `license` expression,
+    /// so that it doesn't break when files shift slightly between versions.
+    #[serde(
+        default,
+        rename = "version-req",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub version_req: Option<semver::VersionReq>,
     /// 1 or more files that are used as the source of truth for the license
     /// expression
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
@@ -165,8 +177,11 @@ pub struct Clarification {
 }
 
 #[derive(Deserialize, Debug)]
-#[serde(deny_unknown_fields)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
 pub struct KrateConfig {
+    /// The versions of the crate this configuration applies to, defaults to
+    /// all versions
+    pub version: Option<semver::VersionReq>,
     /// The list of additional accepted licenses for this crate, again in
     /// priority order
     #[serde(default, deserialize_with = "deserialize_licensee")]
@@ -174,11 +189,126 @@ pub struct KrateConfig {
     /// Overrides the license expression for a crate as long as 1 or more file
     /// checksums match
     pub clarify: Option<Clarification>,
+    /// Overrides the confidence threshold used when scanning the crate's files
+    /// for licenses, eg. for a crate with a heavily modified license text
+    pub threshold: Option<f32>,
+    /// Scans every file in the crate regardless of its depth or extension,
+    /// eg. for `-sys` crates that vendor C libraries whose licenses are only
+    /// in subdirectories such as `vendor/`. License texts found in
+    /// subdirectories are attributed to that directory.
+    #[serde(default)]
+    pub deep_scan: bool,
 }
 
-/// Configures how private crates are handled and detected
+/// Finds the configuration for the specified crate. Keys that exactly match the
+/// crate name take precedence over keys containing `*` or `?` wildcards, and
+/// in both cases the configuration is skipped if its `version` requirement
+/// does not match the crate's version
+pub fn find_krate_config<'c>(
+    crates: &'c BTreeMap<String, KrateConfig>,
+    name: &str,
+    version: &semver::Version,
+) -> Option<&'c KrateConfig> {
+    let version_matches =
+        |kc: &KrateConfig| kc.version.as_ref().map_or(true, |req| req.matches(version));
+
+    if let Some(kc) = crates.get(name).filter(|kc| version_matches(kc)) {
+        return Some(kc);
+    }
+
+    crates.iter().find_map(|(key, kc)| {
+        (key.contains(['*', '?'])
+            && wildcard_match(key.as_bytes(), name.as_bytes())
+            && version_matches(kc))
+        .then_some(kc)
+    })
+}
+
+/// Matches a name against a pattern where `*` matches any number of
+/// characters and `?` matches exactly one
+#[inline]
+pub(crate) fn matches_name(pattern: &str, name: &str) -> bool {
+    wildcard_match(pattern.as_bytes(), name.as_bytes())
+}
+
+fn wildcard_match(pattern: &[u8], name: &[u8]) -> bool {
+    match (pattern.split_first(), name.split_first()) {
+        (None, None) => true,
+        (Some((b'*', rest)), _) => {
+            wildcard_match(rest, name)
+                || name
+                    .split_first()
+                    .is_some_and(|(_, name)| wildcard_match(pattern, name))
+        }
+        (Some((p, prest)), Some((n, nrest))) if *p == b'?' || p == n => {
+            wildcard_match(prest, nrest)
+        }
+        _ => false,
+    }
+}
+
+/// Configures how the files of every crate are scanned for licenses
 #[derive(Deserialize, Default, Debug)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct Scan {
+    /// Globs, relative to the root of each crate, of the files and
+    /// directories that are never scanned, eg. `**/testdata/**` for corpora
+    /// of third party test inputs that would otherwise be detected as
+    /// licenses of the crate
+    #[serde(default, deserialize_with = "deserialize_globs")]
+    pub exclude_globs: Vec<String>,
+    /// Scans the tar and zip archives bundled in crates, eg. test data or
+    /// vendored sources, for the license and notice files in them
+    #[serde(default)]
+    pub archives: bool,
+}
+
+fn deserialize_globs<'de, D>(deserializer: D) -> std::result::Result<Vec<String>, D::Error>
+where
+    D: de::Deserializer<'de>,
+{
+    let globs = Vec::<String>::deserialize(deserializer)?;
+
+    for glob in &globs {
+        ignore::overrides::OverrideBuilder::new("")
+            .add(glob)
+            .map_err(|e| de::Error::custom(format!("'{glob}' is not a valid glob: {e}")))?;
+    }
+
+    Ok(globs)
+}
+
+/// A crate whose licenses are used without being accepted, and why
+#[derive(Deserialize, Debug)]
 #[serde(deny_unknown_fields)]
+pub struct Exception {
+    /// The name of the crate
+    pub name: String,
+    /// Why the crate's licenses don't need to be accepted, eg. because it is
+    /// only used internally and never distributed, which is required so that
+    /// audits can see why each exception was granted
+    #[serde(deserialize_with = "deserialize_reason")]
+    pub reason: String,
+}
+
+fn deserialize_reason<'de, D>(deserializer: D) -> std::result::Result<String, D::Error>
+where
+    D: de::Deserializer<'de>,
+{
+    let reason = String::deserialize(deserializer)?;
+
+    if reason.trim().is_empty() {
+        return Err(de::Error::custom(
+            "the reason for an exception can't be empty",
+        ));
+    }
+
+    Ok(reason)
+}
+
+/// Configures how private crates are handled and detected
+#[derive(Deserialize, Default, Debug)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
 pub struct Private {
     /// If enabled, ignores workspace crates that aren't published, or are
     /// only published to private registries
@@ -189,6 +319,138 @@ pub struct Private {
     /// the crate will not have its license checked
     #[serde(default)]
     pub registries: Vec<String>,
+    /// If enabled, and `ignore` is true, crates that are not from a registry
+    /// or git repository, ie. workspace members and path dependencies, are
+    /// also treated as private
+    #[serde(default)]
+    pub path_dependencies: bool,
+    /// If enabled, and `ignore` is true, crates retrieved from a git
+    /// repository rather than a registry, eg. first-party crates shared
+    /// between repositories, are also treated as private
+    #[serde(default)]
+    pub git_dependencies: bool,
+    /// Crates whose names match one of these patterns, which can contain `*`
+    /// and `?` wildcards, are also treated as private if `ignore` is true
+    #[serde(default)]
+    pub names: Vec<String>,
+    /// Crates whose manifests are in one of these directories, relative to
+    /// the workspace root, are also treated as private if `ignore` is true
+    #[serde(default)]
+    pub directories: Vec<PathBuf>,
+}
+
+impl Private {
+    /// Checks if the crate is private, regardless of whether private crates
+    /// are ignored, returning the reason it is private
+    pub fn private_reason(
+        &self,
+        krate: &crate::Krate,
+        workspace_root: &krates::Utf8Path,
+    ) -> Option<crate::licenses::IgnoreReason> {
+        use crate::licenses::IgnoreReason;
+
+        if let Some(publish) = &krate.publish {
+            if publish.is_empty() || publish.iter().all(|reg| self.registries.contains(reg)) {
+                return Some(IgnoreReason::Unpublished);
+            }
+        }
+
+        if self.path_dependencies && krate.source.is_none() {
+            Some(IgnoreReason::PathDependency)
+        } else if self.git_dependencies
+            && krate
+                .source
+                .as_ref()
+                .is_some_and(|src| src.repr.starts_with("git+"))
+        {
+            Some(IgnoreReason::GitDependency)
+        } else if self
+            .names
+            .iter()
+            .any(|pattern| matches_name(pattern, &krate.name))
+        {
+            Some(IgnoreReason::PrivateName)
+        } else if self
+            .directories
+            .iter()
+            .any(|dir| krate.manifest_path.starts_with(workspace_root.join(dir)))
+        {
+            Some(IgnoreReason::PrivateDirectory)
+        } else {
+            None
+        }
+    }
+}
+
+/// A workaround loaded from disk rather than being built-in to cargo-about
+#[derive(Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct ExternalWorkaround {
+    /// The name of the crate the workaround applies to
+    pub name: String,
+    /// The versions of the crate the workaround applies to, defaults to all
+    /// versions
+    pub version: Option<semver::VersionReq>,
+    /// The clarification applied to matching crates
+    pub clarify: Clarification,
+}
+
+/// How the `source_path` of license texts read from crate sources is output
+#[derive(Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum SourcePathStyle {
+    /// The path of the file on the local machine
+    #[default]
+    Absolute,
+    /// The path of the file relative to the root of its crate
+    Relative,
+    /// The kind of source, name, and version of the crate followed by the
+    /// relative path of the file, eg. `registry:serde-1.0.200/LICENSE-MIT`
+    Logical,
+}
+
+/// Crate metadata removed from the output, as attribution documents are often
+/// published externally
+#[derive(Deserialize, Debug, Default, Clone, Copy)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct Redact {
+    /// Removes the email addresses of crate authors
+    #[serde(default)]
+    pub author_emails: bool,
+    /// Removes the description of crates that weren't retrieved from a
+    /// registry, ie. path and git dependencies
+    #[serde(default)]
+    pub internal_descriptions: bool,
+    /// Removes the local path of the files license texts were read from
+    #[serde(default)]
+    pub source_paths: bool,
+}
+
+/// Text used for every occurrence of a license in the output, regardless of
+/// the text found in crates
+#[derive(Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct LicenseOverride {
+    /// The path of the file containing the text
+    pub file: PathBuf,
+}
+
+/// An external command that is consulted for the final license decision of
+/// crates.
+///
+/// The command is passed a JSON object on stdin with a `crates` array, each
+/// entry containing the `name`, `version`, `id`, `declared` license, `detected`
+/// license, and the license `files` that were found for a crate. It must
+/// write a JSON array of `{ "name", "version", "license" }` objects to stdout,
+/// each of which overrides the license expression for that crate.
+#[derive(Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct ExternalResolver {
+    /// The program to execute
+    pub command: String,
+    /// Additional arguments passed to the program
+    #[serde(default)]
+    pub args: Vec<String>,
 }
 
 #[derive(Deserialize, Debug, Default)]
@@ -198,6 +460,10 @@ pub struct Config {
     /// targets
     #[serde(default)]
     pub targets: Vec<String>,
+    /// If not empty, only the crates whose names match one of these patterns
+    /// have their licenses gathered, all other crates are ignored
+    #[serde(default)]
+    pub only: Vec<String>,
     /// Configures how private crates are handled and detected
     #[serde(default)]
     pub private: Private,
@@ -208,9 +474,31 @@ pub struct Config {
     /// Sets the timeout for requests to clearlydefined.io if it is used. Defaults
     /// to 30 seconds.
     pub clearly_defined_timeout_secs: Option<u64>,
+    /// The directory HTTP responses are cached in, defaults to
+    /// `$CARGO_HOME/cargo-about/http`
+    pub http_cache_dir: Option<PathBuf>,
+    /// Disables caching of HTTP responses
+    #[serde(default)]
+    pub no_http_cache: bool,
+    /// Appended to the `cargo-about/<version>` user agent of every HTTP
+    /// request, eg. to identify the organization making the requests
+    pub user_agent_suffix: Option<String>,
+    /// The maximum number of files retrieved from remote git hosts
+    /// concurrently for clarifications and workarounds. Defaults to 8.
+    pub fetch_concurrency: Option<usize>,
+    /// The number of times retrieving a file from a remote git host is
+    /// retried, with an exponential backoff, before giving up. Defaults to 3.
+    pub fetch_retries: Option<u32>,
     /// Sets the maximum depth from the root of each crate that will be scanned
     /// for license files.
     pub max_depth: Option<u32>,
+    /// Configures how the files of every crate are scanned for licenses
+    #[serde(default)]
+    pub scan: Scan,
+    /// The maximum amount of time, in seconds, spent scanning a single crate
+    /// for license files, after which the scan is abandoned and only the
+    /// crate's declared license expression is used. Defaults to 120 seconds.
+    pub scan_timeout_secs: Option<u64>,
     /// Ignores any build dependencies in the graph
     #[serde(default)]
     pub ignore_build_dependencies: bool,
@@ -231,13 +519,354 @@ pub struct Config {
     /// The list of licenses we will use for all crates, in priority order
     #[serde(deserialize_with = "deserialize_licensee")]
     pub accepted: Vec<spdx::Licensee>,
+    /// Licenses that must never be used, even if they are also accepted.
+    /// Crates that can only be used under a denied license fail with a
+    /// diagnostic pointing at the denied license
+    #[serde(default, deserialize_with = "deserialize_licensee")]
+    pub denied: Vec<spdx::Licensee>,
+    /// Crates whose licenses are used without being accepted, eg. because
+    /// they are only used internally, along with the reason why
+    #[serde(default)]
+    pub exceptions: Vec<Exception>,
+    /// Includes crates whose license requirements can't be satisfied by the
+    /// accepted licenses in the output, marked as not accepted, rather than
+    /// failing
+    #[serde(default)]
+    pub include_unaccepted: bool,
+    /// Includes the crates that were ignored, eg. because they are private,
+    /// in the output along with the reason they were ignored
+    #[serde(default)]
+    pub report_ignored: bool,
+    /// Crate metadata that is removed from the output
+    #[serde(default)]
+    pub redact: Redact,
+    /// How the `source_path` of license texts is output
+    #[serde(default)]
+    pub source_path_style: SourcePathStyle,
+    /// Deduplicates license texts that only differ in their whitespace or
+    /// line wrapping
+    #[serde(default)]
+    pub normalize_license_texts: bool,
+    /// Includes the texts of every alternative of an `OR` expression in the
+    /// output, not just the licenses that were elected, eg. both the MIT and
+    /// Apache-2.0 texts of a dual licensed crate
+    #[serde(default)]
+    pub include_or_alternatives: bool,
+    /// Fails when the canonical text of a license is used because a crate
+    /// doesn't include its license text, as the canonical text is missing
+    /// the crate's copyright information
+    #[serde(default)]
+    pub fail_on_missing_license_text: bool,
+    /// Fails if the crates use more distinct licenses than this, as a tripwire
+    /// for reviewing the licenses in use rather than maintaining an
+    /// exhaustive list of accepted licenses
+    #[serde(default)]
+    pub max_distinct_licenses: Option<usize>,
+    /// Fails if the crates use more distinct licenses of a family than the
+    /// limit for that family, eg. `strong-copyleft = 1`
+    #[serde(default)]
+    pub max_distinct_licenses_per_family: BTreeMap<super::obligations::Family, usize>,
     /// Some crates have extremely complicated licensing which requires tedious
     /// configuration to actually correctly identify. Rather than require every
     /// user of cargo-about to redo that same configuration if they happen to
     /// use those problematic crates, they can apply workarounds instead.
     #[serde(default)]
     pub workarounds: Vec<String>,
+    /// One or more paths to workaround files, or directories of workaround
+    /// files, allowing workarounds to be maintained outside of cargo-about
+    #[serde(default)]
+    pub workarounds_path: Vec<PathBuf>,
+    /// The workarounds loaded from [`Self::workarounds_path`]
+    #[serde(skip)]
+    pub external_workarounds: Vec<ExternalWorkaround>,
+    /// Maps `LicenseRef-` identifiers to the path of a file containing their
+    /// text, which is used for crates that don't contain the text themselves
+    #[serde(default)]
+    pub license_refs: BTreeMap<String, PathBuf>,
+    /// The texts loaded from [`Self::license_refs`]
+    #[serde(skip)]
+    pub license_ref_texts: BTreeMap<String, String>,
+    /// Maps SPDX identifiers to the path of a file containing the text used
+    /// instead of the canonical SPDX text for crates that don't contain the
+    /// license text themselves
+    #[serde(default)]
+    pub license_texts: BTreeMap<String, PathBuf>,
+    /// The texts loaded from [`Self::license_texts`], keyed by SPDX identifier
+    #[serde(skip)]
+    pub license_text_overrides: BTreeMap<String, String>,
+    /// Maps SPDX identifiers to the text used for the license for every
+    /// crate, including crates that contain their own text for the license,
+    /// eg. for a legally approved wording
+    #[serde(default)]
+    pub license_overrides: BTreeMap<String, LicenseOverride>,
+    /// The texts loaded from [`Self::license_overrides`], keyed by SPDX
+    /// identifier
+    #[serde(skip)]
+    pub license_override_texts: BTreeMap<String, String>,
+    /// A directory containing the license files of crates, in
+    /// `<crate>-<version>` subdirectories, which are scanned when running
+    /// offline for crates that don't contain any license files themselves
+    pub license_overrides_dir: Option<PathBuf>,
+    /// The path of the configuration itself, ie. the `about.toml` or the
+    /// `Cargo.toml` containing `[workspace.metadata.about]`, `None` if the
+    /// default configuration is used
+    #[serde(skip)]
+    pub config_path: Option<PathBuf>,
+    /// Every file the configuration was loaded from, including external
+    /// workarounds, license texts, and accepted licenses files
+    #[serde(skip)]
+    pub loaded_files: Vec<PathBuf>,
+    /// The algorithm `clarify` calculates the checksums of clarified files
+    /// with, clarifications with checksums of any algorithm are verified
+    #[serde(default)]
+    pub checksum_algorithm: crate::ChecksumAlgorithm,
+    /// An external command that can override the license decision for crates
+    pub resolver: Option<ExternalResolver>,
+    /// Maps the name of a template in the templates directory to the path
+    /// its rendered output is written to, allowing multiple outputs to be
+    /// generated in a single run
+    #[serde(default)]
+    pub outputs: BTreeMap<String, PathBuf>,
+    /// The locale of the `strings` passed to templates, defaults to `en`
+    pub locale: Option<String>,
+    /// Maps locales to the strings passed to templates for them, which take
+    /// precedence over the built-in translations
+    #[serde(default)]
+    pub translations: BTreeMap<String, BTreeMap<String, String>>,
     /// Crate specific configuration
     #[serde(flatten)]
     pub crates: BTreeMap<String, KrateConfig>,
 }
+
+impl Config {
+    /// Finds the configuration for the specified crate, see [`find_krate_config`]
+    #[inline]
+    pub fn krate_config(&self, krate: &crate::Krate) -> Option<&KrateConfig> {
+        find_krate_config(&self.crates, &krate.name, &krate.version)
+    }
+
+    /// Loads the workaround files from [`Self::workarounds_path`], relative
+    /// paths are resolved relative to the specified root, which is typically
+    /// the directory the configuration was loaded from
+    pub fn load_external_workarounds(&mut self, root: &krates::Utf8Path) -> anyhow::Result<()> {
+        use anyhow::Context as _;
+
+        let mut files = Vec::new();
+        for path in &self.workarounds_path {
+            let path = root.join(path);
+
+            if path.is_dir() {
+                let mut entries = Vec::new();
+                for entry in std::fs::read_dir(&path)
+                    .with_context(|| format!("failed to read workarounds directory '{path}'"))?
+                {
+                    let entry = entry?;
+                    let Ok(entry) = PathBuf::from_path_buf(entry.path()) else {
+                        continue;
+                    };
+
+                    if entry.extension() == Some("toml") {
+                        entries.push(entry);
+                    }
+                }
+
+                entries.sort();
+                files.extend(entries);
+            } else {
+                files.push(path);
+            }
+        }
+
+        for path in files {
+            let contents = std::fs::read_to_string(&path)
+                .with_context(|| format!("failed to read workaround '{path}'"))?;
+            let workaround: ExternalWorkaround = toml::from_str(&contents)
+                .with_context(|| format!("failed to deserialize workaround '{path}'"))?;
+
+            log::debug!("loaded workaround for '{}' from '{path}'", workaround.name);
+            self.external_workarounds.push(workaround);
+            self.loaded_files.push(path);
+        }
+
+        Ok(())
+    }
+
+    /// Adds the licensees in the file to [`Self::accepted`], allowing the
+    /// accepted licenses to be maintained outside of the configuration, eg.
+    /// by a legal team
+    ///
+    /// The file contains one SPDX licensee per line, empty lines and lines
+    /// starting with `#` are ignored
+    pub fn add_accepted_file(&mut self, path: &krates::Utf8Path) -> anyhow::Result<()> {
+        use anyhow::Context as _;
+
+        let contents = std::fs::read_to_string(path)
+            .with_context(|| format!("failed to read accepted licenses from '{path}'"))?;
+
+        for (i, line) in contents.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let licensee = spdx::Licensee::parse(line).map_err(|err| {
+                anyhow::anyhow!(
+                    "'{line}' on line {} of '{path}' is not a valid SPDX licensee: {err}",
+                    i + 1
+                )
+            })?;
+
+            if !self.accepted.contains(&licensee) {
+                self.accepted.push(licensee);
+            }
+        }
+
+        self.loaded_files.push(path.to_owned());
+        Ok(())
+    }
+
+    /// Loads the texts of the [`Self::license_refs`], [`Self::license_texts`],
+    /// and [`Self::license_overrides`], relative paths are resolved relative to the specified root, which is
+    /// typically the directory the configuration was loaded from, as is the
+    /// [`Self::license_overrides_dir`]
+    pub fn load_license_texts(&mut self, root: &krates::Utf8Path) -> anyhow::Result<()> {
+        use anyhow::Context as _;
+
+        if let Some(dir) = &mut self.license_overrides_dir {
+            *dir = root.join(&*dir);
+        }
+
+        for (id, path) in &self.license_refs {
+            let item = spdx::Expression::parse(id)
+                .ok()
+                .and_then(|expr| {
+                    let mut reqs = expr.requirements();
+                    let req = reqs.next()?;
+                    reqs.next().is_none().then(|| req.req.license.clone())
+                })
+                .filter(|item| matches!(item, spdx::LicenseItem::Other { .. }))
+                .with_context(|| format!("'{id}' is not a valid LicenseRef identifier"))?;
+
+            let path = root.join(path);
+            let text = std::fs::read_to_string(&path)
+                .with_context(|| format!("failed to read text of '{id}' from '{path}'"))?;
+            self.loaded_files.push(path);
+
+            self.license_ref_texts.insert(item.to_string(), text);
+        }
+
+        for (id, path) in &self.license_texts {
+            let license = spdx::license_id(id)
+                .with_context(|| format!("'{id}' is not a known SPDX license identifier"))?;
+
+            let path = root.join(path);
+            let text = std::fs::read_to_string(&path)
+                .with_context(|| format!("failed to read text of '{id}' from '{path}'"))?;
+            self.loaded_files.push(path);
+
+            self.license_text_overrides
+                .insert(license.name.to_owned(), text);
+        }
+
+        for (id, lo) in &self.license_overrides {
+            let license = spdx::license_id(id)
+                .with_context(|| format!("'{id}' is not a known SPDX license identifier"))?;
+
+            let path = root.join(&lo.file);
+            let text = std::fs::read_to_string(&path)
+                .with_context(|| format!("failed to read override of '{id}' from '{path}'"))?;
+            self.loaded_files.push(path);
+
+            self.license_override_texts
+                .insert(license.name.to_owned(), text);
+        }
+
+        Ok(())
+    }
+
+    /// The directory HTTP responses are cached in, or `None` if caching is
+    /// disabled
+    pub fn http_cache_root(&self) -> Option<PathBuf> {
+        if self.no_http_cache {
+            None
+        } else {
+            self.http_cache_dir
+                .clone()
+                .or_else(super::http_cache::HttpCache::default_root)
+        }
+    }
+
+    /// The directory files retrieved from remote git hosts are persisted in,
+    /// or `None` if caching is disabled
+    pub fn git_cache_root(&self) -> Option<PathBuf> {
+        if self.no_http_cache {
+            None
+        } else if let Some(dir) = &self.http_cache_dir {
+            Some(dir.join("git"))
+        } else {
+            super::fetch::GitCache::default_root()
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::licenses::IgnoreReason;
+
+    fn krate(name: &str, source: Option<&str>) -> crate::Krate {
+        let version = "0.1.0";
+        let id = match source {
+            Some(source) => format!("{source}#{name}@{version}"),
+            None => format!("path+file:///workspace/{name}#{version}"),
+        };
+
+        serde_json::from_value::<krates::cm::Package>(serde_json::json!({
+            "name": name,
+            "version": version,
+            "id": id,
+            "source": source,
+            "manifest_path": format!("/workspace/{name}/Cargo.toml"),
+            "dependencies": [],
+            "targets": [],
+            "features": {},
+        }))
+        .unwrap()
+        .into()
+    }
+
+    #[test]
+    fn treats_git_dependencies_as_private() {
+        let root = krates::Utf8Path::new("/workspace");
+
+        let registry = krate(
+            "registry",
+            Some("registry+https://github.com/rust-lang/crates.io-index"),
+        );
+        let git = krate(
+            "git",
+            Some("git+https://github.com/EmbarkStudios/git?rev=abc123#abc123"),
+        );
+        let path = krate("path", None);
+
+        let private = Private {
+            ignore: true,
+            git_dependencies: true,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            private.private_reason(&git, root),
+            Some(IgnoreReason::GitDependency)
+        );
+        assert_eq!(private.private_reason(&registry, root), None);
+        assert_eq!(private.private_reason(&path, root), None);
+
+        let private = Private {
+            ignore: true,
+            ..Default::default()
+        };
+
+        assert_eq!(private.private_reason(&git, root), None);
+    }
+}
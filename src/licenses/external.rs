@@ -0,0 +1,161 @@
+//! Support for an external command that can override the license decision for
+//! one or more crates, eg. to consult an organization's internal license
+//! service
+
+use super::{config::ExternalResolver, KrateLicense, LicenseFileKind, LicenseInfo};
+use anyhow::Context as _;
+use serde::{Deserialize, Serialize};
+use std::io::Write as _;
+
+#[derive(Serialize)]
+struct FileEvidence<'a> {
+    path: &'a str,
+    license: &'a str,
+    confidence: f32,
+    /// Either `text`, `header`, or `notice`
+    kind: &'static str,
+}
+
+#[derive(Serialize)]
+struct KrateEvidence<'a> {
+    name: &'a str,
+    version: String,
+    id: String,
+    /// The license expression from the crate's manifest, if any
+    declared: Option<&'a str>,
+    /// The license expression cargo-about determined for the crate, if any
+    detected: Option<&'a str>,
+    files: Vec<FileEvidence<'a>>,
+}
+
+#[derive(Serialize)]
+struct Request<'a> {
+    crates: Vec<KrateEvidence<'a>>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Decision {
+    name: String,
+    version: semver::Version,
+    license: String,
+}
+
+/// Sends all of the gathered license information to the external resolver,
+/// and applies any license expressions it returns
+pub(crate) fn apply(resolver: &ExternalResolver, licensed_krates: &mut [KrateLicense<'_>]) {
+    let decisions = match query(resolver, licensed_krates) {
+        Ok(decisions) => decisions,
+        Err(err) => {
+            log::error!(
+                "failed to query external resolver '{}': {err:#}",
+                resolver.command
+            );
+            return;
+        }
+    };
+
+    for decision in decisions {
+        let Some(kl) = licensed_krates
+            .iter_mut()
+            .find(|kl| kl.krate.name == decision.name && kl.krate.version == decision.version)
+        else {
+            log::warn!(
+                "external resolver returned a decision for '{} {}' which is not in the crate graph",
+                decision.name,
+                decision.version
+            );
+            continue;
+        };
+
+        match spdx::Expression::parse(&decision.license) {
+            Ok(expr) => {
+                log::debug!(
+                    "external resolver applied license '{expr}' to crate '{}'",
+                    kl.krate
+                );
+                kl.lic_info = LicenseInfo::Expr(expr);
+            }
+            Err(err) => {
+                log::error!(
+                    "external resolver returned an invalid license expression '{}' for crate '{}': {err}",
+                    decision.license,
+                    kl.krate
+                );
+            }
+        }
+    }
+}
+
+fn query(
+    resolver: &ExternalResolver,
+    licensed_krates: &[KrateLicense<'_>],
+) -> anyhow::Result<Vec<Decision>> {
+    let exprs: Vec<_> = licensed_krates
+        .iter()
+        .map(|kl| match &kl.lic_info {
+            LicenseInfo::Expr(expr) => Some(expr.to_string()),
+            _ => None,
+        })
+        .collect();
+
+    let request = Request {
+        crates: licensed_krates
+            .iter()
+            .zip(exprs.iter())
+            .filter(|(kl, _)| !matches!(kl.lic_info, LicenseInfo::Ignore(_)))
+            .map(|(kl, detected)| KrateEvidence {
+                name: &kl.krate.name,
+                version: kl.krate.version.to_string(),
+                id: kl.krate.id.repr.clone(),
+                declared: kl.krate.license.as_deref(),
+                detected: detected.as_deref(),
+                files: kl
+                    .license_files
+                    .iter()
+                    .map(|lf| FileEvidence {
+                        path: lf.path.as_str(),
+                        license: lf.license_expr.as_ref(),
+                        confidence: lf.confidence,
+                        kind: match lf.kind {
+                            LicenseFileKind::Header => "header",
+                            LicenseFileKind::Text(_) | LicenseFileKind::AddendumText(..) => "text",
+                            LicenseFileKind::Notice(_) => "notice",
+                        },
+                    })
+                    .collect(),
+            })
+            .collect(),
+    };
+
+    let request = serde_json::to_vec(&request).context("failed to serialize request")?;
+
+    let mut child = std::process::Command::new(&resolver.command)
+        .args(&resolver.args)
+        .stdin(std::process::Stdio::piped())
+        .stdout(std::process::Stdio::piped())
+        .spawn()
+        .context("failed to spawn command")?;
+
+    // Write from a separate thread so a resolver that writes its output before
+    // it has consumed all of its input can't deadlock us
+    let mut stdin = child.stdin.take().context("unable to open stdin")?;
+    let writer = std::thread::spawn(move || stdin.write_all(&request));
+
+    let output = child
+        .wait_with_output()
+        .context("failed to wait for command")?;
+
+    writer
+        .join()
+        .map_err(|_err| anyhow::anyhow!("stdin writer thread panicked"))?
+        .context("failed to write request to stdin")?;
+
+    anyhow::ensure!(
+        output.status.success(),
+        "command failed with exit code {}",
+        output.status.code().unwrap_or(-1)
+    );
+
+    serde_json::from_slice(&output.stdout).context("failed to deserialize decisions")
+}
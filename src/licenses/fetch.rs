@@ -1,10 +1,17 @@
-use super::{config, Krate};
+use super::{config, http_cache::HttpCache, Krate};
 use anyhow::Context as _;
-use krates::Utf8Path as Path;
-use reqwest::blocking::Client;
-use std::{io::Read, sync::Arc};
+use krates::{Utf8Path as Path, Utf8PathBuf as PathBuf};
+use std::{sync::Arc, time::Duration};
 use url::Url;
 
+/// The default number of files retrieved from remote git hosts concurrently
+pub const DEFAULT_CONCURRENCY: usize = 8;
+/// The default number of times a failed retrieval is retried
+pub const DEFAULT_RETRIES: u32 = 3;
+/// The maximum size of a file retrieved from a remote git host, license files
+/// are at most a few dozen kilobytes
+pub const MAX_FILE_SIZE: usize = 1024 * 1024;
+
 #[derive(Copy, Clone, Debug)]
 enum GitHostFlavor {
     Github,
@@ -29,21 +36,15 @@ impl GitHostFlavor {
     /// Fetches the file contents of a path from the specific repository via
     /// a third party site for now until I can find a better solution, that still
     /// doesn't mean requiring access tokens or cloning the entire repository
-    fn fetch(self, client: &Client, repo: &Url, rev: &str, path: &Path) -> anyhow::Result<String> {
-        let project = repo
-            .path()
-            .strip_prefix('/')
-            .context("repo url does not have valid path")?;
-
-        // Some crates in repos with a workspace will try and be nice and give
-        // a subpath as the repo, which is friendly to users, but screws up
-        // things here, so we just chop off excess path parameters
-        let first = project.find('/').context("expected an <org/repo> path")?;
-
-        let project = match project[first + 1..].find('/') {
-            Some(second) => &project[..first + second + 1],
-            None => project,
-        };
+    fn fetch(
+        self,
+        http_cache: &HttpCache,
+        repo: &Url,
+        rev: &str,
+        path: &Path,
+    ) -> anyhow::Result<String> {
+        let client = http_cache.client();
+        let project = project(repo)?;
 
         let req = match self {
             Self::Github => {
@@ -66,17 +67,70 @@ impl GitHostFlavor {
             }
         };
 
-        let mut res = req
-            .send()
-            .context("failed to send request")?
-            .error_for_status()?;
+        let body = http_cache
+            .send_checked(
+                req.build().context("failed to build request")?,
+                Some(MAX_FILE_SIZE),
+                |body| check_contents(path, body).map(drop),
+            )
+            .context("failed to send request")?;
 
-        let mut contents = String::with_capacity(res.content_length().unwrap_or(1024) as usize);
-        res.read_to_string(&mut contents)
-            .context("failed to read contents as utf-8")?;
+        String::from_utf8(body).context("failed to read contents as utf-8")
+    }
+}
 
-        Ok(contents)
+/// The `<org>/<repo>` path of the repository url
+fn project(repo: &Url) -> anyhow::Result<&str> {
+    let project = repo
+        .path()
+        .strip_prefix('/')
+        .context("repo url does not have valid path")?;
+
+    // Some crates in repos with a workspace will try and be nice and give
+    // a subpath as the repo, which is friendly to users, but screws up
+    // things here, so we just chop off excess path parameters
+    let first = project.find('/').context("expected an <org/repo> path")?;
+
+    Ok(match project[first + 1..].find('/') {
+        Some(second) => &project[..first + second + 1],
+        None => project,
+    })
+}
+
+/// Checks that the body of a successful response is actually the text of the
+/// file, as the CDN can respond with an HTML error page rather than an error
+/// status, which would otherwise only be noticed as a checksum mismatch
+fn check_contents<'b>(path: &Path, body: &'b [u8]) -> anyhow::Result<&'b str> {
+    anyhow::ensure!(
+        body.len() <= MAX_FILE_SIZE,
+        "response is {} bytes, larger than the maximum of {MAX_FILE_SIZE} bytes for a retrieved file",
+        body.len()
+    );
+    anyhow::ensure!(
+        !body.contains(&0),
+        "response is binary rather than a text file"
+    );
+
+    let contents = std::str::from_utf8(body).context("failed to read contents as utf-8")?;
+
+    let is_html_file = path
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("html") || ext.eq_ignore_ascii_case("htm"));
+    if !is_html_file {
+        let start = contents.trim_start().as_bytes();
+        let starts_with = |prefix: &str| {
+            start
+                .get(..prefix.len())
+                .is_some_and(|s| s.eq_ignore_ascii_case(prefix.as_bytes()))
+        };
+
+        anyhow::ensure!(
+            !starts_with("<!doctype html") && !starts_with("<html"),
+            "response is an HTML page rather than the contents of the file, the host may have returned an error page"
+        );
     }
+
+    Ok(contents)
 }
 
 /// The information for the git commit when a crate was published
@@ -98,24 +152,105 @@ pub struct VcsInfo {
 /// but not in the actual published package is due to it being in the root but
 /// not copied into each sub-crate in the repository, we can just not re-retrieve
 /// the same file multiple times
+///
+/// Since a file at a specific commit never changes, successfully retrieved
+/// files are also persisted to disk, so that later runs don't need to retrieve
+/// them again, even in offline mode
 #[derive(Clone)]
 pub struct GitCache {
     cache: Arc<parking_lot::RwLock<std::collections::HashMap<u64, Arc<String>>>>,
-    http_client: Option<Client>,
+    http_client: Option<HttpCache>,
+    /// The directory retrieved files are persisted to, if `None` they are
+    /// only cached in memory
+    root: Option<PathBuf>,
+    /// The directory of unpacked crate sources used in place of the registry,
+    /// files are retrieved from its `.repos` directory rather than remote
+    /// git hosts
+    registry_dir: Option<PathBuf>,
+    concurrency: usize,
+    retries: u32,
 }
 
 impl GitCache {
-    pub fn maybe_offline(http_client: Option<Client>) -> Self {
+    pub fn maybe_offline(http_client: Option<HttpCache>, root: Option<PathBuf>) -> Self {
         Self {
             http_client,
             cache: Default::default(),
+            root,
+            registry_dir: None,
+            concurrency: DEFAULT_CONCURRENCY,
+            retries: DEFAULT_RETRIES,
         }
     }
 
-    pub fn online() -> Self {
-        Self {
-            http_client: Some(Client::new()),
-            cache: Default::default(),
+    /// Creates a cache that retrieves files from remote hosts, using the user
+    /// agent and cache directories of the configuration
+    pub fn online(cfg: &config::Config) -> anyhow::Result<Self> {
+        let client = super::http_cache::client_builder(cfg.user_agent_suffix.as_deref())
+            .build()
+            .context("failed to build HTTP client")?;
+
+        Ok(Self::maybe_offline(
+            Some(HttpCache::new(client, cfg.http_cache_root())),
+            cfg.git_cache_root(),
+        ))
+    }
+
+    /// The default location for persisted files, `$CARGO_HOME/cargo-about/git`
+    pub fn default_root() -> Option<PathBuf> {
+        let cargo_home = home::cargo_home().ok()?;
+        let cargo_home = PathBuf::from_path_buf(cargo_home).ok()?;
+        Some(cargo_home.join("cargo-about/git"))
+    }
+
+    /// Retrieves files from `<registry_dir>/.repos/<host>/<org>/<repo>/<rev>/<path>`
+    /// rather than from remote git hosts, so that retrieval can be exercised
+    /// without network access
+    pub fn with_registry_dir(mut self, registry_dir: Option<PathBuf>) -> Self {
+        self.registry_dir = registry_dir;
+        self
+    }
+
+    /// Sets the maximum number of files retrieved concurrently
+    pub fn with_concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency.max(1);
+        self
+    }
+
+    /// Sets the number of times a failed retrieval is retried, with an
+    /// exponential backoff between each attempt
+    pub fn with_retries(mut self, retries: u32) -> Self {
+        self.retries = retries;
+        self
+    }
+
+    /// Maps each item in parallel, with no more than the configured
+    /// concurrency at once, returning the results in the same order as the
+    /// items
+    pub(crate) fn map_concurrent<T, R>(
+        &self,
+        items: Vec<T>,
+        f: impl Fn(T) -> R + Send + Sync,
+    ) -> Vec<R>
+    where
+        T: Send,
+        R: Send,
+    {
+        use rayon::prelude::*;
+
+        if items.len() < 2 {
+            return items.into_iter().map(f).collect();
+        }
+
+        match rayon::ThreadPoolBuilder::new()
+            .num_threads(self.concurrency.min(items.len()))
+            .build()
+        {
+            Ok(pool) => pool.install(|| items.into_par_iter().map(f).collect()),
+            Err(err) => {
+                log::warn!("failed to create thread pool, retrieving files serially: {err}");
+                items.into_iter().map(f).collect()
+            }
         }
     }
 
@@ -173,6 +308,22 @@ impl GitCache {
         let repo_url = url::Url::parse(repo)
             .with_context(|| format!("unable to parse repository url '{repo}'"))?;
 
+        if let Some(registry_dir) = &self.registry_dir {
+            let host = repo_url
+                .domain()
+                .context("the repo url is malformed and does not contain a domain")?;
+            let local_path = registry_dir
+                .join(".repos")
+                .join(host)
+                .join(project(&repo_url)?)
+                .join(rev)
+                .join(path);
+
+            return std::fs::read_to_string(&local_path).with_context(|| {
+                format!("failed to read contents of '{path}' for repo '{repo}' from '{local_path}'")
+            });
+        }
+
         let http_client = self
             .http_client
             .as_ref()
@@ -187,9 +338,25 @@ impl GitCache {
         // packaged properly with the license(s) included
         let flavor = GitHostFlavor::from_repo(&repo_url)?;
 
-        flavor
-            .fetch(http_client, &repo_url, rev, path)
-            .with_context(|| format!("failed to fetch contents of '{path}' from repo '{repo}'"))
+        let mut attempt = 0;
+        loop {
+            match flavor.fetch(http_client, &repo_url, rev, path) {
+                Ok(contents) => return Ok(contents),
+                Err(err) if attempt < self.retries && is_transient(&err) => {
+                    let backoff = Duration::from_millis(250 << attempt);
+                    log::debug!(
+                        "failed to fetch contents of '{path}' from repo '{repo}', retrying in {backoff:?}: {err:#}"
+                    );
+                    std::thread::sleep(backoff);
+                    attempt += 1;
+                }
+                Err(err) => {
+                    return Err(err.context(format!(
+                        "failed to fetch contents of '{path}' from repo '{repo}'"
+                    )));
+                }
+            }
+        }
     }
 
     /// Parses a `.cargo_vcs_info.json` located in the root of a packaged crate
@@ -252,7 +419,40 @@ impl GitCache {
                         return Ok(text.clone());
                     }
 
-                    let contents = Arc::new(self.retrieve_remote(repo, &sha1, &file.path)?);
+                    let disk_path = self
+                        .root
+                        .as_ref()
+                        .map(|root| root.join(format!("{hash:016x}")));
+
+                    // Another process, or thread, might be retrieving the same
+                    // file, in which case we wait for it and use the file it
+                    // persisted rather than retrieving it again
+                    let _lock = disk_path
+                        .as_deref()
+                        .and_then(super::cache_dir::EntryLock::acquire);
+
+                    let contents = if let Some(contents) = disk_path
+                        .as_ref()
+                        .and_then(|path| std::fs::read_to_string(path).ok())
+                    {
+                        log::debug!("using persisted '{}' for crate '{krate}'", file.path);
+                        Arc::new(contents)
+                    } else {
+                        let contents = self.retrieve_remote(repo, &sha1, &file.path)?;
+
+                        if let Some(path) = &disk_path {
+                            if let Err(err) =
+                                super::cache_dir::write_atomic(path, contents.as_bytes())
+                            {
+                                log::warn!(
+                                    "failed to persist '{}' for crate '{krate}': {err:#}",
+                                    file.path
+                                );
+                            }
+                        }
+
+                        Arc::new(contents)
+                    };
 
                     self.cache.write().insert(hash, contents.clone());
 
@@ -270,16 +470,73 @@ impl GitCache {
     }
 }
 
+/// Only timeouts, failures to connect, server errors, and being rate limited
+/// may succeed if the request is retried, everything else, eg. a response that
+/// isn't the contents of the file, will fail the same way again
+fn is_transient(err: &anyhow::Error) -> bool {
+    let Some(err) = err
+        .chain()
+        .find_map(|err| err.downcast_ref::<reqwest::Error>())
+    else {
+        return false;
+    };
+
+    if err.is_timeout() || err.is_connect() {
+        return true;
+    }
+
+    err.status().is_some_and(|status| {
+        status.is_server_error() || status == reqwest::StatusCode::TOO_MANY_REQUESTS
+    })
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
+    use reqwest::blocking::Client;
+
+    #[test]
+    fn rejects_html_error_pages_and_oversized_files() {
+        let path = Path::new("LICENSE");
+
+        assert_eq!(
+            check_contents(path, b"MIT License\n").unwrap(),
+            "MIT License\n"
+        );
+
+        for page in [
+            "<!DOCTYPE html><p>502</p>",
+            "\n  <HTML><body>error</body></html>",
+        ] {
+            let err = check_contents(path, page.as_bytes()).unwrap_err();
+            assert!(err.to_string().contains("HTML page"), "{err}");
+        }
+        assert!(check_contents(Path::new("about.html"), b"<html></html>").is_ok());
+
+        assert!(check_contents(path, b"\x7fELF\0\0").is_err());
+        assert!(check_contents(path, &vec![b'a'; MAX_FILE_SIZE + 1]).is_err());
+    }
+
+    #[test]
+    fn does_not_retry_invalid_contents() {
+        let err = check_contents(Path::new("LICENSE"), b"<html></html>").unwrap_err();
+        assert!(!is_transient(&err.context("failed to fetch")));
+    }
+
+    #[test]
+    fn maps_concurrently_in_order() {
+        let gc = GitCache::maybe_offline(None, None).with_concurrency(4);
+
+        let mapped = gc.map_concurrent((0..100).collect(), |i| i * 2);
+        assert_eq!(mapped, (0..100).map(|i| i * 2).collect::<Vec<_>>());
+    }
 
     #[test]
     #[ignore = "online"]
     fn fetches_github() {
         let contents = GitHostFlavor::Github
             .fetch(
-                &Client::new(),
+                &HttpCache::new(Client::new(), None),
                 &Url::parse("https://github.com/EmbarkStudios/cargo-about").unwrap(),
                 "6f0d247ee7f7b6842abc180c2e4e96581e454ca8", /* 0.3.0 commit */
                 Path::new("LICENSE-MIT"),
@@ -298,7 +555,7 @@ mod test {
     fn fetches_gitlab() {
         let contents = GitHostFlavor::Gitlab
             .fetch(
-                &Client::new(),
+                &HttpCache::new(Client::new(), None),
                 &Url::parse("https://gitlab.com/veloren/veloren").unwrap(),
                 "f92c6fbd49269b6e2cad04ae229d3405a6656053",
                 Path::new("LICENSE"),
@@ -317,7 +574,7 @@ mod test {
     fn fetches_bitbucket() {
         let contents = GitHostFlavor::Bitbucket
             .fetch(
-                &Client::new(),
+                &HttpCache::new(Client::new(), None),
                 &Url::parse("https://bitbucket.org/atlassian/pipelines-examples-rust/").unwrap(),
                 "581100fe400cd0cfb17f54c2aa26121181f82646",
                 Path::new("README.md"),
@@ -0,0 +1,460 @@
+//! A small on-disk cache of HTTP responses, which are revalidated with the
+//! remote server via `ETag` and/or `Last-Modified` rather than being downloaded
+//! again, keeping repeated runs fast and reducing load on the remote APIs
+//!
+//! Each entry is a single file, containing the validators as a line of JSON
+//! followed by the body, so that the validators and the body are always
+//! replaced together when the cache is shared by multiple processes.
+
+use anyhow::Context as _;
+use krates::Utf8PathBuf as PathBuf;
+use parking_lot::Mutex;
+use reqwest::{
+    blocking::{Client, ClientBuilder, Request},
+    header, StatusCode,
+};
+use std::{collections::BTreeSet, sync::Arc};
+
+/// Creates a client builder with the `cargo-about/<version>` user agent,
+/// followed by the specified suffix, if any
+pub fn client_builder(user_agent_suffix: Option<&str>) -> ClientBuilder {
+    let mut user_agent = concat!("cargo-about/", env!("CARGO_PKG_VERSION")).to_owned();
+    if let Some(suffix) = user_agent_suffix.filter(|suffix| !suffix.is_empty()) {
+        user_agent.push(' ');
+        user_agent.push_str(suffix);
+    }
+
+    Client::builder().user_agent(user_agent)
+}
+
+/// Records the requests that would be sent, rather than sending them, so
+/// that the network access of a run can be audited
+#[derive(Clone, Default)]
+pub struct NetworkAudit {
+    requests: Arc<Mutex<BTreeSet<String>>>,
+}
+
+impl NetworkAudit {
+    fn record(&self, req: &Request) {
+        self.requests
+            .lock()
+            .insert(format!("{} {}", req.method(), req.url()));
+    }
+
+    /// The unique requests that would have been sent, in the form
+    /// `<method> <url>`
+    pub fn requests(&self) -> Vec<String> {
+        self.requests.lock().iter().cloned().collect()
+    }
+}
+
+/// The validators for a cached response
+#[derive(serde::Serialize, serde::Deserialize)]
+struct Meta {
+    url: String,
+    etag: Option<String>,
+    last_modified: Option<String>,
+}
+
+/// An HTTP client that caches successful responses on disk
+#[derive(Clone)]
+pub struct HttpCache {
+    client: Client,
+    /// The directory responses are cached in, if `None` no caching is done
+    root: Option<PathBuf>,
+    /// If set, requests are recorded rather than sent
+    audit: Option<NetworkAudit>,
+}
+
+impl HttpCache {
+    /// Creates a cache that stores responses in the specified directory, or
+    /// doesn't cache anything if `None`
+    pub fn new(client: Client, root: Option<PathBuf>) -> Self {
+        Self {
+            client,
+            root,
+            audit: None,
+        }
+    }
+
+    /// Records every request in the audit and fails it, rather than sending it
+    pub fn with_audit(mut self, audit: Option<NetworkAudit>) -> Self {
+        self.audit = audit;
+        self
+    }
+
+    /// The default location for the cache, `$CARGO_HOME/cargo-about/http`
+    pub fn default_root() -> Option<PathBuf> {
+        let cargo_home = home::cargo_home().ok()?;
+        let cargo_home = PathBuf::from_path_buf(cargo_home).ok()?;
+        Some(cargo_home.join("cargo-about/http"))
+    }
+
+    #[inline]
+    pub fn client(&self) -> &Client {
+        &self.client
+    }
+
+    /// Sends the request, returning the body of a successful response. If a
+    /// response for the same request was previously cached, the request is
+    /// made conditional, and the cached body is returned if the server
+    /// responds that it has not been modified.
+    #[inline]
+    pub fn send(&self, req: Request) -> anyhow::Result<Vec<u8>> {
+        self.send_checked(req, None, |_| Ok(()))
+    }
+
+    /// Sends the request the same as [`Self::send`], but fails if the body is
+    /// larger than `max_size`, without reading the rest of it, or if the body
+    /// fails the `check`. Bodies that fail are never cached.
+    pub fn send_checked(
+        &self,
+        mut req: Request,
+        max_size: Option<usize>,
+        check: impl Fn(&[u8]) -> anyhow::Result<()>,
+    ) -> anyhow::Result<Vec<u8>> {
+        if let Some(audit) = &self.audit {
+            audit.record(&req);
+            anyhow::bail!(
+                "network access is disabled while auditing, '{}' was not sent",
+                req.url()
+            );
+        }
+
+        let Some(root) = &self.root else {
+            let body = Self::into_body(self.client.execute(req)?.error_for_status()?, max_size)?;
+            check(&body)?;
+            return Ok(body);
+        };
+
+        let key = Self::key(&req);
+        let entry_path = root.join(format!("{key}.entry"));
+
+        // Concurrent requests for the same entry, eg. from multiple CI jobs
+        // sharing the cache, wait for each other so that only the first one
+        // retrieves the body, and the rest only revalidate it
+        let _lock = super::cache_dir::EntryLock::acquire(&entry_path);
+
+        let cached = std::fs::read(&entry_path).ok().and_then(Self::parse_entry);
+
+        if let Some((meta, _)) = &cached {
+            let headers = req.headers_mut();
+            if let Some(etag) = meta
+                .etag
+                .as_deref()
+                .and_then(|v| header::HeaderValue::from_str(v).ok())
+            {
+                headers.insert(header::IF_NONE_MATCH, etag);
+            }
+            if let Some(lm) = meta
+                .last_modified
+                .as_deref()
+                .and_then(|v| header::HeaderValue::from_str(v).ok())
+            {
+                headers.insert(header::IF_MODIFIED_SINCE, lm);
+            }
+        }
+
+        let url = req.url().to_string();
+        let res = self.client.execute(req)?;
+
+        if res.status() == StatusCode::NOT_MODIFIED {
+            if let Some((_, body)) = cached {
+                log::debug!("using cached response for '{url}'");
+                check(&body)?;
+                return Ok(body);
+            }
+        }
+
+        let res = res.error_for_status()?;
+
+        let header = |name| {
+            res.headers()
+                .get(name)
+                .and_then(|v| v.to_str().ok())
+                .map(String::from)
+        };
+
+        let meta = Meta {
+            url,
+            etag: header(header::ETAG),
+            last_modified: header(header::LAST_MODIFIED),
+        };
+
+        let body = Self::into_body(res, max_size)?;
+        check(&body)?;
+
+        // Responses without any validators can't be revalidated, so there's
+        // no point in caching them
+        if meta.etag.is_some() || meta.last_modified.is_some() {
+            let mut entry = serde_json::to_vec(&meta)?;
+            entry.push(b'\n');
+            entry.extend_from_slice(&body);
+
+            if let Err(err) = super::cache_dir::write_atomic(&entry_path, &entry) {
+                log::warn!("failed to cache response for '{}': {err:#}", meta.url);
+            }
+        }
+
+        Ok(body)
+    }
+
+    /// Reads the body of the response, but only up to one byte past the
+    /// maximum size, so that an oversized body is never buffered in full
+    fn into_body(
+        res: reqwest::blocking::Response,
+        max_size: Option<usize>,
+    ) -> anyhow::Result<Vec<u8>> {
+        use std::io::Read as _;
+
+        let limit = max_size.map_or(u64::MAX, |max| max as u64 + 1);
+        let capacity = res.content_length().unwrap_or(1024).min(limit);
+
+        let mut body = Vec::with_capacity(capacity as usize);
+        res.take(limit)
+            .read_to_end(&mut body)
+            .context("failed to read response body")?;
+
+        if let Some(max) = max_size {
+            anyhow::ensure!(
+                body.len() <= max,
+                "response is larger than the maximum of {max} bytes"
+            );
+        }
+
+        Ok(body)
+    }
+
+    /// Calculates the cache key for a request from its method, url, and body
+    fn key(req: &Request) -> String {
+        use std::hash::Hasher;
+        let mut hasher = twox_hash::XxHash64::default();
+
+        hasher.write(req.method().as_str().as_bytes());
+        hasher.write(req.url().as_str().as_bytes());
+        if let Some(body) = req.body().and_then(|b| b.as_bytes()) {
+            hasher.write(body);
+        }
+
+        format!("{:016x}", hasher.finish())
+    }
+
+    /// Splits a cache entry into its validators and body
+    fn parse_entry(mut entry: Vec<u8>) -> Option<(Meta, Vec<u8>)> {
+        let newline = entry.iter().position(|b| *b == b'\n')?;
+        let meta = serde_json::from_slice(&entry[..newline]).ok()?;
+        entry.drain(..=newline);
+        Some((meta, entry))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Serves each response to a single connection, in order, returning the
+    /// url to request, and the lowercased headers of each request received
+    fn serve(responses: Vec<String>) -> (String, std::thread::JoinHandle<Vec<String>>) {
+        use std::io::{BufRead as _, Write as _};
+
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/LICENSE", listener.local_addr().unwrap());
+
+        let server = std::thread::spawn(move || {
+            responses
+                .into_iter()
+                .map(|res| {
+                    let (mut stream, _) = listener.accept().unwrap();
+                    let mut reader = std::io::BufReader::new(stream.try_clone().unwrap());
+
+                    let mut headers = String::new();
+                    loop {
+                        let mut line = String::new();
+                        if reader.read_line(&mut line).unwrap() == 0 || line == "\r\n" {
+                            break;
+                        }
+                        headers.push_str(&line.to_ascii_lowercase());
+                    }
+
+                    stream.write_all(res.as_bytes()).unwrap();
+                    headers
+                })
+                .collect()
+        });
+
+        (url, server)
+    }
+
+    fn response(status: &str, headers: &[&str], body: &str) -> String {
+        let mut res = format!(
+            "HTTP/1.1 {status}\r\nconnection: close\r\ncontent-length: {}\r\n",
+            body.len()
+        );
+        for header in headers {
+            res.push_str(header);
+            res.push_str("\r\n");
+        }
+        res.push_str("\r\n");
+        res.push_str(body);
+        res
+    }
+
+    struct Fixture {
+        _dir: assert_fs::TempDir,
+        root: PathBuf,
+        cache: HttpCache,
+    }
+
+    impl Fixture {
+        fn new() -> Self {
+            let dir = assert_fs::TempDir::new().unwrap();
+            let root = PathBuf::from_path_buf(dir.path().join("http")).unwrap();
+            let cache = HttpCache::new(Client::new(), Some(root.clone()));
+
+            Self {
+                _dir: dir,
+                root,
+                cache,
+            }
+        }
+
+        fn get(&self, url: &str) -> anyhow::Result<Vec<u8>> {
+            self.cache.send(self.cache.client().get(url).build()?)
+        }
+
+        fn entries(&self) -> Vec<PathBuf> {
+            let Ok(rd) = self.root.read_dir_utf8() else {
+                return Vec::new();
+            };
+
+            rd.filter_map(|entry| {
+                let path = entry.ok()?.into_path();
+                (path.extension() == Some("entry")).then_some(path)
+            })
+            .collect()
+        }
+    }
+
+    #[test]
+    fn stores_and_revalidates_with_etag() {
+        let fx = Fixture::new();
+        let (url, server) = serve(vec![
+            response("200 OK", &["etag: \"v1\""], "MIT License"),
+            response("304 Not Modified", &[], ""),
+        ]);
+
+        assert_eq!(fx.get(&url).unwrap(), b"MIT License");
+        assert_eq!(fx.entries().len(), 1);
+        assert_eq!(fx.get(&url).unwrap(), b"MIT License");
+
+        let requests = server.join().unwrap();
+        assert!(!requests[0].contains("if-none-match"));
+        assert!(requests[1].contains("if-none-match: \"v1\""));
+    }
+
+    #[test]
+    fn stores_and_revalidates_with_last_modified() {
+        let fx = Fixture::new();
+        let (url, server) = serve(vec![
+            response(
+                "200 OK",
+                &["last-modified: Tue, 19 Nov 2024 10:00:00 GMT"],
+                "MIT License",
+            ),
+            response("304 Not Modified", &[], ""),
+        ]);
+
+        assert_eq!(fx.get(&url).unwrap(), b"MIT License");
+        assert_eq!(fx.get(&url).unwrap(), b"MIT License");
+
+        let requests = server.join().unwrap();
+        assert!(!requests[0].contains("if-modified-since"));
+        assert!(requests[1].contains("if-modified-since: tue, 19 nov 2024 10:00:00 gmt"));
+    }
+
+    #[test]
+    fn does_not_store_responses_without_validators() {
+        let fx = Fixture::new();
+        let (url, server) = serve(vec![
+            response("200 OK", &[], "first"),
+            response("200 OK", &[], "second"),
+        ]);
+
+        assert_eq!(fx.get(&url).unwrap(), b"first");
+        assert!(fx.entries().is_empty());
+        assert_eq!(fx.get(&url).unwrap(), b"second");
+
+        let requests = server.join().unwrap();
+        assert!(!requests[1].contains("if-none-match"));
+        assert!(!requests[1].contains("if-modified-since"));
+    }
+
+    #[test]
+    fn replaces_stale_entries() {
+        let fx = Fixture::new();
+        let (url, server) = serve(vec![
+            response("200 OK", &["etag: \"v1\""], "old"),
+            response("200 OK", &["etag: \"v2\""], "new"),
+            response("304 Not Modified", &[], ""),
+        ]);
+
+        assert_eq!(fx.get(&url).unwrap(), b"old");
+        assert_eq!(fx.get(&url).unwrap(), b"new");
+        assert_eq!(fx.get(&url).unwrap(), b"new");
+        assert_eq!(fx.entries().len(), 1);
+
+        let requests = server.join().unwrap();
+        assert!(requests[1].contains("if-none-match: \"v1\""));
+        assert!(requests[2].contains("if-none-match: \"v2\""));
+    }
+
+    #[test]
+    fn ignores_corrupt_entries() {
+        let fx = Fixture::new();
+        let (url, server) = serve(vec![
+            response("200 OK", &["etag: \"v1\""], "MIT License"),
+            response("200 OK", &["etag: \"v1\""], "MIT License"),
+            response("304 Not Modified", &[], ""),
+        ]);
+
+        assert_eq!(fx.get(&url).unwrap(), b"MIT License");
+        for entry in fx.entries() {
+            std::fs::write(entry, b"not json\nMIT License").unwrap();
+        }
+
+        // The corrupt entry is neither used to revalidate, nor returned, but
+        // is replaced by the next successful response
+        assert_eq!(fx.get(&url).unwrap(), b"MIT License");
+        assert_eq!(fx.get(&url).unwrap(), b"MIT License");
+
+        let requests = server.join().unwrap();
+        assert!(!requests[1].contains("if-none-match"));
+        assert!(requests[2].contains("if-none-match: \"v1\""));
+    }
+
+    #[test]
+    fn does_not_store_bodies_that_fail_checks() {
+        let fx = Fixture::new();
+        let (url, server) = serve(vec![
+            response("200 OK", &["etag: \"v1\""], "0123456789"),
+            response("200 OK", &["etag: \"v1\""], "<html></html>"),
+        ]);
+
+        let send = |max_size| {
+            fx.cache.send_checked(
+                fx.cache.client().get(&url).build().unwrap(),
+                Some(max_size),
+                |body| {
+                    anyhow::ensure!(!body.starts_with(b"<html>"), "error page");
+                    Ok(())
+                },
+            )
+        };
+
+        assert!(send(4).is_err());
+        assert!(send(1024).is_err());
+        assert!(fx.entries().is_empty());
+
+        server.join().unwrap();
+    }
+}
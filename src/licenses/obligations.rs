@@ -0,0 +1,252 @@
+//! A small, built-in table of license families and the broad class of
+//! obligations they impose, so that users can get a rough idea of what the
+//! licenses in their dependency graph imply. This is not legal advice!
+
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// The broad family a license belongs to
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Family {
+    /// Permissive licenses, eg. MIT, Apache-2.0, BSD-3-Clause
+    Permissive,
+    /// Licenses whose copyleft terms apply at the file or library level, eg.
+    /// LGPL-2.1, MPL-2.0
+    WeakCopyleft,
+    /// Licenses whose copyleft terms apply to the entire combined work, eg.
+    /// GPL-3.0
+    StrongCopyleft,
+    /// Strong copyleft licenses which also treat providing the software over
+    /// a network as distribution, eg. AGPL-3.0
+    NetworkCopyleft,
+}
+
+impl Family {
+    /// The class of obligation that is implied by the family
+    #[inline]
+    pub fn obligation(self) -> Obligation {
+        match self {
+            Self::Permissive => Obligation::NoticeOnly,
+            Self::WeakCopyleft => Obligation::SameLicense,
+            Self::StrongCopyleft | Self::NetworkCopyleft => Obligation::SourceOffer,
+        }
+    }
+
+    /// A short, plain language summary of what the family implies
+    pub fn summary(self) -> &'static str {
+        match self {
+            Self::Permissive => {
+                "the license text and copyright notices must be included when distributing"
+            }
+            Self::WeakCopyleft => {
+                "modifications to the licensed files or library must be made available under the same license, and it must be possible to replace the library in the combined work"
+            }
+            Self::StrongCopyleft => {
+                "the entire combined work must be distributed under the same license, with an offer of the complete corresponding source code"
+            }
+            Self::NetworkCopyleft => {
+                "the entire combined work must be distributed under the same license, with an offer of the complete corresponding source code, including to users interacting with it over a network"
+            }
+        }
+    }
+
+    #[inline]
+    pub fn is_copyleft(self) -> bool {
+        !matches!(self, Self::Permissive)
+    }
+}
+
+impl fmt::Display for Family {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Permissive => "permissive",
+            Self::WeakCopyleft => "weak-copyleft",
+            Self::StrongCopyleft => "strong-copyleft",
+            Self::NetworkCopyleft => "network-copyleft",
+        })
+    }
+}
+
+/// The broad class of obligation imposed by a license
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Obligation {
+    /// Only the license text and notices need to be included
+    NoticeOnly,
+    /// Modifications need to be released under the same license
+    SameLicense,
+    /// The source code of the combined work must be offered
+    SourceOffer,
+}
+
+impl fmt::Display for Obligation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::NoticeOnly => "notice-only",
+            Self::SameLicense => "same-license",
+            Self::SourceOffer => "source-offer",
+        })
+    }
+}
+
+/// A single requirement in the obligations checklist of a license family
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Requirement {
+    /// The license text must be included
+    IncludeLicense,
+    /// Copyright and `NOTICE` notices must be retained
+    IncludeNotices,
+    /// Modified files must be marked as changed
+    StateChanges,
+    /// Modifications must be released under the same license
+    ShareModifications,
+    /// The source code of the combined work must be offered
+    ProvideSource,
+    /// The source code must also be offered to users interacting with the
+    /// software over a network
+    NetworkSource,
+}
+
+impl Requirement {
+    /// Every requirement, in the order they appear in a checklist
+    pub const ALL: [Self; 6] = [
+        Self::IncludeLicense,
+        Self::IncludeNotices,
+        Self::StateChanges,
+        Self::ShareModifications,
+        Self::ProvideSource,
+        Self::NetworkSource,
+    ];
+
+    /// A short, human readable description of the requirement
+    pub fn description(self) -> &'static str {
+        match self {
+            Self::IncludeLicense => "include the license text",
+            Self::IncludeNotices => "include copyright notices and NOTICE files",
+            Self::StateChanges => "state changes made to the licensed files",
+            Self::ShareModifications => "release modifications under the same license",
+            Self::ProvideSource => "provide the source code of the combined work",
+            Self::NetworkSource => "provide the source code to users over a network",
+        }
+    }
+
+    /// Whether the license imposes the requirement
+    pub fn applies(self, id: spdx::LicenseId) -> bool {
+        let family = family(id);
+
+        match self {
+            Self::IncludeLicense | Self::IncludeNotices => true,
+            Self::StateChanges => family.is_copyleft() || STATE_CHANGES.contains(&id.name),
+            Self::ShareModifications => family.is_copyleft(),
+            Self::ProvideSource => {
+                matches!(family, Family::StrongCopyleft | Family::NetworkCopyleft)
+            }
+            Self::NetworkSource => family == Family::NetworkCopyleft,
+        }
+    }
+}
+
+impl fmt::Display for Requirement {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::IncludeLicense => "include-license",
+            Self::IncludeNotices => "include-notices",
+            Self::StateChanges => "state-changes",
+            Self::ShareModifications => "share-modifications",
+            Self::ProvideSource => "provide-source",
+            Self::NetworkSource => "network-source",
+        })
+    }
+}
+
+/// The permissive licenses that still require modified files to be marked
+/// as changed
+const STATE_CHANGES: &[&str] = &[
+    "Apache-2.0",
+    "Artistic-2.0",
+    "CC-BY-3.0",
+    "CC-BY-4.0",
+    "Zlib",
+];
+
+/// The copyleft licenses we know about, any license not in this table is
+/// treated as permissive unless the SPDX list itself marks it as copyleft, in
+/// which case we conservatively treat it as strong copyleft
+const FAMILIES: &[(&str, Family)] = &[
+    ("AGPL-1.0", Family::NetworkCopyleft),
+    ("AGPL-3.0", Family::NetworkCopyleft),
+    ("AGPL-3.0-only", Family::NetworkCopyleft),
+    ("AGPL-3.0-or-later", Family::NetworkCopyleft),
+    ("CC-BY-SA-4.0", Family::StrongCopyleft),
+    ("CDDL-1.0", Family::WeakCopyleft),
+    ("CDDL-1.1", Family::WeakCopyleft),
+    ("EPL-1.0", Family::WeakCopyleft),
+    ("EPL-2.0", Family::WeakCopyleft),
+    ("EUPL-1.1", Family::StrongCopyleft),
+    ("EUPL-1.2", Family::StrongCopyleft),
+    ("GPL-2.0", Family::StrongCopyleft),
+    ("GPL-2.0-only", Family::StrongCopyleft),
+    ("GPL-2.0-or-later", Family::StrongCopyleft),
+    ("GPL-3.0", Family::StrongCopyleft),
+    ("GPL-3.0-only", Family::StrongCopyleft),
+    ("GPL-3.0-or-later", Family::StrongCopyleft),
+    ("LGPL-2.0", Family::WeakCopyleft),
+    ("LGPL-2.0-only", Family::WeakCopyleft),
+    ("LGPL-2.0-or-later", Family::WeakCopyleft),
+    ("LGPL-2.1", Family::WeakCopyleft),
+    ("LGPL-2.1-only", Family::WeakCopyleft),
+    ("LGPL-2.1-or-later", Family::WeakCopyleft),
+    ("LGPL-3.0", Family::WeakCopyleft),
+    ("LGPL-3.0-only", Family::WeakCopyleft),
+    ("LGPL-3.0-or-later", Family::WeakCopyleft),
+    ("MPL-1.1", Family::WeakCopyleft),
+    ("MPL-2.0", Family::WeakCopyleft),
+    ("OSL-3.0", Family::NetworkCopyleft),
+    ("SSPL-1.0", Family::NetworkCopyleft),
+];
+
+/// Determines the family of the specified license
+pub fn family(id: spdx::LicenseId) -> Family {
+    FAMILIES
+        .iter()
+        .find_map(|(name, family)| (*name == id.name).then_some(*family))
+        .unwrap_or(if id.is_copyleft() {
+            Family::StrongCopyleft
+        } else {
+            Family::Permissive
+        })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn classifies() {
+        let fam = |id| family(spdx::license_id(id).unwrap());
+
+        assert_eq!(fam("MIT"), Family::Permissive);
+        assert_eq!(fam("Apache-2.0"), Family::Permissive);
+        assert_eq!(fam("MPL-2.0"), Family::WeakCopyleft);
+        assert_eq!(fam("LGPL-2.1"), Family::WeakCopyleft);
+        assert_eq!(fam("GPL-3.0"), Family::StrongCopyleft);
+        assert_eq!(fam("AGPL-3.0"), Family::NetworkCopyleft);
+        assert_eq!(fam("GPL-2.0").obligation(), Obligation::SourceOffer);
+    }
+
+    #[test]
+    fn checklist_requirements() {
+        let applies = |req: Requirement, id| req.applies(spdx::license_id(id).unwrap());
+
+        assert!(applies(Requirement::IncludeLicense, "MIT"));
+        assert!(!applies(Requirement::StateChanges, "MIT"));
+        assert!(applies(Requirement::StateChanges, "Apache-2.0"));
+        assert!(applies(Requirement::ShareModifications, "MPL-2.0"));
+        assert!(!applies(Requirement::ProvideSource, "MPL-2.0"));
+        assert!(applies(Requirement::ProvideSource, "GPL-3.0"));
+        assert!(!applies(Requirement::NetworkSource, "GPL-3.0"));
+        assert!(applies(Requirement::NetworkSource, "AGPL-3.0"));
+    }
+}
@@ -14,19 +14,27 @@ pub type Files = codespan::Files<String>;
 struct Accepted<'acc> {
     global: &'acc [Licensee],
     krate: Option<&'acc [Licensee]>,
+    denied: &'acc [Licensee],
 }
 
 impl<'acc> Accepted<'acc> {
     #[inline]
     fn satisfies(&self, req: &spdx::LicenseReq) -> bool {
-        self.iter().any(|licensee| licensee.satisfies(req))
+        !self.is_denied(req) && self.iter().any(|licensee| licensee.satisfies(req))
     }
 
+    #[inline]
+    fn is_denied(&self, req: &spdx::LicenseReq) -> bool {
+        self.denied.iter().any(|licensee| licensee.satisfies(req))
+    }
+
+    /// The accepted licensees, other than those that are also denied
     #[inline]
     fn iter(&'acc self) -> impl Iterator<Item = &'acc Licensee> {
         self.global
             .iter()
             .chain(self.krate.iter().flat_map(|o| o.iter()))
+            .filter(|licensee| !self.denied.contains(licensee))
     }
 }
 
@@ -60,9 +68,138 @@ impl fmt::Display for Accepted<'_> {
 pub struct Resolved {
     /// The minimum license requirements that are required
     pub licenses: Vec<LicenseReq>,
+    /// The subset of [`Self::licenses`] that are not accepted, only populated
+    /// when unaccepted licenses are included rather than failing
+    pub unaccepted: Vec<LicenseReq>,
     /// Diagnostics emitted during the course of the license resolution, may
     /// include errors
     pub diagnostics: Vec<Diagnostic>,
+    /// The requirements that could not be satisfied by the accepted licenses,
+    /// if any
+    pub unsatisfied: Option<Unsatisfied>,
+    /// True if the license requirements are only satisfied because of the
+    /// crate's entry in `exceptions`
+    pub excepted: bool,
+}
+
+/// The license requirements of a crate that could not be satisfied
+#[derive(Debug)]
+pub struct Unsatisfied {
+    /// The crate's license expression
+    pub expression: Expression,
+    /// The requirements that are not accepted
+    pub failed: Vec<LicenseReq>,
+    /// The requirements that are explicitly denied
+    pub denied: Vec<LicenseReq>,
+}
+
+impl Unsatisfied {
+    /// The licensees that could be accepted to satisfy the failed requirements
+    pub fn candidates(&self) -> impl Iterator<Item = Licensee> + '_ {
+        self.failed.iter().map(licensee)
+    }
+
+    /// Whether the expression would be satisfied if the specified licensees
+    /// were accepted in addition to the current ones
+    pub fn is_satisfied_by(&self, additional: &[Licensee]) -> bool {
+        self.expression.evaluate(|req| {
+            !self.denied.contains(req)
+                && (!self.failed.contains(req) || additional.iter().any(|l| l.satisfies(req)))
+        })
+    }
+}
+
+/// The licensee that satisfies the requirement, without its `+`, as that is
+/// not allowed for licensees
+fn licensee(req: &LicenseReq) -> Licensee {
+    let license = match &req.license {
+        spdx::LicenseItem::Spdx { id, .. } => spdx::LicenseItem::Spdx {
+            id: *id,
+            or_later: false,
+        },
+        other @ spdx::LicenseItem::Other { .. } => other.clone(),
+    };
+    Licensee::new(license, req.exception)
+}
+
+/// Suggests the licensees to add to `accepted` so that the license
+/// requirements of every crate that failed are satisfied
+///
+/// This is a greedy approximation of the smallest such set, preferring the
+/// licensees that satisfy the most crates. Crates that can't be satisfied by
+/// any addition, eg. because they only allow denied licenses, are ignored.
+pub fn suggest_accepted(resolved: &[Option<Resolved>]) -> Vec<Licensee> {
+    let unsatisfied: Vec<_> = resolved
+        .iter()
+        .flatten()
+        .filter_map(|res| res.unsatisfied.as_ref())
+        .filter(|us| us.is_satisfied_by(&us.candidates().collect::<Vec<_>>()))
+        .collect();
+
+    let mut candidates: Vec<_> = unsatisfied.iter().flat_map(|us| us.candidates()).collect();
+    candidates.sort();
+    candidates.dedup();
+
+    let mut chosen = Vec::new();
+
+    loop {
+        let remaining: Vec<_> = unsatisfied
+            .iter()
+            .filter(|us| !us.is_satisfied_by(&chosen))
+            .collect();
+
+        let Some(first) = remaining.first() else {
+            break;
+        };
+
+        let mut best = None;
+        for candidate in &candidates {
+            if chosen.contains(candidate) {
+                continue;
+            }
+
+            chosen.push(candidate.clone());
+            let count = remaining
+                .iter()
+                .filter(|us| us.is_satisfied_by(&chosen))
+                .count();
+            chosen.pop();
+
+            if count > best.as_ref().map_or(0, |(c, _)| *c) {
+                best = Some((count, candidate));
+            }
+        }
+
+        if let Some((_, candidate)) = best {
+            chosen.push(candidate.clone());
+            continue;
+        }
+
+        // No single licensee satisfies any of the remaining crates, as they
+        // require multiple licenses at once, eg. `MIT AND Apache-2.0`, so add
+        // the crate's candidates until it's satisfied, then remove any that
+        // turned out to be unnecessary
+        let start = chosen.len();
+        for candidate in first.candidates() {
+            if !chosen.contains(&candidate) {
+                chosen.push(candidate);
+                if first.is_satisfied_by(&chosen) {
+                    break;
+                }
+            }
+        }
+
+        let mut i = start;
+        while i < chosen.len() {
+            let removed = chosen.remove(i);
+            if !first.is_satisfied_by(&chosen) {
+                chosen.insert(i, removed);
+                i += 1;
+            }
+        }
+    }
+
+    chosen
 }
 
 /// Synthesizes a package manifest for a krate with the specified license expression
@@ -96,11 +233,22 @@ fn synthesize_manifest(
 }
 
 /// Find the minimal set of required licenses for each crate.
+///
+/// Licenses that satisfy one of the `denied` licensees are never used, even
+/// if they are also accepted. If `include_unaccepted` is true, crates whose
+/// license requirements can't be satisfied by the accepted licenses emit a
+/// warning rather than an error, and use the unaccepted licenses instead.
+///
+/// Every license of a crate with one of the `exceptions` is accepted for it,
+/// other than denied licenses.
 pub fn resolve(
     licenses: &[KrateLicense<'_>],
     accepted: &[Licensee],
+    denied: &[Licensee],
     krate_cfg: &std::collections::BTreeMap<String, config::KrateConfig>,
+    exceptions: &[config::Exception],
     fail_on_missing: bool,
+    include_unaccepted: bool,
 ) -> (Files, Vec<Option<Resolved>>) {
     let mut files = codespan::Files::new();
 
@@ -109,7 +257,10 @@ pub fn resolve(
         .map(|kl| {
             let mut resolved = Resolved {
                 licenses: Vec::new(),
+                unaccepted: Vec::new(),
                 diagnostics: Vec::new(),
+                unsatisfied: None,
+                excepted: false,
             };
 
             let manifest = std::fs::read_to_string(&kl.krate.manifest_path)
@@ -125,7 +276,7 @@ pub fn resolve(
 
             let expr = match &kl.lic_info {
                 LicenseInfo::Expr(expr) => std::borrow::Cow::Borrowed(expr),
-                LicenseInfo::Ignore => {
+                LicenseInfo::Ignore(_) => {
                     return None;
                 }
                 LicenseInfo::Unknown => {
@@ -220,53 +371,143 @@ pub fn resolve(
             };
 
             // Retrieve additional crate specific licenses
-            let accepted = match krate_cfg.get(&kl.krate.name) {
-                Some(kcfg) => {
-                    if kcfg.accepted.is_empty() {
-                        Accepted {
-                            global: accepted,
-                            krate: None,
-                        }
-                    } else {
-                        Accepted {
-                            global: accepted,
-                            krate: Some(&kcfg.accepted),
-                        }
-                    }
-                }
-                None => Accepted {
-                    global: accepted,
-                    krate: None,
-                },
+            let krate_accepted = config::find_krate_config(krate_cfg, &kl.krate.name, &kl.krate.version)
+                .map(|kcfg| kcfg.accepted.as_slice())
+                .filter(|accepted| !accepted.is_empty());
+
+            let exempt: Vec<_>;
+            let mut accepted = Accepted {
+                global: accepted,
+                krate: krate_accepted,
+                denied,
             };
 
+            // Crates with an exception accept every license in their expression,
+            // after the licenses that are accepted anyway, but only if they
+            // aren't satisfied by the accepted licenses alone, so that the
+            // exception is only reported when it is actually needed
+            if exceptions.iter().any(|exc| exc.name == kl.krate.name)
+                && !expr.evaluate(|req| accepted.satisfies(req))
+            {
+                exempt = krate_accepted
+                    .into_iter()
+                    .flatten()
+                    .cloned()
+                    .chain(expr.requirements().map(|er| licensee(&er.req)))
+                    .collect();
+                accepted.krate = Some(&exempt);
+                resolved.excepted = expr.evaluate(|req| accepted.satisfies(req));
+            }
+
             let manifest_file_id = files.add(kl.krate.manifest_path.clone(), manifest);
 
             // Evaluates the expression against the accepted licenses to ensure it can
             // be satisfied according to the user's configuration
+            let mut unaccepted = Vec::new();
             if let Err(failed) = expr.evaluate_with_failures(|req| accepted.satisfies(req)) {
-                resolved.diagnostics.push(
-                    Diagnostic::new(Severity::Error)
+                let span = |fr: &spdx::expression::ExpressionReq| {
+                    fr.span.start as usize + expr_offset..fr.span.end as usize + expr_offset
+                };
+
+                // Denied licenses get their own diagnostic, as they failed
+                // due to an explicit decision rather than an omission
+                let (denied, failed): (Vec<_>, Vec<_>) =
+                    failed.into_iter().partition(|fr| accepted.is_denied(&fr.req));
+
+                let any_denied = !denied.is_empty();
+                resolved.unsatisfied = Some(Unsatisfied {
+                    expression: expr.as_ref().clone(),
+                    failed: failed.iter().map(|fr| fr.req.clone()).collect(),
+                    denied: denied.iter().map(|fr| fr.req.clone()).collect(),
+                });
+
+                for fr in denied {
+                    resolved.diagnostics.push(
+                        Diagnostic::new(Severity::Error)
+                            .with_message(format!(
+                                "license '{}' is explicitly denied",
+                                &expr.as_ref().as_ref()[fr.span.start as usize..fr.span.end as usize]
+                            ))
+                            .with_labels(vec![Label::new(
+                                LabelStyle::Primary,
+                                manifest_file_id,
+                                span(fr),
+                            )
+                            .with_message("denied")]),
+                    );
+                }
+
+                // Fall back to the unaccepted licenses, after any accepted ones
+                unaccepted = failed.iter().map(|fr| licensee(&fr.req)).collect();
+
+                // Requirements that are only unsatisfied because of their
+                // exception get a diagnostic suggesting the exact licensee to
+                // accept, as otherwise it looks like the license itself is
+                // already accepted
+                let (missing_exception, other): (Vec<_>, Vec<_>) =
+                    failed.iter().zip(&unaccepted).partition(|(fr, _)| {
+                        fr.req.exception.is_some()
+                            && accepted.satisfies(&LicenseReq {
+                                license: fr.req.license.clone(),
+                                exception: None,
+                            })
+                    });
+
+                let severity = if include_unaccepted {
+                    Severity::Warning
+                } else {
+                    Severity::Error
+                };
+
+                for (fr, licensee) in missing_exception {
+                    let exception = fr.req.exception.map_or("", |e| e.name);
+                    resolved.diagnostics.push(
+                        Diagnostic::new(severity)
+                            .with_message(format!(
+                                "license '{}' is accepted, but not with the '{exception}' exception",
+                                fr.req.license
+                            ))
+                            .with_labels(vec![Label::new(
+                                LabelStyle::Primary,
+                                manifest_file_id,
+                                span(fr),
+                            )
+                            .with_message("exception not accepted")])
+                            .with_notes(vec![format!(
+                                "add \"{licensee}\" to `accepted` to accept the license with this exception"
+                            )]),
+                    );
+                }
+
+                if !other.is_empty() {
+                    resolved.diagnostics.push(
+                        Diagnostic::new(severity)
                         .with_message("failed to satisfy license requirements")
-                        .with_labels(
-                            failed
-                                .into_iter()
-                                .map(|fr| {
-                                    let span = fr.span.start as usize + expr_offset
-                                        ..fr.span.end as usize + expr_offset;
-                                    Label::new(LabelStyle::Secondary, manifest_file_id, span)
-                                })
-                                .collect(),
-                        ),
-                );
-
-                return Some(resolved);
+                            .with_labels(
+                                other
+                                    .into_iter()
+                                    .map(|(fr, _)| {
+                                        Label::new(LabelStyle::Secondary, manifest_file_id, span(fr))
+                                    })
+                                    .collect(),
+                            ),
+                    );
+                }
+
+                if !include_unaccepted || any_denied {
+                    return Some(resolved);
+                }
             }
 
             // Attempt to  find the minimal set of licenses needed to satisfy the
             // license requirements, in priority order
-            match expr.minimized_requirements(accepted.iter()) {
+            match expr.minimized_requirements(accepted.iter().chain(&unaccepted)) {
                 Ok(min_reqs) => {
+                    resolved.unaccepted = min_reqs
+                        .iter()
+                        .filter(|req| !accepted.satisfies(req))
+                        .cloned()
+                        .collect();
                     resolved.licenses = min_reqs;
                 }
                 Err(e) => {
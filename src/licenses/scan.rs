@@ -1,32 +1,242 @@
-use super::{LicenseFile, LicenseFileKind};
+use super::{archive, config, LicenseFile, LicenseFileKind};
 use krates::{Utf8Path as Path, Utf8PathBuf as PathBuf};
 use rayon::prelude::*;
+use std::{
+    sync::atomic::{AtomicBool, Ordering},
+    time::{Duration, Instant},
+};
+
+/// The results of scanning a single crate's source directory
+pub(crate) struct ScannedFiles {
+    /// The license files that were positively identified
+    pub(crate) license_files: Vec<LicenseFile>,
+    /// NOTICE files, which don't contain a license but whose contents must
+    /// be reproduced along with the license(s) of the crate
+    pub(crate) notices: Vec<(PathBuf, String)>,
+    /// Problems encountered with individual files, these don't necessarily
+    /// mean that detection failed for the crate as a whole
+    pub(crate) notes: Vec<String>,
+}
+
+enum Found {
+    License(Vec<LicenseFile>),
+    Notice(PathBuf, String),
+    /// The license and notice files found in an archive
+    Archive(Vec<LicenseFile>, Vec<(PathBuf, String)>),
+}
+
+/// Checks if the file is a NOTICE file, eg. `NOTICE` or `NOTICE.txt`
+fn is_notice_file(path: &Path) -> bool {
+    path.file_stem()
+        .is_some_and(|stem| stem.eq_ignore_ascii_case("notice"))
+        && path.extension().map_or(true, |ext| {
+            ext.eq_ignore_ascii_case("txt") || ext.eq_ignore_ascii_case("md")
+        })
+}
+
+/// Reads the `include` and `exclude` fields of the crate's manifest, which
+/// restrict the files that are packaged for the crate
+fn package_globs(root_dir: &Path) -> (Vec<String>, Vec<String>) {
+    let Ok(contents) = std::fs::read_to_string(root_dir.join("Cargo.toml")) else {
+        return Default::default();
+    };
+
+    let Ok(manifest) = contents.parse::<toml::Table>() else {
+        return Default::default();
+    };
+
+    let globs = |key: &str| -> Vec<String> {
+        manifest
+            .get("package")
+            .and_then(|package| package.get(key))
+            .and_then(|globs| globs.as_array())
+            .map(|globs| {
+                globs
+                    .iter()
+                    .filter_map(|glob| glob.as_str().map(String::from))
+                    .collect()
+            })
+            .unwrap_or_default()
+    };
+
+    let mut include = globs("include");
+
+    // The license file is always packaged, even if it isn't included
+    if let Some(license_file) = manifest
+        .get("package")
+        .and_then(|package| package.get("license-file"))
+        .and_then(|license_file| license_file.as_str())
+        .filter(|_| !include.is_empty())
+    {
+        include.push(format!("/{license_file}"));
+    }
+
+    (include, globs("exclude"))
+}
+
+/// Checks if the name of a file in an archive is one that could be a license
+/// or notice file, as archives can contain many files that aren't worth the
+/// cost of being scanned
+fn is_archived_license_name(name: &str) -> bool {
+    let Some(stem) = Path::new(name).file_stem() else {
+        return false;
+    };
+    let stem = stem.to_ascii_lowercase();
+
+    [
+        "license",
+        "licence",
+        "copying",
+        "copyright",
+        "notice",
+        "unlicense",
+    ]
+    .iter()
+    .any(|prefix| stem.starts_with(prefix))
+}
+
+/// Scans the license and notice files in an archive, which are attributed to
+/// `<archive>!/<path in archive>`. As with the nested license texts of a deep
+/// scan, the license texts in an archive only apply to the archive itself.
+fn scan_archive(
+    root_dir: &Path,
+    path: &Path,
+    strat: &askalono::ScanStrategy<'_>,
+    threshold: f32,
+) -> Result<Found, String> {
+    let entries = archive::entries(path, is_archived_license_name)?;
+
+    let sub_root: PathBuf = path
+        .strip_prefix(root_dir)
+        .unwrap_or(path)
+        .as_str()
+        .replace('\\', "/")
+        .into();
+
+    let mut license_files = Vec::new();
+    let mut notices = Vec::new();
+
+    for (name, contents) in entries {
+        let entry_path = PathBuf::from(format!("{path}!/{name}"));
+
+        if is_notice_file(Path::new(&name)) {
+            notices.push((entry_path, contents));
+            continue;
+        }
+
+        // A single entry that can't be identified doesn't prevent the rest of
+        // the archive from being used
+        let lfs = match check_is_license_file(entry_path, contents, strat, threshold) {
+            Ok(lfs) => lfs,
+            Err(err) => {
+                log::warn!("skipping entry '{name}' in '{path}': {err}");
+                continue;
+            }
+        };
+
+        for mut lf in lfs {
+            if let LicenseFileKind::Text(text) = &mut lf.kind {
+                let text = std::mem::take(text);
+                lf.kind = LicenseFileKind::AddendumText(text, sub_root.clone());
+            }
+
+            license_files.push(lf);
+        }
+    }
+
+    Ok(Found::Archive(license_files, notices))
+}
 
 pub(crate) fn scan_files(
     root_dir: &Path,
     strat: &askalono::ScanStrategy<'_>,
     threshold: f32,
     max_depth: Option<usize>,
-) -> anyhow::Result<Vec<LicenseFile>> {
-    let types = {
+    deep: bool,
+    scan_cfg: &config::Scan,
+    timeout: Option<Duration>,
+) -> anyhow::Result<ScannedFiles> {
+    let deadline = timeout.map(|timeout| Instant::now() + timeout);
+    let timed_out = || deadline.is_some_and(|deadline| Instant::now() >= deadline);
+
+    let mut builder = ignore::WalkBuilder::new(root_dir);
+    builder.standard_filters(true).follow_links(true);
+
+    // A deep scan walks every file, as the licenses of vendored sources are
+    // often nested deeply, or in files with extensions that aren't known
+    // text types. Binary files are still skipped when they are read.
+    if !deep {
         let mut tb = ignore::types::TypesBuilder::new();
         tb.add_defaults();
+
+        if scan_cfg.archives {
+            for ext in archive::EXTENSIONS {
+                tb.add("archive", &format!("*.{ext}"))?;
+            }
+        }
+
         tb.select("all");
-        tb.build()?
-    };
 
-    let walker = ignore::WalkBuilder::new(root_dir)
-        .standard_filters(true)
-        .follow_links(true)
-        .max_depth(max_depth)
-        .types(types)
-        .build();
+        builder.max_depth(max_depth).types(tb.build()?);
+    }
+
+    // Only the files that were packaged for the crate are scanned, eg. so
+    // that test fixtures containing third party license texts are skipped
+    let (include, exclude) = package_globs(root_dir);
+
+    if !include.is_empty() || !exclude.is_empty() || !scan_cfg.exclude_globs.is_empty() {
+        let mut ob = ignore::overrides::OverrideBuilder::new(root_dir);
+
+        // As with cargo, `exclude` is ignored if `include` is specified
+        let package_globs = if include.is_empty() {
+            exclude.iter().map(|glob| format!("!{glob}")).collect()
+        } else {
+            include
+        };
+
+        for glob in package_globs {
+            if let Err(err) = ob.add(&glob) {
+                log::warn!("ignoring invalid package glob '{glob}' in '{root_dir}': {err}");
+            }
+        }
+
+        // Globs excluded from the scan of every crate, eg. corpora of third
+        // party test inputs, which frequently contain snippets of other
+        // licenses, take precedence over the package's globs
+        for glob in &scan_cfg.exclude_globs {
+            ob.add(&format!("!{glob}"))?;
+        }
+
+        builder.overrides(ob.build()?);
+    }
+
+    let walker = builder.build();
+
+    let mut files = Vec::new();
+    for entry in walker {
+        if timed_out() {
+            anyhow::bail!(
+                "scan timed out after {}s while walking files",
+                timeout.unwrap_or_default().as_secs()
+            );
+        }
 
-    let files: Vec<_> = walker.filter_map(|e| e.ok()).collect();
+        if let Ok(entry) = entry {
+            files.push(entry);
+        }
+    }
 
-    let license_files: Vec<_> = files
+    let abandoned = AtomicBool::new(false);
+    let scanned: Vec<_> = files
         .into_par_iter()
         .filter_map(|file| {
+            // Skip any remaining work once we've run out of time, the whole
+            // scan is discarded below
+            if timed_out() {
+                abandoned.store(true, Ordering::Relaxed);
+                return None;
+            }
+
             log::trace!("scanning file {}", file.path().display());
 
             if let Some(ft) = file.file_type() {
@@ -42,114 +252,339 @@ pub(crate) fn scan_files(
 
                 if let Ok(md) = file.metadata() {
                     if md.file_type().is_fifo() {
-                        log::error!("skipping FIFO {}", file.path().display());
-                        return None;
+                        return Some(Err(format!("skipping FIFO {}", file.path().display())));
                     }
                 }
             }
 
-            let path = match PathBuf::from_path_buf(file.into_path()) {
-                Ok(pb) => pb,
-                Err(e) => {
-                    log::warn!("skipping path {}, not a valid utf-8 path", e.display());
-                    return None;
+            let path = file.into_path();
+
+            if scan_cfg.archives {
+                if let Some(path) = Path::from_path(&path).filter(|path| archive::is_archive(path))
+                {
+                    let too_large = std::fs::metadata(path)
+                        .is_ok_and(|md| md.len() > archive::MAX_ARCHIVE_SIZE);
+
+                    if too_large {
+                        log::debug!("skipping archive '{path}' as it is too large to scan");
+                        return None;
+                    }
+
+                    return match scan_archive(root_dir, path, strat, threshold) {
+                        Ok(Found::Archive(lfs, notices))
+                            if lfs.is_empty() && notices.is_empty() =>
+                        {
+                            None
+                        }
+                        res => Some(res),
+                    };
                 }
+            }
+
+            let contents = match read_file(&path) {
+                Ok(contents) => contents?,
+                Err(note) => return Some(Err(note)),
             };
 
-            let contents = read_file(&path)?;
+            // Some crates contain paths that aren't valid utf-8, eg. directory
+            // names on Linux, the file is still read via its real path, but
+            // a lossy version is used for display and attribution
+            let path = PathBuf::from_path_buf(path).unwrap_or_else(|path| {
+                let lossy = PathBuf::from(path.to_string_lossy().into_owned());
+                log::debug!("'{lossy}' is not a valid utf-8 path, using it lossily");
+                lossy
+            });
 
-            check_is_license_file(path, contents, strat, threshold)
+            if is_notice_file(&path) {
+                return Some(Ok(Found::Notice(path, contents)));
+            }
+
+            match check_is_license_file(path, contents, strat, threshold) {
+                Ok(lfs) if lfs.is_empty() => None,
+                Ok(lfs) => Some(Ok(Found::License(lfs))),
+                Err(note) => Some(Err(note)),
+            }
         })
         .collect();
 
-    Ok(license_files)
+    anyhow::ensure!(
+        !abandoned.load(Ordering::Relaxed),
+        "scan timed out after {}s while scanning files",
+        timeout.unwrap_or_default().as_secs()
+    );
+
+    let mut license_files = Vec::with_capacity(scanned.len());
+    let mut notices = Vec::new();
+    let mut notes = Vec::new();
+
+    for res in scanned {
+        match res {
+            Ok(Found::License(mut lfs)) => license_files.append(&mut lfs),
+            Ok(Found::Notice(path, contents)) => notices.push((path, contents)),
+            Ok(Found::Archive(mut lfs, mut archived)) => {
+                license_files.append(&mut lfs);
+                notices.append(&mut archived);
+            }
+            Err(note) => notes.push(note),
+        }
+    }
+
+    notices.sort_by(|a, b| a.0.cmp(&b.0));
+
+    // License texts found in subdirectories of a deep scanned crate, eg.
+    // `vendor/zlib/LICENSE`, apply to that subdirectory rather than to the
+    // crate as a whole
+    if deep {
+        for lf in &mut license_files {
+            let Some(sub_root) = lf
+                .path
+                .parent()
+                .and_then(|parent| parent.strip_prefix(root_dir).ok())
+                .filter(|sub_root| !sub_root.as_str().is_empty())
+            else {
+                continue;
+            };
+
+            if let LicenseFileKind::Text(text) = &mut lf.kind {
+                let text = std::mem::take(text);
+                let sub_root = sub_root.as_str().replace('\\', "/").into();
+                lf.kind = LicenseFileKind::AddendumText(text, sub_root);
+            }
+        }
+    }
+
+    Ok(ScannedFiles {
+        license_files,
+        notices,
+        notes,
+    })
 }
 
-fn read_file(path: &Path) -> Option<String> {
+fn read_file(path: &std::path::Path) -> Result<Option<String>, String> {
     match std::fs::read_to_string(path) {
         Err(ref e) if e.kind() == std::io::ErrorKind::InvalidData => {
             // If we fail due to invaliddata, it just means the file in question was
             // probably binary and didn't have valid utf-8 data, so we can ignore it
-            log::debug!("binary file '{path}' detected");
-            None
-        }
-        Err(e) => {
-            log::error!("failed to read '{path}': {e}");
-            None
+            log::debug!("binary file '{}' detected", path.display());
+            Ok(None)
         }
-        Ok(c) => Some(c),
+        Err(e) => Err(format!("failed to read '{}': {e}", path.display())),
+        Ok(c) => Ok(Some(c)),
     }
 }
 
+/// Checks if the specified file contents are a license, returning an error
+/// note if the file looked like a license but could not be turned into a
+/// valid license expression
+///
+/// Files that contain the texts of multiple licenses, eg. the MIT and
+/// Apache-2.0 texts of a dual licensed crate in a single file, are split into
+/// a license file for each of the texts.
 pub(crate) fn check_is_license_file(
     path: PathBuf,
     contents: String,
     strat: &askalono::ScanStrategy<'_>,
     threshold: f32,
-) -> Option<LicenseFile> {
-    match scan_text(&contents, strat, threshold) {
+) -> Result<Vec<LicenseFile>, String> {
+    let scanned = scan_text(&contents, strat, threshold);
+
+    // A file with multiple license texts either matches the license with the
+    // longest text, or doesn't match any single license well as a whole
+    if matches!(
+        scanned,
+        ScanResult::Text(_) | ScanResult::LowLicenseChance(_)
+    ) {
+        let segments = scan_segments(&contents, strat, threshold);
+
+        if segments.len() > 1 {
+            let lines: Vec<_> = contents.split('\n').collect();
+
+            return segments
+                .into_iter()
+                .map(|segment| {
+                    let license_expr =
+                        spdx::Expression::parse(segment.id.name).map_err(|err| {
+                            format!(
+                                "failed to parse license '{}' in '{path}' into a valid expression: {err}",
+                                segment.id.name
+                            )
+                        })?;
+
+                    Ok(LicenseFile {
+                        license_expr,
+                        confidence: segment.confidence,
+                        path: path.clone(),
+                        kind: LicenseFileKind::Text(
+                            lines[segment.lines.0..segment.lines.1].join("\n"),
+                        ),
+                    })
+                })
+                .collect();
+        }
+    }
+
+    match scanned {
         ScanResult::Header(ided) => {
             // askalono only detects single license identifiers, not license
             // expressions, so we need to construct one from a single identifier,
             // this should be made into in infallible function in spdx itself
-            let license_expr = match spdx::Expression::parse(ided.id.name) {
-                Ok(expr) => expr,
-                Err(err) => {
-                    log::error!(
-                        "failed to parse license '{}' into a valid expression: {err}",
-                        ided.id.name
-                    );
-                    return None;
-                }
-            };
+            let license_expr = spdx::Expression::parse(ided.id.name).map_err(|err| {
+                format!(
+                    "failed to parse license '{}' in '{path}' into a valid expression: {err}",
+                    ided.id.name
+                )
+            })?;
 
-            Some(LicenseFile {
+            Ok(vec![LicenseFile {
                 license_expr,
                 confidence: ided.confidence,
                 path,
                 kind: LicenseFileKind::Header,
-            })
+            }])
         }
         ScanResult::Text(ided) => {
-            let license_expr = match spdx::Expression::parse(ided.id.name) {
-                Ok(expr) => expr,
-                Err(err) => {
-                    log::error!(
-                        "failed to parse license '{}' into a valid expression: {err}",
-                        ided.id.name
-                    );
-                    return None;
-                }
-            };
+            let license_expr = spdx::Expression::parse(ided.id.name).map_err(|err| {
+                format!(
+                    "failed to parse license '{}' in '{path}' into a valid expression: {err}",
+                    ided.id.name
+                )
+            })?;
 
-            Some(LicenseFile {
+            Ok(vec![LicenseFile {
                 license_expr,
                 confidence: ided.confidence,
                 path,
                 kind: LicenseFileKind::Text(contents),
-            })
-        }
-        ScanResult::UnknownId(id_str) => {
-            log::error!("found unknown SPDX identifier '{id_str}' scanning '{path}'");
-            None
+            }])
         }
+        ScanResult::UnknownId(id_str) => Err(format!(
+            "found unknown SPDX identifier '{id_str}' scanning '{path}'"
+        )),
         ScanResult::LowLicenseChance(ided) => {
             log::debug!(
                 "found '{}' scanning '{path}' but it only has a confidence score of {}",
                 ided.id.name,
                 ided.confidence,
             );
-            None
+            Ok(Vec::new())
         }
-        ScanResult::NoLicense => None,
+        ScanResult::NoLicense => Ok(Vec::new()),
     }
 }
 
+/// Scans the file specified by a crate's `license-file` field, regardless of
+/// its name, returning a note if it is missing or is not a recognized license
+pub(crate) fn scan_license_file(
+    root_dir: &Path,
+    license_file: &Path,
+    strat: &askalono::ScanStrategy<'_>,
+    threshold: f32,
+) -> Result<Vec<LicenseFile>, String> {
+    let path = root_dir.join(license_file);
+
+    let contents = std::fs::read_to_string(&path)
+        .map_err(|err| format!("unable to read `license-file` '{license_file}': {err}"))?;
+
+    if contents.trim().is_empty() {
+        return Err(format!("`license-file` '{license_file}' is empty"));
+    }
+
+    let lfs = check_is_license_file(path, contents, strat, threshold)?;
+    if lfs.is_empty() {
+        return Err(format!(
+            "`license-file` '{license_file}' is not a recognized license"
+        ));
+    }
+
+    Ok(lfs)
+}
+
 struct Identified {
     confidence: f32,
     id: spdx::LicenseId,
 }
 
+/// A license text found within a larger file
+struct Segment {
+    confidence: f32,
+    id: spdx::LicenseId,
+    /// The 0-based, end exclusive range of lines the text spans
+    lines: (usize, usize),
+}
+
+/// The maximum number of license texts that are searched for in a single file
+const MAX_SEGMENTS: usize = 4;
+
+/// Locates each distinct license text in the contents, ordered by where they
+/// appear, by narrowing the best match down to the lines it spans, and then
+/// searching the lines before and after it for further matches
+fn scan_segments(
+    contents: &str,
+    strat: &askalono::ScanStrategy<'_>,
+    threshold: f32,
+) -> Vec<Segment> {
+    let lines: Vec<_> = contents.split('\n').collect();
+    let mut segments = Vec::<Segment>::new();
+    let mut regions = vec![(0, lines.len())];
+
+    while let Some((start, end)) = regions.pop() {
+        if segments.len() >= MAX_SEGMENTS {
+            break;
+        }
+
+        let region = &lines[start..end];
+        if region.iter().all(|line| line.trim().is_empty()) {
+            continue;
+        }
+
+        let text = askalono::TextData::new(&region.join("\n"));
+        let Some(identified) = strat.scan(&text).ok().and_then(|m| m.license) else {
+            continue;
+        };
+
+        // Only full license texts are split out, headers are too short to be
+        // reliably located within another license's text
+        if identified.kind != askalono::LicenseType::Original {
+            continue;
+        }
+
+        let (optimized, score) = text.optimize_bounds(identified.data);
+        let (seg_start, seg_end) = optimized.lines_view();
+        if score < threshold || seg_start >= seg_end {
+            continue;
+        }
+
+        let Some(id) = spdx::license_id(identified.name) else {
+            continue;
+        };
+
+        if segments.iter().all(|seg| seg.id != id) {
+            segments.push(Segment {
+                confidence: score,
+                id,
+                lines: (start + seg_start, start + seg_end),
+            });
+        }
+
+        regions.push((start, start + seg_start));
+        regions.push((start + seg_end, end));
+    }
+
+    segments.sort_by_key(|seg| seg.lines.0);
+
+    // Copyright statements and titles are ignored when matching, so any lines
+    // between the texts are considered to be part of the text that follows them
+    let mut prev_end = 0;
+    for seg in &mut segments {
+        if let Some(start) = (prev_end..seg.lines.0).find(|i| !lines[*i].trim().is_empty()) {
+            seg.lines.0 = start;
+        }
+        prev_end = seg.lines.1;
+    }
+
+    segments
+}
+
 enum ScanResult {
     Header(Identified),
     Text(Identified),
@@ -24,9 +24,7 @@ pub(crate) fn apply_workarounds<'krate>(
     gc: &GitCache,
     licensed_krates: &mut Vec<KrateLicense<'krate>>,
 ) {
-    if cfg.workarounds.is_empty() {
-        return;
-    }
+    let mut builtin = Vec::new();
 
     for workaround in &cfg.workarounds {
         let Some(retrieve_workaround) = WORKAROUNDS
@@ -38,46 +36,109 @@ pub(crate) fn apply_workarounds<'krate>(
         };
 
         for krate in krates.krates() {
-            if let Err(i) = super::binary_search(licensed_krates, krate) {
-                match retrieve_workaround(krate) {
-                    Ok(Some(clarification)) => {
-                        match crate::licenses::apply_clarification(gc, krate, &clarification) {
-                            Ok(files) => {
-                                log::debug!("applying workaround '{workaround}' to '{krate}'");
-
-                                licensed_krates.insert(
-                                    i,
-                                    KrateLicense {
-                                        krate,
-                                        lic_info: super::LicenseInfo::Expr(clarification.license),
-                                        license_files: files,
-                                    },
-                                );
-                            }
-                            Err(e) => {
-                                log::debug!(
-                                    "unable to apply workaround '{workaround}' to '{krate}': {e:#}"
-                                );
-                            }
-                        }
-                    }
-                    Ok(None) => {}
-                    Err(e) => {
-                        log::debug!(
-                            "unable to apply workaround '{workaround}' to '{krate}': {e:#}"
-                        );
-                    }
+            if super::binary_search(licensed_krates, krate).is_ok() {
+                continue;
+            }
+
+            match retrieve_workaround(krate) {
+                Ok(Some(clarification)) => {
+                    builtin.push((workaround.as_str(), krate, clarification));
+                }
+                Ok(None) => {}
+                Err(e) => {
+                    log::debug!("unable to apply workaround '{workaround}' to '{krate}': {e:#}");
                 }
             }
         }
     }
+
+    // Workarounds loaded from disk are maintained by the user, so they take
+    // precedence over the built-in ones, which are identified by name
+    let mut pending = Vec::new();
+
+    for workaround in &cfg.external_workarounds {
+        for krate in krates.krates() {
+            if krate.name != workaround.name
+                || !workaround
+                    .version
+                    .as_ref()
+                    .map_or(true, |req| req.matches(&krate.version))
+            {
+                continue;
+            }
+
+            if super::binary_search(licensed_krates, krate).is_err() {
+                pending.push((None, krate, &workaround.clarify));
+            }
+        }
+    }
+
+    pending.extend(
+        builtin
+            .iter()
+            .map(|(workaround, krate, clarification)| (Some(*workaround), *krate, clarification)),
+    );
+
+    // Workarounds can require retrieving files from remote git hosts, so they
+    // are applied concurrently, then inserted in order of precedence
+    let applied = gc.map_concurrent(pending, |(workaround, krate, clarification)| {
+        let files = crate::licenses::apply_clarification(gc, krate, clarification);
+        (workaround, krate, clarification, files)
+    });
+
+    for (workaround, krate, clarification, files) in applied {
+        let Err(i) = super::binary_search(licensed_krates, krate) else {
+            continue;
+        };
+
+        let files = match (workaround, files) {
+            (_, Ok(files)) => files,
+            (None, Err(e)) => {
+                log::warn!("unable to apply external workaround to '{krate}': {e:#}");
+                continue;
+            }
+            (Some(workaround), Err(e)) => {
+                log::debug!("unable to apply workaround '{workaround}' to '{krate}': {e:#}");
+                continue;
+            }
+        };
+
+        if let Some(workaround) = workaround {
+            log::debug!("applying workaround '{workaround}' to '{krate}'");
+        } else {
+            log::debug!("applying external workaround to '{krate}'");
+        }
+
+        licensed_krates.insert(
+            i,
+            KrateLicense::new(
+                krate,
+                super::LicenseInfo::Expr(clarification.license.clone()),
+                files,
+            ),
+        );
+    }
 }
 
 #[allow(clippy::type_complexity)]
-const WORKAROUNDS: &[(
-    &str,
-    &dyn Fn(&crate::Krate) -> anyhow::Result<Option<Clarification>>,
-)] = &[
+/// Whether the built-in workaround applies to any crate in the graph, or
+/// `None` if no workaround is registered with the name
+pub(crate) fn applies(workaround: &str, krates: &crate::Krates) -> Option<bool> {
+    let (_, retrieve_workaround) = WORKAROUNDS.iter().find(|(name, _)| *name == workaround)?;
+
+    // Workarounds fail for versions of their crate they don't cover, but they
+    // still apply to the crate
+    Some(
+        krates
+            .krates()
+            .any(|krate| !matches!(retrieve_workaround(krate), Ok(None))),
+    )
+}
+
+/// Retrieves the clarification of a workaround for a crate, if it applies
+type Workaround = dyn Fn(&crate::Krate) -> anyhow::Result<Option<Clarification>>;
+
+const WORKAROUNDS: &[(&str, &Workaround)] = &[
     ("bitvec", &self::bitvec::get),
     ("chrono", &self::chrono::get),
     ("clap", &self::clap::get),
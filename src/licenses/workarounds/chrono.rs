@@ -12,6 +12,7 @@ pub fn get(krate: &crate::Krate) -> anyhow::Result<Option<super::Clarification>>
         license: spdx::Expression::parse("Apache-2.0 OR MIT")
             .context("failed to parse license expression")?,
         override_git_commit: None,
+        version_req: None,
         files: vec![
             ClarificationFile {
                 path: "LICENSE.txt".into(),
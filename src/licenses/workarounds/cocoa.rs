@@ -29,6 +29,7 @@ pub fn get(krate: &crate::Krate) -> anyhow::Result<Option<super::Clarification>>
         license: spdx::Expression::parse("MIT OR Apache-2.0")
             .context("failed to parse license expression")?,
         override_git_commit,
+        version_req: None,
         git: vec![
             ClarificationFile {
                 path: "LICENSE-APACHE".into(),
@@ -14,6 +14,7 @@ pub fn get(krate: &crate::Krate) -> anyhow::Result<Option<super::Clarification>>
         license: spdx::Expression::parse("Apache-2.0")
             .context("failed to parse license expression")?,
         override_git_commit: Some(format!("v{}", krate.version)),
+        version_req: None,
         git: vec![ClarificationFile {
             path: "LICENSE".into(),
             license: None,
@@ -20,6 +20,7 @@ pub fn get(krate: &crate::Krate) -> anyhow::Result<Option<super::Clarification>>
         license: spdx::Expression::parse("ISC AND OpenSSL AND MIT")
             .context("failed to parse license expression")?,
         override_git_commit: None,
+        version_req: None,
         files: vec![
             // This is the ISC license that actually applies to most/all of the rust code
             ClarificationFile {
@@ -10,6 +10,7 @@ pub fn get(krate: &crate::Krate) -> anyhow::Result<Option<super::Clarification>>
         license: spdx::Expression::parse("Apache-2.0 OR MIT OR ISC")
             .context("failed to parse license expression")?,
         override_git_commit: None,
+        version_req: None,
         git: vec![
             ClarificationFile {
                 path: "LICENSE-APACHE".into(),
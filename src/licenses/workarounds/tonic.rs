@@ -9,6 +9,7 @@ pub fn get(krate: &crate::Krate) -> anyhow::Result<Option<super::Clarification>>
     Ok(Some(super::Clarification {
         license: spdx::Expression::parse("MIT").context("failed to parse license expression")?,
         override_git_commit: None,
+        version_req: None,
         git: vec![ClarificationFile {
             path: "LICENSE".into(),
             license: None,
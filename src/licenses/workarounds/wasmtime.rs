@@ -46,6 +46,7 @@ pub fn get(krate: &crate::Krate) -> anyhow::Result<Option<super::Clarification>>
             license: spdx::Expression::parse("Apache-2.0 WITH LLVM-exception")
                 .context("failed to parse license expression")?,
             override_git_commit: None,
+            version_req: None,
             git: vec![ClarificationFile {
                 path: "LICENSE".into(),
                 license: None,
@@ -61,6 +62,7 @@ pub fn get(krate: &crate::Krate) -> anyhow::Result<Option<super::Clarification>>
             license: spdx::Expression::parse("Apache-2.0 WITH LLVM-exception")
                 .context("failed to parse license expression")?,
             override_git_commit: None,
+            version_req: None,
             files: vec![
                 // Both clearlydefined and askalono don't handle license exceptions it seems, so we need to clarify
                 // the file otherwise we will think we won't find the license we expected
@@ -0,0 +1,69 @@
+//! Progress reporting while gathering licenses, which can take minutes for
+//! large crate graphs
+//!
+//! Log messages written while a progress bar is displayed would be drawn over
+//! by it, so loggers should write messages via [`write_log`].
+
+use indicatif::{ProgressBar, ProgressStyle};
+use std::sync::Mutex;
+
+/// The progress bar currently being displayed, if any
+static ACTIVE: Mutex<Option<ProgressBar>> = Mutex::new(None);
+
+/// A progress bar of the crates that have been gathered, which is cleared
+/// when dropped
+pub struct Progress {
+    bar: ProgressBar,
+}
+
+impl Progress {
+    /// Displays a progress bar on stderr for the specified number of crates,
+    /// if stderr is a terminal
+    pub fn new(krates: usize) -> Self {
+        let bar = ProgressBar::new(krates as u64).with_style(
+            ProgressStyle::with_template("{spinner} {msg:<12} [{bar:40}] {pos}/{len} crates")
+                .expect("progress template is valid")
+                .progress_chars("=> "),
+        );
+        bar.enable_steady_tick(std::time::Duration::from_millis(100));
+
+        *ACTIVE.lock().unwrap_or_else(|err| err.into_inner()) = Some(bar.clone());
+        Self { bar }
+    }
+
+    /// Sets the stage of gathering that is in progress, eg. `scanning`
+    #[inline]
+    pub fn stage(&self, stage: &'static str) {
+        self.bar.set_message(stage);
+    }
+
+    /// Sets the number of crates that have been gathered
+    #[inline]
+    pub fn set_gathered(&self, gathered: usize) {
+        self.bar.set_position(gathered as u64);
+    }
+
+    /// Marks one more crate as gathered
+    #[inline]
+    pub fn inc(&self) {
+        self.bar.inc(1);
+    }
+}
+
+impl Drop for Progress {
+    fn drop(&mut self) {
+        self.bar.finish_and_clear();
+        *ACTIVE.lock().unwrap_or_else(|err| err.into_inner()) = None;
+    }
+}
+
+/// Writes a log message to stderr, hiding the progress bar while doing so if
+/// one is being displayed
+pub fn write_log(message: impl std::fmt::Display) {
+    let active = ACTIVE.lock().unwrap_or_else(|err| err.into_inner()).clone();
+
+    match active {
+        Some(bar) => bar.suspend(|| eprintln!("{message}")),
+        None => eprintln!("{message}"),
+    }
+}
@@ -0,0 +1,3102 @@
+//! The full `generate` pipeline, from loading configuration through to
+//! rendering output, usable without going through the `cargo-about` binary
+//!
+//! ```no_run
+//! use cargo_about::run::{self, Pipeline};
+//!
+//! let manifest_path = krates::Utf8PathBuf::from("Cargo.toml");
+//! let cfg = run::load_config(&manifest_path, None)?;
+//! let pipeline = Pipeline::new(manifest_path, cfg).workspace(true);
+//!
+//! let prepared = pipeline.prepare()?;
+//! let input = pipeline.generate(&prepared)?;
+//! let json = serde_json::to_string(&input)?;
+//! # Ok::<_, anyhow::Error>(())
+//! ```
+
+pub mod baseline;
+pub mod changelog;
+pub mod check;
+mod collate;
+pub mod dependents;
+#[cfg(feature = "sqlite")]
+pub mod history;
+pub mod merge;
+pub mod output;
+pub mod redact;
+pub mod reproducible;
+pub mod sanitize;
+#[cfg(feature = "sqlite")]
+pub mod sqlite;
+pub mod strings;
+pub mod validate;
+
+use crate::licenses::{self, config::Config, LicenseInfo};
+use anyhow::Context as _;
+use codespan_reporting::term::{
+    self,
+    termcolor::{ColorChoice, StandardStream},
+};
+use krates::cm::Package;
+use krates::{Utf8Path as Path, Utf8PathBuf as PathBuf};
+use serde::Serialize;
+use std::{collections::BTreeMap, sync::Arc};
+
+/// The version of the JSON output, incremented whenever a field is removed, or
+/// the meaning of an existing field changes. Adding new fields does not change
+/// the version.
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// The [JSON Schema](https://json-schema.org) for the JSON output
+pub const SCHEMA: &str = include_str!("../resources/schema.json");
+
+/// The name of the template used to render the index of the output of each
+/// crate, see [`Templates::index_template`]
+pub const INDEX_TEMPLATE: &str = "per-crate-index";
+
+/// The index rendered if the templates don't contain an [`INDEX_TEMPLATE`]
+const DEFAULT_INDEX: &str = include_str!("../resources/per-crate-index.hbs");
+
+/// The format diagnostics emitted during license resolution are written in
+#[derive(clap::ValueEnum, Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum MessageFormat {
+    /// Rendered for humans, along with the source they refer to
+    #[default]
+    Human,
+    /// One JSON object per line, for consumption by other tools
+    Json,
+}
+
+/// How diagnostics are written to stderr
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct DiagnosticStyle {
+    /// The format diagnostics are written in
+    pub format: MessageFormat,
+    /// Renders [`MessageFormat::Human`] diagnostics with only ASCII
+    /// characters, rather than unicode box drawing characters, which some CI
+    /// log viewers and Windows consoles garble
+    pub ascii: bool,
+}
+
+/// The names of the configuration files that are searched for, in order of
+/// precedence when a directory contains more than one of them
+const CONFIG_NAMES: &[&str] = &["about.toml", "about.yaml", "about.yml", "about.json"];
+
+/// Deserializes a configuration in the format matching the extension of its
+/// path, ie. YAML for `.yaml` and `.yml`, JSON for `.json`, and TOML otherwise
+fn parse_config(path: &Path, contents: &str) -> anyhow::Result<Config> {
+    let cfg = match path.extension() {
+        Some("yaml" | "yml") => serde_norway::from_str(contents).map_err(anyhow::Error::from),
+        Some("json") => serde_json::from_str(contents).map_err(anyhow::Error::from),
+        _ => toml::from_str(contents).map_err(anyhow::Error::from),
+    };
+
+    cfg.with_context(|| format!("unable to deserialize config from '{path}'"))
+}
+
+/// Loads the configuration to use for the specified manifest
+///
+/// If `config_path` is not specified, the directory of the manifest, and each
+/// of its parent directories are searched for an `about.toml`, or an
+/// `about.yaml`, `about.yml` or `about.json` with the same schema. If none is
+/// found, the `[workspace.metadata.about]` table of the workspace manifest is
+/// used instead, falling back to the default configuration if it doesn't exist
+pub fn load_config(manifest_path: &Path, config_path: Option<&Path>) -> anyhow::Result<Config> {
+    if let Some(cfg_path) = config_path {
+        let cfg_str = std::fs::read_to_string(cfg_path)
+            .with_context(|| format!("unable to read '{cfg_path}'"))?;
+        let mut cfg = parse_config(cfg_path, &cfg_str)?;
+        cfg.config_path = Some(cfg_path.to_owned());
+        cfg.loaded_files.push(cfg_path.to_owned());
+        let root = cfg_path.parent().unwrap_or(Path::new("."));
+        cfg.load_external_workarounds(root)?;
+        cfg.load_license_texts(root)?;
+        return Ok(cfg);
+    }
+
+    let mut parent = manifest_path.parent();
+
+    // Move up directories until we find an about.toml, to handle
+    // cases where eg in a workspace there is a top-level about.toml
+    // but the user is only getting a listing for a particular crate from it
+    while let Some(p) = parent {
+        // We _could_ limit ourselves to only directories that also have a Cargo.toml
+        // in them, but there could be cases where someone has multiple
+        // rust projects in subdirectories with a single top level about.toml that is
+        // used across all of them, we could also introduce a metadata entry for the
+        // relative path of the about.toml to use for the crate/workspace
+
+        // if !p.join("Cargo.toml").exists() {
+        //     parent = p.parent();
+        //     continue;
+        // }
+
+        if let Some(cfg_path) = CONFIG_NAMES
+            .iter()
+            .map(|name| p.join(name))
+            .find(|path| path.exists())
+        {
+            let contents = std::fs::read_to_string(&cfg_path)
+                .with_context(|| format!("unable to read '{cfg_path}'"))?;
+            let mut cfg = parse_config(&cfg_path, &contents)?;
+            cfg.config_path = Some(cfg_path.clone());
+            cfg.loaded_files.push(cfg_path.clone());
+            cfg.load_external_workarounds(p)?;
+            cfg.load_license_texts(p)?;
+
+            log::info!("loaded config from '{cfg_path}'");
+            return Ok(cfg);
+        }
+
+        parent = p.parent();
+    }
+
+    if let Some(cfg) = load_workspace_metadata_config(manifest_path)? {
+        return Ok(cfg);
+    }
+
+    log::warn!("no 'about.toml', 'about.yaml' or 'about.json' found, falling back to default configuration");
+    Ok(Config::default())
+}
+
+/// Loads the configuration from the `[workspace.metadata.about]` table of the
+/// workspace manifest, which is the first manifest containing a `[workspace]`
+/// table in the directory of the manifest or any of its parents
+fn load_workspace_metadata_config(manifest_path: &Path) -> anyhow::Result<Option<Config>> {
+    let mut parent = manifest_path.parent();
+
+    while let Some(p) = parent {
+        let cargo_toml = p.join("Cargo.toml");
+        parent = p.parent();
+
+        let Ok(contents) = std::fs::read_to_string(&cargo_toml) else {
+            continue;
+        };
+
+        let manifest: toml::Table =
+            toml::from_str(&contents).with_context(|| format!("failed to parse '{cargo_toml}'"))?;
+
+        let Some(workspace) = manifest.get("workspace") else {
+            continue;
+        };
+
+        let Some(about) = workspace
+            .get("metadata")
+            .and_then(|metadata| metadata.get("about"))
+        else {
+            return Ok(None);
+        };
+
+        let mut cfg: Config = about.clone().try_into().with_context(|| {
+            format!(
+                "unable to deserialize config from `workspace.metadata.about` in '{cargo_toml}'"
+            )
+        })?;
+        cfg.config_path = Some(cargo_toml.clone());
+        cfg.loaded_files.push(cargo_toml.clone());
+        cfg.load_external_workarounds(p)?;
+        cfg.load_license_texts(p)?;
+
+        log::info!("loaded config from `workspace.metadata.about` in '{cargo_toml}'");
+        return Ok(Some(cfg));
+    }
+
+    Ok(None)
+}
+
+/// The engine used to render templates
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum TemplateEngine {
+    /// [Handlebars](https://handlebarsjs.com), with `.hbs` templates
+    #[default]
+    Handlebars,
+    /// [Jinja](https://jinja.palletsprojects.com), via [minijinja](https://docs.rs/minijinja),
+    /// with `.jinja` or `.j2` templates
+    Jinja,
+}
+
+impl TemplateEngine {
+    /// The file extension(s) of templates for the engine
+    fn extensions(self) -> &'static [&'static str] {
+        match self {
+            Self::Handlebars => &["hbs"],
+            Self::Jinja => &["jinja", "j2"],
+        }
+    }
+}
+
+enum Registry {
+    Handlebars(handlebars::Handlebars<'static>),
+    Jinja(minijinja::Environment<'static>),
+}
+
+/// One or more templates used to render the output
+pub struct Templates {
+    registry: Registry,
+    name: Option<String>,
+    /// The template file, or directory of template files, used to retrieve
+    /// the source of a template when reporting errors
+    path: PathBuf,
+    /// The source path of each template, relative to [`Self::path`]
+    sources: BTreeMap<String, PathBuf>,
+    sanitize: sanitize::Sanitize,
+    /// The templates that output HTML, sanitized with [`sanitize::Sanitize::Auto`]
+    html: std::collections::BTreeSet<String>,
+}
+
+impl Templates {
+    /// Loads the template(s) from the specified path, which can either be a
+    /// single template file, or a directory of template files, in which case
+    /// the `name` of the template to render must be supplied to use
+    /// [`Self::render`]
+    pub fn load(
+        template_path: &Path,
+        name: Option<String>,
+        engine: TemplateEngine,
+    ) -> anyhow::Result<Self> {
+        anyhow::ensure!(
+            template_path.exists(),
+            "template(s) path '{template_path}' does not exist"
+        );
+
+        let (registry, sources) = match engine {
+            TemplateEngine::Handlebars => {
+                let reg = handlebars_registry(template_path)?;
+
+                // Template names are the path of the template relative to the
+                // template directory, without the extension
+                let sources = reg
+                    .get_templates()
+                    .keys()
+                    .map(|name| (name.clone(), PathBuf::from(format!("{name}.hbs"))))
+                    .collect();
+
+                (Registry::Handlebars(reg), sources)
+            }
+            TemplateEngine::Jinja => {
+                let mut env = minijinja::Environment::new();
+
+                // Match handlebars, which always escapes HTML
+                env.set_auto_escape_callback(|_name| minijinja::AutoEscape::Html);
+                env.add_filter("wrap", |text: &str, width: Option<usize>| {
+                    wrap_text(text, width.unwrap_or(DEFAULT_WRAP_WIDTH))
+                });
+                // indent, replace, and safe are builtin filters
+                env.add_filter("markdown_escape", markdown_escape_text);
+                env.add_filter("urlencode", url_encode_text);
+
+                let sources = if template_path.is_dir() {
+                    template_sources(template_path, engine.extensions())?
+                } else {
+                    std::iter::once(("tmpl".to_owned(), PathBuf::new())).collect()
+                };
+
+                for (name, rel_path) in &sources {
+                    let path = if template_path.is_dir() {
+                        template_path.join(rel_path)
+                    } else {
+                        template_path.to_owned()
+                    };
+                    let source = std::fs::read_to_string(&path)
+                        .with_context(|| format!("failed to read template '{path}'"))?;
+                    env.add_template_owned(name.clone(), source)
+                        .map_err(|err| located_error("failed to parse", &path, &err))?;
+                }
+
+                (Registry::Jinja(env), sources)
+            }
+        };
+
+        anyhow::ensure!(
+            !sources.is_empty(),
+            "template path '{template_path}' did not contain any {} files",
+            engine.extensions().join(" or ")
+        );
+
+        let name = if template_path.is_dir() {
+            name
+        } else {
+            Some("tmpl".to_owned())
+        };
+
+        let html = sources
+            .iter()
+            .filter(|(_, rel_path)| {
+                let path = if template_path.is_dir() {
+                    template_path.join(rel_path)
+                } else {
+                    template_path.to_owned()
+                };
+                let source = std::fs::read_to_string(&path).unwrap_or_default();
+                sanitize::is_html_template(path.as_str(), &source)
+            })
+            .map(|(name, _)| name.clone())
+            .collect();
+
+        Ok(Self {
+            registry,
+            name,
+            path: template_path.to_owned(),
+            sources,
+            sanitize: sanitize::Sanitize::default(),
+            html,
+        })
+    }
+
+    /// Sets how the crate metadata is sanitized before it is rendered,
+    /// defaults to [`sanitize::Sanitize::Auto`]
+    pub fn sanitize(mut self, sanitize: sanitize::Sanitize) -> Self {
+        self.sanitize = sanitize;
+        self
+    }
+
+    /// Renders the output with the template
+    #[inline]
+    pub fn render(&self, input: &Input<'_>) -> anyhow::Result<String> {
+        let name = self.ensure_exists(None)?;
+        self.render_named(name, input)
+    }
+
+    /// Renders the output with the specified template
+    pub fn render_named(&self, name: &str, input: &Input<'_>) -> anyhow::Result<String> {
+        self.ensure_exists(Some(name))?;
+        self.render_value_named(name, Self::to_value(input)?)
+    }
+
+    /// Renders the index of the output of each crate, with the `path` of the
+    /// output of each crate added to the `crates`, using the [`INDEX_TEMPLATE`]
+    /// if there is one, or a default HTML index otherwise
+    pub fn render_index(&self, input: &Input<'_>, paths: &[String]) -> anyhow::Result<String> {
+        let mut input = Self::to_value(input)?;
+        if let Some(crates) = input["crates"].as_array_mut() {
+            for (krate, path) in crates.iter_mut().zip(paths) {
+                krate["path"] = path.clone().into();
+            }
+        }
+
+        if let Some(name) = self.index_template() {
+            return self.render_value_named(name, input);
+        }
+
+        if self.sanitize != sanitize::Sanitize::None {
+            sanitize::html(&mut input);
+        }
+
+        handlebars::Handlebars::new()
+            .render_template(DEFAULT_INDEX, &input)
+            .context("failed to render the index")
+    }
+
+    /// The extension of the file the specified template, or if `None`, the
+    /// template passed to [`Self::load`], is meant to output, ie. the
+    /// extension before the template's own, eg. `html` for `about.html.hbs`
+    pub fn output_extension(&self, name: Option<&str>) -> Option<String> {
+        let name = match name {
+            Some(name) => name,
+            None => self.name.as_deref()?,
+        };
+        if !self.sources.contains_key(name) {
+            return None;
+        }
+
+        let path = self.source_path(name);
+        Path::new(path.file_stem()?).extension().map(str::to_owned)
+    }
+
+    /// The name of the template used to render the index of the output of
+    /// each crate, ie. the top-level template named [`INDEX_TEMPLATE`], with
+    /// or without the extension of its output, eg. `per-crate-index.md`
+    pub fn index_template(&self) -> Option<&str> {
+        self.sources.keys().map(String::as_str).find(|name| {
+            let stem = name.split_once('.').map_or(*name, |(stem, _)| stem);
+            stem == INDEX_TEMPLATE
+        })
+    }
+
+    fn to_value(input: &Input<'_>) -> anyhow::Result<serde_json::Value> {
+        let redact = input.redact;
+        let prefixes = input.path_prefixes.clone();
+        let mut input = serde_json::to_value(input).context("failed to serialize output")?;
+        redact::packages(&mut input, redact);
+        reproducible::remap_paths(&mut input, &prefixes);
+        Ok(input)
+    }
+
+    /// Renders output that has already been serialized, eg. the output of
+    /// multiple runs combined with [`merge::Merger`]
+    #[inline]
+    pub fn render_value(&self, input: serde_json::Value) -> anyhow::Result<String> {
+        let name = self.ensure_exists(None)?;
+        self.render_value_named(name, input)
+    }
+
+    fn render_value_named(
+        &self,
+        name: &str,
+        mut input: serde_json::Value,
+    ) -> anyhow::Result<String> {
+        let sanitize_html = match self.sanitize {
+            sanitize::Sanitize::Auto => self.html.contains(name),
+            sanitize::Sanitize::Html => true,
+            sanitize::Sanitize::None => false,
+        };
+        if sanitize_html {
+            sanitize::html(&mut input);
+        }
+
+        match &self.registry {
+            Registry::Handlebars(reg) => reg
+                .render(name, &input)
+                .map_err(|err| self.render_error(name, &err)),
+            Registry::Jinja(env) => env
+                .get_template(name)
+                .and_then(|tmpl| tmpl.render(&input))
+                .map_err(|err| {
+                    let source_path = self.source_path(err.name().unwrap_or(name));
+                    located_error("failed to render", &source_path, &err)
+                }),
+        }
+    }
+
+    /// Checks that the specified template, or if `None`, the template passed
+    /// to [`Self::load`], exists, and that every field it references from the
+    /// top-level of the output is actually in the output [`SCHEMA`], so that
+    /// mistakes are caught before the expensive work of gathering licenses
+    pub fn check(&self, name: Option<&str>) -> anyhow::Result<()> {
+        let name = self.ensure_exists(name)?;
+
+        let schema: serde_json::Value = serde_json::from_str(SCHEMA)?;
+        let fields: Vec<_> = schema["properties"]
+            .as_object()
+            .context("schema does not have any properties")?
+            .keys()
+            .map(String::as_str)
+            .collect();
+
+        let unknown = match &self.registry {
+            Registry::Handlebars(reg) => {
+                let Some(template) = reg.get_template(name) else {
+                    return Ok(());
+                };
+
+                unknown_field(template, &fields)
+                    .map(|(field, line, col)| (field.to_owned(), Some((line, col))))
+            }
+            Registry::Jinja(env) => {
+                let template = env.get_template(name)?;
+                let globals: Vec<_> = env.globals().map(|(name, _)| name).collect();
+
+                // The analysis doesn't provide locations, so just report the
+                // first unknown field in a stable order
+                let mut undeclared: Vec<_> = template
+                    .undeclared_variables(false)
+                    .into_iter()
+                    .filter(|var| {
+                        !fields.contains(&var.as_str()) && !globals.contains(&var.as_str())
+                    })
+                    .collect();
+                undeclared.sort();
+                undeclared.into_iter().next().map(|field| (field, None))
+            }
+        };
+
+        if let Some((field, location)) = unknown {
+            let source_path = self.source_path(name);
+            let location = location
+                .map(|(line, col)| format!(" at line {line}, column {col}"))
+                .unwrap_or_default();
+            let suggestion = fields
+                .iter()
+                .find(|known| known.starts_with(&field) || field.starts_with(*known))
+                .map(|known| format!(", did you mean '{known}'?"))
+                .unwrap_or_default();
+
+            anyhow::bail!(
+                "template '{source_path}' references unknown field '{field}'{location}{suggestion}"
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Fails if the specified template does not exist, or, if `None`, if the
+    /// name of the template to render was not provided
+    fn ensure_exists<'n>(&'n self, name: Option<&'n str>) -> anyhow::Result<&'n str> {
+        let name = match name {
+            Some(name) => name,
+            None => self.name.as_deref().context(
+                "specified a directory for templates, but did not provide the name of the template to use",
+            )?,
+        };
+
+        anyhow::ensure!(
+            self.sources.contains_key(name),
+            "template '{name}' does not exist"
+        );
+        Ok(name)
+    }
+
+    /// The paths of the source files of every template
+    pub fn source_paths(&self) -> impl Iterator<Item = PathBuf> + '_ {
+        self.sources.keys().map(|name| self.source_path(name))
+    }
+
+    /// The files in the `static` subdirectory of a templates directory, eg.
+    /// stylesheets, fonts, and logos, which are copied alongside the output,
+    /// along with their path relative to the `static` directory
+    pub fn static_files(&self) -> anyhow::Result<Vec<(PathBuf, PathBuf)>> {
+        let static_dir = self.path.join("static");
+        if !self.path.is_dir() || !static_dir.is_dir() {
+            return Ok(Vec::new());
+        }
+
+        let mut files = Vec::new();
+        for entry in ignore::WalkBuilder::new(&static_dir)
+            .standard_filters(false)
+            .sort_by_file_path(|a, b| a.cmp(b))
+            .build()
+        {
+            let entry = entry?;
+            if !entry.file_type().is_some_and(|ft| ft.is_file()) {
+                continue;
+            }
+
+            let Ok(path) = PathBuf::from_path_buf(entry.into_path()) else {
+                continue;
+            };
+
+            let rel_path = path.strip_prefix(&static_dir)?.to_owned();
+            files.push((path, rel_path));
+        }
+
+        Ok(files)
+    }
+
+    /// The path of the source file for the specified template
+    fn source_path(&self, name: &str) -> PathBuf {
+        match self.sources.get(name) {
+            Some(rel_path) if self.path.is_dir() => self.path.join(rel_path),
+            _ => self.path.clone(),
+        }
+    }
+
+    /// Converts a render error into an error with the location of the error
+    /// in the template, along with a snippet of the template source
+    fn render_error(&self, name: &str, err: &handlebars::RenderError) -> anyhow::Error {
+        let source_path = self.source_path(err.template_name.as_deref().unwrap_or(name));
+
+        let (Some(line), Some(col)) = (err.line_no, err.column_no) else {
+            return anyhow::anyhow!(
+                "failed to render template '{source_path}': {}",
+                err.reason()
+            );
+        };
+
+        let source = std::fs::read_to_string(&source_path).ok();
+        location_error(
+            "failed to render",
+            &source_path,
+            source.as_deref(),
+            line,
+            col,
+            &err.reason().to_string(),
+        )
+    }
+}
+
+/// Creates a handlebars registry with our custom helpers, and the template(s)
+/// at the specified path registered
+fn handlebars_registry(template_path: &Path) -> anyhow::Result<handlebars::Handlebars<'static>> {
+    use handlebars::*;
+
+    let mut reg = Handlebars::new();
+
+    reg.register_helper(
+        "json",
+        Box::new(
+            |h: &Helper<'_>,
+             _r: &Handlebars<'_>,
+             _c: &Context,
+             _rc: &mut RenderContext<'_, '_>,
+             out: &mut dyn Output|
+             -> HelperResult {
+                let param = h
+                    .param(0)
+                    .ok_or_else(|| RenderErrorReason::ParamNotFoundForIndex("json", 0))?;
+
+                match serde_json::to_string_pretty(param.value()) {
+                    Ok(json) => Ok(out.write(&json)?),
+                    Err(err) => Err(RenderErrorReason::Other(err.to_string()).into()),
+                }
+            },
+        ),
+    );
+
+    // The macro only accepts a literal for the default, which is the same as
+    // DEFAULT_WRAP_WIDTH
+    handlebars_helper!(wrap: |text: str, { width: u64 = 80 }| {
+        wrap_text(text, width as usize)
+    });
+    reg.register_helper("wrap", Box::new(wrap));
+
+    handlebars_helper!(indent: |text: str, { prefix: str = "    " }| {
+        indent_text(text, prefix)
+    });
+    reg.register_helper("indent", Box::new(indent));
+
+    handlebars_helper!(replace: |text: str, from: str, to: str| text.replace(from, to));
+    reg.register_helper("replace", Box::new(replace));
+
+    handlebars_helper!(markdown_escape: |text: str| markdown_escape_text(text));
+    reg.register_helper("markdown-escape", Box::new(markdown_escape));
+
+    handlebars_helper!(url_encode: |text: str| url_encode_text(text));
+    reg.register_helper("url-encode", Box::new(url_encode));
+
+    // Writes directly to the output, bypassing the HTML escaping applied to
+    // the values returned by helpers
+    reg.register_helper(
+        "html-escape-off",
+        Box::new(
+            |h: &Helper<'_>,
+             _r: &Handlebars<'_>,
+             _c: &Context,
+             _rc: &mut RenderContext<'_, '_>,
+             out: &mut dyn Output|
+             -> HelperResult {
+                let param = h.param(0).ok_or_else(|| {
+                    RenderErrorReason::ParamNotFoundForIndex("html-escape-off", 0)
+                })?;
+
+                match param.value() {
+                    serde_json::Value::String(s) => Ok(out.write(s)?),
+                    serde_json::Value::Null => Ok(()),
+                    other => Ok(out.write(&other.to_string())?),
+                }
+            },
+        ),
+    );
+
+    if template_path.is_dir() {
+        reg.register_templates_directory(
+            template_path,
+            handlebars::DirectorySourceOptions::default(),
+        )?;
+
+        // Every template can be used as a partial by its name, eg.
+        // `{{> partials/header}}`, but templates in subdirectories can also be
+        // used by just their file name, eg. `{{> header}}`, as long as that
+        // name is unambiguous
+        let mut nested = BTreeMap::<_, Vec<_>>::new();
+        for name in reg.get_templates().keys() {
+            if let Some((_, stem)) = name.rsplit_once('/') {
+                nested
+                    .entry(stem.to_owned())
+                    .or_default()
+                    .push(name.clone());
+            }
+        }
+
+        for (stem, names) in nested {
+            let [name] = names.as_slice() else {
+                continue;
+            };
+
+            if reg.has_template(&stem) {
+                continue;
+            }
+
+            let path = template_path.join(format!("{name}.hbs"));
+            let source = std::fs::read_to_string(&path)
+                .with_context(|| format!("failed to read template '{path}'"))?;
+            reg.register_partial(&stem, source)?;
+        }
+    } else {
+        // Ignore the extension, if the user says they want to use a specific file, that's on them
+        reg.register_template_file("tmpl", template_path)?;
+    }
+
+    Ok(reg)
+}
+
+/// The column width used by the `wrap` helper if one is not specified
+const DEFAULT_WRAP_WIDTH: usize = 80;
+
+/// Wraps each line of the text at word boundaries so that it fits within the
+/// specified number of columns, keeping the indentation of the original line
+///
+/// Lines that already fit are left untouched, as are words longer than the
+/// width, which are placed on a line of their own.
+fn wrap_text(text: &str, width: usize) -> String {
+    let mut wrapped = String::with_capacity(text.len());
+
+    for (i, line) in text.split('\n').enumerate() {
+        if i > 0 {
+            wrapped.push('\n');
+        }
+
+        let line = line.strip_suffix('\r').unwrap_or(line);
+        if line.chars().count() <= width {
+            wrapped.push_str(line);
+            continue;
+        }
+
+        let indent = &line[..line.len() - line.trim_start().len()];
+        let indent_len = indent.chars().count();
+        let mut col = 0;
+
+        for word in line.split_whitespace() {
+            let word_len = word.chars().count();
+
+            if col > indent_len && col + 1 + word_len > width {
+                wrapped.push('\n');
+                col = 0;
+            }
+
+            if col == 0 {
+                wrapped.push_str(indent);
+                col = indent_len;
+            } else if col > indent_len {
+                wrapped.push(' ');
+                col += 1;
+            }
+
+            wrapped.push_str(word);
+            col += word_len;
+        }
+    }
+
+    wrapped
+}
+
+/// Prefixes each non-empty line of the text, eg. to place it inside a code
+/// comment
+///
+/// Empty lines only receive the prefix without its trailing whitespace, so
+/// that the output doesn't contain trailing whitespace.
+fn indent_text(text: &str, prefix: &str) -> String {
+    let mut indented = String::with_capacity(text.len());
+    let bare_prefix = prefix.trim_end();
+
+    for (i, line) in text.split('\n').enumerate() {
+        if i > 0 {
+            indented.push('\n');
+        }
+
+        let line = line.strip_suffix('\r').unwrap_or(line);
+        if line.trim().is_empty() {
+            indented.push_str(bare_prefix);
+        } else {
+            indented.push_str(prefix);
+            indented.push_str(line);
+        }
+    }
+
+    indented
+}
+
+/// Escapes the characters that have a special meaning in Markdown with a
+/// backslash, so that the text is rendered literally
+fn markdown_escape_text(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+
+    for c in text.chars() {
+        if "\\`*_{}[]()#+-.!|<>~".contains(c) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+
+    escaped
+}
+
+/// Percent-encodes every byte of the text that is not an unreserved character
+/// as defined by [RFC 3986](https://www.rfc-editor.org/rfc/rfc3986#section-2.3)
+fn url_encode_text(text: &str) -> String {
+    use std::fmt::Write;
+
+    let mut encoded = String::with_capacity(text.len());
+
+    for b in text.bytes() {
+        if b.is_ascii_alphanumeric() || matches!(b, b'-' | b'.' | b'_' | b'~') {
+            encoded.push(b as char);
+        } else {
+            let _ = write!(encoded, "%{b:02X}");
+        }
+    }
+
+    encoded
+}
+
+/// Gathers the relative path of every template with one of the specified
+/// extensions in the directory, keyed by the name of the template, which is
+/// the relative path without the extension
+fn template_sources(
+    template_dir: &Path,
+    extensions: &[&str],
+) -> anyhow::Result<BTreeMap<String, PathBuf>> {
+    let mut sources = BTreeMap::new();
+
+    for entry in ignore::WalkBuilder::new(template_dir)
+        .standard_filters(false)
+        .build()
+    {
+        let entry = entry?;
+        if !entry.file_type().is_some_and(|ft| ft.is_file()) {
+            continue;
+        }
+
+        let Ok(path) = PathBuf::from_path_buf(entry.into_path()) else {
+            continue;
+        };
+
+        if !path
+            .extension()
+            .is_some_and(|ext| extensions.contains(&ext))
+        {
+            continue;
+        }
+
+        let rel_path = path.strip_prefix(template_dir)?.to_owned();
+        let name = rel_path.with_extension("").as_str().replace('\\', "/");
+        sources.insert(name, rel_path);
+    }
+
+    Ok(sources)
+}
+
+/// Converts a minijinja error into an error with the location of the error in
+/// the template, along with a snippet of the template source
+fn located_error(action: &str, source_path: &Path, err: &minijinja::Error) -> anyhow::Error {
+    let reason = match err.detail() {
+        Some(detail) => format!("{}: {detail}", err.kind()),
+        None => err.kind().to_string(),
+    };
+
+    let Some(line) = err.line() else {
+        return anyhow::anyhow!("{action} template '{source_path}': {reason}");
+    };
+
+    let source = err.template_source();
+
+    // The column is only available via the byte range of the error
+    let col = err
+        .range()
+        .zip(source)
+        .and_then(|(range, source)| {
+            let before = source.get(..range.start)?;
+            Some(before.len() - before.rfind('\n').map_or(0, |i| i + 1) + 1)
+        })
+        .unwrap_or(1);
+
+    location_error(action, source_path, source, line, col, &reason)
+}
+
+/// Formats an error at the specified location of a template, with a snippet
+/// of the line in question if the source is available
+fn location_error(
+    action: &str,
+    source_path: &Path,
+    source: Option<&str>,
+    line: usize,
+    col: usize,
+    reason: &str,
+) -> anyhow::Error {
+    use std::fmt::Write as _;
+
+    let mut msg =
+        format!("{action} template '{source_path}' at line {line}, column {col}: {reason}");
+
+    if let Some(source) = source.and_then(|source| source.lines().nth(line.saturating_sub(1))) {
+        let gutter = line.to_string().len();
+        let _ = write!(
+            msg,
+            "\n{:gutter$} --> {source_path}:{line}:{col}\n{:gutter$} |\n{line} | {source}\n{:gutter$} | {:>col$}",
+            "", "", "", "^"
+        );
+    }
+
+    anyhow::anyhow!(msg)
+}
+
+/// Finds the first field referenced from the top-level context of the template
+/// that is not one of the known fields, along with its line and column
+fn unknown_field<'t>(
+    template: &'t handlebars::Template,
+    fields: &[&str],
+) -> Option<(&'t str, usize, usize)> {
+    use handlebars::template::{Parameter, TemplateElement};
+
+    let unknown = |raw: &'t str| -> Option<&'t str> {
+        let raw = raw.strip_prefix("this.").unwrap_or(raw);
+        let field = raw.split(['.', '/', '[']).next()?;
+        (!field.is_empty() && field != "this" && !fields.contains(&field)).then_some(field)
+    };
+
+    // Only relative paths at the top-level can be checked, local variables
+    // and parent paths are resolved relative to some other context
+    let root_field = |param: &'t Parameter| -> Option<&'t str> {
+        match param {
+            Parameter::Path(handlebars::Path::Relative((_, raw))) => unknown(raw),
+            _ => None,
+        }
+    };
+
+    for (element, mapping) in template.elements.iter().zip(template.mapping.iter()) {
+        let ht = match element {
+            TemplateElement::Expression(ht)
+            | TemplateElement::HtmlExpression(ht)
+            | TemplateElement::HelperBlock(ht) => ht,
+            _ => continue,
+        };
+
+        let found = if !ht.block && ht.params.is_empty() && ht.hash.is_empty() {
+            // A plain `{{field}}` expression, simple names are parsed as
+            // helper names, but since all of the helpers we provide take
+            // parameters they can only be fields
+            match &ht.name {
+                Parameter::Name(name) => (!name.starts_with('@')
+                    && !fields.contains(&name.as_str()))
+                .then_some(name.as_str()),
+                param => root_field(param),
+            }
+        } else {
+            ht.params
+                .iter()
+                .chain(ht.hash.values())
+                .find_map(root_field)
+        };
+
+        if let Some(field) = found {
+            return Some((field, mapping.0, mapping.1));
+        }
+
+        // `if` and `unless` render their blocks in the same context, but the
+        // main block of other helpers, eg. `each` and `with`, is rendered in
+        // a different context that we can't check, though their inverse
+        // `else` block is always rendered in the same context
+        let same_context = matches!(ht.name.as_name(), Some("if" | "unless"));
+        let blocks = ht
+            .template
+            .iter()
+            .filter(|_| same_context)
+            .chain(ht.inverse.iter());
+
+        for block in blocks {
+            if let Some(found) = unknown_field(block, fields) {
+                return Some(found);
+            }
+        }
+    }
+
+    None
+}
+
+/// The crate graph and license store required to gather license information
+pub struct Prepared {
+    pub krates: crate::Krates,
+    /// The license store, if it could be loaded. If not, only license
+    /// expressions are used, and license texts can't be identified.
+    pub store: Option<Arc<licenses::LicenseStore>>,
+    /// The metadata the graph was built from, so that the graph of each
+    /// target can be built without running `cargo metadata` again
+    metadata: krates::cm::Metadata,
+}
+
+/// Builder for running the full license gathering and resolution pipeline
+pub struct Pipeline {
+    manifest_path: PathBuf,
+    cfg: Config,
+    features: Vec<String>,
+    all_features: bool,
+    no_default_features: bool,
+    workspace: bool,
+    packages: Vec<String>,
+    bins: Vec<String>,
+    lock_opts: krates::LockOptions,
+    targets: Vec<String>,
+    threshold: f32,
+    fail_on_missing: bool,
+    color: ColorChoice,
+    message_format: MessageFormat,
+    ascii_diagnostics: bool,
+    verbose_diagnostics: bool,
+    progress: bool,
+    network_audit: Option<licenses::http_cache::NetworkAudit>,
+    registry_dir: Option<PathBuf>,
+    reproducible: bool,
+}
+
+impl Pipeline {
+    /// Creates a pipeline for the specified `Cargo.toml` with the specified
+    /// configuration, see [`load_config`]
+    pub fn new(manifest_path: PathBuf, cfg: Config) -> Self {
+        Self {
+            manifest_path,
+            cfg,
+            features: Vec::new(),
+            all_features: false,
+            no_default_features: false,
+            workspace: false,
+            packages: Vec::new(),
+            bins: Vec::new(),
+            lock_opts: krates::LockOptions {
+                frozen: false,
+                locked: false,
+                offline: false,
+            },
+            targets: Vec::new(),
+            threshold: 0.8,
+            fail_on_missing: false,
+            color: ColorChoice::Never,
+            message_format: MessageFormat::Human,
+            ascii_diagnostics: false,
+            verbose_diagnostics: false,
+            progress: false,
+            network_audit: None,
+            registry_dir: None,
+            reproducible: false,
+        }
+    }
+
+    /// The features to activate
+    pub fn features(mut self, features: Vec<String>) -> Self {
+        self.features = features;
+        self
+    }
+
+    /// Activates all available features
+    pub fn all_features(mut self, all_features: bool) -> Self {
+        self.all_features = all_features;
+        self
+    }
+
+    /// Disables the `default` feature
+    pub fn no_default_features(mut self, no_default_features: bool) -> Self {
+        self.no_default_features = no_default_features;
+        self
+    }
+
+    /// Gathers licenses for the entire workspace, not just the active package
+    pub fn workspace(mut self, workspace: bool) -> Self {
+        self.workspace = workspace;
+        self
+    }
+
+    /// Restricts the crate graph to the specified workspace members and their
+    /// dependencies
+    pub fn packages(mut self, packages: Vec<String>) -> Self {
+        self.packages = packages;
+        self
+    }
+
+    /// Restricts the crate graph to the dependencies of the specified binary
+    /// target(s) in the workspace
+    pub fn bins(mut self, bins: Vec<String>) -> Self {
+        self.bins = bins;
+        self
+    }
+
+    /// The options used when invoking cargo. If `offline` or `frozen` are set,
+    /// no network requests will be made when gathering license information
+    pub fn lock_options(mut self, lock_opts: krates::LockOptions) -> Self {
+        self.lock_opts = lock_opts;
+        self
+    }
+
+    /// The target triples to filter the crate graph by, overriding the
+    /// `targets` in the configuration
+    pub fn targets(mut self, targets: Vec<String>) -> Self {
+        self.targets = targets;
+        self
+    }
+
+    /// Only gathers licenses for the crates matching the specified patterns,
+    /// overriding the `only` in the configuration
+    pub fn only(mut self, only: Vec<String>) -> Self {
+        self.cfg.only = only;
+        self
+    }
+
+    /// The confidence threshold required for license files to be positively
+    /// identified, 0.0 - 1.0
+    pub fn threshold(mut self, threshold: f32) -> Self {
+        self.threshold = threshold;
+        self
+    }
+
+    /// Fails if a license expression cannot be read, synthesized, or clarified
+    /// for a crate
+    pub fn fail_on_missing(mut self, fail_on_missing: bool) -> Self {
+        self.fail_on_missing = fail_on_missing;
+        self
+    }
+
+    /// Whether diagnostics emitted to stderr are colored
+    pub fn color(mut self, color: ColorChoice) -> Self {
+        self.color = color;
+        self
+    }
+
+    /// The format diagnostics are written to stderr in
+    pub fn message_format(mut self, message_format: MessageFormat) -> Self {
+        self.message_format = message_format;
+        self
+    }
+
+    /// Renders diagnostics with only ASCII characters, rather than unicode box
+    /// drawing characters, see [`DiagnosticStyle::ascii`]
+    pub fn ascii_diagnostics(mut self, ascii_diagnostics: bool) -> Self {
+        self.ascii_diagnostics = ascii_diagnostics;
+        self
+    }
+
+    /// How diagnostics are written to stderr
+    fn diagnostic_style(&self) -> DiagnosticStyle {
+        DiagnosticStyle {
+            format: self.message_format,
+            ascii: self.ascii_diagnostics,
+        }
+    }
+
+    /// Emits a diagnostic for each crate, rather than aggregating identical
+    /// diagnostics for multiple crates into one. Only applies to
+    /// [`MessageFormat::Human`]
+    pub fn verbose_diagnostics(mut self, verbose_diagnostics: bool) -> Self {
+        self.verbose_diagnostics = verbose_diagnostics;
+        self
+    }
+
+    /// Displays a progress bar on stderr while gathering licenses, see
+    /// [`crate::progress`]
+    pub fn progress(mut self, progress: bool) -> Self {
+        self.progress = progress;
+        self
+    }
+
+    /// Treats a directory of unpacked crate sources as the registry, so that
+    /// the pipeline can be run hermetically, eg. in tests.
+    ///
+    /// Cargo resolves crates.io dependencies from the directory, which must be
+    /// laid out as a [directory source](https://doc.rust-lang.org/cargo/reference/source-replacement.html#directory-sources),
+    /// files that would be retrieved from a crate's git repository are read
+    /// from `.repos/<host>/<org>/<repo>/<rev>/<path>` in the directory, and
+    /// clearlydefined.io is not queried
+    pub fn registry_dir(mut self, registry_dir: Option<PathBuf>) -> Self {
+        self.registry_dir = registry_dir;
+        self
+    }
+
+    /// Generates output that is identical across runs and machines, see
+    /// [`reproducible`]. Absolute source paths are output in the logical
+    /// style instead, as they differ between machines
+    pub fn reproducible(mut self, reproducible: bool) -> Self {
+        self.reproducible = reproducible;
+        if reproducible && self.cfg.source_path_style == licenses::config::SourcePathStyle::Absolute
+        {
+            self.cfg.source_path_style = licenses::config::SourcePathStyle::Logical;
+        }
+        self
+    }
+
+    /// The configuration used by the pipeline
+    #[inline]
+    pub fn config(&self) -> &Config {
+        &self.cfg
+    }
+
+    /// The path of the `Cargo.toml` the pipeline gathers licenses for
+    #[inline]
+    pub fn manifest_path(&self) -> &Path {
+        &self.manifest_path
+    }
+
+    /// Records every HTTP request in the audit rather than sending it. Cargo
+    /// is also run offline, so the crate graph must be resolvable without
+    /// network access
+    pub fn network_audit(mut self, audit: licenses::http_cache::NetworkAudit) -> Self {
+        self.network_audit = Some(audit);
+        self
+    }
+
+    /// Runs `cargo metadata` for the workspace
+    fn metadata(&self) -> anyhow::Result<krates::cm::Metadata> {
+        log::info!("gathering crates for {}", self.manifest_path);
+        crate::crates_metadata(
+            &self.manifest_path,
+            self.no_default_features,
+            self.all_features,
+            self.features.clone(),
+            krates::LockOptions {
+                offline: self.lock_opts.offline || self.network_audit.is_some(),
+                ..self.lock_opts
+            },
+            self.registry_dir.as_deref(),
+        )
+    }
+
+    /// Builds a crate graph for each of the sets of targets from the metadata
+    /// of the workspace
+    fn graphs(
+        &self,
+        metadata: &krates::cm::Metadata,
+        target_sets: &[&[String]],
+    ) -> anyhow::Result<Vec<crate::Krates>> {
+        crate::graphs_from_metadata(
+            metadata,
+            self.workspace,
+            &self.packages,
+            &self.bins,
+            &self.cfg,
+            target_sets,
+        )
+    }
+
+    /// Gathers the crate graph and loads the license store in parallel
+    pub fn prepare(&self) -> anyhow::Result<Prepared> {
+        let (graph, store) = rayon::join(
+            || -> anyhow::Result<_> {
+                let metadata = self.metadata()?;
+                let krates = self.graphs(&metadata, &[&self.targets])?.remove(0);
+                Ok((metadata, krates))
+            },
+            || {
+                log::info!("loading license store");
+                licenses::store_from_cache()
+            },
+        );
+
+        let (metadata, krates) = graph?;
+        log::info!("gathered {} crates", krates.len());
+
+        let store = match store {
+            Ok(store) => Some(Arc::new(store)),
+            Err(err) => {
+                log::error!("{err:#}, falling back to only using license expressions, license texts will not be identified");
+                None
+            }
+        };
+
+        Ok(Prepared {
+            krates,
+            store,
+            metadata,
+        })
+    }
+
+    /// Gathers the license information for every crate in the graph, without
+    /// resolving it against the accepted licenses
+    pub fn gather<'k>(
+        &self,
+        prepared: &'k Prepared,
+    ) -> anyhow::Result<Vec<licenses::KrateLicense<'k>>> {
+        let cfg = &self.cfg;
+
+        let client = if !self.lock_opts.offline
+            && !self.lock_opts.frozen
+            && self.registry_dir.is_none()
+        {
+            Some(licenses::http_cache::client_builder(cfg.user_agent_suffix.as_deref()).build()?)
+        } else {
+            None
+        };
+
+        let gatherer = match &prepared.store {
+            Some(store) => licenses::Gatherer::with_store(store.clone()),
+            None => licenses::Gatherer::expression_only(),
+        };
+
+        let summary = gatherer
+            .with_confidence_threshold(self.threshold)
+            .with_max_depth(cfg.max_depth.map(|md| md as _))
+            .with_scan_timeout(Some(std::time::Duration::from_secs(
+                cfg.scan_timeout_secs.unwrap_or(120),
+            )))
+            .with_network_audit(self.network_audit.clone())
+            .with_progress(self.progress)
+            .with_registry_dir(self.registry_dir.clone())
+            .gather(&prepared.krates, cfg, client);
+
+        Ok(summary)
+    }
+
+    /// Gathers and resolves the licenses for every crate in the graph, returning
+    /// the data used for output
+    pub fn generate<'k>(&self, prepared: &'k Prepared) -> anyhow::Result<Input<'k>> {
+        let summary = self.gather(prepared)?;
+        self.resolve(prepared, &summary)
+    }
+
+    /// Gathers the licenses for every crate in the graph once, then resolves
+    /// them separately for each target, with only the crates that are built
+    /// for the target, so that eg. an attribution document can be written for
+    /// each platform without scanning the same crates again.
+    ///
+    /// The graph must have been prepared for all of the targets, see
+    /// [`Self::targets`]
+    pub fn generate_by_target<'k>(
+        &self,
+        prepared: &'k Prepared,
+        targets: &[String],
+    ) -> anyhow::Result<Vec<(String, Input<'k>)>> {
+        let summary = self.gather(prepared)?;
+
+        let target_sets: Vec<_> = targets.iter().map(std::slice::from_ref).collect();
+        let graphs = self.graphs(&prepared.metadata, &target_sets)?;
+
+        let mut inputs = Vec::with_capacity(targets.len());
+        let mut used = Vec::new();
+
+        for (target, krates) in targets.iter().zip(&graphs) {
+            let ids: std::collections::BTreeSet<_> =
+                krates.krates().map(|krate| &krate.id).collect();
+
+            let summary: Vec<_> = summary
+                .iter()
+                .filter(|kl| ids.contains(&kl.krate.id))
+                .cloned()
+                .collect();
+
+            // The dependency paths are found in the target's own graph, as
+            // the path in the graph of every target can go through crates
+            // that aren't built for this one
+            let (input, resolved) = self
+                .resolve_in(prepared, krates, &summary, false)
+                .with_context(|| format!("failed to generate licenses for target '{target}'"))?;
+
+            used.extend(
+                summary
+                    .iter()
+                    .zip(resolved)
+                    .filter_map(|(kl, res)| Some((kl.krate, res?))),
+            );
+            inputs.push((target.clone(), input));
+        }
+
+        // A licensee or exception is only unused if no target uses it
+        let used: Vec<_> = used.iter().map(|(krate, res)| (*krate, res)).collect();
+        let stream = StandardStream::stderr(self.color);
+        for diag in unused_diagnostics(&self.cfg, &used) {
+            emit_diagnostic(
+                &mut stream.lock(),
+                None,
+                &diag,
+                &licenses::resolution::Files::new(),
+                self.diagnostic_style(),
+            )?;
+        }
+
+        Ok(inputs)
+    }
+
+    /// Resolves licenses that were already gathered, see [`Self::gather`],
+    /// returning the data used for output
+    pub fn resolve<'k>(
+        &self,
+        prepared: &'k Prepared,
+        summary: &[licenses::KrateLicense<'k>],
+    ) -> anyhow::Result<Input<'k>> {
+        let (input, _) = self.resolve_in(prepared, &prepared.krates, summary, true)?;
+        Ok(input)
+    }
+
+    /// Resolves licenses the same as [`Self::resolve`], but with the
+    /// dependency paths of crates found in the specified graph, eg. the graph
+    /// of a single target, returning the resolution of each crate as well.
+    ///
+    /// Unused accepted licensees and exceptions are only reported if
+    /// `report_unused` is true, as they can be used by another target
+    fn resolve_in<'k>(
+        &self,
+        prepared: &'k Prepared,
+        krates: &crate::Krates,
+        summary: &[licenses::KrateLicense<'k>],
+        report_unused: bool,
+    ) -> anyhow::Result<(Input<'k>, Vec<Option<licenses::Resolved>>)> {
+        let cfg = &self.cfg;
+
+        let (files, mut resolved) = licenses::resolution::resolve(
+            summary,
+            &cfg.accepted,
+            &cfg.denied,
+            &cfg.crates,
+            &cfg.exceptions,
+            self.fail_on_missing,
+            cfg.include_unaccepted,
+        );
+
+        // It's often unclear why a crate that fails to resolve is even in the
+        // graph, so its errors include the path from the workspace to it
+        let paths = dependents::paths(krates);
+        for (kl, res) in summary.iter().zip(resolved.iter_mut()) {
+            let (Some(res), Some(path)) = (res, paths.get(&kl.krate.id)) else {
+                continue;
+            };
+            if path.is_empty() {
+                continue;
+            }
+
+            let note = dependents::note(path, kl.krate);
+            for diag in res
+                .diagnostics
+                .iter_mut()
+                .filter(|diag| diag.severity >= licenses::resolution::Severity::Error)
+            {
+                diag.notes.push(note.clone());
+            }
+        }
+
+        let stream = StandardStream::stderr(self.color);
+        let generate = if report_unused {
+            generate
+        } else {
+            generate_input
+        };
+        let mut input = generate(
+            summary,
+            &resolved,
+            &files,
+            cfg,
+            &stream,
+            self.diagnostic_style(),
+            self.verbose_diagnostics,
+        )?;
+        for pl in &mut input.crates {
+            if let Some(path) = paths.get(&pl.package.id) {
+                pl.dependents = path.iter().map(|krate| krate.to_string()).collect();
+            }
+        }
+        input.degraded = prepared.store.is_none();
+        input.strings = strings::resolve(cfg, &self.project_name(prepared))?;
+        input.metadata = Metadata::new(
+            &self.manifest_path,
+            &prepared.krates.workspace_root().join("Cargo.lock"),
+            cfg,
+            self.reproducible,
+        );
+        if self.reproducible {
+            // The users of a license are otherwise ordered by their package
+            // id, which contains the local path of path dependencies
+            for lic in &mut input.licenses {
+                lic.used_by.sort_by(|a, b| {
+                    (&a.krate.name, &a.krate.version).cmp(&(&b.krate.name, &b.krate.version))
+                });
+            }
+            input.path_prefixes = reproducible::path_prefixes(prepared.krates.workspace_root());
+        }
+        Ok((input, resolved))
+    }
+
+    /// The name of the package the pipeline gathers licenses for, or the name
+    /// of the workspace directory for virtual manifests
+    fn project_name(&self, prepared: &Prepared) -> String {
+        let manifest_path = self
+            .manifest_path
+            .canonicalize_utf8()
+            .unwrap_or_else(|_| self.manifest_path.clone());
+
+        prepared
+            .krates
+            .krates()
+            .find(|krate| krate.manifest_path == manifest_path)
+            .map(|krate| krate.name.clone())
+            .or_else(|| {
+                prepared
+                    .krates
+                    .workspace_root()
+                    .file_name()
+                    .map(String::from)
+            })
+            .unwrap_or_else(|| "this project".to_owned())
+    }
+
+    /// Validates the configuration against the crate graph, emitting a
+    /// diagnostic for each problem found, see [`validate::config`]. Fails if
+    /// any of the problems are errors
+    pub fn validate_config(&self, prepared: &Prepared) -> anyhow::Result<()> {
+        let (files, diags) = validate::config(&self.cfg, &prepared.krates);
+
+        let stream = StandardStream::stderr(self.color);
+        for diag in &diags {
+            emit_diagnostic(
+                &mut stream.lock(),
+                None,
+                diag,
+                &files,
+                self.diagnostic_style(),
+            )?;
+        }
+
+        let num_errors = diags
+            .iter()
+            .filter(|diag| diag.severity >= licenses::resolution::Severity::Error)
+            .count();
+
+        anyhow::ensure!(
+            num_errors == 0,
+            "encountered {num_errors} errors validating the configuration"
+        );
+
+        Ok(())
+    }
+}
+
+/// A stable subset of a crate's metadata, so that templates don't need to
+/// depend on the shape of the full package, which is determined by
+/// `cargo metadata`
+#[derive(Clone, Serialize)]
+pub struct CrateMetadata {
+    pub description: Option<String>,
+    pub repository: Option<String>,
+    pub homepage: Option<String>,
+    pub documentation: Option<String>,
+    /// The URL of the crate's page on crates.io, if it was retrieved from
+    /// crates.io
+    pub crates_io_url: Option<String>,
+}
+
+impl CrateMetadata {
+    /// Copies the metadata of the package, removing any that is redacted
+    pub fn new(krate: &Package, redact: licenses::config::Redact) -> Self {
+        let crates_io_url = krate
+            .source
+            .as_ref()
+            .is_some_and(|src| src.is_crates_io())
+            .then(|| format!("https://crates.io/crates/{}/{}", krate.name, krate.version));
+
+        let internal = !redact::is_registry(krate.source.as_ref().map(|src| src.repr.as_str()));
+
+        Self {
+            description: krate
+                .description
+                .clone()
+                .filter(|_| !(redact.internal_descriptions && internal)),
+            repository: krate.repository.clone(),
+            homepage: krate.homepage.clone(),
+            documentation: krate.documentation.clone(),
+            crates_io_url,
+        }
+    }
+}
+
+#[derive(Clone, Serialize)]
+pub struct UsedBy<'a> {
+    #[serde(rename = "crate")]
+    pub krate: &'a krates::cm::Package,
+    pub path: Option<PathBuf>,
+    /// True if the crate's text for the license only differs from the
+    /// license's text in whitespace, only possible when license texts are
+    /// normalized
+    pub normalized: bool,
+    #[serde(flatten)]
+    pub metadata: CrateMetadata,
+}
+
+#[derive(Clone, Serialize)]
+pub struct License<'a> {
+    /// The full name of the license
+    pub name: String,
+    /// The SPDX short identifier for the license
+    pub id: String,
+    /// True if this is the first license of its kind in the flat array
+    pub first_of_kind: bool,
+    /// The full license text
+    pub text: String,
+    /// The path where the license text was sourced from
+    pub source_path: Option<PathBuf>,
+    /// The list of crates this license was applied to
+    pub used_by: Vec<UsedBy<'a>>,
+    /// The NOTICE files of the crates this license was applied to, which must
+    /// be reproduced along with the license text
+    pub notices: Vec<Notice<'a>>,
+    /// False if any of the crates this license was applied to could only be
+    /// used under it without it being accepted
+    pub accepted: bool,
+}
+
+/// The contents of a crate's NOTICE file
+#[derive(Clone, Serialize)]
+pub struct Notice<'a> {
+    #[serde(rename = "crate")]
+    pub krate: &'a krates::cm::Package,
+    /// The path of the NOTICE file
+    pub path: PathBuf,
+    /// The full text of the NOTICE file
+    pub text: String,
+}
+
+#[derive(Serialize)]
+pub struct LicenseSet {
+    /// The number of crates that use the license
+    pub count: usize,
+    /// The full name of the license
+    pub name: String,
+    /// The SPDX short identifier for the license
+    pub id: String,
+    /// The indices into [`Input::licenses`] of each unique text of the license
+    pub indices: Vec<usize>,
+    /// The text of the first license of this kind
+    pub text: String,
+}
+
+/// The licenses in the output that belong to the same family, and thus imply
+/// the same class of obligations
+#[derive(Serialize)]
+pub struct ObligationSet {
+    pub family: licenses::obligations::Family,
+    pub obligation: licenses::obligations::Obligation,
+    pub summary: &'static str,
+    /// The SPDX identifiers of the licenses in the family
+    pub licenses: Vec<String>,
+}
+
+/// The obligations checklist of the licenses in the output that belong to
+/// the same family
+#[derive(Serialize)]
+pub struct Checklist {
+    pub family: licenses::obligations::Family,
+    /// The SPDX identifiers of the licenses in the family
+    pub licenses: Vec<String>,
+    /// Every requirement, and whether it applies to the family
+    pub items: Vec<ChecklistItem>,
+}
+
+#[derive(Serialize)]
+pub struct ChecklistItem {
+    pub requirement: licenses::obligations::Requirement,
+    /// A short, human readable description of the requirement
+    pub description: &'static str,
+    /// True if any license in the family imposes the requirement
+    pub required: bool,
+    /// The SPDX identifiers of the licenses in the family that impose the
+    /// requirement
+    pub licenses: Vec<String>,
+}
+
+/// The versions of the tool and data used to generate the output, so that
+/// differences between two outputs can be attributed to changes in the tool
+/// rather than changes in the dependency graph
+#[derive(Clone, Serialize)]
+pub struct ToolInfo {
+    /// The version of cargo-about
+    pub version: &'static str,
+    /// The version of the SPDX license list used to parse license expressions
+    pub spdx_version: &'static str,
+    /// The date the embedded license store used for license detection was
+    /// generated
+    pub license_store_date: &'static str,
+    /// The digest of the embedded license store used for license detection
+    pub license_store_digest: String,
+    /// The workarounds that were enabled
+    pub workarounds: Vec<String>,
+}
+
+impl ToolInfo {
+    pub fn new(cfg: &Config) -> Self {
+        Self {
+            version: env!("CARGO_PKG_VERSION"),
+            spdx_version: spdx::identifiers::VERSION,
+            license_store_date: licenses::STORE_DATE,
+            license_store_digest: licenses::store_digest(),
+            workarounds: cfg.workarounds.clone(),
+        }
+    }
+}
+
+/// The policy the output was generated with, so that documents can include an
+/// appendix of eg. the licenses accepted under company policy, which auditors
+/// frequently request alongside the attribution
+#[derive(Clone, Serialize)]
+pub struct Policy {
+    /// The accepted licensees, in order of preference
+    pub accepted: Vec<String>,
+    /// The workarounds that were enabled
+    pub workarounds: Vec<String>,
+    /// The path of the configuration, `None` if the default configuration was
+    /// used
+    pub config_path: Option<PathBuf>,
+}
+
+impl Policy {
+    pub fn new(cfg: &Config) -> Self {
+        Self {
+            accepted: cfg.accepted.iter().map(|lic| lic.to_string()).collect(),
+            workarounds: cfg.workarounds.clone(),
+            config_path: cfg.config_path.clone(),
+        }
+    }
+}
+
+/// Information about how and when the output was generated, so that
+/// generated documents can be traced back to their inputs, and downstream
+/// caches can detect when they are stale
+#[derive(Clone, Default, Serialize)]
+pub struct Metadata {
+    /// The time the output was generated, in RFC 3339 format, see
+    /// [`reproducible::generated_at`]
+    pub generated_at: String,
+    /// The version of cargo-about
+    pub version: &'static str,
+    /// The path of the manifest the output was generated for
+    pub manifest_path: PathBuf,
+    /// The SHA-256 digest of the workspace's `Cargo.lock`, `None` if there
+    /// is no lock file
+    pub lockfile_digest: Option<String>,
+    /// The SHA-256 digest of the configuration, `None` if the default
+    /// configuration was used
+    pub config_digest: Option<String>,
+}
+
+impl Metadata {
+    pub fn new(
+        manifest_path: &Path,
+        lockfile_path: &Path,
+        cfg: &Config,
+        reproducible: bool,
+    ) -> Self {
+        let digest = |path: &Path| {
+            let contents = std::fs::read(path).ok()?;
+            let digest = ring::digest::digest(&ring::digest::SHA256, &contents);
+            Some(format!("sha256:{}", crate::to_hex(digest.as_ref())))
+        };
+
+        Self {
+            generated_at: reproducible::generated_at(reproducible),
+            version: env!("CARGO_PKG_VERSION"),
+            manifest_path: manifest_path.to_owned(),
+            lockfile_digest: digest(lockfile_path),
+            config_digest: cfg.config_path.as_deref().and_then(digest),
+        }
+    }
+}
+
+/// The data that is serialized as JSON, or passed to templates for rendering
+#[derive(Serialize)]
+pub struct Input<'a> {
+    /// The [`SCHEMA_VERSION`] of the output
+    pub schema_version: u32,
+    pub tool: ToolInfo,
+    /// True if the license store could not be loaded, meaning only license
+    /// expressions were used and license texts could not be identified
+    pub degraded: bool,
+    pub overview: Vec<LicenseSet>,
+    pub licenses: Vec<License<'a>>,
+    pub crates: Vec<PackageLicense<'a>>,
+    /// The crates that were ignored, only populated if `report-ignored` is
+    /// enabled in the configuration
+    pub ignored: Vec<IgnoredCrate<'a>>,
+    /// The crates whose licenses are used without being accepted, due to the
+    /// `exceptions` in the configuration, and the reason for each
+    pub exceptions: Vec<ExceptedCrate<'a>>,
+    /// Only populated if one or more copyleft licenses are in the output
+    pub obligations: Vec<ObligationSet>,
+    /// The obligations checklist of each license family in the output
+    pub checklist: Vec<Checklist>,
+    /// The localized strings for the configured locale, see [`strings::resolve`]
+    pub strings: BTreeMap<String, String>,
+    /// The policy the output was generated with
+    pub policy: Policy,
+    /// How and when the output was generated
+    pub metadata: Metadata,
+    /// The package metadata redacted when the output is serialized, see
+    /// [`redact::packages`]
+    #[serde(skip)]
+    pub redact: licenses::config::Redact,
+    /// The local directories replaced with placeholders when the output is
+    /// serialized, see [`reproducible::remap_paths`]
+    #[serde(skip)]
+    pub path_prefixes: Vec<(String, String)>,
+    /// The paths on disk of the license and notice files gathered for the
+    /// crates, regardless of how [`License::source_path`] is output
+    #[serde(skip)]
+    pub license_files: Vec<PathBuf>,
+}
+
+impl<'a> Input<'a> {
+    /// Restricts the output to the crate at the specified index in
+    /// [`Self::crates`] and the licenses it uses, so that the same templates
+    /// can be rendered once per crate
+    pub fn for_crate(&self, index: usize) -> Input<'a> {
+        let pl = &self.crates[index];
+
+        let mut licenses: Vec<_> = self
+            .licenses
+            .iter()
+            .filter(|lic| lic.used_by.iter().any(|ub| ub.krate.id == pl.package.id))
+            .map(|lic| {
+                let mut lic = lic.clone();
+                lic.used_by.retain(|ub| ub.krate.id == pl.package.id);
+                lic.notices
+                    .retain(|notice| notice.krate.id == pl.package.id);
+                lic.first_of_kind = false;
+                lic
+            })
+            .collect();
+
+        let overview = overview(&mut licenses);
+
+        Input {
+            schema_version: self.schema_version,
+            tool: self.tool.clone(),
+            degraded: self.degraded,
+            obligations: obligations(&overview),
+            checklist: checklist(&overview),
+            overview,
+            licenses,
+            crates: vec![pl.clone()],
+            ignored: Vec::new(),
+            exceptions: self
+                .exceptions
+                .iter()
+                .filter(|exc| exc.package.id == pl.package.id)
+                .cloned()
+                .collect(),
+            strings: self.strings.clone(),
+            policy: self.policy.clone(),
+            metadata: self.metadata.clone(),
+            redact: self.redact,
+            path_prefixes: self.path_prefixes.clone(),
+            license_files: self.license_files.clone(),
+        }
+    }
+
+    /// Splits the output into one entry per crate, each with the full text of
+    /// the licenses that apply to it, used for `json-lines` output
+    pub fn by_crate(&self) -> Vec<CrateLicenses<'_>> {
+        self.crates
+            .iter()
+            .map(|pl| CrateLicenses {
+                package: pl.package,
+                license: &pl.license,
+                licenses: self
+                    .licenses
+                    .iter()
+                    .filter(|lic| lic.used_by.iter().any(|ub| ub.krate.id == pl.package.id))
+                    .map(|lic| CrateLicense {
+                        name: &lic.name,
+                        id: &lic.id,
+                        text: &lic.text,
+                        source_path: lic.source_path.as_deref(),
+                        notices: lic
+                            .notices
+                            .iter()
+                            .filter(|notice| notice.krate.id == pl.package.id)
+                            .map(|notice| CrateNotice {
+                                path: &notice.path,
+                                text: &notice.text,
+                            })
+                            .collect(),
+                    })
+                    .collect(),
+            })
+            .collect()
+    }
+}
+
+/// A single crate and the licenses that apply to it
+#[derive(Serialize)]
+pub struct CrateLicenses<'a> {
+    pub package: &'a Package,
+    /// The license expression for the crate
+    pub license: &'a str,
+    pub licenses: Vec<CrateLicense<'a>>,
+}
+
+/// A license that applies to a specific crate
+#[derive(Serialize)]
+pub struct CrateLicense<'a> {
+    /// The full name of the license
+    pub name: &'a str,
+    /// The SPDX short identifier for the license
+    pub id: &'a str,
+    /// The full license text
+    pub text: &'a str,
+    /// The path where the license text was sourced from
+    pub source_path: Option<&'a Path>,
+    /// The crate's NOTICE files for the license
+    pub notices: Vec<CrateNotice<'a>>,
+}
+
+/// The contents of one of a crate's NOTICE files
+#[derive(Serialize)]
+pub struct CrateNotice<'a> {
+    /// The path of the NOTICE file
+    pub path: &'a Path,
+    /// The full text of the NOTICE file
+    pub text: &'a str,
+}
+
+/// A diagnostic emitted with [`MessageFormat::Json`]
+#[derive(Serialize)]
+struct JsonDiagnostic<'a> {
+    /// The crate the diagnostic refers to, `None` for diagnostics about the
+    /// crate graph as a whole
+    #[serde(rename = "crate")]
+    krate: Option<JsonCrate<'a>>,
+    /// One of `bug`, `error`, `warning`, `note`, or `help`
+    severity: &'static str,
+    message: &'a str,
+    /// The license requirements the diagnostic refers to
+    labels: Vec<JsonLabel<'a>>,
+    notes: &'a [String],
+}
+
+#[derive(Serialize)]
+struct JsonCrate<'a> {
+    name: &'a str,
+    version: String,
+}
+
+#[derive(Serialize)]
+struct JsonLabel<'a> {
+    /// Either `primary` or `secondary`
+    style: &'static str,
+    message: &'a str,
+    /// The file containing the license expression, usually the crate's manifest
+    file: String,
+    /// The text of the span, eg. the license requirement that failed
+    text: &'a str,
+    span: JsonSpan,
+}
+
+/// The byte range of a label, along with the 1-based line and column it starts at
+#[derive(Serialize)]
+struct JsonSpan {
+    start: usize,
+    end: usize,
+    line: usize,
+    column: usize,
+}
+
+impl<'a> JsonDiagnostic<'a> {
+    fn new(
+        krate: Option<&'a crate::Krate>,
+        diag: &'a licenses::resolution::Diagnostic,
+        files: &'a licenses::resolution::Files,
+    ) -> Self {
+        use codespan_reporting::diagnostic::{LabelStyle, Severity};
+
+        let labels = diag
+            .labels
+            .iter()
+            .map(|label| {
+                let source: &str = files.source(label.file_id);
+                let (line, column) = files
+                    .location(label.file_id, label.range.start as u32)
+                    .map_or((0, 0), |loc| {
+                        (loc.line.to_usize() + 1, loc.column.to_usize() + 1)
+                    });
+
+                JsonLabel {
+                    style: match label.style {
+                        LabelStyle::Primary => "primary",
+                        LabelStyle::Secondary => "secondary",
+                    },
+                    message: &label.message,
+                    file: files.name(label.file_id).to_string_lossy().into_owned(),
+                    text: source.get(label.range.clone()).unwrap_or_default(),
+                    span: JsonSpan {
+                        start: label.range.start,
+                        end: label.range.end,
+                        line,
+                        column,
+                    },
+                }
+            })
+            .collect();
+
+        Self {
+            krate: krate.map(|krate| JsonCrate {
+                name: &krate.name,
+                version: krate.version.to_string(),
+            }),
+            severity: match diag.severity {
+                Severity::Bug => "bug",
+                Severity::Error => "error",
+                Severity::Warning => "warning",
+                Severity::Note => "note",
+                Severity::Help => "help",
+            },
+            message: &diag.message,
+            labels,
+            notes: &diag.notes,
+        }
+    }
+}
+
+/// Collapses all whitespace, including line breaks, into single spaces, so
+/// that texts that only differ in their wrapping are considered equal
+fn normalize_license_text(text: &str) -> String {
+    let mut normalized = String::with_capacity(text.len());
+    for word in text.split_whitespace() {
+        if !normalized.is_empty() {
+            normalized.push(' ');
+        }
+        normalized.push_str(word);
+    }
+    normalized
+}
+
+/// Writes a diagnostic to the stream in the specified style
+fn emit_diagnostic(
+    stream: &mut term::termcolor::StandardStreamLock<'_>,
+    krate: Option<&crate::Krate>,
+    diag: &licenses::resolution::Diagnostic,
+    files: &licenses::resolution::Files,
+    style: DiagnosticStyle,
+) -> anyhow::Result<()> {
+    match style.format {
+        MessageFormat::Human => {
+            let config = term::Config {
+                chars: if style.ascii {
+                    term::Chars::ascii()
+                } else {
+                    term::Chars::box_drawing()
+                },
+                ..Default::default()
+            };
+            term::emit(stream, &config, files, diag)?;
+        }
+        MessageFormat::Json => {
+            use std::io::Write as _;
+
+            let json = JsonDiagnostic::new(krate, diag, files);
+            serde_json::to_writer(&mut *stream, &json)?;
+            writeln!(stream)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Whether two diagnostics are identical other than the files they refer to,
+/// eg. the same license requirement failing for multiple crates
+fn is_same_diagnostic(
+    a: &licenses::resolution::Diagnostic,
+    b: &licenses::resolution::Diagnostic,
+    files: &licenses::resolution::Files,
+) -> bool {
+    let text = |label: &codespan_reporting::diagnostic::Label<codespan::FileId>| {
+        files
+            .source(label.file_id)
+            .get(label.range.clone())
+            .unwrap_or_default()
+    };
+
+    // The dependency paths of crates differ even when the problem is the same
+    let notes = |diag: &licenses::resolution::Diagnostic| {
+        diag.notes
+            .iter()
+            .filter(|note| !note.starts_with(dependents::NOTE_PREFIX))
+            .cloned()
+            .collect::<Vec<_>>()
+    };
+
+    a.severity == b.severity
+        && a.message == b.message
+        && notes(a) == notes(b)
+        && a.labels.len() == b.labels.len()
+        && a.labels
+            .iter()
+            .zip(&b.labels)
+            .all(|(a, b)| a.style == b.style && a.message == b.message && text(a) == text(b))
+}
+
+/// Combines a diagnostic emitted for multiple crates into a single one that
+/// lists the crates
+fn aggregate_diagnostic(
+    krates: &[&crate::Krate],
+    diag: &licenses::resolution::Diagnostic,
+    files: &licenses::resolution::Files,
+) -> licenses::resolution::Diagnostic {
+    let texts: Vec<_> = diag
+        .labels
+        .iter()
+        .filter_map(|label| files.source(label.file_id).get(label.range.clone()))
+        .map(|text| format!("'{text}'"))
+        .collect();
+
+    let krates: Vec<_> = krates.iter().map(|krate| krate.to_string()).collect();
+    let summary = if texts.is_empty() {
+        format!("{} crates: {}", krates.len(), krates.join(", "))
+    } else {
+        format!(
+            "{} in {} crates: {}",
+            texts.join(", "),
+            krates.len(),
+            krates.join(", ")
+        )
+    };
+
+    let mut notes = vec![summary];
+    notes.extend(
+        diag.notes
+            .iter()
+            .filter(|note| !note.starts_with(dependents::NOTE_PREFIX))
+            .cloned(),
+    );
+    notes.push("use `--verbose-diagnostics` to show the diagnostic for each crate".to_owned());
+
+    licenses::resolution::Diagnostic::new(diag.severity)
+        .with_message(diag.message.clone())
+        .with_notes(notes)
+}
+
+/// Converts the gathered and resolved license information into the data used
+/// for output, emitting any diagnostics to the specified stream
+///
+/// Fails if any of the diagnostics were errors
+pub fn generate<'kl>(
+    nfos: &[licenses::KrateLicense<'kl>],
+    resolved: &[Option<licenses::Resolved>],
+    files: &licenses::resolution::Files,
+    cfg: &Config,
+    stream: &StandardStream,
+    style: DiagnosticStyle,
+    verbose_diagnostics: bool,
+) -> anyhow::Result<Input<'kl>> {
+    let input = generate_input(
+        nfos,
+        resolved,
+        files,
+        cfg,
+        stream,
+        style,
+        verbose_diagnostics,
+    )?;
+
+    let used: Vec<_> = nfos
+        .iter()
+        .zip(resolved.iter())
+        .filter_map(|(nfo, res)| Some((nfo.krate, res.as_ref()?)))
+        .collect();
+    for diag in unused_diagnostics(cfg, &used) {
+        emit_diagnostic(&mut stream.lock(), None, &diag, files, style)?;
+    }
+
+    Ok(input)
+}
+
+/// Warns about the accepted licensees and exceptions that none of the
+/// resolved crates use, eg. the crates of every target when the output is
+/// split by target, so that each is only reported once
+fn unused_diagnostics(
+    cfg: &Config,
+    used: &[(&crate::Krate, &licenses::Resolved)],
+) -> Vec<licenses::resolution::Diagnostic> {
+    let mut diags = Vec::new();
+
+    // Accepted licensees that no crate uses overstate what the project
+    // actually permits, so they are reported so they can be removed
+    let unused: Vec<_> = cfg
+        .accepted
+        .iter()
+        .filter(|licensee| {
+            !used
+                .iter()
+                .flat_map(|(_, res)| &res.licenses)
+                .any(|req| licensee.satisfies(req))
+        })
+        .map(|licensee| format!("\"{licensee}\""))
+        .collect();
+
+    if !unused.is_empty() {
+        diags.push(
+            licenses::resolution::Diagnostic::warning()
+                .with_message("the following accepted licensees are not used by any crate")
+                .with_notes(vec![unused.join(", ")]),
+        );
+    }
+
+    // Likewise exceptions that no crate relies on, as the crate is either no
+    // longer in the graph, or its licenses are now accepted anyway
+    let unused: Vec<_> = cfg
+        .exceptions
+        .iter()
+        .filter(|exc| {
+            !used
+                .iter()
+                .any(|(krate, res)| krate.name == exc.name && res.excepted)
+        })
+        .map(|exc| format!("'{}'", exc.name))
+        .collect();
+
+    if !unused.is_empty() {
+        diags.push(
+            licenses::resolution::Diagnostic::warning()
+                .with_message("the following crates in `exceptions` don't need an exception")
+                .with_notes(vec![unused.join(", ")]),
+        );
+    }
+
+    diags
+}
+
+/// Converts the gathered and resolved license information into the data used
+/// for output the same as [`generate`], but without reporting unused
+/// accepted licensees or exceptions
+fn generate_input<'kl>(
+    nfos: &[licenses::KrateLicense<'kl>],
+    resolved: &[Option<licenses::Resolved>],
+    files: &licenses::resolution::Files,
+    cfg: &Config,
+    stream: &StandardStream,
+    style: DiagnosticStyle,
+    verbose_diagnostics: bool,
+) -> anyhow::Result<Input<'kl>> {
+    use licenses::resolution::Severity;
+
+    let mut num_errors = 0;
+
+    // Identical diagnostics for multiple crates, eg. 30 crates failing due to
+    // the same unaccepted license, are emitted once after every crate has
+    // been resolved, rather than as near identical reports for each crate
+    let aggregate = style.format == MessageFormat::Human && !verbose_diagnostics;
+    let mut aggregated: Vec<(Vec<&crate::Krate>, &licenses::resolution::Diagnostic)> = Vec::new();
+
+    // The elected licenses of crates that don't include their license text,
+    // only collected if the canonical text is not allowed as a fallback
+    let mut missing_texts: Vec<(&crate::Krate, String)> = Vec::new();
+
+    let mut licenses = {
+        let mut licenses = BTreeMap::new();
+        for (krate_license, resolved) in nfos
+            .iter()
+            .zip(resolved.iter())
+            .filter_map(|(kl, res)| res.as_ref().map(|res| (kl, res)))
+        {
+            if !resolved.diagnostics.is_empty() {
+                let mut streaml = stream.lock();
+
+                for diag in &resolved.diagnostics {
+                    if diag.severity >= Severity::Error {
+                        num_errors += 1;
+                    }
+
+                    if aggregate {
+                        match aggregated
+                            .iter_mut()
+                            .find(|(_, existing)| is_same_diagnostic(existing, diag, files))
+                        {
+                            Some((krates, _)) => krates.push(krate_license.krate),
+                            None => aggregated.push((vec![krate_license.krate], diag)),
+                        }
+                        continue;
+                    }
+
+                    emit_diagnostic(&mut streaml, Some(krate_license.krate), diag, files, style)?;
+                }
+            }
+
+            // The licenses of the alternatives that weren't elected are only
+            // reproduced, so they don't affect whether the crate is accepted
+            let mut alternatives = Vec::new();
+            if cfg.include_or_alternatives {
+                if let LicenseInfo::Expr(expr) = &krate_license.lic_info {
+                    for ereq in expr.requirements() {
+                        if !resolved.licenses.contains(&ereq.req)
+                            && !alternatives.contains(&ereq.req)
+                        {
+                            alternatives.push(ereq.req.clone());
+                        }
+                    }
+                }
+            }
+
+            let license_iter = resolved.licenses.iter().chain(&alternatives).flat_map(|license| {
+                let accepted = !resolved.unaccepted.contains(license);
+                // LicenseRefs have no canonical text, so the text must either
+                // come from a file in the crate, eg. via a clarification, or
+                // from the `license-refs` configuration. SPDX licenses fall back
+                // to their canonical text, unless it is overridden by the
+                // `license-texts` configuration
+                let (name, id, fallback) = match &license.license {
+                    spdx::LicenseItem::Spdx { id, .. } => {
+                        let fallback = cfg
+                            .license_text_overrides
+                            .get(id.name)
+                            .map_or_else(|| id.text(), String::as_str);
+                        (id.full_name.to_owned(), id.name.to_owned(), Some(fallback))
+                    }
+                    other @ spdx::LicenseItem::Other { .. } => {
+                        let id = other.to_string();
+                        let fallback = cfg.license_ref_texts.get(&id).map(String::as_str);
+                        (id.clone(), id, fallback)
+                    }
+                };
+
+                let is_license = |ereq: &spdx::LicenseReq| match &license.license {
+                    spdx::LicenseItem::Spdx { id, .. } => ereq.license.id() == Some(*id),
+                    other @ spdx::LicenseItem::Other { .. } => ereq.license == *other,
+                };
+
+                // Attempt to retrieve the actual license file from the crate, note that in some cases
+                // _sigh_ there are actually multiple license texts for the same license with different
+                // copyright holders/authors/attribution so we can't just return 1
+                let mut license_texts: Vec<_> = krate_license
+                    .license_files
+                    .iter()
+                    .filter_map(|lf| {
+                        // Check if this is the actual license file we want
+                        if !lf.license_expr.evaluate(is_license) {
+                            return None;
+                        }
+
+                        let sub_root = match &lf.kind {
+                            licenses::LicenseFileKind::AddendumText(_, sub_root) => {
+                                Some(sub_root.clone())
+                            }
+                            _ => None,
+                        };
+
+                        match &lf.kind {
+                            licenses::LicenseFileKind::Text(text)
+                            | licenses::LicenseFileKind::AddendumText(text, _) => {
+                                let license = License {
+                                    name: name.clone(),
+                                    id: id.clone(),
+                                    text: text.clone(),
+                                    source_path: (!cfg.redact.source_paths).then(|| {
+                                        output_source_path(
+                                            krate_license.krate,
+                                            &lf.path,
+                                            cfg.source_path_style,
+                                        )
+                                    }),
+                                    used_by: Vec::new(),
+                                    notices: Vec::new(),
+                                    first_of_kind: false,
+                                    accepted,
+                                };
+                                Some((license, sub_root))
+                            }
+                            licenses::LicenseFileKind::Header
+                            | licenses::LicenseFileKind::Notice(_) => None,
+                        }
+                    })
+                    .collect();
+
+                if license_texts.is_empty() {
+                    let Some(fallback) = fallback else {
+                        log::warn!(
+                            "{license} has no license file for crate '{}'",
+                            krate_license.krate
+                        );
+                        return license_texts;
+                    };
+
+                    log::debug!(
+                        "unable to find text for license '{license}' for crate '{}', falling back to canonical text",
+                        krate_license.krate
+                    );
+
+                    // The canonical text has no copyright holder information,
+                    // unlike texts that are configured
+                    let is_canonical = match &license.license {
+                        spdx::LicenseItem::Spdx { id, .. } => {
+                            !cfg.license_text_overrides.contains_key(id.name)
+                                && !cfg.license_override_texts.contains_key(id.name)
+                        }
+                        spdx::LicenseItem::Other { .. } => false,
+                    };
+
+                    if cfg.fail_on_missing_license_text
+                        && is_canonical
+                        && resolved.licenses.contains(license)
+                    {
+                        missing_texts.push((krate_license.krate, license.to_string()));
+                    }
+
+                    // If the crate doesn't have the actual license file,
+                    // fallback to the canonical or configured license text
+                    license_texts.push((
+                        License {
+                            name,
+                            id,
+                            text: fallback.to_owned(),
+                            source_path: None,
+                            used_by: Vec::new(),
+                            notices: Vec::new(),
+                            first_of_kind: false,
+                            accepted,
+                        },
+                        None,
+                    ));
+                }
+
+                // The `license-overrides` configuration replaces every text of
+                // the license, including those found in the crate
+                if let spdx::LicenseItem::Spdx { id, .. } = &license.license {
+                    if let Some(text) = cfg.license_override_texts.get(id.name) {
+                        license_texts.truncate(1);
+                        for (license, _) in &mut license_texts {
+                            license.text.clone_from(text);
+                            license.source_path = None;
+                        }
+                    }
+                }
+
+                let notices: Vec<_> = krate_license
+                    .license_files
+                    .iter()
+                    .filter_map(|lf| match &lf.kind {
+                        licenses::LicenseFileKind::Notice(text)
+                            if lf.license_expr.evaluate(is_license) =>
+                        {
+                            Some(Notice {
+                                krate: krate_license.krate,
+                                path: lf.path.clone(),
+                                text: text.clone(),
+                            })
+                        }
+                        _ => None,
+                    })
+                    .collect();
+
+                for (license, _) in &mut license_texts {
+                    license.notices.clone_from(&notices);
+                }
+
+                license_texts
+            });
+
+            let addenda = addendum_licenses(krate_license, resolved, cfg);
+
+            for (mut license, sub_root) in license_iter.chain(addenda) {
+                let notices = std::mem::take(&mut license.notices);
+
+                let entry = licenses
+                    .entry(license.name.clone())
+                    .or_insert_with(BTreeMap::new);
+
+                let key = if cfg.normalize_license_texts {
+                    normalize_license_text(&license.text)
+                } else {
+                    license.text.clone()
+                };
+
+                let accepted = license.accepted;
+                let (lic, normalized) = match entry.entry(key) {
+                    std::collections::btree_map::Entry::Vacant(vacant) => {
+                        (vacant.insert(license), false)
+                    }
+                    std::collections::btree_map::Entry::Occupied(occupied) => {
+                        let normalized = occupied.get().text != license.text;
+                        (occupied.into_mut(), normalized)
+                    }
+                };
+
+                if normalized {
+                    log::debug!(
+                        "text of '{}' for crate '{}' differs from the text used only in whitespace",
+                        lic.id,
+                        krate_license.krate
+                    );
+                }
+
+                lic.accepted &= accepted;
+                lic.used_by.push(UsedBy {
+                    krate: krate_license.krate,
+                    path: sub_root,
+                    normalized,
+                    metadata: CrateMetadata::new(krate_license.krate, cfg.redact),
+                });
+
+                for notice in notices {
+                    if !lic.notices.iter().any(|n| n.text == notice.text) {
+                        lic.notices.push(notice);
+                    }
+                }
+            }
+        }
+
+        let mut licenses: Vec<_> = licenses
+            .into_values()
+            .flat_map(|v| v.into_values())
+            .collect();
+
+        let collator = collate::Collator::new();
+
+        // Sort the krates that use a license lexicographically
+        for lic in &mut licenses {
+            lic.used_by
+                .sort_by(|a, b| collator.compare_users(a.krate, b.krate));
+        }
+
+        licenses.sort_by(|a, b| collator.compare(&a.id, &b.id));
+        licenses
+    };
+
+    for (krates, diag) in aggregated {
+        if let [krate] = krates.as_slice() {
+            emit_diagnostic(&mut stream.lock(), Some(krate), diag, files, style)?;
+        } else {
+            let diag = aggregate_diagnostic(&krates, diag, files);
+            emit_diagnostic(&mut stream.lock(), None, &diag, files, style)?;
+        }
+    }
+
+    for (krate, license) in missing_texts {
+        num_errors += 1;
+
+        let diag = licenses::resolution::Diagnostic::error()
+            .with_message(format!(
+                "no license text for '{license}' was found in the crate, and the canonical text has no copyright information"
+            ))
+            .with_notes(vec![
+                "the text can be provided with a clarification, or in the `license-overrides-dir`".to_owned(),
+            ]);
+
+        emit_diagnostic(&mut stream.lock(), Some(krate), &diag, files, style)?;
+    }
+
+    // Rather than having users add licensees one at a time until every crate
+    // is satisfied, suggest all of the additions that are needed at once
+    let suggested = licenses::resolution::suggest_accepted(resolved);
+    if !suggested.is_empty() {
+        let suggested: Vec<_> = suggested.iter().map(|l| format!("\"{l}\"")).collect();
+        let diag = licenses::resolution::Diagnostic::help()
+            .with_message(
+                "adding the following to `accepted` would satisfy the license requirements of every crate",
+            )
+            .with_notes(vec![suggested.join(", ")]);
+
+        emit_diagnostic(&mut stream.lock(), None, &diag, files, style)?;
+    }
+
+    for diag in distinct_license_limits(resolved, cfg) {
+        num_errors += 1;
+        emit_diagnostic(&mut stream.lock(), None, &diag, files, style)?;
+    }
+
+    if num_errors > 0 {
+        anyhow::bail!(
+            "encountered {num_errors} errors resolving licenses, unable to generate output"
+        );
+    }
+
+    let overview = overview(&mut licenses);
+
+    let obligations = obligations(&overview);
+    let checklist = checklist(&overview);
+
+    let mut crates: Vec<_> = nfos
+        .iter()
+        .zip(resolved.iter())
+        .filter(|(nfo, _)| !matches!(nfo.lic_info, LicenseInfo::Ignore(_)))
+        .map(|(nfo, resolved)| PackageLicense {
+            package: &nfo.krate.0,
+            license: nfo.lic_info.to_string(),
+            metadata: CrateMetadata::new(nfo.krate, cfg.redact),
+            detection: nfo.detection,
+            notes: nfo.notes.clone(),
+            accepted: resolved
+                .as_ref()
+                .map_or(true, |res| res.unaccepted.is_empty()),
+            dependents: Vec::new(),
+        })
+        .collect();
+
+    // The gathered crates are ordered by their bytes, which is only wrong
+    // with collation
+    if cfg!(feature = "collation") {
+        let collator = collate::Collator::new();
+        crates.sort_by(|a, b| collator.compare_packages(a.package, b.package));
+    }
+
+    let ignored = if cfg.report_ignored {
+        nfos.iter()
+            .filter_map(|nfo| match nfo.lic_info {
+                LicenseInfo::Ignore(reason) => Some(IgnoredCrate {
+                    package: &nfo.krate.0,
+                    reason,
+                }),
+                _ => None,
+            })
+            .collect()
+    } else {
+        Vec::new()
+    };
+
+    // Only the crates whose licenses are accepted because of their exception
+    // are reported, rather than every crate with an exception
+    let exceptions = nfos
+        .iter()
+        .zip(resolved.iter())
+        .filter(|(_, res)| res.as_ref().is_some_and(|res| res.excepted))
+        .filter_map(|(nfo, _)| {
+            let exception = cfg
+                .exceptions
+                .iter()
+                .find(|exc| exc.name == nfo.krate.name)?;
+            Some(ExceptedCrate {
+                package: &nfo.krate.0,
+                license: nfo.lic_info.to_string(),
+                reason: exception.reason.clone(),
+            })
+        })
+        .collect();
+
+    Ok(Input {
+        schema_version: SCHEMA_VERSION,
+        tool: ToolInfo::new(cfg),
+        degraded: false,
+        overview,
+        licenses,
+        crates,
+        ignored,
+        exceptions,
+        obligations,
+        checklist,
+        strings: BTreeMap::new(),
+        policy: Policy::new(cfg),
+        metadata: Metadata::default(),
+        redact: cfg.redact,
+        path_prefixes: Vec::new(),
+        license_files: nfos
+            .iter()
+            .flat_map(|nfo| nfo.license_files.iter().map(|lf| lf.path.clone()))
+            .collect(),
+    })
+}
+
+/// Converts the path of a license file in a crate to the configured style,
+/// so that outputs don't need to depend on the machine they were generated on
+fn output_source_path(
+    krate: &crate::Krate,
+    path: &Path,
+    style: licenses::config::SourcePathStyle,
+) -> PathBuf {
+    use licenses::config::SourcePathStyle;
+
+    let relative = || {
+        let rel_path = krate
+            .manifest_path
+            .parent()
+            .and_then(|root| path.strip_prefix(root).ok())
+            .unwrap_or(path);
+        rel_path.as_str().replace('\\', "/")
+    };
+
+    match style {
+        SourcePathStyle::Absolute => path.to_owned(),
+        SourcePathStyle::Relative => relative().into(),
+        SourcePathStyle::Logical => {
+            let source = krate.source.as_ref().map(|src| src.repr.as_str());
+            let kind = if redact::is_registry(source) {
+                "registry"
+            } else if source.is_some_and(|src| src.starts_with("git+")) {
+                "git"
+            } else {
+                "path"
+            };
+
+            format!("{kind}:{}-{}/{}", krate.name, krate.version, relative()).into()
+        }
+    }
+}
+
+/// The license texts found in subdirectories of a deep scanned crate for
+/// licenses that the crate itself isn't licensed under, eg. the Zlib text of
+/// a vendored zlib in an MIT licensed `-sys` crate, as they still need to be
+/// reproduced. Each is attributed to the subdirectory it was found in.
+fn addendum_licenses<'k>(
+    krate_license: &licenses::KrateLicense<'k>,
+    resolved: &licenses::Resolved,
+    cfg: &Config,
+) -> Vec<(License<'k>, Option<PathBuf>)> {
+    let krate = krate_license.krate;
+    let krate_accepted = cfg
+        .krate_config(krate)
+        .map_or(&[][..], |kc| kc.accepted.as_slice());
+
+    krate_license
+        .license_files
+        .iter()
+        .filter_map(|lf| {
+            let licenses::LicenseFileKind::AddendumText(text, sub_root) = &lf.kind else {
+                return None;
+            };
+
+            // Texts of the crate's own licenses are already attributed
+            if lf
+                .license_expr
+                .requirements()
+                .any(|ereq| resolved.licenses.contains(&ereq.req))
+            {
+                return None;
+            }
+
+            let mut reqs = lf.license_expr.requirements();
+            let (name, id) = match (reqs.next(), reqs.next()) {
+                (Some(ereq), None) => match &ereq.req.license {
+                    spdx::LicenseItem::Spdx { id, .. } => {
+                        (id.full_name.to_owned(), id.name.to_owned())
+                    }
+                    other @ spdx::LicenseItem::Other { .. } => {
+                        (other.to_string(), other.to_string())
+                    }
+                },
+                _ => (lf.license_expr.to_string(), lf.license_expr.to_string()),
+            };
+
+            let accepted = lf.license_expr.evaluate(|req| {
+                cfg.accepted
+                    .iter()
+                    .chain(krate_accepted)
+                    .any(|licensee| licensee.satisfies(req))
+            });
+
+            let license = License {
+                name,
+                id,
+                text: text.clone(),
+                source_path: (!cfg.redact.source_paths)
+                    .then(|| output_source_path(krate, &lf.path, cfg.source_path_style)),
+                used_by: Vec::new(),
+                notices: Vec::new(),
+                first_of_kind: false,
+                accepted,
+            };
+
+            Some((license, Some(sub_root.clone())))
+        })
+        .collect()
+}
+
+/// Groups the licenses by their SPDX identifier, marking the first of each
+/// kind, with the most used licenses first
+fn overview(licenses: &mut [License<'_>]) -> Vec<LicenseSet> {
+    let mut overview: Vec<LicenseSet> = Vec::with_capacity(256);
+
+    for (ndx, lic) in licenses.iter_mut().enumerate() {
+        match overview.binary_search_by(|i| i.id.cmp(&lic.id)) {
+            Ok(i) => {
+                let ov = &mut overview[i];
+                ov.indices.push(ndx);
+                ov.count += lic.used_by.len();
+            }
+            Err(i) => {
+                let mut ls = LicenseSet {
+                    count: lic.used_by.len(),
+                    name: lic.name.clone(),
+                    id: lic.id.clone(),
+                    indices: Vec::with_capacity(10),
+                    text: lic.text.clone(),
+                };
+
+                ls.indices.push(ndx);
+                overview.insert(i, ls);
+                lic.first_of_kind = true;
+            }
+        }
+    }
+
+    // Show the most used licenses first
+    overview.sort_by_key(|ls| std::cmp::Reverse(ls.count));
+
+    overview
+}
+
+fn obligations(overview: &[LicenseSet]) -> Vec<ObligationSet> {
+    use licenses::obligations::family;
+
+    let mut families = BTreeMap::<_, Vec<String>>::new();
+    for ls in overview {
+        let Some(id) = spdx::license_id(&ls.id) else {
+            continue;
+        };
+
+        families.entry(family(id)).or_default().push(ls.id.clone());
+    }
+
+    if !families.keys().any(|fam| fam.is_copyleft()) {
+        return Vec::new();
+    }
+
+    families
+        .into_iter()
+        .map(|(family, licenses)| {
+            if family.is_copyleft() {
+                log::info!(
+                    "{family} license(s) {} imply '{}' obligations",
+                    licenses.join(", "),
+                    family.obligation()
+                );
+            }
+
+            ObligationSet {
+                family,
+                obligation: family.obligation(),
+                summary: family.summary(),
+                licenses,
+            }
+        })
+        .collect()
+}
+
+/// Checks the number of distinct licenses used by the crates, in total and
+/// for each family, against the configured limits, returning an error for
+/// each limit that is exceeded
+fn distinct_license_limits(
+    resolved: &[Option<licenses::Resolved>],
+    cfg: &Config,
+) -> Vec<licenses::resolution::Diagnostic> {
+    use licenses::resolution::Diagnostic;
+
+    if cfg.max_distinct_licenses.is_none() && cfg.max_distinct_licenses_per_family.is_empty() {
+        return Vec::new();
+    }
+
+    // The number of crates using each license, regardless of eg. `+`, as
+    // `GPL-2.0+` is not a different license than `GPL-2.0`
+    let mut in_use = BTreeMap::<String, usize>::new();
+    for res in resolved.iter().flatten() {
+        let mut ids: Vec<_> = res
+            .licenses
+            .iter()
+            .map(|req| match &req.license {
+                spdx::LicenseItem::Spdx { id, .. } => id.name.to_owned(),
+                other @ spdx::LicenseItem::Other { .. } => other.to_string(),
+            })
+            .collect();
+        ids.sort();
+        ids.dedup();
+
+        for id in ids {
+            *in_use.entry(id).or_default() += 1;
+        }
+    }
+
+    let describe = |ids: &[&String]| {
+        ids.iter()
+            .map(|id| format!("{id} ({} crates)", in_use[*id]))
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+
+    let mut diags = Vec::new();
+
+    if let Some(max) = cfg.max_distinct_licenses {
+        if in_use.len() > max {
+            let ids: Vec<_> = in_use.keys().collect();
+            diags.push(
+                Diagnostic::error()
+                    .with_message(format!(
+                        "the crates use {} distinct licenses, more than the {max} allowed by `max-distinct-licenses`",
+                        ids.len()
+                    ))
+                    .with_notes(vec![describe(&ids)]),
+            );
+        }
+    }
+
+    for (family, max) in &cfg.max_distinct_licenses_per_family {
+        let ids: Vec<_> = in_use
+            .keys()
+            .filter(|id| {
+                spdx::license_id(id).is_some_and(|id| licenses::obligations::family(id) == *family)
+            })
+            .collect();
+
+        if ids.len() > *max {
+            diags.push(
+                Diagnostic::error()
+                    .with_message(format!(
+                        "the crates use {} distinct {family} licenses, more than the {max} allowed by `max-distinct-licenses-per-family`",
+                        ids.len()
+                    ))
+                    .with_notes(vec![describe(&ids)]),
+            );
+        }
+    }
+
+    diags
+}
+
+/// Builds the obligations checklist of each license family in the overview,
+/// unlike [`obligations`] this is populated for permissive licenses as well,
+/// as they still have obligations, eg. including notices
+fn checklist(overview: &[LicenseSet]) -> Vec<Checklist> {
+    use licenses::obligations::{family, Requirement};
+
+    let mut families = BTreeMap::<_, Vec<_>>::new();
+    for ls in overview {
+        let Some(id) = spdx::license_id(&ls.id) else {
+            continue;
+        };
+
+        families.entry(family(id)).or_default().push(id);
+    }
+
+    families
+        .into_iter()
+        .map(|(family, ids)| {
+            let items = Requirement::ALL
+                .into_iter()
+                .map(|requirement| {
+                    let licenses: Vec<_> = ids
+                        .iter()
+                        .filter(|id| requirement.applies(**id))
+                        .map(|id| id.name.to_owned())
+                        .collect();
+
+                    ChecklistItem {
+                        requirement,
+                        description: requirement.description(),
+                        required: !licenses.is_empty(),
+                        licenses,
+                    }
+                })
+                .collect();
+
+            Checklist {
+                family,
+                licenses: ids.iter().map(|id| id.name.to_owned()).collect(),
+                items,
+            }
+        })
+        .collect()
+}
+
+#[derive(Clone, Serialize)]
+pub struct PackageLicense<'a> {
+    pub package: &'a Package,
+    pub license: String,
+    #[serde(flatten)]
+    pub metadata: CrateMetadata,
+    /// The overall outcome of license detection for the crate
+    pub detection: licenses::Detection,
+    /// Non-fatal problems encountered while gathering license information
+    /// for the crate
+    pub notes: Vec<String>,
+    /// False if the crate could only be used under one or more licenses that
+    /// are not accepted
+    pub accepted: bool,
+    /// The crates, eg. `reqwest 0.12.0`, that bring the crate into the graph,
+    /// along the shortest path from a workspace member, starting with the
+    /// workspace member. Empty for workspace members
+    pub dependents: Vec<String>,
+}
+
+/// A crate whose license was not gathered, and the reason why
+#[derive(Serialize)]
+pub struct IgnoredCrate<'a> {
+    pub package: &'a Package,
+    pub reason: licenses::IgnoreReason,
+}
+
+/// A crate whose licenses are used without being accepted, and the reason
+/// the exception was granted
+#[derive(Clone, Serialize)]
+pub struct ExceptedCrate<'a> {
+    pub package: &'a Package,
+    /// The license expression of the crate
+    pub license: String,
+    pub reason: String,
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn wraps_long_lines_keeping_indentation() {
+        let text = "short line\n\n    an indented line that is too long to fit\r\nsupercalifragilistic word";
+
+        assert_eq!(
+            wrap_text(text, 20),
+            "short line\n\n    an indented line\n    that is too long\n    to fit\nsupercalifragilistic\nword"
+        );
+    }
+
+    #[test]
+    fn converts_source_paths() {
+        use licenses::config::SourcePathStyle;
+
+        let krate: crate::Krate = serde_json::from_value::<Package>(serde_json::json!({
+            "name": "serde",
+            "version": "1.0.200",
+            "id": "registry+https://github.com/rust-lang/crates.io-index#serde@1.0.200",
+            "source": "registry+https://github.com/rust-lang/crates.io-index",
+            "manifest_path": "/registry/serde-1.0.200/Cargo.toml",
+            "dependencies": [],
+            "targets": [],
+            "features": {},
+        }))
+        .unwrap()
+        .into();
+
+        let path = Path::new("/registry/serde-1.0.200/LICENSE-MIT");
+        assert_eq!(
+            output_source_path(&krate, path, SourcePathStyle::Absolute),
+            path
+        );
+        assert_eq!(
+            output_source_path(&krate, path, SourcePathStyle::Relative),
+            "LICENSE-MIT"
+        );
+        assert_eq!(
+            output_source_path(&krate, path, SourcePathStyle::Logical),
+            "registry:serde-1.0.200/LICENSE-MIT"
+        );
+    }
+
+    #[test]
+    fn text_helpers() {
+        assert_eq!(
+            indent_text("first\n\r\nsecond", " * "),
+            " * first\n *\n * second"
+        );
+        assert_eq!(
+            markdown_escape_text("*not* [a](link) #1"),
+            "\\*not\\* \\[a\\]\\(link\\) \\#1"
+        );
+        assert_eq!(
+            url_encode_text("MIT OR Apache-2.0/ü"),
+            "MIT%20OR%20Apache-2.0%2F%C3%BC"
+        );
+    }
+
+    #[test]
+    fn schema_matches_output() {
+        let schema: serde_json::Value = serde_json::from_str(SCHEMA).unwrap();
+
+        assert_eq!(
+            schema["properties"]["schema_version"]["const"],
+            SCHEMA_VERSION
+        );
+
+        let input = serde_json::to_value(Input {
+            schema_version: SCHEMA_VERSION,
+            tool: ToolInfo::new(&Config::default()),
+            degraded: false,
+            overview: Vec::new(),
+            licenses: Vec::new(),
+            crates: Vec::new(),
+            ignored: Vec::new(),
+            exceptions: Vec::new(),
+            obligations: Vec::new(),
+            checklist: Vec::new(),
+            strings: BTreeMap::new(),
+            policy: Policy::new(&Config::default()),
+            metadata: Metadata::default(),
+            redact: Default::default(),
+            path_prefixes: Vec::new(),
+            license_files: Vec::new(),
+        })
+        .unwrap();
+
+        let mut output_keys: Vec<_> = input.as_object().unwrap().keys().cloned().collect();
+        let mut schema_keys: Vec<_> = schema["required"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|k| k.as_str().unwrap().to_owned())
+            .collect();
+
+        output_keys.sort();
+        schema_keys.sort();
+        assert_eq!(output_keys, schema_keys);
+    }
+}
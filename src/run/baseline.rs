@@ -0,0 +1,113 @@
+//! Records the licenses each crate resolved to, so that projects with existing
+//! license violations that can't be fixed immediately can still fail on new ones
+//!
+//! A baseline is written with all of the crates in the output, and a later
+//! check only fails for crates whose licenses are not accepted, and that are
+//! not in the baseline with the same version and license.
+
+use super::Input;
+use anyhow::Context as _;
+use krates::Utf8Path as Path;
+use std::{collections::BTreeSet, fmt};
+
+/// A crate and the license(s) it resolved to
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Entry {
+    pub name: String,
+    pub version: String,
+    /// The SPDX identifiers of the licenses used for the crate, joined with
+    /// `AND`, or the crate's license expression if none of them could be
+    /// resolved to a license text
+    pub license: String,
+}
+
+impl fmt::Display for Entry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {} ({})", self.name, self.version, self.license)
+    }
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Default, Debug)]
+pub struct Baseline {
+    #[serde(default, rename = "crate")]
+    pub crates: BTreeSet<Entry>,
+}
+
+/// The differences between the current output and a baseline
+#[derive(Default, Debug)]
+pub struct Deviations {
+    /// Crates whose licenses are not accepted, and are not in the baseline
+    pub violations: Vec<Entry>,
+    /// Entries in the baseline that no longer match any crate, and can be
+    /// removed by writing the baseline again
+    pub stale: Vec<Entry>,
+}
+
+/// Retrieves the entry for every crate in the output, along with whether its
+/// licenses are all accepted
+pub(super) fn entries(input: &Input<'_>) -> Vec<(Entry, bool)> {
+    input
+        .crates
+        .iter()
+        .map(|pl| {
+            let ids: BTreeSet<_> = input
+                .licenses
+                .iter()
+                .filter(|lic| lic.used_by.iter().any(|ub| ub.krate.id == pl.package.id))
+                .map(|lic| lic.id.as_str())
+                .collect();
+
+            let license = if ids.is_empty() {
+                pl.license.clone()
+            } else {
+                ids.into_iter().collect::<Vec<_>>().join(" AND ")
+            };
+
+            (
+                Entry {
+                    name: pl.package.name.clone(),
+                    version: pl.package.version.to_string(),
+                    license,
+                },
+                pl.accepted,
+            )
+        })
+        .collect()
+}
+
+impl Baseline {
+    /// Creates a baseline from every crate in the output
+    pub fn new(input: &Input<'_>) -> Self {
+        Self {
+            crates: entries(input).into_iter().map(|(entry, _)| entry).collect(),
+        }
+    }
+
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let contents = std::fs::read_to_string(path)
+            .with_context(|| format!("failed to read baseline '{path}'"))?;
+        toml::from_str(&contents).with_context(|| format!("failed to deserialize '{path}'"))
+    }
+
+    pub fn write(&self, path: &Path) -> anyhow::Result<()> {
+        let contents = toml::to_string(self).context("failed to serialize baseline")?;
+        std::fs::write(path, contents).with_context(|| format!("failed to write '{path}'"))
+    }
+
+    /// Compares the output against the baseline
+    pub fn check(&self, input: &Input<'_>) -> Deviations {
+        let mut deviations = Deviations::default();
+        let mut matched = BTreeSet::new();
+
+        for (entry, accepted) in entries(input) {
+            if self.crates.contains(&entry) {
+                matched.insert(entry);
+            } else if !accepted {
+                deviations.violations.push(entry);
+            }
+        }
+
+        deviations.stale = self.crates.difference(&matched).cloned().collect();
+        deviations
+    }
+}
@@ -0,0 +1,297 @@
+//! Lists the third-party crates that were added or updated since a previous
+//! revision of `Cargo.lock`, along with their licenses, as a markdown section
+//! for inclusion in release notes
+
+use super::Input;
+use anyhow::Context as _;
+use krates::Utf8Path as Path;
+use std::collections::{BTreeMap, BTreeSet};
+
+/// The title of the rendered section
+pub const TITLE: &str = "New third-party components and licenses in this release";
+
+#[derive(serde::Deserialize)]
+struct Lockfile {
+    #[serde(default)]
+    package: Vec<LockedPackage>,
+}
+
+#[derive(serde::Deserialize)]
+struct LockedPackage {
+    name: String,
+    version: semver::Version,
+    /// Only present for crates that aren't local to the workspace
+    source: Option<String>,
+}
+
+/// The versions of each third-party crate in a `Cargo.lock`, ie. the crates
+/// that come from a registry or git repository rather than the workspace
+pub fn third_party(contents: &str) -> anyhow::Result<BTreeMap<String, BTreeSet<semver::Version>>> {
+    let lockfile: Lockfile = toml::from_str(contents).context("failed to parse Cargo.lock")?;
+
+    let mut crates = BTreeMap::<_, BTreeSet<_>>::new();
+    for pkg in lockfile.package {
+        if pkg.source.is_some() {
+            crates.entry(pkg.name).or_default().insert(pkg.version);
+        }
+    }
+
+    Ok(crates)
+}
+
+/// A crate that was added or updated
+#[derive(Debug, PartialEq, Eq)]
+pub struct Change {
+    pub name: String,
+    pub version: semver::Version,
+    /// The version the crate was updated from, or `None` if it was added
+    pub previous: Option<semver::Version>,
+}
+
+/// Compares the third-party crates in two versions of a `Cargo.lock`. A crate
+/// version that isn't in the old lockfile is an update if a different version
+/// of the same crate was removed, otherwise it was added, eg. as a second
+/// major version alongside an existing one.
+pub fn diff(old: &str, new: &str) -> anyhow::Result<Vec<Change>> {
+    let old = third_party(old).context("unable to read the previous lockfile")?;
+    let new = third_party(new)?;
+
+    let mut changes = Vec::new();
+    for (name, versions) in &new {
+        let previous = old.get(name);
+
+        // The removed versions, from highest to lowest, so that the highest
+        // removed version is matched with the highest added version
+        let mut removed: Vec<_> = previous
+            .into_iter()
+            .flatten()
+            .filter(|vers| !versions.contains(*vers))
+            .rev()
+            .collect();
+
+        for version in versions.iter().rev() {
+            if previous.is_some_and(|prev| prev.contains(version)) {
+                continue;
+            }
+
+            changes.push(Change {
+                name: name.clone(),
+                version: version.clone(),
+                previous: (!removed.is_empty()).then(|| removed.remove(0).clone()),
+            });
+        }
+    }
+
+    changes.sort_by(|a, b| (&a.name, &a.version).cmp(&(&b.name, &b.version)));
+    Ok(changes)
+}
+
+/// Locates the `Cargo.lock` for the manifest, ie. the first one found in the
+/// manifest's directory or one of its parents
+pub fn find_lockfile(manifest_path: &Path) -> anyhow::Result<krates::Utf8PathBuf> {
+    manifest_path
+        .ancestors()
+        .skip(1)
+        .map(|dir| dir.join("Cargo.lock"))
+        .find(|path| path.exists())
+        .with_context(|| format!("unable to find the Cargo.lock for '{manifest_path}'"))
+}
+
+/// Reads the contents of the lockfile at the specified git revision
+pub fn lockfile_at(lockfile: &Path, rev: &str) -> anyhow::Result<String> {
+    let dir = lockfile
+        .parent()
+        .context("lockfile has no parent directory")?;
+
+    let output = std::process::Command::new("git")
+        .arg("show")
+        .arg(format!("{rev}:./Cargo.lock"))
+        .current_dir(dir)
+        .output()
+        .context("failed to run git")?;
+
+    anyhow::ensure!(
+        output.status.success(),
+        "failed to read '{lockfile}' at revision '{rev}': {}",
+        String::from_utf8_lossy(&output.stderr).trim()
+    );
+
+    String::from_utf8(output.stdout)
+        .with_context(|| format!("'{lockfile}' at revision '{rev}' is not utf-8"))
+}
+
+/// Writes a table of the changed crates and their licenses
+fn write_table(out: &mut String, title: &str, rows: &[(&Change, &str)]) {
+    use std::fmt::Write as _;
+
+    if rows.is_empty() {
+        return;
+    }
+
+    let _ = write!(
+        out,
+        "\n### {title}\n\n| Crate | Version | License |\n| --- | --- | --- |\n"
+    );
+    for (change, license) in rows {
+        let version = match &change.previous {
+            Some(prev) => format!("{prev} → {}", change.version),
+            None => change.version.to_string(),
+        };
+        let _ = writeln!(out, "| {} | {version} | {license} |", change.name);
+    }
+}
+
+/// Renders the section when no crates in the output were added or updated
+pub fn unchanged() -> String {
+    format!("## {TITLE}\n\nNo third-party components were added or updated.\n")
+}
+
+/// Renders the changed crates in the output, and the licenses they use, as a
+/// markdown section. Changed crates that aren't in the output, eg. because
+/// they are ignored, are not listed.
+pub fn render(input: &Input<'_>, changes: &[Change]) -> String {
+    use std::fmt::Write as _;
+
+    let entries = super::baseline::entries(input);
+    let (added, updated): (Vec<_>, Vec<_>) = changes
+        .iter()
+        .filter_map(|change| {
+            let version = change.version.to_string();
+            let (entry, _) = entries
+                .iter()
+                .find(|(entry, _)| entry.name == change.name && entry.version == version)?;
+            Some((change, entry.license.as_str()))
+        })
+        .partition(|(change, _)| change.previous.is_none());
+
+    if added.is_empty() && updated.is_empty() {
+        return unchanged();
+    }
+
+    let mut out = format!("## {TITLE}\n");
+
+    write_table(&mut out, "New components", &added);
+    write_table(&mut out, "Updated components", &updated);
+
+    let is_changed = |name: &str, version: &semver::Version| {
+        changes
+            .iter()
+            .any(|change| change.name == name && change.version == *version)
+    };
+
+    let mut licenses = BTreeMap::<_, (&str, BTreeSet<&str>)>::new();
+    for lic in &input.licenses {
+        for ub in &lic.used_by {
+            if is_changed(&ub.krate.name, &ub.krate.version) {
+                licenses
+                    .entry(lic.id.as_str())
+                    .or_insert_with(|| (lic.name.as_str(), BTreeSet::new()))
+                    .1
+                    .insert(ub.krate.name.as_str());
+            }
+        }
+    }
+
+    if !licenses.is_empty() {
+        out.push_str("\n### Licenses\n\n");
+        for (id, (name, crates)) in licenses {
+            let crates: Vec<_> = crates.into_iter().collect();
+            let _ = writeln!(out, "- {name} (`{id}`): {}", crates.join(", "));
+        }
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const OLD: &str = r#"
+version = 3
+
+[[package]]
+name = "my-crate"
+version = "0.1.0"
+dependencies = ["serde"]
+
+[[package]]
+name = "serde"
+version = "1.0.100"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+
+[[package]]
+name = "bitflags"
+version = "1.3.2"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+
+[[package]]
+name = "removed"
+version = "0.1.0"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+"#;
+
+    const NEW: &str = r#"
+version = 3
+
+[[package]]
+name = "my-crate"
+version = "0.2.0"
+dependencies = ["serde"]
+
+[[package]]
+name = "serde"
+version = "1.0.200"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+
+[[package]]
+name = "bitflags"
+version = "1.3.2"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+
+[[package]]
+name = "bitflags"
+version = "2.4.0"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+
+[[package]]
+name = "smallvec"
+version = "1.11.0"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+"#;
+
+    fn vers(v: &str) -> semver::Version {
+        v.parse().unwrap()
+    }
+
+    #[test]
+    fn diffs_third_party_crates() {
+        let changes = diff(OLD, NEW).unwrap();
+
+        assert_eq!(
+            changes,
+            [
+                Change {
+                    name: "bitflags".to_owned(),
+                    version: vers("2.4.0"),
+                    previous: None,
+                },
+                Change {
+                    name: "serde".to_owned(),
+                    version: vers("1.0.200"),
+                    previous: Some(vers("1.0.100")),
+                },
+                Change {
+                    name: "smallvec".to_owned(),
+                    version: vers("1.11.0"),
+                    previous: None,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn unchanged_lockfile_has_no_changes() {
+        assert!(diff(NEW, NEW).unwrap().is_empty());
+    }
+}
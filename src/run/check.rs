@@ -0,0 +1,134 @@
+//! Checks the licenses of a crate graph against the configuration without
+//! rendering any output or writing diagnostics, eg. for other cargo plugins
+//! such as `cargo-release` or `cargo-dist` that want to gate releases on
+//! license compliance without running `cargo-about` itself
+//!
+//! ```no_run
+//! use cargo_about::run;
+//!
+//! let manifest_path = krates::Utf8PathBuf::from("Cargo.toml");
+//! let cfg = run::load_config(&manifest_path, None)?;
+//!
+//! let result = run::check::check_licenses(&manifest_path, cfg)?;
+//! for violation in &result.violations {
+//!     eprintln!("{} {} is not compliant", violation.name, violation.version);
+//! }
+//!
+//! anyhow::ensure!(result.passed(), "license check failed");
+//! # Ok::<_, anyhow::Error>(())
+//! ```
+
+use super::{Pipeline, Prepared};
+use crate::licenses::{self, config::Config};
+use krates::Utf8Path as Path;
+use serde::Serialize;
+
+/// A crate whose license requirements are not satisfied by the configuration
+#[derive(Clone, Debug, Serialize)]
+pub struct Violation {
+    pub name: String,
+    pub version: semver::Version,
+    /// The crate's license expression, if one could be determined
+    pub expression: Option<String>,
+    /// The license requirements that are not accepted
+    pub unaccepted: Vec<String>,
+    /// The license requirements that are explicitly denied
+    pub denied: Vec<String>,
+    /// The messages of the errors emitted while resolving the crate's
+    /// licenses
+    pub errors: Vec<String>,
+}
+
+/// The result of checking the licenses of every crate in the graph
+#[derive(Clone, Debug, Serialize)]
+pub struct CheckResult {
+    /// The number of crates whose licenses were checked
+    pub checked: usize,
+    /// The crates whose license requirements are not satisfied
+    pub violations: Vec<Violation>,
+    /// The licensees that, if accepted, would satisfy the license
+    /// requirements of every crate that failed
+    pub suggested: Vec<String>,
+}
+
+impl CheckResult {
+    /// Whether every crate's license requirements are satisfied
+    #[inline]
+    pub fn passed(&self) -> bool {
+        self.violations.is_empty()
+    }
+}
+
+/// Checks the licenses of the crate graph of the manifest against the
+/// configuration, see [`Pipeline::check`]
+pub fn check_licenses(manifest_path: &Path, cfg: Config) -> anyhow::Result<CheckResult> {
+    let pipeline = Pipeline::new(manifest_path.to_owned(), cfg);
+    let prepared = pipeline.prepare()?;
+    pipeline.check(&prepared)
+}
+
+impl Pipeline {
+    /// Gathers and resolves the licenses for every crate in the graph,
+    /// returning the crates whose license requirements are not satisfied
+    /// rather than emitting diagnostics or failing.
+    ///
+    /// Crates whose licenses are not accepted are violations even if
+    /// `include-unaccepted` is set.
+    pub fn check(&self, prepared: &Prepared) -> anyhow::Result<CheckResult> {
+        let cfg = self.config();
+        let summary = self.gather(prepared)?;
+
+        let (_files, resolved) = licenses::resolution::resolve(
+            &summary,
+            &cfg.accepted,
+            &cfg.denied,
+            &cfg.crates,
+            &cfg.exceptions,
+            self.fail_on_missing,
+            cfg.include_unaccepted,
+        );
+
+        let reqs = |reqs: &[spdx::LicenseReq]| reqs.iter().map(|req| req.to_string()).collect();
+
+        let violations = summary
+            .iter()
+            .zip(&resolved)
+            .filter_map(|(kl, resolved)| {
+                let resolved = resolved.as_ref()?;
+
+                let errors: Vec<_> = resolved
+                    .diagnostics
+                    .iter()
+                    .filter(|diag| diag.severity >= licenses::resolution::Severity::Error)
+                    .map(|diag| diag.message.clone())
+                    .collect();
+
+                if errors.is_empty() && resolved.unsatisfied.is_none() {
+                    return None;
+                }
+
+                let unsatisfied = resolved.unsatisfied.as_ref();
+                Some(Violation {
+                    name: kl.krate.name.clone(),
+                    version: kl.krate.version.clone(),
+                    expression: match &kl.lic_info {
+                        licenses::LicenseInfo::Expr(expr) => Some(expr.to_string()),
+                        _ => unsatisfied.map(|us| us.expression.to_string()),
+                    },
+                    unaccepted: unsatisfied.map_or_else(Vec::new, |us| reqs(&us.failed)),
+                    denied: unsatisfied.map_or_else(Vec::new, |us| reqs(&us.denied)),
+                    errors,
+                })
+            })
+            .collect();
+
+        Ok(CheckResult {
+            checked: summary.len(),
+            violations,
+            suggested: licenses::resolution::suggest_accepted(&resolved)
+                .iter()
+                .map(|licensee| licensee.to_string())
+                .collect(),
+        })
+    }
+}
@@ -0,0 +1,93 @@
+//! Ordering of the crates and licenses in the output
+//!
+//! Byte ordering sorts eg. `Zoë` before `ábc`, so when the `collation` feature
+//! is enabled strings are instead ordered with the Unicode collation
+//! algorithm, so that human facing documents are sorted the way readers expect.
+
+use std::cmp::Ordering;
+
+/// Compares the strings and crates in the output
+pub(crate) struct Collator {
+    #[cfg(feature = "collation")]
+    inner: icu_collator::Collator,
+}
+
+impl Collator {
+    /// Creates a collator using the root collation, which orders the
+    /// characters of most scripts sensibly without needing a locale
+    pub(crate) fn new() -> Self {
+        Self {
+            #[cfg(feature = "collation")]
+            inner: icu_collator::Collator::try_new(
+                &Default::default(),
+                icu_collator::CollatorOptions::new(),
+            )
+            .expect("the root collation is compiled in"),
+        }
+    }
+
+    /// Compares two strings, by their collation if the `collation` feature is
+    /// enabled, otherwise by their bytes
+    #[inline]
+    #[cfg_attr(not(feature = "collation"), allow(clippy::unused_self))]
+    pub(crate) fn compare(&self, a: &str, b: &str) -> Ordering {
+        #[cfg(feature = "collation")]
+        {
+            self.inner.compare(a, b).then_with(|| a.cmp(b))
+        }
+        #[cfg(not(feature = "collation"))]
+        {
+            a.cmp(b)
+        }
+    }
+
+    /// Compares two packages by their name and then version
+    #[inline]
+    pub(crate) fn compare_packages(
+        &self,
+        a: &krates::cm::Package,
+        b: &krates::cm::Package,
+    ) -> Ordering {
+        self.compare(&a.name, &b.name)
+            .then_with(|| a.version.cmp(&b.version))
+    }
+
+    /// Compares the crates that use a license, which are ordered by their
+    /// package id unless the `collation` feature is enabled, as the id of a
+    /// crate begins with its source in newer versions of cargo
+    #[inline]
+    #[cfg_attr(not(feature = "collation"), allow(clippy::unused_self))]
+    pub(crate) fn compare_users(
+        &self,
+        a: &krates::cm::Package,
+        b: &krates::cm::Package,
+    ) -> Ordering {
+        #[cfg(feature = "collation")]
+        {
+            self.compare_packages(a, b).then_with(|| a.id.cmp(&b.id))
+        }
+        #[cfg(not(feature = "collation"))]
+        {
+            a.id.cmp(&b.id)
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Collator;
+    use std::cmp::Ordering;
+
+    #[test]
+    fn orders_strings() {
+        let collator = Collator::new();
+
+        assert_eq!(collator.compare("MIT", "Apache-2.0"), Ordering::Greater);
+        assert_eq!(collator.compare("MIT", "MIT"), Ordering::Equal);
+
+        #[cfg(feature = "collation")]
+        assert_eq!(collator.compare("ábc", "Zoë"), Ordering::Less);
+        #[cfg(not(feature = "collation"))]
+        assert_eq!(collator.compare("ábc", "Zoë"), Ordering::Greater);
+    }
+}
@@ -0,0 +1,57 @@
+//! Explains why a crate is in the graph at all, by finding the shortest chain
+//! of dependencies from a workspace member to it, eg. when a crate fails
+//! license resolution that is several levels removed from the workspace
+
+use crate::{Krate, Krates};
+use krates::cm::PackageId;
+use std::collections::{BTreeMap, VecDeque};
+
+/// The prefix of the note with the dependency path of a crate, which is added
+/// to the diagnostics of crates that fail license resolution
+pub const NOTE_PREFIX: &str = "dependency path: ";
+
+/// Finds the shortest path from a workspace member to every crate in the
+/// graph, ie. the crates that depend on each other, starting with the
+/// workspace member, to bring the crate into the graph. The path of a
+/// workspace member is empty.
+pub fn paths(krates: &Krates) -> BTreeMap<&PackageId, Vec<&Krate>> {
+    let mut paths = BTreeMap::new();
+    let mut queue = VecDeque::new();
+
+    for member in krates.workspace_members() {
+        if let krates::Node::Krate { id, krate, .. } = member {
+            if let Some(nid) = krates.nid_for_kid(id) {
+                paths.insert(&krate.id, Vec::new());
+                queue.push_back(nid);
+            }
+        }
+    }
+
+    // A breadth first search from every workspace member at once, so the
+    // first path found to each crate is the shortest one
+    while let Some(nid) = queue.pop_front() {
+        let krate = &krates[nid];
+        let mut path = paths.get(&krate.id).cloned().unwrap_or_default();
+        path.push(krate);
+
+        for dep in krates.direct_dependencies(nid) {
+            if !paths.contains_key(&dep.krate.id) {
+                paths.insert(&dep.krate.id, path.clone());
+                queue.push_back(dep.node_id);
+            }
+        }
+    }
+
+    paths
+}
+
+/// The note with the dependency path of a crate, eg.
+/// `dependency path: my-crate 0.1.0 -> reqwest 0.12.0 -> hyper 1.0.0`
+pub fn note(path: &[&Krate], krate: &Krate) -> String {
+    let mut note = NOTE_PREFIX.to_owned();
+    for dependent in path {
+        note.push_str(&format!("{dependent} -> "));
+    }
+    note.push_str(&krate.to_string());
+    note
+}
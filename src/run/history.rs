@@ -0,0 +1,154 @@
+//! Records a summary of each run in a sqlite database, so that changes in the
+//! license footprint of a project can be tracked over time
+//!
+//! Unlike [`super::sqlite`], only the number of crates using each license is
+//! stored, rather than the full output, so that recording every build of a
+//! project stays cheap.
+
+use super::Input;
+use anyhow::Context as _;
+use krates::Utf8Path as Path;
+use std::collections::BTreeMap;
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS history (
+    id INTEGER PRIMARY KEY,
+    timestamp TEXT NOT NULL DEFAULT (datetime('now')),
+    manifest_path TEXT NOT NULL,
+    git_rev TEXT,
+    crates INTEGER NOT NULL,
+    failures INTEGER NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS history_licenses (
+    record_id INTEGER NOT NULL REFERENCES history(id),
+    license TEXT NOT NULL,
+    count INTEGER NOT NULL,
+    PRIMARY KEY (record_id, license)
+);
+";
+
+/// The summary of a single run
+pub struct Record {
+    /// The UTC time the run was recorded, in the `YYYY-MM-DD HH:MM:SS` format
+    pub timestamp: String,
+    pub manifest_path: String,
+    /// The git revision of the repository containing the manifest, if any
+    pub git_rev: Option<String>,
+    /// The number of crates in the output
+    pub crates: usize,
+    /// The number of crates whose license information could not be gathered
+    pub failures: usize,
+    /// The number of crates using each license
+    pub licenses: BTreeMap<String, usize>,
+}
+
+/// Retrieves the revision checked out in the git repository containing the
+/// manifest, or `None` if it is not in a git repository
+pub fn git_rev(manifest_path: &Path) -> Option<String> {
+    let output = std::process::Command::new("git")
+        .args(["rev-parse", "HEAD"])
+        .current_dir(manifest_path.parent()?)
+        .stderr(std::process::Stdio::null())
+        .output()
+        .ok()?;
+
+    if !output.status.success() {
+        return None;
+    }
+
+    let rev = String::from_utf8(output.stdout).ok()?;
+    let rev = rev.trim();
+    (!rev.is_empty()).then(|| rev.to_owned())
+}
+
+/// Appends a summary of the output to the database at the specified path,
+/// creating it if it doesn't exist
+pub fn record(
+    input: &Input<'_>,
+    manifest_path: &Path,
+    git_rev: Option<&str>,
+    db_path: &Path,
+) -> anyhow::Result<()> {
+    let mut conn = rusqlite::Connection::open(db_path)
+        .with_context(|| format!("failed to open '{db_path}'"))?;
+
+    let tx = conn.transaction()?;
+    tx.execute_batch(SCHEMA)
+        .context("failed to create database schema")?;
+
+    let failures = input
+        .crates
+        .iter()
+        .filter(|pl| pl.detection == crate::licenses::Detection::Failed)
+        .count();
+
+    tx.execute(
+        "INSERT INTO history (manifest_path, git_rev, crates, failures) VALUES (?1, ?2, ?3, ?4)",
+        rusqlite::params![
+            manifest_path.as_str(),
+            git_rev,
+            input.crates.len(),
+            failures
+        ],
+    )?;
+    let record_id = tx.last_insert_rowid();
+
+    {
+        let mut insert_license = tx.prepare(
+            "INSERT INTO history_licenses (record_id, license, count) VALUES (?1, ?2, ?3)",
+        )?;
+
+        for ls in &input.overview {
+            insert_license.execute(rusqlite::params![record_id, ls.id, ls.count])?;
+        }
+    }
+
+    tx.commit().context("failed to commit record")?;
+    Ok(())
+}
+
+/// Loads every record in the database, oldest first
+pub fn load(db_path: &Path) -> anyhow::Result<Vec<Record>> {
+    anyhow::ensure!(db_path.exists(), "database '{db_path}' does not exist");
+
+    let conn =
+        rusqlite::Connection::open_with_flags(db_path, rusqlite::OpenFlags::SQLITE_OPEN_READ_ONLY)
+            .with_context(|| format!("failed to open '{db_path}'"))?;
+
+    let mut records = BTreeMap::new();
+
+    {
+        let mut query = conn
+            .prepare(
+                "SELECT id, timestamp, manifest_path, git_rev, crates, failures FROM history ORDER BY id",
+            )
+            .context("database does not contain any history")?;
+        let mut rows = query.query([])?;
+        while let Some(row) = rows.next()? {
+            let id: i64 = row.get(0)?;
+            records.insert(
+                id,
+                Record {
+                    timestamp: row.get(1)?,
+                    manifest_path: row.get(2)?,
+                    git_rev: row.get(3)?,
+                    crates: row.get(4)?,
+                    failures: row.get(5)?,
+                    licenses: BTreeMap::new(),
+                },
+            );
+        }
+    }
+
+    let mut query = conn.prepare("SELECT record_id, license, count FROM history_licenses")?;
+    let mut rows = query.query([])?;
+    while let Some(row) = rows.next()? {
+        let id: i64 = row.get(0)?;
+        if let Some(record) = records.get_mut(&id) {
+            record.licenses.insert(row.get(1)?, row.get(2)?);
+        }
+    }
+
+    Ok(records.into_values().collect())
+}
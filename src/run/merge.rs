@@ -0,0 +1,301 @@
+//! Merges the JSON output of multiple runs, eg. of separate workspaces that
+//! are shipped as a single product, so that they can be rendered as a single
+//! attribution document
+//!
+//! The output of a run borrows the packages from the crate graph, so merged
+//! outputs are kept as JSON rather than being deserialized into an [`Input`](super::Input).
+
+use super::{checklist, obligations, LicenseSet, SCHEMA_VERSION};
+use anyhow::Context as _;
+use serde_json::{Map, Value};
+
+/// Combines the JSON outputs of multiple runs, deduplicating the crates and
+/// license texts that appear in more than one of them
+#[derive(Default)]
+pub struct Merger {
+    tool: Option<Value>,
+    strings: Option<Value>,
+    policy: Option<Value>,
+    metadata: Option<Value>,
+    degraded: bool,
+    crates: Vec<Value>,
+    ignored: Vec<Value>,
+    exceptions: Vec<Value>,
+    licenses: Vec<Map<String, Value>>,
+}
+
+/// The id of the package of a crate, or the crate that uses a license
+fn package_id<'v>(value: &'v Value, key: &str) -> Option<&'v str> {
+    value.get(key)?.get("id")?.as_str()
+}
+
+/// Appends the values of the array to `into`, skipping those whose package
+/// is already present
+fn extend_unique(into: &mut Vec<Value>, from: Option<Value>, key: &str) {
+    let Some(Value::Array(from)) = from else {
+        return;
+    };
+
+    for value in from {
+        let id = package_id(&value, key);
+        if id.is_none() || !into.iter().any(|existing| package_id(existing, key) == id) {
+            into.push(value);
+        }
+    }
+}
+
+impl Merger {
+    /// Adds the output of a run, which must have been generated with the
+    /// same [`SCHEMA_VERSION`]
+    pub fn add(&mut self, output: Value) -> anyhow::Result<()> {
+        let Value::Object(mut output) = output else {
+            anyhow::bail!("output is not a JSON object");
+        };
+
+        let version = output
+            .get("schema_version")
+            .and_then(Value::as_u64)
+            .context("output does not have a schema_version")?;
+        anyhow::ensure!(
+            version == u64::from(SCHEMA_VERSION),
+            "output has schema version {version}, but only version {SCHEMA_VERSION} can be merged"
+        );
+
+        if self.tool.is_none() {
+            self.tool = output.remove("tool");
+        }
+
+        if self.strings.is_none() {
+            self.strings = output.remove("strings");
+        }
+
+        if self.policy.is_none() {
+            self.policy = output.remove("policy");
+        }
+
+        if self.metadata.is_none() {
+            self.metadata = output.remove("metadata");
+        }
+
+        self.degraded |= output
+            .get("degraded")
+            .and_then(Value::as_bool)
+            .unwrap_or_default();
+
+        extend_unique(&mut self.crates, output.remove("crates"), "package");
+        extend_unique(&mut self.ignored, output.remove("ignored"), "package");
+        extend_unique(&mut self.exceptions, output.remove("exceptions"), "package");
+
+        let Some(Value::Array(licenses)) = output.remove("licenses") else {
+            anyhow::bail!("output does not have a licenses array");
+        };
+
+        for license in licenses {
+            let Value::Object(license) = license else {
+                anyhow::bail!("license is not a JSON object");
+            };
+
+            let existing = self.licenses.iter_mut().find(|existing| {
+                existing.get("id") == license.get("id")
+                    && existing.get("text") == license.get("text")
+            });
+
+            let Some(existing) = existing else {
+                self.licenses.push(license);
+                continue;
+            };
+
+            if let Some(Value::Array(used_by)) = license.get("used_by") {
+                let Some(Value::Array(existing_used_by)) = existing.get_mut("used_by") else {
+                    anyhow::bail!("license does not have a used_by array");
+                };
+
+                for ub in used_by {
+                    let id = package_id(ub, "crate");
+                    if !existing_used_by
+                        .iter()
+                        .any(|existing| package_id(existing, "crate") == id)
+                    {
+                        existing_used_by.push(ub.clone());
+                    }
+                }
+            }
+
+            if let Some(Value::Array(notices)) = license.get("notices") {
+                let Some(Value::Array(existing_notices)) = existing.get_mut("notices") else {
+                    anyhow::bail!("license does not have a notices array");
+                };
+
+                for notice in notices {
+                    if !existing_notices
+                        .iter()
+                        .any(|existing| existing["text"] == notice["text"])
+                    {
+                        existing_notices.push(notice.clone());
+                    }
+                }
+            }
+
+            let accepted = [&*existing, &license]
+                .iter()
+                .all(|lic| lic.get("accepted").and_then(Value::as_bool) != Some(false));
+            existing.insert("accepted".to_owned(), Value::Bool(accepted));
+        }
+
+        Ok(())
+    }
+
+    /// Finishes merging, recalculating the overview, obligations, and
+    /// checklist for the merged licenses
+    pub fn finish(self) -> Value {
+        let Self {
+            tool,
+            strings,
+            policy,
+            metadata,
+            degraded,
+            mut crates,
+            mut ignored,
+            mut exceptions,
+            mut licenses,
+        } = self;
+
+        crates.sort_by(|a, b| package_id(a, "package").cmp(&package_id(b, "package")));
+        ignored.sort_by(|a, b| package_id(a, "package").cmp(&package_id(b, "package")));
+        exceptions.sort_by(|a, b| package_id(a, "package").cmp(&package_id(b, "package")));
+
+        for license in &mut licenses {
+            if let Some(Value::Array(used_by)) = license.get_mut("used_by") {
+                used_by.sort_by(|a, b| package_id(a, "crate").cmp(&package_id(b, "crate")));
+            }
+        }
+
+        let str_field = |license: &Map<String, Value>, key: &str| {
+            license
+                .get(key)
+                .and_then(Value::as_str)
+                .unwrap_or_default()
+                .to_owned()
+        };
+
+        licenses.sort_by_key(|license| str_field(license, "id"));
+
+        let mut overview: Vec<LicenseSet> = Vec::new();
+        for (ndx, license) in licenses.iter_mut().enumerate() {
+            let id = str_field(license, "id");
+            let count = license
+                .get("used_by")
+                .and_then(Value::as_array)
+                .map_or(0, Vec::len);
+
+            let first_of_kind = if let Some(ls) = overview.iter_mut().find(|ls| ls.id == id) {
+                ls.indices.push(ndx);
+                ls.count += count;
+                false
+            } else {
+                overview.push(LicenseSet {
+                    count,
+                    name: str_field(license, "name"),
+                    id,
+                    indices: vec![ndx],
+                    text: str_field(license, "text"),
+                });
+                true
+            };
+
+            license.insert("first_of_kind".to_owned(), Value::Bool(first_of_kind));
+        }
+
+        // Show the most used licenses first
+        overview.sort_by_key(|ls| std::cmp::Reverse(ls.count));
+
+        let obligations = obligations(&overview);
+        let checklist = checklist(&overview);
+
+        serde_json::json!({
+            "schema_version": SCHEMA_VERSION,
+            "tool": tool,
+            "degraded": degraded,
+            "overview": overview,
+            "licenses": licenses,
+            "crates": crates,
+            "ignored": ignored,
+            "exceptions": exceptions,
+            "obligations": obligations,
+            "checklist": checklist,
+            "strings": strings.unwrap_or_else(|| Value::Object(Map::new())),
+            "policy": policy.unwrap_or_else(|| serde_json::json!({
+                "accepted": [],
+                "workarounds": [],
+                "config_path": null,
+            })),
+            "metadata": metadata.unwrap_or_else(|| serde_json::json!({
+                "generated_at": "",
+                "version": "",
+                "manifest_path": "",
+                "lockfile_digest": null,
+                "config_digest": null,
+            })),
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use serde_json::json;
+
+    fn output(krate: &str, text: &str) -> Value {
+        let package = json!({ "id": krate, "manifest_path": "Cargo.toml" });
+        json!({
+            "schema_version": SCHEMA_VERSION,
+            "tool": {},
+            "degraded": false,
+            "overview": [],
+            "licenses": [{
+                "name": "MIT License",
+                "id": "MIT",
+                "first_of_kind": true,
+                "text": text,
+                "source_path": null,
+                "used_by": [{ "crate": package }],
+                "notices": [],
+                "accepted": true,
+            }],
+            "crates": [{ "package": package }],
+            "ignored": [],
+            "obligations": [],
+            "strings": {},
+        })
+    }
+
+    #[test]
+    fn dedups_crates_and_license_texts() {
+        let mut merger = Merger::default();
+        merger.add(output("a", "MIT text")).unwrap();
+        merger.add(output("b", "MIT text")).unwrap();
+        merger.add(output("b", "MIT text")).unwrap();
+        merger.add(output("c", "other MIT text")).unwrap();
+
+        let merged = merger.finish();
+
+        assert_eq!(merged["crates"].as_array().unwrap().len(), 3);
+
+        let licenses = merged["licenses"].as_array().unwrap();
+        assert_eq!(licenses.len(), 2);
+        assert_eq!(licenses[0]["used_by"].as_array().unwrap().len(), 2);
+        assert_eq!(licenses[0]["first_of_kind"], true);
+        assert_eq!(licenses[1]["first_of_kind"], false);
+
+        assert_eq!(merged["overview"][0]["count"], 3);
+        assert_eq!(merged["overview"][0]["indices"], json!([0, 1]));
+    }
+
+    #[test]
+    fn rejects_other_schema_versions() {
+        let mut output = output("a", "MIT text");
+        output["schema_version"] = json!(SCHEMA_VERSION + 1);
+
+        assert!(Merger::default().add(output).is_err());
+    }
+}
@@ -0,0 +1,347 @@
+//! Formats the output of a run, eg. as JSON, or by rendering templates
+//!
+//! The built-in formats are registered in [`Formatters`], and tools using
+//! cargo-about as a library can register their own formats alongside them,
+//! rather than needing to convert the JSON output.
+//!
+//! ```no_run
+//! use cargo_about::run::{self, output::{Formatters, OutputFormatter}, Input, Pipeline};
+//!
+//! struct CrateList;
+//!
+//! impl OutputFormatter for CrateList {
+//!     fn format(&self, input: &Input<'_>) -> anyhow::Result<String> {
+//!         Ok(input
+//!             .crates
+//!             .iter()
+//!             .map(|pl| format!("{} {}: {}", pl.package.name, pl.package.version, pl.license))
+//!             .collect::<Vec<_>>()
+//!             .join("\n"))
+//!     }
+//! }
+//!
+//! let mut formatters = Formatters::default();
+//! formatters.register("crate-list", CrateList);
+//!
+//! let manifest_path = krates::Utf8PathBuf::from("Cargo.toml");
+//! let cfg = run::load_config(&manifest_path, None)?;
+//! let pipeline = Pipeline::new(manifest_path, cfg);
+//!
+//! let prepared = pipeline.prepare()?;
+//! let input = pipeline.generate(&prepared)?;
+//! println!("{}", formatters.get("crate-list")?.format(&input)?);
+//! # Ok::<_, anyhow::Error>(())
+//! ```
+
+use super::{redact, reproducible, sanitize, Input, Templates};
+use std::collections::BTreeMap;
+
+/// Formats the output of a run
+pub trait OutputFormatter: Send + Sync {
+    /// Formats the output
+    fn format(&self, input: &Input<'_>) -> anyhow::Result<String>;
+}
+
+/// Renders the output with the template passed to [`Templates::load`]
+///
+/// Templates are loaded from a path, so they aren't registered in
+/// [`Formatters`] by default.
+impl OutputFormatter for Templates {
+    fn format(&self, input: &Input<'_>) -> anyhow::Result<String> {
+        self.render(input)
+    }
+}
+
+/// Serializes the output as JSON
+#[derive(Default)]
+pub struct Json {
+    /// Outputs one JSON object per line for each crate, see [`Input::by_crate`]
+    lines: bool,
+    sanitize_html: bool,
+}
+
+impl Json {
+    /// Outputs one JSON object per line for each crate, rather than the full
+    /// output as a single object
+    pub fn lines() -> Self {
+        Self {
+            lines: true,
+            ..Self::default()
+        }
+    }
+
+    /// Sanitizes the crate metadata for HTML, see [`sanitize::html`]
+    pub fn sanitize_html(mut self, sanitize_html: bool) -> Self {
+        self.sanitize_html = sanitize_html;
+        self
+    }
+
+    fn to_string(&self, value: impl serde::Serialize, input: &Input<'_>) -> anyhow::Result<String> {
+        let mut value = serde_json::to_value(value)?;
+        redact::packages(&mut value, input.redact);
+        reproducible::remap_paths(&mut value, &input.path_prefixes);
+        if self.sanitize_html {
+            sanitize::html(&mut value);
+        }
+        Ok(serde_json::to_string(&value)?)
+    }
+}
+
+impl OutputFormatter for Json {
+    fn format(&self, input: &Input<'_>) -> anyhow::Result<String> {
+        if self.lines {
+            let lines = input
+                .by_crate()
+                .iter()
+                .map(|krate| self.to_string(krate, input))
+                .collect::<anyhow::Result<Vec<_>>>()?;
+            Ok(lines.join("\n"))
+        } else {
+            self.to_string(input, input)
+        }
+    }
+}
+
+/// Formats a summary of the output as OCI image annotations, a flat JSON
+/// object of string keys and values, so that container images of Rust
+/// services can carry their license metadata alongside the image
+#[derive(Default)]
+pub struct OciAnnotations {
+    /// The name and digest of the full attribution document
+    document: Option<(String, String)>,
+}
+
+impl OciAnnotations {
+    /// The prefix of every annotation key, in the reverse domain notation
+    /// required by the OCI image specification
+    pub const PREFIX: &'static str = "com.embark-studios.cargo-about";
+
+    /// References the full attribution document, eg. the rendered HTML, by
+    /// its name and SHA-256 digest, so that it can be attached to the image
+    /// separately and verified against the annotations
+    pub fn document(mut self, name: impl Into<String>, contents: &[u8]) -> Self {
+        let digest = ring::digest::digest(&ring::digest::SHA256, contents);
+        self.document = Some((
+            name.into(),
+            format!("sha256:{}", crate::to_hex(digest.as_ref())),
+        ));
+        self
+    }
+}
+
+impl OutputFormatter for OciAnnotations {
+    fn format(&self, input: &Input<'_>) -> anyhow::Result<String> {
+        let prefix = Self::PREFIX;
+        let licenses: Vec<_> = input.overview.iter().map(|ls| ls.id.as_str()).collect();
+        let expression = licenses.join(" AND ");
+
+        let mut annotations = BTreeMap::new();
+        // The standard annotation, which tools that don't know about
+        // cargo-about read, is the combination of every license in use, as
+        // they all apply to the binaries in the image
+        annotations.insert(
+            "org.opencontainers.image.licenses".to_owned(),
+            expression.clone(),
+        );
+        annotations.insert(format!("{prefix}.licenses"), expression);
+        annotations.insert(format!("{prefix}.crates"), input.crates.len().to_string());
+        annotations.insert(format!("{prefix}.version"), input.tool.version.to_owned());
+
+        if let Some((name, digest)) = &self.document {
+            annotations.insert(format!("{prefix}.document.name"), name.clone());
+            annotations.insert(format!("{prefix}.document.digest"), digest.clone());
+        }
+
+        Ok(serde_json::to_string_pretty(&annotations)?)
+    }
+}
+
+/// Formats a row for each crate, with its name, version, declared license,
+/// resolved license, the source paths of its license texts, repository, and
+/// whether its license is accepted, so that it can be opened as a spreadsheet
+pub struct Table {
+    delimiter: char,
+}
+
+impl Table {
+    /// Comma separated values, quoted as described in RFC 4180
+    pub fn csv() -> Self {
+        Self { delimiter: ',' }
+    }
+
+    /// Tab separated values
+    pub fn tsv() -> Self {
+        Self { delimiter: '\t' }
+    }
+
+    fn cell(&self, value: &str) -> String {
+        // Spreadsheets evaluate cells that start with these as formulas, which
+        // a malicious crate could use to inject one via its metadata
+        let value = if value.starts_with(['=', '+', '-', '@']) {
+            format!("'{value}")
+        } else {
+            value.to_owned()
+        };
+
+        if self.delimiter == '\t' {
+            return value.replace(['\t', '\r', '\n'], " ");
+        }
+
+        if value.contains([self.delimiter, '"', '\r', '\n']) {
+            format!("\"{}\"", value.replace('"', "\"\""))
+        } else {
+            value
+        }
+    }
+}
+
+impl OutputFormatter for Table {
+    fn format(&self, input: &Input<'_>) -> anyhow::Result<String> {
+        let delimiter = self.delimiter.to_string();
+
+        let mut rows = vec![[
+            "name",
+            "version",
+            "declared_license",
+            "resolved_license",
+            "source_path",
+            "repository",
+            "accepted",
+        ]
+        .join(&delimiter)];
+
+        for (pl, krate) in input.crates.iter().zip(input.by_crate()) {
+            let mut resolved: Vec<_> = krate.licenses.iter().map(|lic| lic.id).collect();
+            resolved.sort_unstable();
+            resolved.dedup();
+
+            let source_paths: Vec<_> = krate
+                .licenses
+                .iter()
+                .filter_map(|lic| lic.source_path.map(|path| path.as_str()))
+                .collect();
+
+            let row: [&str; 7] = [
+                pl.package.name.as_str(),
+                &pl.package.version.to_string(),
+                pl.package.license.as_deref().unwrap_or_default(),
+                &resolved.join(" AND "),
+                &source_paths.join("; "),
+                pl.metadata.repository.as_deref().unwrap_or_default(),
+                if pl.accepted { "yes" } else { "no" },
+            ];
+
+            rows.push(
+                row.iter()
+                    .map(|value| self.cell(value))
+                    .collect::<Vec<_>>()
+                    .join(&delimiter),
+            );
+        }
+
+        Ok(rows.join("\n"))
+    }
+}
+
+/// The output formats available by name
+pub struct Formatters {
+    formatters: BTreeMap<String, Box<dyn OutputFormatter>>,
+}
+
+impl Default for Formatters {
+    /// Creates a registry with the built-in `json`, `json-lines`,
+    /// `oci-annotations`, `csv`, and `tsv` formats
+    fn default() -> Self {
+        let mut formatters = Self {
+            formatters: BTreeMap::new(),
+        };
+        formatters
+            .register("json", Json::default())
+            .register("json-lines", Json::lines())
+            .register("oci-annotations", OciAnnotations::default())
+            .register("csv", Table::csv())
+            .register("tsv", Table::tsv());
+        formatters
+    }
+}
+
+impl Formatters {
+    /// Registers a format, replacing any format already registered with the
+    /// same name
+    pub fn register(
+        &mut self,
+        name: impl Into<String>,
+        formatter: impl OutputFormatter + 'static,
+    ) -> &mut Self {
+        self.formatters.insert(name.into(), Box::new(formatter));
+        self
+    }
+
+    /// Retrieves the format registered with the name
+    pub fn get(&self, name: &str) -> anyhow::Result<&dyn OutputFormatter> {
+        self.formatters
+            .get(name)
+            .map(|formatter| formatter.as_ref())
+            .ok_or_else(|| {
+                anyhow::anyhow!(
+                    "unknown output format '{name}', expected one of: {}",
+                    self.names().collect::<Vec<_>>().join(", ")
+                )
+            })
+    }
+
+    /// The names of every registered format
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.formatters.keys().map(String::as_str)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    struct Empty;
+
+    impl OutputFormatter for Empty {
+        fn format(&self, _input: &Input<'_>) -> anyhow::Result<String> {
+            Ok(String::new())
+        }
+    }
+
+    #[test]
+    fn registers_formats_alongside_builtins() {
+        let mut formatters = Formatters::default();
+        formatters.register("empty", Empty);
+
+        assert_eq!(
+            formatters.names().collect::<Vec<_>>(),
+            [
+                "csv",
+                "empty",
+                "json",
+                "json-lines",
+                "oci-annotations",
+                "tsv"
+            ]
+        );
+
+        let err = formatters.get("xml").err().unwrap();
+        assert_eq!(
+            err.to_string(),
+            "unknown output format 'xml', expected one of: csv, empty, json, json-lines, oci-annotations, tsv"
+        );
+    }
+
+    #[test]
+    fn quotes_table_cells() {
+        let csv = Table::csv();
+        assert_eq!(csv.cell("MIT"), "MIT");
+        assert_eq!(csv.cell("a, b"), "\"a, b\"");
+        assert_eq!(csv.cell("say \"hi\""), "\"say \"\"hi\"\"\"");
+        assert_eq!(csv.cell("=1+1"), "'=1+1");
+
+        let tsv = Table::tsv();
+        assert_eq!(tsv.cell("a, b"), "a, b");
+        assert_eq!(tsv.cell("a\tb\nc"), "a b c");
+    }
+}
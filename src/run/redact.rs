@@ -0,0 +1,90 @@
+//! Removes selected crate metadata from the output, as attribution documents
+//! are often published externally, and crates that aren't published, eg.
+//! internal path dependencies, can contain details that shouldn't be
+//!
+//! The fields owned by the output, such as the `source_path` of licenses, are
+//! redacted when the output is generated, whereas the metadata of packages
+//! is redacted when the output is serialized, see [`packages`].
+
+use crate::licenses::config::Redact;
+
+/// Checks if the crate was retrieved from a registry, as opposed to being a
+/// path or git dependency, from the `source` of its package
+pub(crate) fn is_registry(source: Option<&str>) -> bool {
+    source.is_some_and(|src| src.starts_with("registry+") || src.starts_with("sparse+"))
+}
+
+/// Redacts the metadata of every package in the serialized output
+pub fn packages(output: &mut serde_json::Value, redact: Redact) {
+    if !redact.author_emails && !redact.internal_descriptions {
+        return;
+    }
+
+    match output {
+        serde_json::Value::Object(map) => {
+            if super::sanitize::is_package(map) {
+                redact_package(map, redact);
+            } else {
+                map.values_mut().for_each(|value| packages(value, redact));
+            }
+        }
+        serde_json::Value::Array(values) => {
+            for value in values {
+                packages(value, redact);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn redact_package(map: &mut serde_json::Map<String, serde_json::Value>, redact: Redact) {
+    if redact.author_emails {
+        if let Some(serde_json::Value::Array(authors)) = map.get_mut("authors") {
+            for author in authors {
+                if let serde_json::Value::String(author) = author {
+                    *author = strip_email(author);
+                }
+            }
+        }
+    }
+
+    if redact.internal_descriptions && !is_registry(map.get("source").and_then(|s| s.as_str())) {
+        if let Some(description) = map.get_mut("description") {
+            *description = serde_json::Value::Null;
+        }
+    }
+}
+
+/// Removes the email address from an author, eg. `Jane Doe <jane@doe.com>`
+/// becomes `Jane Doe`, an author that is only an email address is replaced
+/// with an empty string
+fn strip_email(author: &str) -> String {
+    let Some(start) = author.find('<') else {
+        return if author.contains('@') {
+            String::new()
+        } else {
+            author.to_owned()
+        };
+    };
+
+    let end = author[start..]
+        .find('>')
+        .map_or(author.len(), |end| start + end + 1);
+
+    format!("{}{}", &author[..start], &author[end..])
+        .trim()
+        .to_owned()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn strips_emails() {
+        assert_eq!(strip_email("Jane Doe <jane@doe.com>"), "Jane Doe");
+        assert_eq!(strip_email("<jane@doe.com>"), "");
+        assert_eq!(strip_email("jane@doe.com"), "");
+        assert_eq!(strip_email("The Jane Doe Project"), "The Jane Doe Project");
+    }
+}
@@ -0,0 +1,158 @@
+//! Removes the details of the machine and time the output was generated on,
+//! so that attribution files checked into version control only change when
+//! the licenses in use do
+//!
+//! The timestamp honors [`SOURCE_DATE_EPOCH`](https://reproducible-builds.org/specs/source-date-epoch/),
+//! and the local paths in the output, eg. the manifests of crates in
+//! `CARGO_HOME`, are remapped to placeholders when the output is serialized,
+//! see [`remap_paths`].
+
+use krates::Utf8Path as Path;
+
+/// The time the output was generated, in RFC 3339 format.
+///
+/// `SOURCE_DATE_EPOCH` is used instead of the current time if it is set, and
+/// if the output is reproducible and it isn't set, the time is omitted.
+pub fn generated_at(reproducible: bool) -> String {
+    let time = match std::env::var("SOURCE_DATE_EPOCH") {
+        Ok(epoch) => {
+            let time = epoch
+                .trim()
+                .parse()
+                .ok()
+                .and_then(|epoch| time::OffsetDateTime::from_unix_timestamp(epoch).ok());
+
+            if let Some(time) = time {
+                time
+            } else {
+                log::warn!("ignoring SOURCE_DATE_EPOCH '{epoch}', which is not a unix timestamp");
+                if reproducible {
+                    return String::new();
+                }
+                time::OffsetDateTime::now_utc()
+            }
+        }
+        Err(_) if reproducible => return String::new(),
+        Err(_) => time::OffsetDateTime::now_utc(),
+    };
+
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        time.year(),
+        u8::from(time.month()),
+        time.day(),
+        time.hour(),
+        time.minute(),
+        time.second()
+    )
+}
+
+/// The local directories that paths in the output are relative to, and the
+/// placeholders they are replaced with, longest first, so that eg. a
+/// `CARGO_HOME` inside the workspace is matched before the workspace itself
+pub fn path_prefixes(workspace_root: &Path) -> Vec<(String, String)> {
+    let mut prefixes = vec![(workspace_root.as_str().to_owned(), "$WORKSPACE".to_owned())];
+
+    if let Some(cargo_home) = home::cargo_home()
+        .ok()
+        .and_then(|ch| ch.to_str().map(String::from))
+    {
+        prefixes.push((cargo_home, "$CARGO_HOME".to_owned()));
+    }
+
+    prefixes.retain(|(prefix, _)| !prefix.is_empty());
+    prefixes.sort_by_key(|(prefix, _)| std::cmp::Reverse(prefix.len()));
+    prefixes
+}
+
+/// Replaces the prefixes of every local path in the serialized output with
+/// their placeholder, eg. the `manifest_path` of a crate downloaded from
+/// crates.io becomes `$CARGO_HOME/registry/src/...`, and the id of a path
+/// dependency becomes `path+file://$WORKSPACE/...`, or `path+file://$WORKSPACE#...`
+/// for the workspace root
+pub fn remap_paths(output: &mut serde_json::Value, prefixes: &[(String, String)]) {
+    if prefixes.is_empty() {
+        return;
+    }
+
+    match output {
+        serde_json::Value::String(s) => {
+            for (prefix, placeholder) in prefixes {
+                if s == prefix {
+                    placeholder.clone_into(s);
+                    break;
+                }
+
+                // The id of a crate in the workspace root ends the directory
+                // with the fragment rather than a separator, eg.
+                // `path+file:///work/my-crate#0.1.0`
+                if s.starts_with(&format!("path+file://{prefix}#")) {
+                    *s = s.replacen(&format!("{prefix}#"), &format!("{placeholder}#"), 1);
+                    break;
+                }
+
+                // Only whole directories are replaced, so that eg. a sibling
+                // `/work/my-crate-2` isn't mistaken for `/work/my-crate`
+                if [format!("{prefix}/"), format!("{prefix}\\")]
+                    .iter()
+                    .any(|dir| s.contains(dir.as_str()))
+                {
+                    // Separators are normalized, as they differ between platforms
+                    *s = s
+                        .replace(&format!("{prefix}/"), &format!("{placeholder}/"))
+                        .replace(&format!("{prefix}\\"), &format!("{placeholder}/"))
+                        .replace('\\', "/");
+                    break;
+                }
+            }
+        }
+        serde_json::Value::Object(map) => {
+            for value in map.values_mut() {
+                remap_paths(value, prefixes);
+            }
+        }
+        serde_json::Value::Array(values) => {
+            for value in values {
+                remap_paths(value, prefixes);
+            }
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn remaps_path_prefixes() {
+        let prefixes = [
+            ("/work/my-crate/.cargo".to_owned(), "$CARGO_HOME".to_owned()),
+            ("/work/my-crate".to_owned(), "$WORKSPACE".to_owned()),
+        ];
+
+        let mut output = serde_json::json!({
+            "crates": [{
+                "id": "path+file:///work/my-crate#my-crate@0.1.0",
+            }, {
+                "id": "path+file:///work/my-crate/member#0.1.0",
+                "manifest_path": "/work/my-crate/.cargo/registry/src/index/serde-1.0.0/Cargo.toml",
+                "name": "/work/my-crate-2/Cargo.toml",
+            }],
+        });
+        remap_paths(&mut output, &prefixes);
+
+        assert_eq!(
+            output,
+            serde_json::json!({
+                "crates": [{
+                    "id": "path+file://$WORKSPACE#my-crate@0.1.0",
+                }, {
+                    "id": "path+file://$WORKSPACE/member#0.1.0",
+                    "manifest_path": "$CARGO_HOME/registry/src/index/serde-1.0.0/Cargo.toml",
+                    "name": "/work/my-crate-2/Cargo.toml",
+                }],
+            })
+        );
+    }
+}
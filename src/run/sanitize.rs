@@ -0,0 +1,169 @@
+//! Sanitizes the crate metadata in the output before it is rendered, as it is
+//! supplied by the authors of each crate rather than the user, so that a
+//! malicious crate can't inject markup into attribution pages
+//!
+//! Handlebars and jinja already escape HTML, but templates commonly output
+//! fields raw, eg. `{{{text}}}`, and escaping doesn't prevent a `javascript:`
+//! URL from being used as a link.
+
+use std::borrow::Cow;
+
+/// How the crate metadata in the output is sanitized
+#[derive(clap::ValueEnum, Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum Sanitize {
+    /// Sanitizes HTML when rendering a template that outputs HTML, ie. has
+    /// `.html` in its name or contains an `<html>` element
+    #[default]
+    Auto,
+    /// Always sanitizes HTML, including for JSON output
+    Html,
+    /// Outputs crate metadata as is
+    None,
+}
+
+/// The package fields that are rendered as links, and are therefore removed
+/// if they aren't `http(s)` URLs
+const URL_FIELDS: &[&str] = &["repository", "homepage", "documentation"];
+
+/// The fields of [`super::CrateMetadata`], which is flattened into other
+/// objects in the output
+const METADATA_FIELDS: &[&str] = &["description", "repository", "homepage", "documentation"];
+
+/// Checks if the template looks like it outputs HTML
+pub(crate) fn is_html_template(path: &str, source: &str) -> bool {
+    path.contains(".html") || source.to_ascii_lowercase().contains("<html")
+}
+
+/// Sanitizes every package in the serialized output for HTML
+///
+/// HTML tags are removed from each string in the package's metadata, and URLs
+/// that don't use the `http` or `https` scheme are removed entirely. License
+/// and NOTICE texts are left untouched, as placeholders such as `<year>` are
+/// part of many license texts, so they should always be rendered escaped.
+pub fn html(output: &mut serde_json::Value) {
+    match output {
+        serde_json::Value::Object(map) => {
+            if is_package(map) {
+                sanitize_package(map);
+            } else {
+                sanitize_metadata(map);
+                map.values_mut().for_each(html);
+            }
+        }
+        serde_json::Value::Array(values) => values.iter_mut().for_each(html),
+        _ => {}
+    }
+}
+
+/// Packages are the only objects in the output with a manifest path
+pub(crate) fn is_package(map: &serde_json::Map<String, serde_json::Value>) -> bool {
+    map.contains_key("manifest_path") && map.contains_key("id")
+}
+
+fn sanitize_package(map: &mut serde_json::Map<String, serde_json::Value>) {
+    for (key, value) in map.iter_mut() {
+        if URL_FIELDS.contains(&key.as_str()) {
+            if let serde_json::Value::String(url) = value {
+                if !is_http_url(url) {
+                    *value = serde_json::Value::Null;
+                    continue;
+                }
+            }
+        }
+
+        strip_tags_in(value);
+    }
+}
+
+/// Sanitizes the package metadata that is copied into objects other than the
+/// package itself
+fn sanitize_metadata(map: &mut serde_json::Map<String, serde_json::Value>) {
+    for key in METADATA_FIELDS {
+        let Some(value) = map.get_mut(*key) else {
+            continue;
+        };
+
+        if URL_FIELDS.contains(key) {
+            if let serde_json::Value::String(url) = value {
+                if !is_http_url(url) {
+                    *value = serde_json::Value::Null;
+                    continue;
+                }
+            }
+        }
+
+        strip_tags_in(value);
+    }
+}
+
+fn strip_tags_in(value: &mut serde_json::Value) {
+    match value {
+        serde_json::Value::String(s) => {
+            if let Cow::Owned(stripped) = strip_tags(s) {
+                *s = stripped;
+            }
+        }
+        serde_json::Value::Array(values) => values.iter_mut().for_each(strip_tags_in),
+        serde_json::Value::Object(map) => map.values_mut().for_each(strip_tags_in),
+        _ => {}
+    }
+}
+
+fn is_http_url(url: &str) -> bool {
+    let url = url.trim_start().to_ascii_lowercase();
+    url.starts_with("https://") || url.starts_with("http://")
+}
+
+/// Removes anything that looks like an HTML tag, comment, or declaration,
+/// including one that is never closed
+///
+/// A `<` that isn't followed by a letter, `/`, `!` or `?` can't start a tag,
+/// so it is kept, eg. `a < b`.
+fn strip_tags(s: &str) -> Cow<'_, str> {
+    let starts_tag = |c: Option<char>| {
+        c.is_some_and(|c| c.is_ascii_alphabetic() || matches!(c, '/' | '!' | '?'))
+    };
+
+    if !s.contains('<') {
+        return Cow::Borrowed(s);
+    }
+
+    let mut chars = s.chars().peekable();
+    let mut stripped = String::with_capacity(s.len());
+    while let Some(c) = chars.next() {
+        if c == '<' && starts_tag(chars.peek().copied()) {
+            for c in chars.by_ref() {
+                if c == '>' {
+                    break;
+                }
+            }
+        } else {
+            stripped.push(c);
+        }
+    }
+
+    if stripped.len() == s.len() {
+        Cow::Borrowed(s)
+    } else {
+        Cow::Owned(stripped)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn strips_tags_but_not_comparisons() {
+        assert_eq!(
+            strip_tags("an <b>evil</b> <script>alert(1)</script> crate"),
+            "an evil alert(1) crate"
+        );
+        assert_eq!(strip_tags("a < b && b > c"), "a < b && b > c");
+        assert_eq!(
+            strip_tags("unclosed <img src=x onerror=alert(1)"),
+            "unclosed "
+        );
+        assert!(matches!(strip_tags("plain"), Cow::Borrowed("plain")));
+    }
+}
@@ -0,0 +1,170 @@
+//! Exports the output to a sqlite database, so that the results of many runs,
+//! across many projects, can be queried with SQL
+//!
+//! Each export appends a new row to the `runs` table, and every other table
+//! references the run its rows belong to via `run_id`. The version of the
+//! database schema is stored in `PRAGMA user_version`, and is incremented
+//! whenever a table or column is removed, or changes meaning.
+
+use super::Input;
+use anyhow::Context as _;
+use krates::Utf8Path as Path;
+
+/// The version of the database schema
+pub const SCHEMA_VERSION: u32 = 1;
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS runs (
+    id INTEGER PRIMARY KEY,
+    timestamp TEXT NOT NULL DEFAULT (datetime('now')),
+    manifest_path TEXT NOT NULL,
+    output_schema_version INTEGER NOT NULL,
+    tool_version TEXT NOT NULL,
+    spdx_version TEXT NOT NULL,
+    license_store_date TEXT NOT NULL,
+    license_store_digest TEXT NOT NULL,
+    degraded INTEGER NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS crates (
+    run_id INTEGER NOT NULL REFERENCES runs(id),
+    id TEXT NOT NULL,
+    name TEXT NOT NULL,
+    version TEXT NOT NULL,
+    source TEXT,
+    repository TEXT,
+    license TEXT NOT NULL,
+    detection TEXT NOT NULL,
+    PRIMARY KEY (run_id, id)
+);
+
+CREATE TABLE IF NOT EXISTS licenses (
+    run_id INTEGER NOT NULL REFERENCES runs(id),
+    id INTEGER NOT NULL,
+    spdx_id TEXT NOT NULL,
+    name TEXT NOT NULL,
+    text TEXT NOT NULL,
+    source_path TEXT,
+    PRIMARY KEY (run_id, id)
+);
+
+CREATE TABLE IF NOT EXISTS crate_licenses (
+    run_id INTEGER NOT NULL REFERENCES runs(id),
+    crate_id TEXT NOT NULL,
+    license_id INTEGER NOT NULL,
+    PRIMARY KEY (run_id, crate_id, license_id)
+);
+
+CREATE TABLE IF NOT EXISTS notices (
+    run_id INTEGER NOT NULL REFERENCES runs(id),
+    crate_id TEXT NOT NULL,
+    license_id INTEGER NOT NULL,
+    path TEXT NOT NULL,
+    text TEXT NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS notes (
+    run_id INTEGER NOT NULL REFERENCES runs(id),
+    crate_id TEXT NOT NULL,
+    note TEXT NOT NULL
+);
+";
+
+/// Appends the output as a new run to the database at the specified path,
+/// creating it if it doesn't exist
+pub fn export(input: &Input<'_>, manifest_path: &Path, db_path: &Path) -> anyhow::Result<()> {
+    let mut conn = rusqlite::Connection::open(db_path)
+        .with_context(|| format!("failed to open '{db_path}'"))?;
+
+    let version: u32 = conn.pragma_query_value(None, "user_version", |row| row.get(0))?;
+    anyhow::ensure!(
+        version == 0 || version == SCHEMA_VERSION,
+        "'{db_path}' has schema version {version}, but only version {SCHEMA_VERSION} is supported"
+    );
+
+    let tx = conn.transaction()?;
+    tx.execute_batch(SCHEMA)
+        .context("failed to create database schema")?;
+    tx.pragma_update(None, "user_version", SCHEMA_VERSION)?;
+
+    tx.execute(
+        "INSERT INTO runs (manifest_path, output_schema_version, tool_version, spdx_version, license_store_date, license_store_digest, degraded)
+        VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+        rusqlite::params![
+            manifest_path.as_str(),
+            input.schema_version,
+            input.tool.version,
+            input.tool.spdx_version,
+            input.tool.license_store_date,
+            input.tool.license_store_digest,
+            input.degraded,
+        ],
+    )?;
+    let run_id = tx.last_insert_rowid();
+
+    {
+        let mut insert_crate = tx.prepare(
+            "INSERT INTO crates (run_id, id, name, version, source, repository, license, detection)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+        )?;
+        let mut insert_note =
+            tx.prepare("INSERT INTO notes (run_id, crate_id, note) VALUES (?1, ?2, ?3)")?;
+
+        for pl in &input.crates {
+            let pkg = pl.package;
+            insert_crate.execute(rusqlite::params![
+                run_id,
+                pkg.id.repr,
+                pkg.name,
+                pkg.version.to_string(),
+                pkg.source.as_ref().map(|src| src.repr.as_str()),
+                pkg.repository,
+                pl.license,
+                pl.detection.to_string(),
+            ])?;
+
+            for note in &pl.notes {
+                insert_note.execute(rusqlite::params![run_id, pkg.id.repr, note])?;
+            }
+        }
+
+        let mut insert_license = tx.prepare(
+            "INSERT INTO licenses (run_id, id, spdx_id, name, text, source_path)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        )?;
+        let mut insert_usage = tx.prepare(
+            "INSERT OR IGNORE INTO crate_licenses (run_id, crate_id, license_id) VALUES (?1, ?2, ?3)",
+        )?;
+        let mut insert_notice = tx.prepare(
+            "INSERT INTO notices (run_id, crate_id, license_id, path, text) VALUES (?1, ?2, ?3, ?4, ?5)",
+        )?;
+
+        for (id, license) in input.licenses.iter().enumerate() {
+            insert_license.execute(rusqlite::params![
+                run_id,
+                id,
+                license.id,
+                license.name,
+                license.text,
+                license.source_path.as_ref().map(|sp| sp.as_str()),
+            ])?;
+
+            for used_by in &license.used_by {
+                insert_usage.execute(rusqlite::params![run_id, used_by.krate.id.repr, id])?;
+            }
+
+            for notice in &license.notices {
+                insert_notice.execute(rusqlite::params![
+                    run_id,
+                    notice.krate.id.repr,
+                    id,
+                    notice.path.as_str(),
+                    notice.text,
+                ])?;
+            }
+        }
+    }
+
+    tx.commit().context("failed to commit run")?;
+    Ok(())
+}
@@ -0,0 +1,158 @@
+//! The localized strings passed to templates as `strings`, so that the
+//! headings of attribution documents can be output in the language of the
+//! jurisdiction they are shipped in
+
+use crate::licenses::config::Config;
+use std::collections::BTreeMap;
+
+/// The keys of the built-in strings, every built-in locale has a translation
+/// for each of them
+const KEYS: &[&str] = &[
+    "title",
+    "intro",
+    "overview",
+    "all_licenses",
+    "used_by",
+    "notice_for",
+];
+
+/// The built-in translations, in the same order as [`KEYS`]
+const BUILTIN: &[(&str, [&str; 6])] = &[
+    (
+        "en",
+        [
+            "Third Party Licenses",
+            "This page lists the licenses of the projects used in {name}.",
+            "Overview of licenses:",
+            "All license text:",
+            "Used by:",
+            "Notice for",
+        ],
+    ),
+    (
+        "de",
+        [
+            "Lizenzen von Drittanbietern",
+            "Diese Seite listet die Lizenzen der Projekte auf, die in {name} verwendet werden.",
+            "Übersicht der Lizenzen:",
+            "Alle Lizenztexte:",
+            "Verwendet von:",
+            "Hinweis für",
+        ],
+    ),
+    (
+        "es",
+        [
+            "Licencias de terceros",
+            "Esta página enumera las licencias de los proyectos utilizados en {name}.",
+            "Resumen de licencias:",
+            "Texto de todas las licencias:",
+            "Utilizado por:",
+            "Aviso para",
+        ],
+    ),
+    (
+        "fr",
+        [
+            "Licences tierces",
+            "Cette page répertorie les licences des projets utilisés dans {name}.",
+            "Aperçu des licences :",
+            "Texte de toutes les licences :",
+            "Utilisé par :",
+            "Avis pour",
+        ],
+    ),
+];
+
+/// The locales that have built-in translations
+pub fn builtin_locales() -> impl Iterator<Item = &'static str> {
+    BUILTIN.iter().map(|(locale, _)| *locale)
+}
+
+/// Resolves the strings for the configured locale, which defaults to `en`.
+///
+/// Strings that aren't translated for the locale fall back to English, and
+/// the [`Config::translations`] for the locale take precedence over the
+/// built-in ones, eg. to add a locale or custom strings. If there is no
+/// translation for a locale such as `de-AT`, the translation for its language,
+/// `de`, is used instead. `{name}` is replaced with the name of the project.
+pub fn resolve(cfg: &Config, name: &str) -> anyhow::Result<BTreeMap<String, String>> {
+    let locale = cfg.locale.as_deref().unwrap_or("en");
+    let language = locale.split(['-', '_']).next().unwrap_or(locale);
+
+    let builtin = |locale: &str| {
+        BUILTIN
+            .iter()
+            .find_map(|(loc, strings)| loc.eq_ignore_ascii_case(locale).then_some(strings))
+    };
+    let configured = |locale: &str| {
+        cfg.translations
+            .iter()
+            .find_map(|(loc, strings)| loc.eq_ignore_ascii_case(locale).then_some(strings))
+    };
+
+    let mut strings: BTreeMap<_, _> = KEYS
+        .iter()
+        .zip(BUILTIN[0].1)
+        .map(|(key, value)| ((*key).to_owned(), value.to_owned()))
+        .collect();
+
+    let mut found = locale.eq_ignore_ascii_case("en");
+    for locale in [language, locale] {
+        if let Some(translated) = builtin(locale) {
+            found = true;
+            for (key, value) in KEYS.iter().zip(translated) {
+                strings.insert((*key).to_owned(), (*value).to_owned());
+            }
+        }
+
+        if let Some(translated) = configured(locale) {
+            found = true;
+            strings.extend(translated.clone());
+        }
+    }
+
+    anyhow::ensure!(
+        found,
+        "no translation for locale '{locale}', the built-in locales are {}, others can be added to `translations` in the configuration",
+        builtin_locales().collect::<Vec<_>>().join(", ")
+    );
+
+    for value in strings.values_mut() {
+        if value.contains("{name}") {
+            *value = value.replace("{name}", name);
+        }
+    }
+
+    Ok(strings)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn resolves_locales() {
+        let mut cfg = Config::default();
+
+        let strings = resolve(&cfg, "my-crate").unwrap();
+        assert_eq!(strings["title"], "Third Party Licenses");
+        assert_eq!(
+            strings["intro"],
+            "This page lists the licenses of the projects used in my-crate."
+        );
+
+        cfg.locale = Some("de-AT".to_owned());
+        cfg.translations.insert(
+            "de-AT".to_owned(),
+            BTreeMap::from([("used_by".to_owned(), "Benutzt von:".to_owned())]),
+        );
+
+        let strings = resolve(&cfg, "my-crate").unwrap();
+        assert_eq!(strings["title"], "Lizenzen von Drittanbietern");
+        assert_eq!(strings["used_by"], "Benutzt von:");
+
+        cfg.locale = Some("xx".to_owned());
+        assert!(resolve(&cfg, "my-crate").is_err());
+    }
+}
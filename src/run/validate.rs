@@ -0,0 +1,230 @@
+//! Validates the configuration against the crate graph, so that mistakes such
+//! as misspelled crate names or clarification paths are reported up front,
+//! rather than as warnings in the middle of generating output
+
+use crate::{
+    licenses::{
+        config::{self, Clarification, Config},
+        resolution::{Diagnostic, Files},
+    },
+    Krate, Krates,
+};
+use codespan_reporting::diagnostic::Label;
+use krates::Utf8Path as Path;
+
+/// The configuration file, used to point diagnostics at the offending entries
+struct Source {
+    file_id: codespan::FileId,
+    doc: toml_edit::ImDocument<String>,
+    /// The configuration was loaded from `[workspace.metadata.about]` rather
+    /// than an `about.toml`
+    is_manifest: bool,
+}
+
+impl Source {
+    fn load(files: &mut Files, path: &Path) -> Option<Self> {
+        let contents = std::fs::read_to_string(path).ok()?;
+        let doc = toml_edit::ImDocument::parse(contents.clone()).ok()?;
+
+        Some(Self {
+            file_id: files.add(path.as_str(), contents),
+            doc,
+            is_manifest: path.file_name() == Some("Cargo.toml"),
+        })
+    }
+
+    /// The table the configuration was deserialized from
+    fn root(&self) -> &dyn toml_edit::TableLike {
+        let root = self.doc.as_table();
+        if !self.is_manifest {
+            return root;
+        }
+
+        root.get("workspace")
+            .and_then(|ws| ws.get("metadata"))
+            .and_then(|md| md.get("about"))
+            .and_then(toml_edit::Item::as_table_like)
+            .unwrap_or(root)
+    }
+
+    fn key_label(&self, key: &str) -> Option<Label<codespan::FileId>> {
+        let (key, _) = self.root().get_key_value(key)?;
+        Some(Label::primary(self.file_id, key.span()?))
+    }
+
+    fn item_label(
+        &self,
+        item: impl FnOnce(&dyn toml_edit::TableLike) -> Option<&toml_edit::Item>,
+    ) -> Option<Label<codespan::FileId>> {
+        Some(Label::primary(self.file_id, item(self.root())?.span()?))
+    }
+}
+
+/// Whether the crate matches the name, which can contain wildcards, and
+/// version requirement of a crate configuration or workaround
+fn matches(krate: &Krate, name: &str, version: Option<&semver::VersionReq>) -> bool {
+    let name_matches = if name.contains(['*', '?']) {
+        config::matches_name(name, &krate.name)
+    } else {
+        krate.name == name
+    };
+
+    name_matches && version.map_or(true, |req| req.matches(&krate.version))
+}
+
+/// The clarification files that don't exist in the crates they apply to
+fn missing_files<'c>(
+    clarification: &'c Clarification,
+    matching: &[&Krate],
+) -> Vec<(usize, &'c Path, String)> {
+    let mut missing = Vec::new();
+
+    for krate in matching {
+        let Some(root) = krate.manifest_path.parent() else {
+            continue;
+        };
+
+        for (i, file) in clarification.files.iter().enumerate() {
+            if !root.join(&file.path).exists() {
+                missing.push((i, file.path.as_path(), krate.to_string()));
+            }
+        }
+    }
+
+    missing
+}
+
+/// Validates the configuration against the crate graph, returning a
+/// diagnostic for each problem found
+///
+/// - Crate configurations, exceptions, and external workarounds that don't
+///   apply to any crate in the graph are warnings
+/// - Clarification `files` that don't exist in the crates they apply to are
+///   errors. `git` files are not checked, as they can only be retrieved from
+///   the network
+/// - Built-in workarounds that don't exist are errors, and those that don't
+///   apply to any crate are warnings
+pub fn config(cfg: &Config, krates: &Krates) -> (Files, Vec<Diagnostic>) {
+    let mut files = Files::new();
+    let source = cfg
+        .config_path
+        .as_deref()
+        .and_then(|path| Source::load(&mut files, path));
+
+    let mut diags = Vec::new();
+
+    for (key, kc) in &cfg.crates {
+        let matching: Vec<_> = krates
+            .krates()
+            .filter(|krate| matches(krate, key, kc.version.as_ref()))
+            .collect();
+
+        if matching.is_empty() {
+            let mut diag = Diagnostic::warning()
+                .with_message(format!(
+                    "configuration for '{key}' does not apply to any crate in the graph"
+                ))
+                .with_labels(
+                    source
+                        .as_ref()
+                        .and_then(|src| src.key_label(key))
+                        .into_iter()
+                        .collect(),
+                );
+
+            if let Some(req) = &kc.version {
+                if krates.krates().any(|krate| matches(krate, key, None)) {
+                    diag = diag.with_notes(vec![format!(
+                        "the crate is in the graph, but no version matches '{req}'"
+                    )]);
+                }
+            }
+
+            diags.push(diag);
+            continue;
+        }
+
+        let Some(clarification) = &kc.clarify else {
+            continue;
+        };
+
+        for (i, path, krate) in missing_files(clarification, &matching) {
+            let file_label = source.as_ref().and_then(|src| {
+                src.item_label(|root| {
+                    root.get(key)?
+                        .get("clarify")?
+                        .get("files")?
+                        .get(i)?
+                        .get("path")
+                })
+            });
+
+            diags.push(
+                Diagnostic::error()
+                    .with_message(format!(
+                        "clarification file '{path}' does not exist in crate '{krate}'"
+                    ))
+                    .with_labels(file_label.into_iter().collect()),
+            );
+        }
+    }
+
+    for (i, exception) in cfg.exceptions.iter().enumerate() {
+        if krates.krates().any(|krate| krate.name == exception.name) {
+            continue;
+        }
+
+        let label = source
+            .as_ref()
+            .and_then(|src| src.item_label(|root| root.get("exceptions")?.get(i)?.get("name")));
+
+        diags.push(
+            Diagnostic::warning()
+                .with_message(format!(
+                    "exception for '{}' does not apply to any crate in the graph",
+                    exception.name
+                ))
+                .with_labels(label.into_iter().collect()),
+        );
+    }
+
+    for workaround in &cfg.external_workarounds {
+        let matching: Vec<_> = krates
+            .krates()
+            .filter(|krate| matches(krate, &workaround.name, workaround.version.as_ref()))
+            .collect();
+
+        if matching.is_empty() {
+            diags.push(Diagnostic::warning().with_message(format!(
+                "external workaround for '{}' does not apply to any crate in the graph",
+                workaround.name
+            )));
+            continue;
+        }
+
+        for (_, path, krate) in missing_files(&workaround.clarify, &matching) {
+            diags.push(Diagnostic::error().with_message(format!(
+                "external workaround file '{path}' does not exist in crate '{krate}'"
+            )));
+        }
+    }
+
+    for (i, workaround) in cfg.workarounds.iter().enumerate() {
+        let diag = match crate::licenses::workarounds::applies(workaround, krates) {
+            Some(true) => continue,
+            Some(false) => Diagnostic::warning().with_message(format!(
+                "workaround '{workaround}' does not apply to any crate in the graph"
+            )),
+            None => Diagnostic::error().with_message(format!(
+                "no workaround registered for the '{workaround}' crate"
+            )),
+        };
+
+        let workaround_label = source
+            .as_ref()
+            .and_then(|src| src.item_label(|root| root.get("workarounds")?.get(i)));
+        diags.push(diag.with_labels(workaround_label.into_iter().collect()));
+    }
+
+    (files, diags)
+}
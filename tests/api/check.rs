@@ -0,0 +1,111 @@
+use super::{manifest_path, pipeline};
+use crate::utils::Package;
+
+use anyhow::Result;
+use cargo_about::run;
+
+/// A workspace with one crate for each outcome, an accepted `MIT` crate, which
+/// depends on a denied `GPL-3.0-only` crate, and a crate with the unaccepted
+/// `Apache-2.0` license
+fn packages(about_toml: &str) -> Result<(Package, Package, Package)> {
+    let denied = Package::builder()
+        .name("package-denied")
+        .license(Some("GPL-3.0-only"))
+        .build()?;
+
+    let unaccepted = Package::builder()
+        .name("package-unaccepted")
+        .license(Some("Apache-2.0"))
+        .build()?;
+
+    let root = Package::builder()
+        .name("package-root")
+        .license(Some("MIT"))
+        .dependency(&denied)
+        .dependency(&unaccepted)
+        .file("about.toml", about_toml)
+        .build()?;
+
+    Ok((root, denied, unaccepted))
+}
+
+#[test]
+fn reports_violations() -> Result<()> {
+    let (root, _denied, _unaccepted) = packages(
+        r#"
+accepted = ["MIT"]
+denied = ["GPL-3.0"]
+"#,
+    )?;
+
+    let manifest_path = manifest_path(&root)?;
+    let cfg = run::load_config(&manifest_path, None)?;
+    let result = run::check::check_licenses(&manifest_path, cfg)?;
+
+    assert!(!result.passed());
+    assert_eq!(result.checked, 3);
+
+    let [denied, unaccepted] = result.violations.as_slice() else {
+        anyhow::bail!("expected 2 violations: {:?}", result.violations);
+    };
+
+    assert_eq!(denied.name, "package-denied");
+    assert_eq!(denied.expression.as_deref(), Some("GPL-3.0-only"));
+    assert_eq!(denied.denied, ["GPL-3.0"]);
+    assert!(denied.unaccepted.is_empty());
+    assert!(!denied.errors.is_empty());
+
+    assert_eq!(unaccepted.name, "package-unaccepted");
+    assert_eq!(unaccepted.expression.as_deref(), Some("Apache-2.0"));
+    assert_eq!(unaccepted.unaccepted, ["Apache-2.0"]);
+    assert!(unaccepted.denied.is_empty());
+
+    // The denied license can't be accepted, so it isn't suggested
+    assert_eq!(result.suggested, ["Apache-2.0"]);
+
+    Ok(())
+}
+
+#[test]
+fn passes_when_every_license_is_accepted() -> Result<()> {
+    let (root, _denied, _unaccepted) = packages(
+        r#"
+accepted = ["MIT", "GPL-3.0", "Apache-2.0"]
+"#,
+    )?;
+
+    let manifest_path = manifest_path(&root)?;
+    let cfg = run::load_config(&manifest_path, None)?;
+    let result = run::check::check_licenses(&manifest_path, cfg)?;
+
+    assert!(result.passed(), "{:?}", result.violations);
+    assert!(result.suggested.is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn include_unaccepted_still_reports_violations() -> Result<()> {
+    let (root, _denied, _unaccepted) = packages(
+        r#"
+accepted = ["MIT", "GPL-3.0"]
+include-unaccepted = true
+"#,
+    )?;
+
+    let cfg = run::load_config(&manifest_path(&root)?, None)?;
+    let pipeline = pipeline(&root, cfg)?;
+    let prepared = pipeline.prepare()?;
+    let result = pipeline.check(&prepared)?;
+
+    let [unaccepted] = result.violations.as_slice() else {
+        anyhow::bail!("expected 1 violation: {:?}", result.violations);
+    };
+
+    assert_eq!(unaccepted.name, "package-unaccepted");
+    assert_eq!(unaccepted.unaccepted, ["Apache-2.0"]);
+    // Unaccepted licenses are only warnings when they are included
+    assert!(unaccepted.errors.is_empty());
+
+    Ok(())
+}
@@ -0,0 +1,68 @@
+use super::pipeline;
+use crate::utils::Package;
+
+use anyhow::Result;
+use cargo_about::licenses::{config::Config, Detection, LicenseInfo};
+
+#[test]
+fn marks_crates_as_declared_without_license_store() -> Result<()> {
+    let package = Package::builder()
+        .license(Some("MIT"))
+        .file("LICENSE", include_str!("../../LICENSE-MIT"))
+        .build()?;
+
+    let pipeline = pipeline(&package, Config::default())?;
+    let mut prepared = pipeline.prepare()?;
+    prepared.store = None;
+
+    let gathered = pipeline.gather(&prepared)?;
+    let [kl] = gathered.as_slice() else {
+        anyhow::bail!("expected a single crate, gathered {}", gathered.len());
+    };
+
+    assert_eq!(kl.detection, Detection::Declared);
+    assert!(matches!(&kl.lic_info, LicenseInfo::Expr(expr) if expr.as_ref() == "MIT"));
+    assert!(kl.license_files.is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn marks_crates_without_license_files_as_not_found() -> Result<()> {
+    let package = Package::builder().license(Some("MIT")).build()?;
+
+    let pipeline = pipeline(&package, Config::default())?;
+    let prepared = pipeline.prepare()?;
+
+    let gathered = pipeline.gather(&prepared)?;
+    let [kl] = gathered.as_slice() else {
+        anyhow::bail!("expected a single crate, gathered {}", gathered.len());
+    };
+
+    assert_eq!(kl.detection, Detection::NotFound);
+    assert!(kl.license_files.is_empty());
+    assert!(kl.notes.is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn marks_crates_with_license_files_as_found() -> Result<()> {
+    let package = Package::builder()
+        .license(Some("MIT"))
+        .file("LICENSE", include_str!("../../LICENSE-MIT"))
+        .build()?;
+
+    let pipeline = pipeline(&package, Config::default())?;
+    let prepared = pipeline.prepare()?;
+
+    let gathered = pipeline.gather(&prepared)?;
+    let [kl] = gathered.as_slice() else {
+        anyhow::bail!("expected a single crate, gathered {}", gathered.len());
+    };
+
+    assert_eq!(kl.detection, Detection::Found);
+    assert!(!kl.license_files.is_empty());
+
+    Ok(())
+}
@@ -0,0 +1,27 @@
+//! Tests of the library API, for the parts of the pipeline that the CLI
+//! doesn't expose directly
+
+mod check;
+mod gather;
+
+use crate::utils::Package;
+use anyhow::Result;
+use cargo_about::{licenses::config::Config, run::Pipeline};
+use krates::Utf8PathBuf as PathBuf;
+
+pub fn manifest_path(package: &Package) -> Result<PathBuf> {
+    PathBuf::from_path_buf(package.dir.path().join("Cargo.toml"))
+        .map_err(|pb| anyhow::anyhow!("'{}' is not a utf-8 path", pb.display()))
+}
+
+/// Creates a pipeline for the package, which doesn't access the network
+pub fn pipeline(package: &Package, cfg: Config) -> Result<Pipeline> {
+    let manifest_path = manifest_path(package)?;
+    let lock_opts = krates::LockOptions {
+        frozen: false,
+        locked: false,
+        offline: true,
+    };
+
+    Ok(Pipeline::new(manifest_path, cfg).lock_options(lock_opts))
+}
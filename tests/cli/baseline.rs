@@ -0,0 +1,54 @@
+use crate::utils::*;
+
+use anyhow::Result;
+
+#[test]
+fn only_fails_on_violations_missing_from_baseline() -> Result<()> {
+    let mut package_builder = Package::builder();
+
+    let package_b = package_builder
+        .license(Some("Apache-2.0"))
+        .name("package-b")
+        .build()?;
+
+    let package_a = package_builder
+        .license(Some("MIT"))
+        .name("package-a")
+        .accepted(&["MIT"])
+        .dependency(&package_b)
+        .build()?;
+
+    CargoAbout::new(&package_a)?
+        .baseline()
+        .arg("write")
+        .assert()
+        .success();
+
+    let baseline = std::fs::read_to_string(package_a.dir.join("about-baseline.toml"))?;
+    assert!(baseline.contains("name = \"package-b\""));
+    assert!(baseline.contains("license = \"Apache-2.0\""));
+
+    CargoAbout::new(&package_a)?
+        .baseline()
+        .arg("check")
+        .assert()
+        .success();
+
+    std::fs::write(package_a.dir.join("empty-baseline.toml"), "")?;
+
+    CargoAbout::new(&package_a)?
+        .baseline()
+        .arg("check")
+        .arg("--baseline")
+        .arg("empty-baseline.toml")
+        .assert()
+        .failure()
+        .stderr(predicates::str::contains(
+            "crate 'package-b 0.0.0 (Apache-2.0)' has licenses that are not accepted",
+        ))
+        .stderr(predicates::str::contains(
+            "1 crate(s) deviate from the baseline",
+        ));
+
+    Ok(())
+}
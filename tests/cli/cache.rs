@@ -0,0 +1,58 @@
+use crate::utils::*;
+
+use anyhow::Result;
+use predicates::prelude::*;
+
+#[test]
+fn reports_and_cleans_caches() -> Result<()> {
+    let package = Package::builder().build()?;
+
+    let cache_dir = package.dir.join("cache");
+    std::fs::create_dir_all(cache_dir.join("git"))?;
+    std::fs::write(cache_dir.join("0123456789abcdef.entry"), "{}\nbody")?;
+    std::fs::write(cache_dir.join("0123456789abcdef.lock"), "")?;
+    std::fs::write(cache_dir.join("git/fedcba9876543210"), "license text")?;
+
+    std::fs::write(
+        package.dir.join(ABOUT_CONFIG_FILENAME),
+        format!(
+            "accepted = [\"MIT\"]\nhttp-cache-dir = {:?}\n",
+            cache_dir.to_str().unwrap()
+        ),
+    )?;
+
+    CargoAbout::new(&package)?
+        .cache()
+        .arg("dir")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("http: "))
+        .stdout(predicate::str::contains("git: "));
+
+    CargoAbout::new(&package)?
+        .cache()
+        .arg("stats")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("http: 1 entries, 7 B"))
+        .stdout(predicate::str::contains("git: 1 entries, 12 B"));
+
+    CargoAbout::new(&package)?
+        .cache()
+        .arg("clean")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("http: removed 1 entries"))
+        .stdout(predicate::str::contains("git: removed 1 entries"));
+
+    assert!(!cache_dir.exists());
+
+    CargoAbout::new(&package)?
+        .cache()
+        .arg("stats")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("http: 0 entries, 0 B"));
+
+    Ok(())
+}
@@ -0,0 +1,54 @@
+use crate::utils::*;
+
+use anyhow::Result;
+use predicates::prelude::*;
+use std::process::Command;
+
+/// Runs git in the package's directory
+fn git(package: &Package, args: &[&str]) -> Result<()> {
+    let status = Command::new("git")
+        .args(["-c", "user.name=test", "-c", "user.email=test@example.com"])
+        .args(args)
+        .current_dir(&package.dir)
+        .status()?;
+    anyhow::ensure!(status.success(), "git {args:?} failed");
+    Ok(())
+}
+
+#[test]
+fn reports_no_changes_since_revision_with_same_lockfile() -> Result<()> {
+    let package = Package::builder().build()?;
+
+    let status = Command::new("cargo")
+        .args(["generate-lockfile", "--offline"])
+        .current_dir(&package.dir)
+        .status()?;
+    anyhow::ensure!(status.success(), "failed to generate lockfile");
+
+    git(&package, &["init", "-q"])?;
+    git(&package, &["add", "-A"])?;
+    git(&package, &["commit", "-q", "-m", "initial"])?;
+
+    CargoAbout::new(&package)?
+        .changelog()
+        .arg("--since")
+        .arg("HEAD")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "## New third-party components and licenses in this release",
+        ))
+        .stdout(predicate::str::contains(
+            "No third-party components were added or updated.",
+        ));
+
+    CargoAbout::new(&package)?
+        .changelog()
+        .arg("--since")
+        .arg("does-not-exist")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("at revision 'does-not-exist'"));
+
+    Ok(())
+}
@@ -0,0 +1,322 @@
+use crate::utils::*;
+
+use anyhow::Result;
+use predicates::prelude::*;
+
+#[test]
+fn writes_clarification_section_for_crate() -> Result<()> {
+    let package = Package::builder()
+        .file("LICENSE", &mit_license_text("2022", "Package Owner"))
+        .file(
+            "about.toml",
+            "accepted = [\"MIT\"]\n\n[package-a]\naccepted = [\"ISC\"]\n",
+        )
+        .build()?;
+
+    CargoAbout::new(&package)?
+        .clarify()
+        .arg("--crate")
+        .arg("package-a")
+        .arg("--write")
+        .arg("LICENSE")
+        .arg("path")
+        .arg(".")
+        .assert()
+        .success();
+
+    let about_toml = std::fs::read_to_string(package.dir.join(ABOUT_CONFIG_FILENAME))?;
+    assert!(about_toml.contains("[package-a]\naccepted = [\"ISC\"]\n"));
+    assert!(about_toml.contains("[package-a.clarify]\nlicense = \"MIT\"\n"));
+    assert!(about_toml.contains("[[package-a.clarify.files]]\npath = \"LICENSE\"\n"));
+
+    Ok(())
+}
+
+#[test]
+fn writes_checksum_with_configured_algorithm() -> Result<()> {
+    let package = Package::builder()
+        .file("LICENSE", &mit_license_text("2022", "Package Owner"))
+        .file(
+            "about.toml",
+            "accepted = [\"MIT\"]\nchecksum-algorithm = \"blake3\"\n",
+        )
+        .build()?;
+
+    CargoAbout::new(&package)?
+        .clarify()
+        .arg("--crate")
+        .arg("package")
+        .arg("--write")
+        .arg("LICENSE")
+        .arg("path")
+        .arg(".")
+        .assert()
+        .success();
+
+    let about_toml = std::fs::read_to_string(package.dir.join(ABOUT_CONFIG_FILENAME))?;
+    assert!(about_toml.contains("checksum = \"blake3:"));
+
+    CargoAbout::new(&package)?
+        .generate()
+        .template(package.template()?)
+        .assert()
+        .success()
+        .stderr(predicates::str::contains("checksum").not())
+        .stdout(licenses_count(1));
+
+    Ok(())
+}
+
+#[test]
+fn uses_checksum_algorithm_of_yaml_config_but_only_writes_toml() -> Result<()> {
+    let package = Package::builder()
+        .file("LICENSE", &mit_license_text("2022", "Package Owner"))
+        .file(
+            "about.yaml",
+            "accepted:\n  - MIT\nchecksum-algorithm: blake3\n",
+        )
+        .build()?;
+
+    CargoAbout::new(&package)?
+        .clarify()
+        .arg("--crate")
+        .arg("package")
+        .arg("--config")
+        .arg("about.yaml")
+        .arg("LICENSE")
+        .arg("path")
+        .arg(".")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("checksum = \"blake3:"));
+
+    CargoAbout::new(&package)?
+        .clarify()
+        .arg("--crate")
+        .arg("package")
+        .arg("--config")
+        .arg("about.yaml")
+        .arg("--write")
+        .arg("LICENSE")
+        .arg("path")
+        .arg(".")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "--write can only update a TOML config",
+        ));
+
+    Ok(())
+}
+
+#[test]
+fn clarifies_ranges_of_lines_with_start_and_end_text() -> Result<()> {
+    let mit = mit_license_text("2022", "Package Owner");
+    let license = format!(
+        "Third party notices\n\n{mit}\n{}",
+        include_str!("../../LICENSE-APACHE")
+    );
+    let last = 2 + mit.lines().count();
+
+    let package = Package::builder()
+        .file("LICENSE", &license)
+        .accepted(&["MIT"])
+        .build()?;
+
+    CargoAbout::new(&package)?
+        .clarify()
+        .arg("--crate")
+        .arg("package")
+        .arg("--write")
+        .arg("--lines")
+        .arg(&format!("3..={last}"))
+        .arg("LICENSE")
+        .arg("path")
+        .arg(".")
+        .assert()
+        .success();
+
+    let about_toml: toml::Table =
+        std::fs::read_to_string(package.dir.join(ABOUT_CONFIG_FILENAME))?.parse()?;
+    let clarify = &about_toml["package"]["clarify"];
+    assert_eq!(clarify["license"].as_str(), Some("MIT"));
+
+    // The end text is written as a literal string if it contains quotes, so
+    // the parsed value is checked rather than the raw TOML
+    let file = &clarify["files"][0];
+    assert_eq!(
+        file["start"].as_str(),
+        Some("Copyright (c) 2022 Package Owner")
+    );
+    let end = file["end"].as_str().unwrap_or_default();
+    assert!(
+        !end.is_empty() && mit.contains(end),
+        "unexpected end '{end}'"
+    );
+
+    CargoAbout::new(&package)?
+        .generate()
+        .template(package.template()?)
+        .assert()
+        .success()
+        .stderr(predicates::str::contains("subsection").not())
+        .stderr(predicates::str::contains("checksum").not())
+        .stdout(licenses_count(1));
+
+    Ok(())
+}
+
+#[test]
+fn extends_subsection_text_until_unique() -> Result<()> {
+    let license = format!(
+        "{}\n{}",
+        mit_license_text("2022", "Package Owner"),
+        mit_license_text("2023", "Other Owner")
+    );
+
+    let package = Package::builder()
+        .file("LICENSE", &license)
+        .accepted(&["MIT"])
+        .build()?;
+
+    CargoAbout::new(&package)?
+        .clarify()
+        .arg("--crate")
+        .arg("package")
+        .arg("--write")
+        .arg("-s")
+        .arg("Copyright (c)!!SOFTWARE.")
+        .arg("LICENSE")
+        .arg("path")
+        .arg(".")
+        .assert()
+        .success();
+
+    let about_toml = std::fs::read_to_string(package.dir.join(ABOUT_CONFIG_FILENAME))?;
+    assert!(about_toml.contains("start = \"Copyright (c) 2022 Package Owner\"\n"));
+
+    CargoAbout::new(&package)?
+        .generate()
+        .template(package.template()?)
+        .assert()
+        .success()
+        .stderr(predicates::str::contains("subsection").not())
+        .stderr(predicates::str::contains("checksum").not());
+
+    Ok(())
+}
+
+#[test]
+fn reports_subsection_start_that_is_not_unique_with_checksum_mismatch() -> Result<()> {
+    let license = format!(
+        "{}\n{}",
+        mit_license_text("2022", "Package Owner"),
+        mit_license_text("2023", "Other Owner")
+    );
+
+    let package = Package::builder()
+        .file("LICENSE", &license)
+        .file(
+            "about.toml",
+            r#"accepted = ["MIT"]
+
+[package.clarify]
+license = "MIT"
+
+[[package.clarify.files]]
+path = "LICENSE"
+checksum = "0000000000000000000000000000000000000000000000000000000000000000"
+start = "Copyright (c)"
+"#,
+        )
+        .build()?;
+
+    CargoAbout::new(&package)?
+        .generate()
+        .template(package.template()?)
+        .assert()
+        .stderr(predicates::str::contains(
+            "subsection start 'Copyright (c)' appears more than once",
+        ))
+        .stderr(predicates::str::contains("checksum mismatch"));
+
+    Ok(())
+}
+
+#[test]
+fn reports_subsection_end_that_is_not_unique_with_checksum_mismatch() -> Result<()> {
+    let license = format!(
+        "{}\n{}",
+        mit_license_text("2022", "Package Owner"),
+        mit_license_text("2023", "Other Owner")
+    );
+
+    let package = Package::builder()
+        .file("LICENSE", &license)
+        .file(
+            "about.toml",
+            r#"accepted = ["MIT"]
+
+[package.clarify]
+license = "MIT"
+
+[[package.clarify.files]]
+path = "LICENSE"
+checksum = "0000000000000000000000000000000000000000000000000000000000000000"
+start = "Copyright (c) 2022"
+end = "portions of the Software."
+"#,
+        )
+        .build()?;
+
+    CargoAbout::new(&package)?
+        .generate()
+        .template(package.template()?)
+        .assert()
+        .stderr(predicates::str::contains(
+            "subsection end 'portions of the Software.' appears more than once after the start",
+        ))
+        .stderr(predicates::str::contains("checksum mismatch"));
+
+    Ok(())
+}
+
+#[test]
+fn finds_crate_source_in_any_registry_in_cargo_home() -> Result<()> {
+    let package = Package::builder().build()?;
+
+    let status = std::process::Command::new("cargo")
+        .args(["generate-lockfile", "--offline"])
+        .current_dir(&package.dir)
+        .status()?;
+    anyhow::ensure!(status.success(), "failed to generate lockfile");
+
+    let cargo_home = package.dir.join("cargo-home");
+    std::fs::create_dir_all(cargo_home.join("registry/src/index.crates.io-6f17d22bba15001f"))?;
+    std::fs::create_dir_all(cargo_home.join("registry/src/my-registry-0123456789abcdef"))?;
+
+    CargoAbout::new(&package)?
+        .env("CARGO_HOME", &cargo_home)
+        .clarify()
+        .arg("LICENSE")
+        .arg("crate")
+        .arg("fixture-crate-0.1.0")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "unable to find the source of 'fixture-crate-0.1.0' in any registry",
+        ));
+
+    // The version of a crate specified by name is resolved from the lockfile
+    CargoAbout::new(&package)?
+        .env("CARGO_HOME", &cargo_home)
+        .clarify()
+        .arg("LICENSE")
+        .arg("crate")
+        .arg("fixture-crate")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("crate 'fixture-crate' is not in"));
+
+    Ok(())
+}
@@ -0,0 +1,63 @@
+use crate::utils::*;
+
+use anyhow::Result;
+use predicates::prelude::*;
+
+#[test]
+fn writes_artifact_per_target_with_manifest() -> Result<()> {
+    let package = Package::builder()
+        .license(Some("MIT"))
+        .accepted(&["MIT"])
+        .build()?;
+
+    let out_dir = package.dir.join("dist");
+
+    let assert = CargoAbout::new(&package)?
+        .dist()
+        .arg("--target")
+        .arg("x86_64-unknown-linux-gnu")
+        .arg("--target")
+        .arg("aarch64-apple-darwin")
+        .arg("--out-dir")
+        .arg(out_dir.to_str().unwrap())
+        .template(package.template()?)
+        .assert()
+        .success();
+
+    let manifest: serde_json::Value = serde_json::from_slice(&assert.get_output().stdout)?;
+    let artifacts = manifest["artifacts"].as_array().unwrap();
+    assert_eq!(artifacts.len(), 2);
+
+    for (artifact, target) in artifacts
+        .iter()
+        .zip(["x86_64-unknown-linux-gnu", "aarch64-apple-darwin"])
+    {
+        assert_eq!(artifact["target"], target);
+        assert_eq!(artifact["licenses"], serde_json::json!(["MIT"]));
+
+        let path = out_dir.join(target).join("THIRD-PARTY-LICENSES.html");
+        assert_eq!(artifact["path"], path.to_str().unwrap());
+
+        let output = std::fs::read_to_string(path)?;
+        assert!(licenses_count(1).eval(&output));
+    }
+
+    Ok(())
+}
+
+#[test]
+fn requires_a_target() -> Result<()> {
+    let package = Package::builder().license(Some("MIT")).build()?;
+
+    CargoAbout::new(&package)?
+        .dist()
+        .arg("--out-dir")
+        .arg(package.dir.join("dist").to_str().unwrap())
+        .assert()
+        .failure()
+        .stderr(predicates::str::contains(
+            "at least one target must be specified",
+        ));
+
+    Ok(())
+}
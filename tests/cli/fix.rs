@@ -0,0 +1,80 @@
+use crate::utils::*;
+
+use anyhow::Result;
+use predicates::prelude::*;
+
+#[test]
+fn writes_chosen_fixes_to_config() -> Result<()> {
+    let package_b = Package::builder()
+        .name("package-b")
+        .license(Some("AGPL-3.0-only"))
+        .build()?;
+
+    let package = Package::builder()
+        .license(Some("GPL-3.0-only"))
+        .accepted(&["MIT"])
+        .dependency(&package_b)
+        .file("answers.txt", "1\ni\n")
+        .build()?;
+
+    CargoAbout::new(&package)?
+        .fix()
+        .stdin(&package.dir.join("answers.txt"))?
+        .assert()
+        .success()
+        .stderr(predicate::str::contains(
+            "crate 'package 0.0.0' failed license resolution",
+        ))
+        .stderr(predicate::str::contains("[1] accept 'GPL-3.0'"))
+        .stderr(predicate::str::contains(
+            "crate 'package-b 0.0.0' failed license resolution",
+        ));
+
+    let config: toml::Table = std::fs::read_to_string(package.dir.join("about.toml"))?.parse()?;
+    assert_eq!(
+        config["accepted"],
+        toml::Value::from(vec!["MIT", "GPL-3.0"])
+    );
+    assert_eq!(config["private"]["ignore"], toml::Value::from(true));
+    assert_eq!(
+        config["private"]["names"],
+        toml::Value::from(vec!["package-b"])
+    );
+
+    // Every failure was fixed
+    CargoAbout::new(&package)?
+        .generate()
+        .template(package.template()?)
+        .assert()
+        .success();
+
+    Ok(())
+}
+
+#[test]
+fn writes_fixes_to_about_toml_rather_than_accepted_file() -> Result<()> {
+    let package = Package::builder()
+        .license(Some("GPL-3.0-only"))
+        .no_about_config()
+        .file("approved.txt", "MIT\n")
+        .file("answers.txt", "1\n")
+        .build()?;
+
+    CargoAbout::new(&package)?
+        .fix()
+        .arg("--accepted-file")
+        .arg("approved.txt")
+        .stdin(&package.dir.join("answers.txt"))?
+        .assert()
+        .success();
+
+    assert_eq!(
+        std::fs::read_to_string(package.dir.join("approved.txt"))?,
+        "MIT\n"
+    );
+
+    let config: toml::Table = std::fs::read_to_string(package.dir.join("about.toml"))?.parse()?;
+    assert_eq!(config["accepted"], toml::Value::from(vec!["GPL-3.0"]));
+
+    Ok(())
+}
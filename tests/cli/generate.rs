@@ -57,7 +57,7 @@ fn fails_back_to_default_about_config_when_absent() -> Result<()> {
         .template(package.template()?)
         .assert()
         .stderr(predicate::str::contains(
-            "no 'about.toml' found, falling back to default configuration",
+            "no 'about.toml', 'about.yaml' or 'about.json' found, falling back to default configuration",
         ));
 
     Ok(())
@@ -118,6 +118,7 @@ fn reports_no_licenses_when_no_license_and_accepted_field_empty() -> Result<()>
         .template(package.template()?)
         .assert()
         .success()
+        .stderr(contains_missing_license_field_warning(&package))
         .stderr(unable_to_synthesize_license_expr_warning(&package))
         .stdout(overview_count(0))
         .stdout(licenses_count(0));
@@ -142,6 +143,185 @@ fn fails_when_license_field_valid_and_accepted_field_empty() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn suggests_all_licensees_needed_to_satisfy_every_crate() -> Result<()> {
+    let package_b = Package::builder()
+        .name("package-b")
+        .license(Some("Apache-2.0"))
+        .build()?;
+    let package_c = Package::builder()
+        .name("package-c")
+        .license(Some("ISC OR Apache-2.0"))
+        .build()?;
+    let package_d = Package::builder()
+        .name("package-d")
+        .license(Some("Zlib AND BSD-3-Clause"))
+        .build()?;
+
+    let package_a = Package::builder()
+        .name("package-a")
+        .license(Some("MIT"))
+        .dependency(&package_b)
+        .dependency(&package_c)
+        .dependency(&package_d)
+        .accepted(&["MIT"])
+        .build()?;
+
+    CargoAbout::new(&package_a)?
+        .generate()
+        .template(package_a.template()?)
+        .assert()
+        .failure()
+        .stderr(predicates::str::contains(
+            "adding the following to `accepted` would satisfy the license requirements of every crate",
+        ))
+        .stderr(predicates::str::contains(
+            r#""Apache-2.0", "Zlib", "BSD-3-Clause""#,
+        ))
+        .stdout("");
+
+    Ok(())
+}
+
+#[test]
+fn aggregates_identical_failures_across_crates() -> Result<()> {
+    let package_b = Package::builder()
+        .name("package-b")
+        .license(Some("Apache-2.0"))
+        .build()?;
+    let package_c = Package::builder()
+        .name("package-c")
+        .license(Some("Apache-2.0"))
+        .build()?;
+
+    let package_a = Package::builder()
+        .name("package-a")
+        .license(Some("MIT"))
+        .dependency(&package_b)
+        .dependency(&package_c)
+        .accepted(&["MIT"])
+        .build()?;
+
+    let stderr = CargoAbout::new(&package_a)?
+        .generate()
+        .template(package_a.template()?)
+        .assert()
+        .failure()
+        .get_output()
+        .stderr
+        .clone();
+    let stderr = String::from_utf8(stderr)?;
+
+    assert_eq!(
+        stderr
+            .matches("failed to satisfy license requirements")
+            .count(),
+        1
+    );
+    assert!(stderr.contains("'Apache-2.0' in 2 crates: package-b 0.0.0, package-c 0.0.0"));
+    assert!(stderr.contains("encountered 2 errors resolving licenses"));
+
+    let stderr = CargoAbout::new(&package_a)?
+        .generate()
+        .arg("--verbose-diagnostics")
+        .template(package_a.template()?)
+        .assert()
+        .failure()
+        .get_output()
+        .stderr
+        .clone();
+    let stderr = String::from_utf8(stderr)?;
+
+    assert_eq!(
+        stderr
+            .matches("failed to satisfy license requirements")
+            .count(),
+        2
+    );
+    assert!(!stderr.contains("in 2 crates"));
+
+    Ok(())
+}
+
+#[test]
+fn warns_about_accepted_licensees_not_used_by_any_crate() -> Result<()> {
+    let package = Package::builder()
+        .license(Some("MIT OR Apache-2.0"))
+        .file("about.toml", r#"accepted = ["MIT", "Apache-2.0", "ISC"]"#)
+        .build()?;
+
+    CargoAbout::new(&package)?
+        .generate()
+        .template(package.template()?)
+        .assert()
+        .success()
+        .stderr(predicates::str::contains(
+            "the following accepted licensees are not used by any crate",
+        ))
+        .stderr(predicates::str::contains(r#""Apache-2.0""#))
+        .stderr(predicates::str::contains(r#""ISC""#))
+        .stderr(predicates::str::contains(r#""MIT""#).not());
+
+    Ok(())
+}
+
+#[test]
+fn warns_about_unused_accepted_licensees_once_across_targets() -> Result<()> {
+    let package_win = Package::builder()
+        .name("package-win")
+        .license(Some("Apache-2.0"))
+        .build()?;
+
+    let package = Package::builder()
+        .file(
+            "Cargo.toml",
+            &format!(
+                r#"[package]
+name = "package"
+version = "0.0.0"
+license = "MIT"
+
+[target.'cfg(windows)'.dependencies]
+package-win = {{ version = "0.0.0", path = {:?} }}
+"#,
+                package_win.dir.to_str().unwrap()
+            ),
+        )
+        .file("about.toml", r#"accepted = ["MIT", "Apache-2.0", "ISC"]"#)
+        .build()?;
+
+    let output = CargoAbout::new(&package)?
+        .generate()
+        .arg("--split-by-target")
+        .arg("--target")
+        .arg("x86_64-unknown-linux-gnu")
+        .arg("--target")
+        .arg("x86_64-pc-windows-msvc")
+        .arg("--format")
+        .arg("json")
+        .arg("-o")
+        .arg("about.json")
+        .assert()
+        .success()
+        .get_output()
+        .stderr
+        .clone();
+    let stderr = String::from_utf8(output)?;
+
+    // Apache-2.0 is only used on windows, which is still a use
+    assert_eq!(
+        stderr
+            .matches("the following accepted licensees are not used by any crate")
+            .count(),
+        1,
+        "{stderr}"
+    );
+    assert!(stderr.contains(r#""ISC""#), "{stderr}");
+    assert!(!stderr.contains(r#""Apache-2.0""#), "{stderr}");
+
+    Ok(())
+}
+
 #[test]
 fn reports_no_licenses_when_license_field_unknown() -> Result<()> {
     let package = Package::builder()
@@ -184,8 +364,6 @@ fn reports_a_license_when_license_field_valid() -> Result<()> {
     Ok(())
 }
 
-// TODO: might be nice to let the user know that there was a license file field, but
-// that the file was missing.
 #[test]
 fn reports_no_licenses_when_license_file_field_but_no_file() -> Result<()> {
     let package = Package::builder()
@@ -198,6 +376,9 @@ fn reports_no_licenses_when_license_file_field_but_no_file() -> Result<()> {
         .template(package.template()?)
         .assert()
         .success()
+        .stderr(predicates::str::contains(
+            "crate 'package 0.0.0': unable to read `license-file` 'LICENSE'",
+        ))
         .stderr(unable_to_synthesize_license_expr_warning(&package))
         .stdout(overview_count(0))
         .stdout(licenses_count(0));
@@ -205,8 +386,6 @@ fn reports_no_licenses_when_license_file_field_but_no_file() -> Result<()> {
     Ok(())
 }
 
-// TODO: might be nice to let the user know that there was a license file field, but
-// that the file was empty / unrecognizable.
 #[test]
 fn reports_no_licenses_when_license_file_field_but_file_empty() -> Result<()> {
     let package = Package::builder()
@@ -219,6 +398,9 @@ fn reports_no_licenses_when_license_file_field_but_file_empty() -> Result<()> {
         .template(package.template()?)
         .assert()
         .success()
+        .stderr(predicates::str::contains(
+            "crate 'package 0.0.0': `license-file` 'LICENSE' is empty",
+        ))
         .stderr(unable_to_synthesize_license_expr_warning(&package))
         .stdout(overview_count(0))
         .stdout(licenses_count(0));
@@ -243,6 +425,9 @@ fn reports_no_licenses_when_manifest_has_license_file_field_with_non_spdx_text()
         .template(package.template()?)
         .assert()
         .success()
+        .stderr(predicates::str::contains(
+            "crate 'package 0.0.0': `license-file` 'LICENSE' is not a recognized license",
+        ))
         .stderr(unable_to_synthesize_license_expr_warning(&package))
         .stdout(overview_count(0))
         .stdout(licenses_count(0));
@@ -265,11 +450,7 @@ fn reports_custom_spdx_license_text_when_manifest_has_license_file_field_with_sp
         .template(package.template()?)
         .assert()
         .success()
-        // TODO: There should not be a warning about a missing licenses field
-        // since the manifest does have a license file field and according to the
-        // cargo docs, a manifest should have a license field or a license file
-        // field but not both.
-        .stderr(contains_missing_license_field_warning(&package))
+        .stderr("")
         .stdout(overview_count(1))
         .stdout(licenses_count(1))
         .stdout(predicates::str::contains(&license_text));
@@ -278,7 +459,7 @@ fn reports_custom_spdx_license_text_when_manifest_has_license_file_field_with_sp
 }
 
 #[test]
-fn reports_no_licenses_when_manifest_has_license_file_field_with_spdx_license_text_and_non_std_filename(
+fn reports_custom_spdx_license_text_when_manifest_has_license_file_field_with_non_std_filename(
 ) -> Result<()> {
     let license_text = mit_license_text("2022", "Big Birdz");
 
@@ -292,20 +473,37 @@ fn reports_no_licenses_when_manifest_has_license_file_field_with_spdx_license_te
         .template(package.template()?)
         .assert()
         .success()
-        // TODO: There should not be a warning about a missing licenses field
-        // since the manifest does have a license file field and according to the
-        // cargo docs, a manifest should have a license field or a license file
-        // field but not both.
-        .stderr(contains_missing_license_field_warning(&package))
-        // TODO: I would've expected this test case to succeed given that the
-        // name of the license file is given in the manifest and it is clear
-        // that the license can be detected from its text (which works when
-        // the license file is named `LICENSE`).
-        // I suppose I can understand if scanning all files in the repo would
-        // make the tool annoyingly slow.
-        .stderr(unable_to_synthesize_license_expr_warning(&package))
-        .stdout(overview_count(0))
-        .stdout(licenses_count(0));
+        .stderr("")
+        .stdout(overview_count(1))
+        .stdout(licenses_count(1))
+        .stdout(predicates::str::contains(&license_text));
+
+    Ok(())
+}
+
+#[cfg(unix)]
+#[test]
+fn reports_license_file_in_non_utf8_directory() -> Result<()> {
+    use std::os::unix::ffi::OsStrExt;
+
+    let license_text = mit_license_text("2022", "Big Birdz");
+
+    let package = Package::builder().accepted(&["MIT"]).build()?;
+
+    let dir = package
+        .dir
+        .join(std::ffi::OsStr::from_bytes(b"licenses-\xff"));
+    std::fs::create_dir(&dir)?;
+    std::fs::write(dir.join("LICENSE"), &license_text)?;
+
+    CargoAbout::new(&package)?
+        .generate()
+        .template(package.template()?)
+        .assert()
+        .success()
+        .stdout(overview_count(1))
+        .stdout(licenses_count(1))
+        .stdout(predicates::str::contains(&license_text));
 
     Ok(())
 }
@@ -390,6 +588,51 @@ fn reports_all_licenses_when_dependency_has_same_spdx_license_and_different_text
     Ok(())
 }
 
+#[test]
+fn reports_one_license_when_dependency_text_only_differs_in_wrapping_and_normalized() -> Result<()>
+{
+    let package_a_license_text = mit_license_text("2022", "Package Owner");
+    let package_b_license_text = package_a_license_text.replacen(" the ", "\nthe ", 3);
+
+    let package_b = Package::builder()
+        .license_file("LICENSE", Some(&package_b_license_text))
+        .name("package-b")
+        .build()?;
+
+    let package_a = Package::builder()
+        .name("package-a")
+        .license_file("LICENSE", Some(&package_a_license_text))
+        .dependency(&package_b)
+        .file(
+            "about.toml",
+            "accepted = [\"MIT\"]\nnormalize-license-texts = true\n",
+        )
+        .build()?;
+
+    let output = CargoAbout::new(&package_a)?
+        .generate()
+        .arg("--format")
+        .arg("json")
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+
+    let output: serde_json::Value = serde_json::from_slice(&output)?;
+    let licenses = output["licenses"].as_array().unwrap();
+    assert_eq!(licenses.len(), 1);
+
+    let used_by = licenses[0]["used_by"].as_array().unwrap();
+    assert_eq!(used_by.len(), 2);
+    assert_eq!(
+        used_by.iter().filter(|ub| ub["normalized"] == true).count(),
+        1
+    );
+
+    Ok(())
+}
+
 #[test]
 fn reports_all_licenses_when_dependency_has_different_license_and_text() -> Result<()> {
     let package_b = Package::builder()
@@ -444,3 +687,2519 @@ fn fails_when_dependency_has_non_accepted_license_field() -> Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn accepts_licenses_of_crates_with_exceptions() -> Result<()> {
+    let mut package_builder = Package::builder();
+
+    let package_b = package_builder
+        .license(Some("GPL-3.0-only"))
+        .name("package-b")
+        .build()?;
+
+    let package_a = package_builder
+        .license(Some("MIT"))
+        .name("package-a")
+        .dependency(&package_b)
+        .file(
+            "about.toml",
+            r#"
+accepted = ["MIT"]
+exceptions = [{ name = "package-b", reason = "internal use only, not distributed" }]
+"#,
+        )
+        .build()?;
+
+    let stdout = CargoAbout::new(&package_a)?
+        .generate()
+        .arg("--format")
+        .arg("json")
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    let output: serde_json::Value = serde_json::from_slice(&stdout)?;
+
+    assert_eq!(output["exceptions"].as_array().map(Vec::len), Some(1));
+    assert_eq!(output["exceptions"][0]["package"]["name"], "package-b");
+    assert_eq!(output["exceptions"][0]["license"], "GPL-3.0-only");
+    assert_eq!(
+        output["exceptions"][0]["reason"],
+        "internal use only, not distributed"
+    );
+
+    // Every exception must say why it was granted
+    let package_a = package_builder
+        .file(
+            "about.toml",
+            r#"
+accepted = ["MIT"]
+exceptions = [{ name = "package-b", reason = " " }]
+"#,
+        )
+        .build()?;
+
+    CargoAbout::new(&package_a)?
+        .generate()
+        .arg("--format")
+        .arg("json")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "the reason for an exception can't be empty",
+        ));
+
+    Ok(())
+}
+
+#[test]
+fn warns_about_exceptions_that_are_not_needed() -> Result<()> {
+    let package_b = Package::builder()
+        .license(Some("MIT"))
+        .name("package-b")
+        .build()?;
+
+    let package_a = Package::builder()
+        .license(Some("MIT"))
+        .name("package-a")
+        .dependency(&package_b)
+        .file(
+            "about.toml",
+            r#"
+accepted = ["MIT"]
+exceptions = [
+    { name = "package-b", reason = "already accepted" },
+    { name = "package-z", reason = "not in the graph" },
+]
+"#,
+        )
+        .build()?;
+
+    let output = CargoAbout::new(&package_a)?
+        .generate()
+        .arg("--format")
+        .arg("json")
+        .assert()
+        .success()
+        .stderr(predicate::str::contains(
+            "the following crates in `exceptions` don't need an exception",
+        ))
+        .stderr(predicate::str::contains("'package-b', 'package-z'"))
+        .get_output()
+        .stdout
+        .clone();
+    let output: serde_json::Value = serde_json::from_slice(&output)?;
+
+    assert_eq!(output["exceptions"].as_array().map(Vec::len), Some(0));
+
+    Ok(())
+}
+
+#[test]
+fn warns_about_unneeded_exceptions_once_across_targets() -> Result<()> {
+    let package_win = Package::builder()
+        .name("package-win")
+        .license(Some("Apache-2.0"))
+        .build()?;
+
+    let package = Package::builder()
+        .file(
+            "Cargo.toml",
+            &format!(
+                r#"[package]
+name = "package"
+version = "0.0.0"
+license = "MIT"
+
+[target.'cfg(windows)'.dependencies]
+package-win = {{ version = "0.0.0", path = {:?} }}
+"#,
+                package_win.dir.to_str().unwrap()
+            ),
+        )
+        .file(
+            "about.toml",
+            r#"
+accepted = ["MIT"]
+exceptions = [
+    { name = "package-win", reason = "only shipped on windows" },
+    { name = "package-z", reason = "not in the graph" },
+]
+"#,
+        )
+        .build()?;
+
+    let output = CargoAbout::new(&package)?
+        .generate()
+        .arg("--split-by-target")
+        .arg("--target")
+        .arg("x86_64-unknown-linux-gnu")
+        .arg("--target")
+        .arg("x86_64-pc-windows-msvc")
+        .arg("--format")
+        .arg("json")
+        .arg("-o")
+        .arg("about.json")
+        .assert()
+        .success()
+        .get_output()
+        .stderr
+        .clone();
+    let stderr = String::from_utf8(output)?;
+
+    // package-win is only excepted on windows, which is still a use
+    assert_eq!(
+        stderr
+            .matches("the following crates in `exceptions` don't need an exception")
+            .count(),
+        1,
+        "{stderr}"
+    );
+    assert!(stderr.contains("'package-z'"), "{stderr}");
+    assert!(!stderr.contains("'package-win'"), "{stderr}");
+
+    Ok(())
+}
+
+#[test]
+fn reports_dependency_path_of_crates() -> Result<()> {
+    let package_c = Package::builder()
+        .license(Some("Apache-2.0"))
+        .name("package-c")
+        .build()?;
+
+    let package_b = Package::builder()
+        .license(Some("MIT"))
+        .name("package-b")
+        .dependency(&package_c)
+        .build()?;
+
+    let package_a = Package::builder()
+        .license(Some("MIT"))
+        .name("package-a")
+        .accepted(&["MIT"])
+        .dependency(&package_b)
+        .build()?;
+
+    CargoAbout::new(&package_a)?
+        .generate()
+        .template(package_a.template()?)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "dependency path: package-a 0.0.0 -> package-b 0.0.0 -> package-c 0.0.0",
+        ));
+
+    let stdout = CargoAbout::new(&package_a)?
+        .generate()
+        .arg("--continue-on-error")
+        .arg("--format")
+        .arg("json")
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    let output: serde_json::Value = serde_json::from_slice(&stdout)?;
+
+    let dependents = |name: &str| {
+        output["crates"]
+            .as_array()
+            .and_then(|crates| crates.iter().find(|pl| pl["package"]["name"] == name))
+            .map(|pl| pl["dependents"].clone())
+    };
+    assert_eq!(dependents("package-a"), Some(serde_json::json!([])));
+    assert_eq!(
+        dependents("package-c"),
+        Some(serde_json::json!(["package-a 0.0.0", "package-b 0.0.0"]))
+    );
+
+    Ok(())
+}
+
+#[test]
+fn renders_diagnostics_with_ascii_characters_when_color_ascii() -> Result<()> {
+    let mut package_builder = Package::builder();
+
+    let package_b = package_builder
+        .license(Some("Apache-2.0"))
+        .name("package-b")
+        .build()?;
+
+    let package_a = package_builder
+        .license(Some("MIT"))
+        .name("package-a")
+        .accepted(&["MIT"])
+        .dependency(&package_b)
+        .build()?;
+
+    CargoAbout::new(&package_a)?
+        .arg("--color")
+        .arg("ascii")
+        .generate()
+        .template(package_a.template()?)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("-->"))
+        .stderr(predicate::str::contains("┌─").not())
+        .stderr(predicate::str::contains("\x1B[").not());
+
+    Ok(())
+}
+
+#[test]
+fn includes_non_accepted_license_when_continuing_on_error() -> Result<()> {
+    let mut package_builder = Package::builder();
+
+    let package_b = package_builder
+        .license(Some("Apache-2.0"))
+        .name("package-b")
+        .build()?;
+
+    let package_a = package_builder
+        .license(Some("MIT"))
+        .name("package-a")
+        .accepted(&["MIT"])
+        .dependency(&package_b)
+        .build()?;
+
+    let output = CargoAbout::new(&package_a)?
+        .generate()
+        .arg("--format")
+        .arg("json")
+        .arg("--continue-on-error")
+        .assert()
+        .success()
+        .stderr(predicates::str::contains(
+            "warning: failed to satisfy license requirements",
+        ))
+        .get_output()
+        .stdout
+        .clone();
+
+    let output: serde_json::Value = serde_json::from_slice(&output)?;
+
+    for krate in output["crates"].as_array().unwrap() {
+        let accepted = krate["package"]["name"] == "package-a";
+        assert_eq!(krate["accepted"], accepted);
+    }
+
+    for license in output["licenses"].as_array().unwrap() {
+        let accepted = license["id"] == "MIT";
+        assert_eq!(license["accepted"], accepted);
+    }
+
+    assert_eq!(output["licenses"].as_array().unwrap().len(), 2);
+
+    Ok(())
+}
+
+#[test]
+fn reports_license_ref_text_from_config() -> Result<()> {
+    let package = Package::builder()
+        .license(Some("MIT AND LicenseRef-Proprietary"))
+        .file(
+            "about.toml",
+            r#"
+accepted = ["MIT", "LicenseRef-Proprietary"]
+
+[license-refs]
+LicenseRef-Proprietary = "licenses/proprietary.txt"
+"#,
+        )
+        .file(
+            "licenses/proprietary.txt",
+            "All rights reserved by Big Birdz.",
+        )
+        .build()?;
+
+    let output = CargoAbout::new(&package)?
+        .generate()
+        .arg("--format")
+        .arg("json")
+        .assert()
+        .success()
+        .stderr(predicates::str::contains("has no license file").not())
+        .get_output()
+        .stdout
+        .clone();
+
+    let output: serde_json::Value = serde_json::from_slice(&output)?;
+    let license_ref = output["licenses"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|lic| lic["id"] == "LicenseRef-Proprietary")
+        .expect("LicenseRef text was not included");
+    assert_eq!(license_ref["text"], "All rights reserved by Big Birdz.");
+
+    Ok(())
+}
+
+#[test]
+fn uses_configured_license_text_instead_of_canonical_text() -> Result<()> {
+    let package = Package::builder()
+        .license(Some("MIT"))
+        .file(
+            "about.toml",
+            r#"
+accepted = ["MIT"]
+
+[license-texts]
+MIT = "licenses/MIT.txt"
+"#,
+        )
+        .file(
+            "licenses/MIT.txt",
+            "Our preferred wording of the MIT license.",
+        )
+        .build()?;
+
+    CargoAbout::new(&package)?
+        .generate()
+        .template(package.template()?)
+        .assert()
+        .success()
+        .stdout(licenses_count(1))
+        .stdout(predicates::str::contains(
+            "Our preferred wording of the MIT license.",
+        ))
+        .stdout(contains_default_mit_license_content().not());
+
+    Ok(())
+}
+
+#[test]
+fn license_override_replaces_crate_license_text() -> Result<()> {
+    let package = Package::builder()
+        .license(Some("MIT"))
+        .file(
+            "about.toml",
+            r#"
+accepted = ["MIT"]
+
+[license-overrides.MIT]
+file = "legal/MIT.txt"
+"#,
+        )
+        .file("LICENSE", &mit_license_text("2024", "Big Birdz"))
+        .file("legal/MIT.txt", "The legally approved MIT license.")
+        .build()?;
+
+    CargoAbout::new(&package)?
+        .generate()
+        .template(package.template()?)
+        .assert()
+        .success()
+        .stdout(licenses_count(1))
+        .stdout(predicates::str::contains(
+            "The legally approved MIT license.",
+        ))
+        .stdout(predicates::str::contains("Big Birdz").not());
+
+    Ok(())
+}
+
+#[test]
+fn fails_with_dedicated_diagnostic_when_license_denied() -> Result<()> {
+    let package = Package::builder()
+        .license(Some("GPL-3.0-only"))
+        .file(
+            "about.toml",
+            r#"
+accepted = ["MIT", "GPL-3.0"]
+denied = ["GPL-3.0"]
+"#,
+        )
+        .build()?;
+
+    CargoAbout::new(&package)?
+        .generate()
+        .template(package.template()?)
+        .assert()
+        .failure()
+        .stderr(predicates::str::contains(
+            "license 'GPL-3.0-only' is explicitly denied",
+        ))
+        .stderr(predicates::str::contains("failed to satisfy license requirements").not());
+
+    let package = Package::builder()
+        .license(Some("GPL-3.0-only OR MIT"))
+        .file(
+            "about.toml",
+            r#"
+accepted = ["GPL-3.0", "MIT"]
+denied = ["GPL-3.0"]
+"#,
+        )
+        .build()?;
+
+    CargoAbout::new(&package)?
+        .generate()
+        .template(package.template()?)
+        .assert()
+        .success()
+        .stdout(overview_count(1));
+
+    Ok(())
+}
+
+#[cfg(unix)]
+#[test]
+fn suggests_licensee_when_only_exception_is_not_accepted() -> Result<()> {
+    let package = Package::builder()
+        .license(Some("Apache-2.0 WITH LLVM-exception"))
+        .accepted(&["Apache-2.0"])
+        .build()?;
+
+    CargoAbout::new(&package)?
+        .generate()
+        .template(package.template()?)
+        .assert()
+        .failure()
+        .stderr(predicates::str::contains(
+            "license 'Apache-2.0' is accepted, but not with the 'LLVM-exception' exception",
+        ))
+        .stderr(predicates::str::contains(
+            "add \"Apache-2.0 WITH LLVM-exception\" to `accepted`",
+        ))
+        .stderr(predicates::str::contains("failed to satisfy license requirements").not());
+
+    Ok(())
+}
+
+#[test]
+fn applies_license_from_external_resolver() -> Result<()> {
+    let package = Package::builder()
+        .file(
+            "about.toml",
+            r#"
+accepted = ["MIT"]
+
+[resolver]
+command = "sh"
+args = ["-c", "cat > /dev/null; echo '[{\"name\": \"package\", \"version\": \"0.0.0\", \"license\": \"MIT\"}]'"]
+"#,
+        )
+        .build()?;
+
+    CargoAbout::new(&package)?
+        .generate()
+        .template(package.template()?)
+        .assert()
+        .success()
+        .stdout(overview_count(1))
+        .stdout(licenses_count(1))
+        .stdout(contains_default_mit_license_content());
+
+    Ok(())
+}
+
+#[test]
+fn renders_multiple_templates_to_multiple_output_files() -> Result<()> {
+    let package = Package::builder()
+        .license(Some("MIT"))
+        .accepted(&["MIT"])
+        .file(
+            "templates/first.hbs",
+            "first:{{#each licenses}}{{id}}{{/each}}",
+        )
+        .file(
+            "templates/second.hbs",
+            "second:{{#each overview}}{{count}}{{/each}}",
+        )
+        .build()?;
+
+    CargoAbout::new(&package)?
+        .generate()
+        .arg("-o")
+        .arg("first=first.txt")
+        .arg("-o")
+        .arg("second=second.txt")
+        .template("templates")
+        .assert()
+        .success()
+        .stdout("");
+
+    assert_eq!(
+        std::fs::read_to_string(package.dir.join("first.txt"))?,
+        "first:MIT"
+    );
+    assert_eq!(
+        std::fs::read_to_string(package.dir.join("second.txt"))?,
+        "second:1"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn ignores_configured_outputs_for_non_template_formats() -> Result<()> {
+    let package = Package::builder()
+        .license(Some("MIT"))
+        .file(
+            "about.toml",
+            r#"
+accepted = ["MIT"]
+
+[outputs]
+first = "first.txt"
+"#,
+        )
+        .file(
+            "templates/first.hbs",
+            "first:{{#each licenses}}{{id}}{{/each}}",
+        )
+        .build()?;
+
+    let stdout = CargoAbout::new(&package)?
+        .generate()
+        .arg("--format")
+        .arg("json")
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    let output: serde_json::Value = serde_json::from_slice(&stdout)?;
+    assert_eq!(output["crates"].as_array().map(Vec::len), Some(1));
+    assert!(!package.dir.join("first.txt").exists());
+
+    // The configured outputs are still used with a templates directory
+    CargoAbout::new(&package)?
+        .generate()
+        .template("templates")
+        .assert()
+        .success();
+
+    assert_eq!(
+        std::fs::read_to_string(package.dir.join("first.txt"))?,
+        "first:MIT"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn renders_partials_and_copies_static_files_next_to_output() -> Result<()> {
+    let package = Package::builder()
+        .license(Some("MIT"))
+        .accepted(&["MIT"])
+        .file(
+            "templates/about.hbs",
+            "{{> header}}{{#each licenses}}{{id}}{{/each}}",
+        )
+        .file(
+            "templates/partials/header.hbs",
+            "<link rel=\"stylesheet\" href=\"style.css\">",
+        )
+        .file("templates/static/style.css", "body { color: black; }")
+        .file("templates/static/fonts/font.txt", "font")
+        .build()?;
+
+    CargoAbout::new(&package)?
+        .generate()
+        .arg("-n")
+        .arg("about")
+        .arg("-o")
+        .arg("about.html")
+        .template("templates")
+        .assert()
+        .success()
+        .stdout("");
+
+    assert_eq!(
+        std::fs::read_to_string(package.dir.join("about.html"))?,
+        "<link rel=\"stylesheet\" href=\"style.css\">MIT"
+    );
+    assert_eq!(
+        std::fs::read_to_string(package.dir.join("style.css"))?,
+        "body { color: black; }"
+    );
+    assert_eq!(
+        std::fs::read_to_string(package.dir.join("fonts/font.txt"))?,
+        "font"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn writes_output_file_with_requested_encoding() -> Result<()> {
+    let package = Package::builder()
+        .license(Some("MIT"))
+        .accepted(&["MIT"])
+        .file("template.hbs", "{{#each licenses}}{{id}}{{/each}}")
+        .build()?;
+
+    CargoAbout::new(&package)?
+        .generate()
+        .arg("--encoding")
+        .arg("utf8-bom")
+        .arg("-o")
+        .arg("utf8-bom.txt")
+        .template("template.hbs")
+        .assert()
+        .success();
+
+    assert_eq!(
+        std::fs::read(package.dir.join("utf8-bom.txt"))?,
+        b"\xef\xbb\xbfMIT"
+    );
+
+    CargoAbout::new(&package)?
+        .generate()
+        .arg("--encoding")
+        .arg("utf16le")
+        .arg("-o")
+        .arg("utf16le.txt")
+        .template("template.hbs")
+        .assert()
+        .success();
+
+    assert_eq!(
+        std::fs::read(package.dir.join("utf16le.txt"))?,
+        b"\xff\xfeM\0I\0T\0"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn applies_workaround_loaded_from_workarounds_path() -> Result<()> {
+    let package = Package::builder()
+        .file("LICENSE", "Custom license text")
+        .file(
+            "about.toml",
+            r#"
+accepted = ["MIT"]
+workarounds-path = ["about-workarounds"]
+"#,
+        )
+        .file(
+            "about-workarounds/package.toml",
+            r#"
+name = "package"
+version = "0.0"
+
+[clarify]
+license = "MIT"
+
+[[clarify.files]]
+path = "LICENSE"
+checksum = "41c8b86392181b7a6ff6510880a63c8463a91ab14a60a70d698ea7412f684a4b"
+"#,
+        )
+        .build()?;
+
+    CargoAbout::new(&package)?
+        .generate()
+        .template(package.template()?)
+        .assert()
+        .success()
+        .stdout(overview_count(1))
+        .stdout(licenses_count(1))
+        .stdout(predicate::str::contains("Custom license text"));
+
+    Ok(())
+}
+
+#[test]
+fn resolves_crates_and_git_files_from_registry_dir() -> Result<()> {
+    const SHA1: &str = "0123456789abcdef0123456789abcdef01234567";
+
+    let package = Package::builder()
+        .file(
+            "Cargo.toml",
+            r#"
+[package]
+name = "package"
+version = "0.0.0"
+license = "MIT"
+
+[dependencies]
+fixture = "0.1.0"
+"#,
+        )
+        .file(
+            "about.toml",
+            r#"
+accepted = ["MIT"]
+
+[fixture.clarify]
+license = "MIT"
+
+[[fixture.clarify.git]]
+path = "LICENSE"
+checksum = "41c8b86392181b7a6ff6510880a63c8463a91ab14a60a70d698ea7412f684a4b"
+"#,
+        )
+        .file(
+            "registry/fixture-0.1.0/Cargo.toml",
+            r#"
+[package]
+name = "fixture"
+version = "0.1.0"
+repository = "https://github.com/org/fixture"
+"#,
+        )
+        .file("registry/fixture-0.1.0/src/lib.rs", "")
+        .file(
+            "registry/fixture-0.1.0/.cargo-checksum.json",
+            r#"{"files":{},"package":null}"#,
+        )
+        .file(
+            "registry/fixture-0.1.0/.cargo_vcs_info.json",
+            &format!(r#"{{"git":{{"sha1":"{SHA1}"}},"path_in_vcs":""}}"#),
+        )
+        .file(
+            &format!("registry/.repos/github.com/org/fixture/{SHA1}/LICENSE"),
+            "Custom license text",
+        )
+        .build()?;
+
+    CargoAbout::new(&package)?
+        .generate()
+        .arg("--registry-dir")
+        .arg(package.dir.join("registry").to_str().unwrap())
+        .template(package.template()?)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Custom license text"));
+
+    Ok(())
+}
+
+#[test]
+fn applies_clarification_embedded_in_dependency_manifest() -> Result<()> {
+    let package_b = Package::builder()
+        .name("package-b")
+        .file(
+            "Cargo.toml",
+            r#"
+[package]
+name = "package-b"
+version = "0.0.0"
+
+[package.metadata.about]
+license = "MIT"
+
+[[package.metadata.about.files]]
+path = "LICENSE"
+checksum = "41c8b86392181b7a6ff6510880a63c8463a91ab14a60a70d698ea7412f684a4b"
+"#,
+        )
+        .file("LICENSE", "Custom license text")
+        .build()?;
+
+    let package_a = Package::builder()
+        .name("package-a")
+        .license(Some("MIT"))
+        .dependency(&package_b)
+        .accepted(&["MIT"])
+        .build()?;
+
+    CargoAbout::new(&package_a)?
+        .generate()
+        .template(package_a.template()?)
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("package-b").not())
+        .stdout(licenses_count(2))
+        .stdout(predicate::str::contains("Custom license text"));
+
+    Ok(())
+}
+
+#[test]
+fn loads_config_from_workspace_metadata_when_no_about_toml() -> Result<()> {
+    let manifest = r#"
+[package]
+name = "package"
+version = "0.0.0"
+license = "MIT"
+
+[workspace]
+
+[workspace.metadata.about]
+accepted = ["MIT"]
+"#;
+
+    let package = Package::builder()
+        .no_about_config()
+        .file("Cargo.toml", manifest)
+        .build()?;
+
+    CargoAbout::new(&package)?
+        .generate()
+        .template(package.template()?)
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("no 'about.toml' found").not())
+        .stdout(licenses_count(1));
+
+    // about.toml takes precedence over the workspace metadata
+    let package = Package::builder()
+        .accepted(&["Apache-2.0"])
+        .file("Cargo.toml", manifest)
+        .build()?;
+
+    CargoAbout::new(&package)?
+        .generate()
+        .template(package.template()?)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "failed to satisfy license requirements",
+        ));
+
+    Ok(())
+}
+
+#[test]
+fn loads_yaml_and_json_config() -> Result<()> {
+    for (name, config) in [
+        ("about.yaml", "accepted:\n  - MIT\n"),
+        ("about.yml", "accepted: [MIT]\n"),
+        ("about.json", r#"{ "accepted": ["MIT"] }"#),
+    ] {
+        let package = Package::builder()
+            .license(Some("MIT"))
+            .no_about_config()
+            .file(name, config)
+            .build()?;
+
+        CargoAbout::new(&package)?
+            .generate()
+            .template(package.template()?)
+            .assert()
+            .success()
+            .stdout(licenses_count(1));
+    }
+
+    // Unknown fields are rejected just as in about.toml
+    let package = Package::builder()
+        .license(Some("MIT"))
+        .no_about_config()
+        .file("about.json", r#"{ "accepted": ["MIT"], "acepted": [] }"#)
+        .build()?;
+
+    CargoAbout::new(&package)?
+        .generate()
+        .template(package.template()?)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "unable to deserialize config from",
+        ));
+
+    Ok(())
+}
+
+#[test]
+fn uses_license_files_from_overrides_dir_when_offline() -> Result<()> {
+    // The overrides are outside of the crate, as they would otherwise be
+    // found when scanning the crate itself
+    let overrides = assert_fs::TempDir::new()?;
+    std::fs::create_dir(overrides.path().join("package-0.0.0"))?;
+    std::fs::write(
+        overrides.path().join("package-0.0.0/LICENSE"),
+        mit_license_text("2024", "Jane Doe"),
+    )?;
+
+    let package = Package::builder()
+        .license(Some("MIT"))
+        .file(
+            "about.toml",
+            &format!(
+                "accepted = [\"MIT\"]\nlicense-overrides-dir = {:?}\n",
+                overrides.path().to_str().unwrap()
+            ),
+        )
+        .build()?;
+
+    CargoAbout::new(&package)?
+        .generate()
+        .arg("--offline")
+        .template(package.template()?)
+        .assert()
+        .success()
+        .stdout(licenses_count(1))
+        .stdout(contains_mit_license_content("2024", "Jane Doe"));
+
+    // The overrides are only used when running offline
+    CargoAbout::new(&package)?
+        .generate()
+        .template(package.template()?)
+        .assert()
+        .success()
+        .stdout(licenses_count(1))
+        .stdout(contains_default_mit_license_content());
+
+    Ok(())
+}
+
+#[test]
+fn writes_depinfo_listing_every_consulted_file() -> Result<()> {
+    let package_b = Package::builder()
+        .name("package-b")
+        .license(Some("MIT"))
+        .file("LICENSE", &mit_license_text("2024", "Jane Doe"))
+        .build()?;
+
+    let package_a = Package::builder()
+        .name("package-a")
+        .license(Some("MIT"))
+        .dependency(&package_b)
+        .accepted(&["MIT"])
+        .build()?;
+
+    let depinfo = package_a.dir.join("about.d");
+
+    CargoAbout::new(&package_a)?
+        .generate()
+        .arg("-o")
+        .arg("about.html")
+        .arg("--emit-depinfo")
+        .arg(depinfo.to_str().unwrap())
+        // License files are still listed when their paths in the output
+        // don't exist on disk
+        .arg("--reproducible")
+        .template(package_a.template()?)
+        .assert()
+        .success();
+
+    let depinfo = std::fs::read_to_string(depinfo)?;
+    let mut lines = depinfo
+        .lines()
+        .map(|line| line.trim().trim_end_matches(" \\"));
+
+    // Paths are written as they were specified
+    assert_eq!(lines.next(), Some("about.html:"));
+    let deps: Vec<_> = lines.collect();
+
+    for dep in [
+        package_a.dir.join("Cargo.toml"),
+        package_a.dir.join("Cargo.lock"),
+        package_a.dir.join("about.toml"),
+        "about.hbs".into(),
+        package_b.dir.join("Cargo.toml"),
+        package_b.dir.join("LICENSE"),
+    ] {
+        assert!(
+            deps.contains(&dep.to_str().unwrap()),
+            "missing {}",
+            dep.display()
+        );
+    }
+
+    Ok(())
+}
+
+#[test]
+fn ignores_private_crates_matching_names_or_path_dependencies() -> Result<()> {
+    let package_b = Package::builder()
+        .name("package-b")
+        .license(Some("GPL-3.0-only"))
+        .build()?;
+
+    let package = |private: &str| {
+        Package::builder()
+            .name("package-a")
+            .license(Some("MIT"))
+            .dependency(&package_b)
+            .file(
+                "about.toml",
+                &format!("accepted = [\"MIT\"]\nprivate = {{ ignore = true, {private} }}\n"),
+            )
+            .build()
+    };
+
+    let package_a = package("names = [\"*-b\"]")?;
+    CargoAbout::new(&package_a)?
+        .generate()
+        .template(package_a.template()?)
+        .assert()
+        .success()
+        .stdout(overview_count(1));
+
+    let package_a = package("path-dependencies = true")?;
+    CargoAbout::new(&package_a)?
+        .generate()
+        .template(package_a.template()?)
+        .assert()
+        .success()
+        .stdout(overview_count(0));
+
+    Ok(())
+}
+
+#[test]
+fn reports_ignored_crates_with_reason() -> Result<()> {
+    let package_b = Package::builder()
+        .name("package-b")
+        .license(Some("GPL-3.0-only"))
+        .build()?;
+
+    let package_a = Package::builder()
+        .name("package-a")
+        .license(Some("MIT"))
+        .dependency(&package_b)
+        .file(
+            "about.toml",
+            r#"
+accepted = ["MIT"]
+report-ignored = true
+private = { ignore = true, names = ["package-b"] }
+"#,
+        )
+        .build()?;
+
+    let output = CargoAbout::new(&package_a)?
+        .generate()
+        .arg("--format")
+        .arg("json")
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+
+    let output: serde_json::Value = serde_json::from_slice(&output)?;
+    let ignored = output["ignored"].as_array().unwrap();
+    assert_eq!(ignored.len(), 1);
+    assert_eq!(ignored[0]["package"]["name"], "package-b");
+    assert_eq!(ignored[0]["reason"], "private-name");
+
+    Ok(())
+}
+
+#[test]
+fn applies_crate_config_matching_name_glob_and_version() -> Result<()> {
+    let config = |version: &str| {
+        format!(
+            r#"
+accepted = []
+
+["pack*"]
+version = "{version}"
+accepted = ["MIT"]
+"#
+        )
+    };
+
+    let package = Package::builder()
+        .license(Some("MIT"))
+        .file("about.toml", &config("0.0"))
+        .build()?;
+
+    CargoAbout::new(&package)?
+        .generate()
+        .template(package.template()?)
+        .assert()
+        .success()
+        .stdout(overview_count(1));
+
+    let package = Package::builder()
+        .license(Some("MIT"))
+        .file("about.toml", &config("^1.0"))
+        .build()?;
+
+    CargoAbout::new(&package)?
+        .generate()
+        .template(package.template()?)
+        .assert()
+        .failure()
+        .stderr(predicates::str::contains(
+            "failed to satisfy license requirements",
+        ));
+
+    Ok(())
+}
+
+#[test]
+fn uses_declared_license_when_scan_times_out() -> Result<()> {
+    let package = Package::builder()
+        .license(Some("MIT"))
+        .file(
+            "about.toml",
+            r#"
+accepted = ["MIT"]
+scan-timeout-secs = 0
+"#,
+        )
+        .build()?;
+
+    CargoAbout::new(&package)?
+        .generate()
+        .template(package.template()?)
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("scan timed out after 0s"))
+        .stdout(overview_count(1))
+        .stdout(contains_default_mit_license_content());
+
+    Ok(())
+}
+
+#[test]
+fn only_reports_licenses_for_crates_in_allowlist() -> Result<()> {
+    let package_b = Package::builder()
+        .name("package-b")
+        .license(Some("Apache-2.0"))
+        .build()?;
+
+    let package_a = Package::builder()
+        .name("package-a")
+        .license(Some("MIT"))
+        .dependency(&package_b)
+        .accepted(&["MIT", "Apache-2.0"])
+        .build()?;
+
+    CargoAbout::new(&package_a)?
+        .generate()
+        .arg("--only")
+        .arg("*-b")
+        .template(package_a.template()?)
+        .assert()
+        .success()
+        .stdout(overview_count(1))
+        .stdout(licenses_count(1))
+        .stdout(contains_default_apache2_license_content());
+
+    Ok(())
+}
+
+#[test]
+fn restricts_graph_to_selected_workspace_members() -> Result<()> {
+    let package = Package::builder()
+        .file(
+            "Cargo.toml",
+            r#"
+[workspace]
+members = ["member-a", "member-b"]
+resolver = "2"
+"#,
+        )
+        .file(
+            "member-a/Cargo.toml",
+            r#"
+[package]
+name = "member-a"
+version = "0.0.0"
+license = "MIT"
+"#,
+        )
+        .file("member-a/src/lib.rs", "")
+        .file(
+            "member-b/Cargo.toml",
+            r#"
+[package]
+name = "member-b"
+version = "0.0.0"
+license = "Apache-2.0"
+"#,
+        )
+        .file("member-b/src/lib.rs", "")
+        .accepted(&["MIT", "Apache-2.0"])
+        .build()?;
+
+    CargoAbout::new(&package)?
+        .generate()
+        .arg("-p")
+        .arg("member-b")
+        .template(package.template()?)
+        .assert()
+        .success()
+        .stdout(overview_count(1))
+        .stdout(licenses_count(1))
+        .stdout(contains_default_apache2_license_content());
+
+    CargoAbout::new(&package)?
+        .generate()
+        .arg("--package")
+        .arg("member-c")
+        .template(package.template()?)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "package 'member-c' is not a member of the workspace",
+        ));
+
+    Ok(())
+}
+
+#[test]
+fn dry_run_reports_output_without_writing_it() -> Result<()> {
+    let package = Package::builder()
+        .license(Some("MIT"))
+        .accepted(&["MIT"])
+        .build()?;
+
+    CargoAbout::new(&package)?
+        .generate()
+        .arg("--dry-run")
+        .arg("-o")
+        .arg("about.html")
+        .template(package.template()?)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("1 crates, 1 licenses"))
+        .stdout(predicate::str::contains("  1 MIT"))
+        .stdout(predicate::str::is_match(
+            r"would write \d+ bytes to 'about.html'",
+        )?);
+
+    assert!(!package.dir.join("about.html").exists());
+
+    Ok(())
+}
+
+#[test]
+fn writes_an_output_per_target_when_split_by_target() -> Result<()> {
+    let package = Package::builder()
+        .license(Some("MIT"))
+        .accepted(&["MIT"])
+        .build()?;
+
+    CargoAbout::new(&package)?
+        .generate()
+        .arg("--split-by-target")
+        .arg("--target")
+        .arg("x86_64-unknown-linux-gnu")
+        .arg("--target")
+        .arg("x86_64-pc-windows-msvc")
+        .arg("--format")
+        .arg("json")
+        .arg("-o")
+        .arg("about.json")
+        .assert()
+        .success();
+
+    for target in ["x86_64-unknown-linux-gnu", "x86_64-pc-windows-msvc"] {
+        let output = std::fs::read_to_string(package.dir.join(format!("about-{target}.json")))?;
+        let output: serde_json::Value = serde_json::from_str(&output)?;
+        assert_eq!(output["crates"].as_array().map(Vec::len), Some(1));
+    }
+    assert!(!package.dir.join("about.json").exists());
+
+    CargoAbout::new(&package)?
+        .generate()
+        .arg("--split-by-target")
+        .arg("--format")
+        .arg("json")
+        .arg("-o")
+        .arg("about.json")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "at least one target must be specified",
+        ));
+
+    Ok(())
+}
+
+#[test]
+fn reports_notice_files_with_license() -> Result<()> {
+    let package = Package::builder()
+        .license(Some("Apache-2.0"))
+        .accepted(&["Apache-2.0"])
+        .file("NOTICE", "Copyright 2024 The Package Authors")
+        .file(
+            "notices.hbs",
+            "{{#each licenses}}{{id}}:{{#each notices}}[{{text}}]{{/each}}{{/each}}",
+        )
+        .build()?;
+
+    CargoAbout::new(&package)?
+        .generate()
+        .template("notices.hbs")
+        .assert()
+        .success()
+        .stdout("Apache-2.0:[Copyright 2024 The Package Authors]\n");
+
+    Ok(())
+}
+
+#[test]
+fn restricts_graph_to_dependencies_of_selected_binary() -> Result<()> {
+    let package = Package::builder()
+        .file(
+            "Cargo.toml",
+            r#"
+[workspace]
+members = ["member-a", "member-b"]
+resolver = "2"
+"#,
+        )
+        .file(
+            "member-a/Cargo.toml",
+            r#"
+[package]
+name = "member-a"
+version = "0.0.0"
+license = "MIT"
+
+[[bin]]
+name = "tool-a"
+path = "src/main.rs"
+"#,
+        )
+        .file("member-a/src/main.rs", "fn main() {}")
+        .file(
+            "member-b/Cargo.toml",
+            r#"
+[package]
+name = "member-b"
+version = "0.0.0"
+license = "Apache-2.0"
+"#,
+        )
+        .file("member-b/src/main.rs", "fn main() {}")
+        .accepted(&["MIT", "Apache-2.0"])
+        .build()?;
+
+    CargoAbout::new(&package)?
+        .generate()
+        .arg("--bin")
+        .arg("tool-a")
+        .template(package.template()?)
+        .assert()
+        .success()
+        .stdout(overview_count(1))
+        .stdout(licenses_count(1))
+        .stdout(contains_default_mit_license_content());
+
+    CargoAbout::new(&package)?
+        .generate()
+        .arg("--bin")
+        .arg("tool-c")
+        .template(package.template()?)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "no binary target named 'tool-c' in the workspace",
+        ));
+
+    Ok(())
+}
+
+#[test]
+fn reports_template_render_error_location() -> Result<()> {
+    let package = Package::builder()
+        .license(Some("MIT"))
+        .accepted(&["MIT"])
+        .file(
+            "broken.hbs",
+            "Licenses:\n{{#each licenses}}{{unknown-helper id}}{{/each}}\n",
+        )
+        .build()?;
+
+    CargoAbout::new(&package)?
+        .generate()
+        .template("broken.hbs")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "failed to render template 'broken.hbs' at line 2, column 19: Helper not found unknown-helper",
+        ))
+        .stderr(predicate::str::contains(
+            "2 | {{#each licenses}}{{unknown-helper id}}{{/each}}",
+        ));
+
+    Ok(())
+}
+
+#[test]
+fn fails_before_gathering_when_template_references_unknown_field() -> Result<()> {
+    let package = Package::builder()
+        .license(Some("MIT"))
+        .accepted(&["MIT"])
+        .file(
+            "typo.hbs",
+            "{{tool.version}}\n{{#if degraded}}degraded{{/if}}\n{{#each license}}{{name}}{{/each}}\n",
+        )
+        .build()?;
+
+    CargoAbout::new(&package)?
+        .generate()
+        .template("typo.hbs")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "template 'typo.hbs' references unknown field 'license' at line 3, column 1, did you mean 'licenses'?",
+        ));
+
+    Ok(())
+}
+
+#[test]
+fn records_tool_and_data_versions() -> Result<()> {
+    let package = Package::builder()
+        .license(Some("MIT"))
+        .accepted(&["MIT"])
+        .build()?;
+
+    let output = CargoAbout::new(&package)?
+        .generate()
+        .arg("--format")
+        .arg("json")
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    let output: serde_json::Value = serde_json::from_slice(&output)?;
+
+    assert_eq!(output["tool"]["version"], env!("CARGO_PKG_VERSION"));
+    assert_eq!(
+        output["tool"]["license_store_date"],
+        cargo_about::licenses::STORE_DATE
+    );
+    assert_eq!(
+        output["tool"]["license_store_digest"],
+        cargo_about::licenses::store_digest()
+    );
+
+    Ok(())
+}
+
+#[test]
+fn exposes_policy_to_templates() -> Result<()> {
+    let package = Package::builder()
+        .license(Some("MIT"))
+        .accepted(&["MIT", "Apache-2.0"])
+        .file(
+            "policy.hbs",
+            "{{#each policy.accepted}}{{this}};{{/each}}{{#if policy.config_path}}configured{{/if}}",
+        )
+        .build()?;
+
+    CargoAbout::new(&package)?
+        .generate()
+        .template("policy.hbs")
+        .assert()
+        .success()
+        .stdout("MIT;Apache-2.0;configured\n");
+
+    Ok(())
+}
+
+#[test]
+fn exposes_generation_metadata_to_templates() -> Result<()> {
+    let package = Package::builder()
+        .license(Some("MIT"))
+        .accepted(&["MIT"])
+        .file(
+            "metadata.hbs",
+            "{{metadata.version}};{{#if metadata.config_digest}}{{metadata.config_digest}}{{/if}};{{#if metadata.generated_at}}generated{{/if}}",
+        )
+        .build()?;
+
+    let stdout = CargoAbout::new(&package)?
+        .generate()
+        .template("metadata.hbs")
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    let stdout = String::from_utf8(stdout)?;
+    let fields: Vec<_> = stdout.trim_end().split(';').collect();
+    assert_eq!(fields[0], env!("CARGO_PKG_VERSION"));
+    assert!(fields[1].starts_with("sha256:"));
+    assert_eq!(fields[2], "generated");
+
+    Ok(())
+}
+
+#[test]
+fn generates_reproducible_output() -> Result<()> {
+    let package = Package::builder()
+        .license(Some("MIT"))
+        .accepted(&["MIT"])
+        .build()?;
+
+    let generate = |epoch: Option<&str>| -> Result<String> {
+        let mut cmd = CargoAbout::new(&package)?;
+        if let Some(epoch) = epoch {
+            cmd.env("SOURCE_DATE_EPOCH", epoch);
+        }
+
+        let stdout = cmd
+            .generate()
+            .arg("--reproducible")
+            .arg("--format")
+            .arg("json")
+            .assert()
+            .success()
+            .get_output()
+            .stdout
+            .clone();
+        Ok(String::from_utf8(stdout)?)
+    };
+
+    let first = generate(None)?;
+    assert_eq!(first, generate(None)?);
+
+    let output: serde_json::Value = serde_json::from_str(&first)?;
+    assert_eq!(output["metadata"]["generated_at"], "");
+    assert_eq!(output["metadata"]["manifest_path"], "$WORKSPACE/Cargo.toml");
+    assert!(!first.contains(package.dir.path().to_str().unwrap()));
+
+    let output: serde_json::Value = serde_json::from_str(&generate(Some("0"))?)?;
+    assert_eq!(output["metadata"]["generated_at"], "1970-01-01T00:00:00Z");
+
+    Ok(())
+}
+
+#[test]
+fn renders_jinja_templates() -> Result<()> {
+    let package = Package::builder()
+        .license(Some("MIT"))
+        .accepted(&["MIT"])
+        .file(
+            "about.jinja",
+            "{% for license in licenses %}{{ loop.index }}:{{ license.id }}:{{ license.used_by | length }}{% endfor %}",
+        )
+        .build()?;
+
+    CargoAbout::new(&package)?
+        .generate()
+        .arg("--format")
+        .arg("jinja")
+        .template("about.jinja")
+        .assert()
+        .success()
+        .stdout("1:MIT:1\n");
+
+    Ok(())
+}
+
+#[test]
+fn wraps_license_texts_with_wrap_helper() -> Result<()> {
+    let package = Package::builder()
+        .license(Some("MIT"))
+        .accepted(&["MIT"])
+        .file(
+            "about.hbs",
+            "{{#each licenses}}{{{wrap text width=40}}}{{/each}}",
+        )
+        .file(
+            "about.jinja",
+            "{% for license in licenses %}{{ license.text | wrap(40) | safe }}{% endfor %}",
+        )
+        .build()?;
+
+    let handlebars = CargoAbout::new(&package)?
+        .generate()
+        .template("about.hbs")
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    let handlebars = String::from_utf8(handlebars)?;
+
+    assert!(handlebars.contains("Permission is hereby granted"));
+    assert!(handlebars.lines().all(|line| line.chars().count() <= 40));
+    assert!(handlebars.lines().any(|line| line.chars().count() > 30));
+
+    CargoAbout::new(&package)?
+        .generate()
+        .arg("--format")
+        .arg("jinja")
+        .template("about.jinja")
+        .assert()
+        .success()
+        .stdout(handlebars);
+
+    Ok(())
+}
+
+#[test]
+fn fails_before_gathering_when_jinja_template_references_unknown_field() -> Result<()> {
+    let package = Package::builder()
+        .license(Some("MIT"))
+        .accepted(&["MIT"])
+        .file(
+            "typo.jinja",
+            "{% for license in license %}{{ license.name }}{% endfor %}",
+        )
+        .build()?;
+
+    CargoAbout::new(&package)?
+        .generate()
+        .arg("--format")
+        .arg("jinja")
+        .template("typo.jinja")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "template 'typo.jinja' references unknown field 'license', did you mean 'licenses'?",
+        ));
+
+    Ok(())
+}
+
+#[test]
+fn outputs_one_json_object_per_crate_with_json_lines() -> Result<()> {
+    let package_b = Package::builder()
+        .name("package-b")
+        .license(Some("Apache-2.0"))
+        .build()?;
+
+    let package_a = Package::builder()
+        .name("package-a")
+        .license(Some("MIT"))
+        .accepted(&["MIT", "Apache-2.0"])
+        .dependency(&package_b)
+        .build()?;
+
+    let output = CargoAbout::new(&package_a)?
+        .generate()
+        .arg("--format")
+        .arg("json-lines")
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+
+    let lines = String::from_utf8(output)?
+        .lines()
+        .map(serde_json::from_str)
+        .collect::<Result<Vec<serde_json::Value>, _>>()?;
+    assert_eq!(lines.len(), 2);
+
+    for line in &lines {
+        let expected = if line["package"]["name"] == "package-b" {
+            "Apache-2.0"
+        } else {
+            "MIT"
+        };
+        assert_eq!(line["license"], expected);
+        assert_eq!(line["licenses"][0]["id"], expected);
+        assert!(line["licenses"][0]["text"].as_str().is_some());
+    }
+
+    Ok(())
+}
+
+#[test]
+fn outputs_one_row_per_crate_with_csv() -> Result<()> {
+    let package_b = Package::builder()
+        .name("package-b")
+        .license(Some("Apache-2.0"))
+        .build()?;
+
+    let package_a = Package::builder()
+        .name("package-a")
+        .license(Some("MIT"))
+        .accepted(&["MIT", "Apache-2.0"])
+        .dependency(&package_b)
+        .build()?;
+
+    let output = CargoAbout::new(&package_a)?
+        .generate()
+        .arg("--format")
+        .arg("csv")
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    let output = String::from_utf8(output)?;
+
+    let rows: Vec<Vec<_>> = output
+        .lines()
+        .map(|line| line.split(',').collect())
+        .collect();
+    assert_eq!(rows.len(), 3);
+    assert_eq!(
+        rows[0],
+        [
+            "name",
+            "version",
+            "declared_license",
+            "resolved_license",
+            "source_path",
+            "repository",
+            "accepted"
+        ]
+    );
+
+    for row in &rows[1..] {
+        let expected = if row[0] == "package-b" {
+            "Apache-2.0"
+        } else {
+            "MIT"
+        };
+        assert_eq!(row[1], "0.0.0");
+        assert_eq!(row[2], expected);
+        assert_eq!(row[3], expected);
+        assert_eq!(row[6], "yes");
+    }
+
+    CargoAbout::new(&package_a)?
+        .generate()
+        .arg("--format")
+        .arg("tsv")
+        .assert()
+        .success()
+        .stdout(predicate::str::starts_with(
+            "name\tversion\tdeclared_license\tresolved_license",
+        ));
+
+    Ok(())
+}
+
+#[test]
+fn outputs_empty_declared_license_with_csv() -> Result<()> {
+    let package = Package::builder()
+        .name("package-a")
+        .license(None)
+        .file("LICENSE", include_str!("../../LICENSE-MIT"))
+        .accepted(&["MIT"])
+        .build()?;
+
+    let output = CargoAbout::new(&package)?
+        .generate()
+        .arg("--format")
+        .arg("csv")
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    let output = String::from_utf8(output)?;
+
+    let row: Vec<_> = output
+        .lines()
+        .nth(1)
+        .unwrap_or_default()
+        .split(',')
+        .collect();
+    assert_eq!(row[0], "package-a");
+    assert_eq!(row[2], "");
+    assert_eq!(row[3], "MIT");
+
+    Ok(())
+}
+
+#[test]
+#[cfg(feature = "sqlite")]
+fn appends_runs_to_sqlite_database() -> Result<()> {
+    let package_b = Package::builder()
+        .name("package-b")
+        .license(Some("Apache-2.0"))
+        .build()?;
+
+    let package_a = Package::builder()
+        .name("package-a")
+        .license(Some("MIT"))
+        .accepted(&["MIT", "Apache-2.0"])
+        .dependency(&package_b)
+        .build()?;
+
+    for _ in 0..2 {
+        CargoAbout::new(&package_a)?
+            .generate()
+            .arg("--format")
+            .arg("sqlite")
+            .arg("-o")
+            .arg("about.db")
+            .assert()
+            .success()
+            .stdout("");
+    }
+
+    let conn = rusqlite::Connection::open(package_a.dir.join("about.db"))?;
+
+    let runs: i64 = conn.query_row("SELECT COUNT(*) FROM runs", [], |row| row.get(0))?;
+    assert_eq!(runs, 2);
+
+    let license: String = conn.query_row(
+        "SELECT l.spdx_id FROM licenses l
+        JOIN crate_licenses cl ON cl.run_id = l.run_id AND cl.license_id = l.id
+        JOIN crates c ON c.run_id = cl.run_id AND c.id = cl.crate_id
+        WHERE c.name = 'package-b' AND c.run_id = 2",
+        [],
+        |row| row.get(0),
+    )?;
+    assert_eq!(license, "Apache-2.0");
+
+    Ok(())
+}
+
+#[test]
+fn fails_when_sqlite_database_path_missing() -> Result<()> {
+    let package = Package::builder().build()?;
+
+    CargoAbout::new(&package)?
+        .generate()
+        .arg("--format")
+        .arg("sqlite")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "the path of the database must be specified with -o, --output-file",
+        ));
+
+    Ok(())
+}
+
+#[test]
+fn fails_when_sqlite_used_with_template() -> Result<()> {
+    let package = Package::builder().build()?;
+
+    CargoAbout::new(&package)?
+        .generate()
+        .arg("--format")
+        .arg("sqlite")
+        .arg("-o")
+        .arg("about.db")
+        .template(package.template()?)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "templates can't be used with sqlite output format",
+        ));
+
+    assert!(!package.dir.join("about.db").exists());
+
+    Ok(())
+}
+
+#[test]
+fn network_audit_prints_nothing_for_local_crates() -> Result<()> {
+    let package = Package::builder().build()?;
+
+    CargoAbout::new(&package)?
+        .generate()
+        .arg("--no-network-audit")
+        .arg("-o")
+        .arg("about.html")
+        .assert()
+        .success()
+        .stdout("");
+
+    assert!(!package.dir.join("about.html").exists());
+
+    Ok(())
+}
+
+#[test]
+fn writes_json_diagnostics_with_message_format_json() -> Result<()> {
+    let package = Package::builder()
+        .name("denied-package")
+        .license(Some("MIT AND GPL-3.0-only"))
+        .file(
+            "about.toml",
+            r#"
+accepted = ["MIT", "GPL-3.0"]
+denied = ["GPL-3.0"]
+"#,
+        )
+        .build()?;
+
+    let assert = CargoAbout::new(&package)?
+        .generate()
+        .arg("--message-format")
+        .arg("json")
+        .template(package.template()?)
+        .assert()
+        .failure()
+        .stderr(predicates::str::contains("explicitly denied"))
+        .stderr(predicates::str::contains("┌─").not());
+
+    let stderr = String::from_utf8(assert.get_output().stderr.clone())?;
+    let diagnostics: Vec<serde_json::Value> = stderr
+        .lines()
+        .filter(|line| line.starts_with('{'))
+        .map(serde_json::from_str)
+        .collect::<Result<_, _>>()?;
+
+    assert_eq!(diagnostics.len(), 1);
+
+    let diag = &diagnostics[0];
+    assert_eq!(diag["crate"]["name"], "denied-package");
+    assert_eq!(diag["crate"]["version"], "0.0.0");
+    assert_eq!(diag["severity"], "error");
+    assert_eq!(
+        diag["message"],
+        "license 'GPL-3.0-only' is explicitly denied"
+    );
+    assert_eq!(diag["labels"][0]["style"], "primary");
+    assert_eq!(diag["labels"][0]["message"], "denied");
+    assert_eq!(diag["labels"][0]["text"], "GPL-3.0-only");
+
+    Ok(())
+}
+
+#[test]
+fn merges_accepted_licenses_from_accepted_file() -> Result<()> {
+    let package = Package::builder()
+        .license(Some("Apache-2.0"))
+        .accepted(&["MIT"])
+        .file(
+            "approved-licenses.txt",
+            "# Maintained by legal\nApache-2.0\n\nISC\n",
+        )
+        .file("invalid-licenses.txt", "MIT\nnot a license\n")
+        .build()?;
+
+    CargoAbout::new(&package)?
+        .generate()
+        .template(package.template()?)
+        .assert()
+        .failure()
+        .stderr(predicates::str::contains(
+            "failed to satisfy license requirements",
+        ));
+
+    CargoAbout::new(&package)?
+        .generate()
+        .arg("--accepted-file")
+        .arg(package.dir.join("approved-licenses.txt").to_str().unwrap())
+        .template(package.template()?)
+        .assert()
+        .success()
+        .stdout(licenses_count(1));
+
+    CargoAbout::new(&package)?
+        .generate()
+        .arg("--accepted-file")
+        .arg(package.dir.join("invalid-licenses.txt").to_str().unwrap())
+        .template(package.template()?)
+        .assert()
+        .failure()
+        .stderr(predicates::str::contains("'not a license' on line 2 of"));
+
+    Ok(())
+}
+
+#[test]
+fn accepts_licenses_from_the_command_line() -> Result<()> {
+    let package = Package::builder()
+        .license(Some("Apache-2.0"))
+        .accepted(&["MIT"])
+        .build()?;
+
+    CargoAbout::new(&package)?
+        .generate()
+        .arg("--accept")
+        .arg("Apache-2.0")
+        .template(package.template()?)
+        .assert()
+        .success()
+        .stdout(licenses_count(1));
+
+    CargoAbout::new(&package)?
+        .generate()
+        .arg("--accept")
+        .arg("ISC")
+        .arg("--accept-only")
+        .template(package.template()?)
+        .assert()
+        .failure()
+        .stderr(predicates::str::contains(
+            "failed to satisfy license requirements",
+        ));
+
+    CargoAbout::new(&package)?
+        .generate()
+        .arg("--accept")
+        .arg("not a license")
+        .template(package.template()?)
+        .assert()
+        .failure()
+        .stderr(predicates::str::contains(
+            "'not a license' passed to --accept is not a valid SPDX licensee",
+        ));
+
+    Ok(())
+}
+
+#[test]
+fn sanitizes_crate_metadata_in_html_output() -> Result<()> {
+    let package = Package::builder()
+        .file(
+            "Cargo.toml",
+            r#"
+[package]
+name = "package"
+version = "0.0.0"
+license = "MIT"
+description = "<script>alert(1)</script>a < b"
+repository = "javascript:alert(1)"
+homepage = "https://example.com"
+"#,
+        )
+        .accepted(&["MIT"])
+        .file(
+            "about.hbs",
+            r#"<html>{{#each crates}}{{{package.description}}}|{{package.repository}}|{{package.homepage}}|{{{description}}}|{{repository}}|{{crates_io_url}}{{/each}}</html>"#,
+        )
+        .build()?;
+
+    CargoAbout::new(&package)?
+        .generate()
+        .template("about.hbs")
+        .assert()
+        .success()
+        .stdout("<html>alert(1)a < b||https://example.com|alert(1)a < b||</html>\n");
+
+    CargoAbout::new(&package)?
+        .generate()
+        .arg("--sanitize")
+        .arg("none")
+        .template("about.hbs")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "<script>alert(1)</script>a < b|javascript:alert(1)|",
+        ));
+
+    // JSON is only sanitized when explicitly requested
+    CargoAbout::new(&package)?
+        .generate()
+        .arg("--format")
+        .arg("json")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("<script>"));
+
+    CargoAbout::new(&package)?
+        .generate()
+        .arg("--format")
+        .arg("json")
+        .arg("--sanitize")
+        .arg("html")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("<script>").not())
+        .stdout(predicate::str::contains("javascript:").not());
+
+    Ok(())
+}
+
+#[test]
+fn redacts_crate_metadata() -> Result<()> {
+    let package = Package::builder()
+        .file(
+            "Cargo.toml",
+            r#"
+[package]
+name = "package"
+version = "0.0.0"
+license = "MIT"
+description = "Our secret internal crate"
+authors = ["Jane Doe <jane@internal.corp>"]
+"#,
+        )
+        .file("LICENSE", &mit_license_text("2024", "Jane Doe"))
+        .file(
+            "about.toml",
+            r#"
+accepted = ["MIT"]
+
+[redact]
+author-emails = true
+internal-descriptions = true
+source-paths = true
+"#,
+        )
+        .build()?;
+
+    let output = CargoAbout::new(&package)?
+        .generate()
+        .arg("--format")
+        .arg("json")
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+
+    let output: serde_json::Value = serde_json::from_slice(&output)?;
+    let krate = &output["crates"][0];
+    assert_eq!(krate["package"]["authors"][0], "Jane Doe");
+    assert!(krate["package"]["description"].is_null());
+    assert!(krate["description"].is_null());
+    assert!(output["licenses"][0]["source_path"].is_null());
+
+    Ok(())
+}
+
+#[test]
+fn renders_template_once_per_crate_with_index() -> Result<()> {
+    let package_b = Package::builder()
+        .name("package-b")
+        .license(Some("Apache-2.0"))
+        .build()?;
+
+    let package_a = Package::builder()
+        .name("package-a")
+        .license(Some("MIT"))
+        .dependency(&package_b)
+        .accepted(&["MIT", "Apache-2.0"])
+        .build()?;
+
+    let out_dir = package_a.dir.join("licenses");
+
+    CargoAbout::new(&package_a)?
+        .generate()
+        .arg("--per-crate-output")
+        .arg(out_dir.to_str().unwrap())
+        .template(package_a.template()?)
+        .assert()
+        .success()
+        .stdout("");
+
+    let page_a = std::fs::read_to_string(out_dir.join("package-a-0.0.0.html"))?;
+    assert!(licenses_count(1).eval(&page_a));
+    assert!(contains_default_mit_license_content().eval(&page_a));
+    assert!(!page_a.contains("Apache License"));
+
+    let page_b = std::fs::read_to_string(out_dir.join("package-b-0.0.0.html"))?;
+    assert!(licenses_count(1).eval(&page_b));
+    assert!(page_b.contains("Apache License"));
+
+    let index = std::fs::read_to_string(out_dir.join("index.html"))?;
+    assert!(index.contains(r#"<a href="package-a-0.0.0.html">package-a 0.0.0</a> (MIT)"#));
+    assert!(index.contains(r#"<a href="package-b-0.0.0.html">package-b 0.0.0</a> (Apache-2.0)"#));
+
+    Ok(())
+}
+
+#[test]
+fn per_crate_output_uses_extension_of_template_and_index_template() -> Result<()> {
+    let package = Package::builder()
+        .license(Some("MIT"))
+        .accepted(&["MIT"])
+        .file(
+            "templates/about.md.hbs",
+            "{{#each crates}}# {{package.name}}\n{{/each}}",
+        )
+        .file(
+            "templates/per-crate-index.md.hbs",
+            "{{#each crates}}- [{{package.name}}]({{path}})\n{{/each}}",
+        )
+        .build()?;
+
+    let out_dir = package.dir.join("licenses");
+
+    CargoAbout::new(&package)?
+        .generate()
+        .arg("--per-crate-output")
+        .arg(out_dir.to_str().unwrap())
+        .arg("--name")
+        .arg("about.md")
+        .template("templates")
+        .assert()
+        .success()
+        .stdout("");
+
+    let page = std::fs::read_to_string(out_dir.join("package-0.0.0.md"))?;
+    assert_eq!(page, "# package\n");
+    assert!(!out_dir.join("package-0.0.0.html").exists());
+
+    let index = std::fs::read_to_string(out_dir.join("index.md"))?;
+    assert_eq!(index, "- [package](package-0.0.0.md)\n");
+    assert!(!out_dir.join("index.html").exists());
+
+    Ok(())
+}
+
+#[test]
+fn deep_scan_attributes_vendored_licenses_to_subdirectory() -> Result<()> {
+    let mit_text = mit_license_text("1995", "Vendored Authors");
+
+    let output = |about: &str| -> Result<serde_json::Value> {
+        let package = Package::builder()
+            .license(Some("Apache-2.0"))
+            .file("vendor/zlib/LICENSE", &mit_text)
+            .file("about.toml", about)
+            .build()?;
+
+        let output = CargoAbout::new(&package)?
+            .generate()
+            .arg("--format")
+            .arg("json")
+            .assert()
+            .success()
+            .get_output()
+            .stdout
+            .clone();
+
+        Ok(serde_json::from_slice(&output)?)
+    };
+
+    let shallow = output("accepted = [\"Apache-2.0\", \"MIT\"]\n")?;
+    let licenses = shallow["licenses"].as_array().unwrap();
+    assert_eq!(licenses.len(), 1);
+    assert_eq!(licenses[0]["id"], "Apache-2.0");
+
+    let deep = output("accepted = [\"Apache-2.0\"]\n\n[package]\ndeep-scan = true\n")?;
+    let licenses = deep["licenses"].as_array().unwrap();
+    assert_eq!(licenses.len(), 2);
+
+    let mit = licenses.iter().find(|lic| lic["id"] == "MIT").unwrap();
+    assert!(contains_mit_license_content("1995", "Vendored Authors")
+        .eval(mit["text"].as_str().unwrap()));
+    assert_eq!(mit["used_by"][0]["path"], "vendor/zlib");
+    assert_eq!(mit["accepted"], false);
+
+    Ok(())
+}
+
+#[test]
+fn outputs_oci_annotations_referencing_document() -> Result<()> {
+    // The accepted licensees are written in order, as the preferred one is
+    // picked from the expression
+    let package = Package::builder()
+        .license(Some("MIT OR Apache-2.0"))
+        .file("about.toml", r#"accepted = ["MIT", "Apache-2.0"]"#)
+        .build()?;
+
+    let document = package.dir.join("THIRD-PARTY-LICENSES.html");
+    std::fs::write(&document, "<html></html>")?;
+
+    let output = CargoAbout::new(&package)?
+        .generate()
+        .arg("--format")
+        .arg("oci-annotations")
+        .arg("--oci-document")
+        .arg(document.to_str().unwrap())
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+
+    let annotations: serde_json::Value = serde_json::from_slice(&output)?;
+    assert_eq!(annotations["org.opencontainers.image.licenses"], "MIT");
+    assert_eq!(
+        annotations["com.embark-studios.cargo-about.licenses"],
+        "MIT"
+    );
+    assert_eq!(annotations["com.embark-studios.cargo-about.crates"], "1");
+    assert_eq!(
+        annotations["com.embark-studios.cargo-about.document.name"],
+        "THIRD-PARTY-LICENSES.html"
+    );
+    assert_eq!(
+        annotations["com.embark-studios.cargo-about.document.digest"],
+        "sha256:b633a587c652d02386c4f16f8c6f6aab7352d97f16367c3c40576214372dd628"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn trusts_clarification_for_version_req_while_compatible() -> Result<()> {
+    let mit_text = mit_license_text("2024", "Package Authors");
+
+    let package = |clarified: &str| {
+        Package::builder()
+            .license(Some("MIT"))
+            .file("LICENSE", &mit_text)
+            .file(
+                "about.toml",
+                &format!(
+                    "accepted = [\"Apache-2.0\"]\n\n[package.clarify]\nlicense = \"{clarified}\"\nversion-req = \"0.0\"\n"
+                ),
+            )
+            .build()
+    };
+
+    CargoAbout::new(&package("MIT OR Apache-2.0")?)?
+        .generate()
+        .arg("--format")
+        .arg("json")
+        .assert()
+        .success()
+        .stdout(predicates::str::contains(r#""id":"Apache-2.0""#));
+
+    CargoAbout::new(&package("Apache-2.0")?)?
+        .generate()
+        .arg("--format")
+        .arg("json")
+        .assert()
+        .failure()
+        .stderr(predicates::str::contains(
+            "the detected licenses diverge from 'Apache-2.0'",
+        ));
+
+    Ok(())
+}
+
+#[test]
+fn outputs_obligations_checklist() -> Result<()> {
+    let package = Package::builder()
+        .license(Some("Apache-2.0"))
+        .accepted(&["Apache-2.0"])
+        .build()?;
+
+    let output = CargoAbout::new(&package)?
+        .generate()
+        .arg("--format")
+        .arg("json")
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+
+    let output: serde_json::Value = serde_json::from_slice(&output)?;
+    let checklist = output["checklist"].as_array().unwrap();
+    assert_eq!(checklist.len(), 1);
+    assert_eq!(checklist[0]["family"], "permissive");
+
+    let required = |requirement: &str| {
+        checklist[0]["items"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|item| item["requirement"] == requirement)
+            .unwrap()["required"]
+            .clone()
+    };
+    assert_eq!(required("include-notices"), true);
+    assert_eq!(required("state-changes"), true);
+    assert_eq!(required("provide-source"), false);
+
+    Ok(())
+}
+
+#[test]
+fn includes_texts_of_or_alternatives() -> Result<()> {
+    let package = |about: &str| {
+        Package::builder()
+            .license(Some("MIT OR Apache-2.0"))
+            .file("about.toml", about)
+            .build()
+    };
+
+    let ids = |package: &Package| -> Result<Vec<String>> {
+        let output = CargoAbout::new(package)?
+            .generate()
+            .arg("--format")
+            .arg("json")
+            .assert()
+            .success()
+            .get_output()
+            .stdout
+            .clone();
+
+        let output: serde_json::Value = serde_json::from_slice(&output)?;
+        Ok(output["licenses"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|lic| lic["id"].as_str().unwrap().to_owned())
+            .collect())
+    };
+
+    assert_eq!(ids(&package("accepted = [\"MIT\"]\n")?)?, ["MIT"]);
+    assert_eq!(
+        ids(&package(
+            "accepted = [\"MIT\"]\ninclude-or-alternatives = true\n"
+        )?)?,
+        ["Apache-2.0", "MIT"]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn fails_on_missing_license_text() -> Result<()> {
+    let package = Package::builder()
+        .license(Some("MIT"))
+        .accepted(&["MIT"])
+        .build()?;
+
+    CargoAbout::new(&package)?
+        .generate()
+        .template(package.template()?)
+        .assert()
+        .success();
+
+    CargoAbout::new(&package)?
+        .generate()
+        .arg("--fail-on-missing-license-text")
+        .template(package.template()?)
+        .assert()
+        .failure()
+        .stderr(predicates::str::contains(
+            "no license text for 'MIT' was found in the crate",
+        ))
+        .stderr(predicates::str::contains(
+            "encountered 1 errors resolving licenses",
+        ));
+
+    Ok(())
+}
+
+#[test]
+fn fails_when_distinct_licenses_exceed_limits() -> Result<()> {
+    let package_b = Package::builder()
+        .name("package-b")
+        .license(Some("Apache-2.0"))
+        .build()?;
+
+    let package_c = Package::builder()
+        .name("package-c")
+        .license(Some("MPL-2.0"))
+        .build()?;
+
+    let config =
+        |limits: &str| format!("accepted = [\"MIT\", \"Apache-2.0\", \"MPL-2.0\"]\n{limits}\n");
+
+    let package_a = Package::builder()
+        .name("package-a")
+        .license(Some("MIT"))
+        .dependency(&package_b)
+        .dependency(&package_c)
+        .file("about.toml", &config("max-distinct-licenses = 3"))
+        .build()?;
+
+    CargoAbout::new(&package_a)?
+        .generate()
+        .template(package_a.template()?)
+        .assert()
+        .success();
+
+    std::fs::write(
+        package_a.dir.join("about.toml"),
+        config("max-distinct-licenses = 2"),
+    )?;
+
+    CargoAbout::new(&package_a)?
+        .generate()
+        .template(package_a.template()?)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "the crates use 3 distinct licenses, more than the 2 allowed by `max-distinct-licenses`",
+        ))
+        .stderr(predicate::str::contains("MPL-2.0 (1 crates)"));
+
+    std::fs::write(
+        package_a.dir.join("about.toml"),
+        config("[max-distinct-licenses-per-family]\nweak-copyleft = 0"),
+    )?;
+
+    CargoAbout::new(&package_a)?
+        .generate()
+        .template(package_a.template()?)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "the crates use 1 distinct weak-copyleft licenses, more than the 0 allowed by `max-distinct-licenses-per-family`",
+        ));
+
+    Ok(())
+}
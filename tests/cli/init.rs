@@ -145,3 +145,51 @@ fn overwrites_config_only_when_no_handlebars_and_overwrite_specified() -> Result
 
     Ok(())
 }
+
+#[test]
+fn writes_project_name_and_copyright_year_into_template() -> Result<()> {
+    let package = Package::builder()
+        .name("sekret")
+        .no_template()
+        .no_about_config()
+        .build()?;
+
+    CargoAbout::new(&package)?
+        .init()
+        .arg("--copyright-year")
+        .arg("2021")
+        .assert()
+        .success();
+
+    let template = std::fs::read_to_string(package.dir.child(ABOUT_TEMPLATE_FILENAME))?;
+    assert!(template.contains("the projects used in sekret."));
+    assert!(template.contains("Copyright &copy; 2021 sekret"));
+    assert!(!template.contains("cargo-about"));
+
+    Ok(())
+}
+
+#[test]
+fn writes_localized_template_and_config() -> Result<()> {
+    let package = Package::builder()
+        .name("sekret")
+        .no_template()
+        .no_about_config()
+        .build()?;
+
+    CargoAbout::new(&package)?
+        .init()
+        .arg("--locale")
+        .arg("de")
+        .assert()
+        .success();
+
+    let template = std::fs::read_to_string(package.dir.child(ABOUT_TEMPLATE_FILENAME))?;
+    assert!(template.contains("die in sekret verwendet werden."));
+    assert!(template.contains("{{strings.title}}"));
+
+    let config = std::fs::read_to_string(package.dir.child(ABOUT_CONFIG_FILENAME))?;
+    assert!(config.contains("locale = \"de\""));
+
+    Ok(())
+}
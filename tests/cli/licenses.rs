@@ -0,0 +1,388 @@
+use crate::utils::*;
+
+use anyhow::Result;
+use predicates::prelude::*;
+
+#[test]
+fn lists_detected_license_files_per_crate() -> Result<()> {
+    let package_b = Package::builder()
+        .name("package-b")
+        .license(Some("Apache-2.0"))
+        .build()?;
+
+    let package_a = Package::builder()
+        .name("package-a")
+        .license(None)
+        .file("LICENSE", &mit_license_text("2024", "Jane Doe"))
+        .dependency(&package_b)
+        .build()?;
+
+    CargoAbout::new(&package_a)?
+        .licenses()
+        .assert()
+        .success()
+        .stdout(
+            "\
+crate      version  license     file     file license  confidence  registry
+package-a  0.0.0    Unknown     LICENSE  MIT           1.00        unverified
+package-b  0.0.0    Apache-2.0                                     unverified
+",
+        );
+
+    CargoAbout::new(&package_a)?
+        .licenses()
+        .arg("--format")
+        .arg("csv")
+        .assert()
+        .success()
+        .stdout(
+            "\
+crate,version,license,file,file license,confidence,registry
+package-a,0.0.0,Unknown,LICENSE,MIT,1.00,unverified
+package-b,0.0.0,Apache-2.0,,,,unverified
+",
+        );
+
+    let json = CargoAbout::new(&package_a)?
+        .licenses()
+        .arg("--format")
+        .arg("json")
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    let json: serde_json::Value = serde_json::from_slice(&json)?;
+
+    assert_eq!(
+        json,
+        serde_json::json!([
+            {
+                "name": "package-a",
+                "version": "0.0.0",
+                "license": "Unknown",
+                "files": [{ "path": "LICENSE", "license": "MIT", "confidence": 1.0 }],
+                "registry": "unverified"
+            },
+            {
+                "name": "package-b",
+                "version": "0.0.0",
+                "license": "Apache-2.0",
+                "files": [],
+                "registry": "unverified"
+            }
+        ])
+    );
+
+    Ok(())
+}
+
+#[test]
+fn splits_file_containing_multiple_license_texts() -> Result<()> {
+    let license = format!(
+        "{}\n\n{}",
+        mit_license_text("2024", "Jane Doe"),
+        include_str!("../../LICENSE-APACHE")
+    );
+
+    let package = Package::builder()
+        .license(Some("MIT OR Apache-2.0"))
+        .file("LICENSE", &license)
+        .accepted(&["MIT"])
+        .build()?;
+
+    CargoAbout::new(&package)?
+        .licenses()
+        .arg("--format")
+        .arg("csv")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "MIT OR Apache-2.0,LICENSE,Apache-2.0,",
+        ))
+        .stdout(predicate::str::contains("MIT OR Apache-2.0,LICENSE,MIT,"));
+
+    // Only the MIT text is used, rather than the whole file
+    CargoAbout::new(&package)?
+        .generate()
+        .template(package.template()?)
+        .assert()
+        .success()
+        .stdout(licenses_count(1))
+        .stdout(contains_mit_license_content("2024", "Jane Doe"))
+        .stdout(predicate::str::contains("Apache License").not());
+
+    Ok(())
+}
+
+#[test]
+fn uses_threshold_configured_for_crate() -> Result<()> {
+    let license = format!(
+        "{}\n\nThis software is provided without any guarantee whatsoever, and the authors accept no responsibility for anything that happens as a result of using it.\n",
+        mit_license_text("2024", "Jane Doe")
+            .split("THE SOFTWARE IS PROVIDED")
+            .next()
+            .unwrap()
+            .trim_end()
+    );
+
+    let package = Package::builder()
+        .license(None)
+        .file("LICENSE", &license)
+        .build()?;
+
+    CargoAbout::new(&package)?
+        .licenses()
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("LICENSE").not());
+
+    let package = Package::builder()
+        .license(None)
+        .file("LICENSE", &license)
+        .file(
+            "about.toml",
+            "accepted = [\"MIT\"]\n\n[package]\nthreshold = 0.6\n",
+        )
+        .build()?;
+
+    CargoAbout::new(&package)?
+        .licenses()
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Unknown  LICENSE  MIT"));
+
+    Ok(())
+}
+
+#[test]
+fn excludes_files_matching_global_globs_from_scan() -> Result<()> {
+    let package = Package::builder()
+        .license(Some("MIT"))
+        .file("LICENSE", &mit_license_text("2024", "Jane Doe"))
+        .file("testdata/LICENSE", include_str!("../../LICENSE-APACHE"))
+        .build()?;
+
+    CargoAbout::new(&package)?
+        .licenses()
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("testdata"));
+
+    let package = Package::builder()
+        .license(Some("MIT"))
+        .file("LICENSE", &mit_license_text("2024", "Jane Doe"))
+        .file("testdata/LICENSE", include_str!("../../LICENSE-APACHE"))
+        .file(
+            "about.toml",
+            "accepted = [\"MIT\"]\n\n[scan]\nexclude-globs = [\"**/testdata/**\"]\n",
+        )
+        .build()?;
+
+    CargoAbout::new(&package)?
+        .licenses()
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("testdata").not())
+        .stdout(predicate::str::contains("LICENSE  MIT"));
+
+    Ok(())
+}
+
+#[test]
+fn only_scans_files_packaged_for_crate() -> Result<()> {
+    let package = Package::builder()
+        .file(
+            "Cargo.toml",
+            r#"
+[package]
+name = "package"
+version = "0.0.0"
+license = "MIT"
+exclude = ["tests/fixtures/*"]
+"#,
+        )
+        .file("LICENSE", &mit_license_text("2024", "Jane Doe"))
+        .file(
+            "tests/fixtures/LICENSE",
+            include_str!("../../LICENSE-APACHE"),
+        )
+        .accepted(&["MIT"])
+        .build()?;
+
+    CargoAbout::new(&package)?
+        .licenses()
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("fixtures").not())
+        .stdout(predicate::str::contains("LICENSE  MIT"));
+
+    let package = Package::builder()
+        .file(
+            "Cargo.toml",
+            r#"
+[package]
+name = "package"
+version = "0.0.0"
+license = "MIT"
+include = ["/src/**", "/LICENSE"]
+"#,
+        )
+        .file("LICENSE", &mit_license_text("2024", "Jane Doe"))
+        .file(
+            "tests/fixtures/LICENSE",
+            include_str!("../../LICENSE-APACHE"),
+        )
+        .accepted(&["MIT"])
+        .build()?;
+
+    CargoAbout::new(&package)?
+        .licenses()
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("fixtures").not())
+        .stdout(predicate::str::contains("LICENSE  MIT"));
+
+    Ok(())
+}
+
+#[test]
+fn cross_checks_found_licenses_against_crates_io_license() -> Result<()> {
+    let package = Package::builder()
+        .file(
+            "Cargo.toml",
+            r#"
+[package]
+name = "package"
+version = "0.0.0"
+license = "MIT"
+
+[dependencies]
+agrees = "0.1.0"
+disagrees = "0.1.0"
+"#,
+        )
+        .file(
+            "registry/agrees-0.1.0/Cargo.toml",
+            r#"
+[package]
+name = "agrees"
+version = "0.1.0"
+license = "MIT OR Apache-2.0"
+"#,
+        )
+        .file("registry/agrees-0.1.0/src/lib.rs", "")
+        .file(
+            "registry/agrees-0.1.0/LICENSE-MIT",
+            &mit_license_text("2024", "Jane Doe"),
+        )
+        .file(
+            "registry/agrees-0.1.0/.cargo-checksum.json",
+            r#"{"files":{},"package":null}"#,
+        )
+        .file(
+            "registry/disagrees-0.1.0/Cargo.toml",
+            r#"
+[package]
+name = "disagrees"
+version = "0.1.0"
+license = "MIT"
+"#,
+        )
+        .file("registry/disagrees-0.1.0/src/lib.rs", "")
+        .file(
+            "registry/disagrees-0.1.0/LICENSE",
+            include_str!("../../LICENSE-APACHE"),
+        )
+        .file(
+            "registry/disagrees-0.1.0/.cargo-checksum.json",
+            r#"{"files":{},"package":null}"#,
+        )
+        .build()?;
+
+    CargoAbout::new(&package)?
+        .licenses()
+        .arg("--registry-dir")
+        .arg(package.dir.join("registry").to_str().unwrap())
+        .arg("--format")
+        .arg("csv")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "agrees,0.1.0,MIT OR Apache-2.0,LICENSE-MIT,MIT,1.00,agrees",
+        ))
+        .stdout(predicate::str::is_match(
+            "disagrees,0.1.0,MIT,LICENSE,Apache-2.0,[0-9.]+,disagrees",
+        )?)
+        // The root package is local, so there's no registry claim to check
+        .stdout(predicate::str::is_match(
+            "(?m)^package,0.0.0,MIT,.*,unverified$",
+        )?);
+
+    Ok(())
+}
+
+/// Builds an uncompressed tar archive containing a single file
+fn tar(name: &str, contents: &str) -> String {
+    let mut header = [0u8; 512];
+    header[..name.len()].copy_from_slice(name.as_bytes());
+    header[100..107].copy_from_slice(b"0000644");
+    header[108..115].copy_from_slice(b"0000000");
+    header[116..123].copy_from_slice(b"0000000");
+    header[124..135].copy_from_slice(format!("{:011o}", contents.len()).as_bytes());
+    header[136..147].copy_from_slice(b"00000000000");
+    header[156] = b'0';
+    header[257..263].copy_from_slice(b"ustar\0");
+    header[263..265].copy_from_slice(b"00");
+
+    // The checksum is calculated with the checksum field filled with spaces
+    header[148..156].copy_from_slice(b"        ");
+    let checksum: u32 = header.iter().map(|b| u32::from(*b)).sum();
+    header[148..155].copy_from_slice(format!("{checksum:06o}\0").as_bytes());
+
+    let mut tar = String::from_utf8(header.to_vec()).unwrap();
+    tar.push_str(contents);
+
+    // Pad the contents to the block size, followed by the 2 empty blocks that
+    // end the archive
+    let padding = (512 - contents.len() % 512) % 512;
+    tar.push_str(&"\0".repeat(padding + 1024));
+    tar
+}
+
+#[test]
+fn detects_license_files_in_archives() -> Result<()> {
+    let corpus = tar("LICENSE", include_str!("../../LICENSE-APACHE"));
+
+    let package = Package::builder()
+        .license(Some("MIT"))
+        .file("LICENSE", &mit_license_text("2024", "Jane Doe"))
+        .file("testdata/corpus.tar", &corpus)
+        .accepted(&["MIT"])
+        .build()?;
+
+    CargoAbout::new(&package)?
+        .licenses()
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("corpus.tar!").not());
+
+    let package = Package::builder()
+        .license(Some("MIT"))
+        .file("LICENSE", &mit_license_text("2024", "Jane Doe"))
+        .file("testdata/corpus.tar", &corpus)
+        .file(
+            "about.toml",
+            "accepted = [\"MIT\"]\n\n[scan]\narchives = true\n",
+        )
+        .build()?;
+
+    CargoAbout::new(&package)?
+        .licenses()
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("testdata/corpus.tar!/LICENSE"))
+        .stdout(predicate::str::contains("Apache-2.0"));
+
+    Ok(())
+}
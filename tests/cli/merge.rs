@@ -0,0 +1,51 @@
+use crate::utils::*;
+
+use anyhow::Result;
+
+#[test]
+fn merges_outputs_of_separate_workspaces() -> Result<()> {
+    let package_a = Package::builder()
+        .name("package-a")
+        .license(Some("MIT"))
+        .accepted(&["MIT"])
+        .build()?;
+
+    let package_b = Package::builder()
+        .name("package-b")
+        .license(Some("Apache-2.0"))
+        .accepted(&["Apache-2.0"])
+        .build()?;
+
+    let mut inputs = Vec::new();
+    for package in [&package_a, &package_b] {
+        let path = package_a.dir.join(format!("{}.json", package.name));
+        let path = path.to_str().unwrap().to_owned();
+
+        CargoAbout::new(package)?
+            .generate()
+            .arg("--format")
+            .arg("json")
+            .arg("-o")
+            .arg(&path)
+            .assert()
+            .success();
+
+        inputs.push(path);
+    }
+
+    // Merging the same output again must not duplicate anything
+    inputs.push(inputs[0].clone());
+
+    let mut cmd = CargoAbout::new(&package_a)?;
+    cmd.arg("merge").arg("-t").arg(package_a.template()?);
+    for input in &inputs {
+        cmd.arg(input);
+    }
+
+    cmd.assert()
+        .success()
+        .stdout(overview_count(2))
+        .stdout(licenses_count(2));
+
+    Ok(())
+}
@@ -1,2 +1,13 @@
+mod baseline;
+mod cache;
+mod changelog;
+mod clarify;
+mod dist;
+mod fix;
 mod generate;
 mod init;
+mod licenses;
+mod merge;
+#[cfg(feature = "sqlite")]
+mod record;
+mod validate_config;
@@ -0,0 +1,84 @@
+use crate::utils::*;
+
+use anyhow::Result;
+use predicates::prelude::*;
+
+#[test]
+fn shows_license_changes_between_recorded_runs() -> Result<()> {
+    let package_b = Package::builder()
+        .name("package-b")
+        .license(Some("Apache-2.0"))
+        .build()?;
+
+    let package_a = Package::builder()
+        .name("package-a")
+        .license(Some("MIT"))
+        .accepted(&["MIT", "Apache-2.0"])
+        .build()?;
+
+    let db = package_a.dir.join("history.db");
+    let db = db.to_str().unwrap();
+
+    CargoAbout::new(&package_a)?
+        .record()
+        .arg("--db")
+        .arg(db)
+        .assert()
+        .success()
+        .stdout("");
+
+    let package_a = Package::builder()
+        .name("package-a")
+        .license(Some("MIT"))
+        .accepted(&["MIT", "Apache-2.0"])
+        .dependency(&package_b)
+        .build()?;
+
+    CargoAbout::new(&package_a)?
+        .record()
+        .arg("--db")
+        .arg(db)
+        .assert()
+        .success();
+
+    CargoAbout::new(&package_a)?
+        .trend()
+        .arg("--db")
+        .arg(db)
+        .assert()
+        .success()
+        .stdout(predicates::str::contains(
+            "1 crates, 1 licenses, 0 failures\n  1 MIT\n",
+        ))
+        .stdout(predicates::str::contains(
+            "2 crates (+1), 2 licenses (+1), 0 failures\n  1 Apache-2.0 (+1)\n",
+        ));
+
+    CargoAbout::new(&package_a)?
+        .trend()
+        .arg("--db")
+        .arg(db)
+        .arg("--limit")
+        .arg("1")
+        .assert()
+        .success()
+        .stdout(predicates::str::contains("1 crates, 1 licenses").not())
+        .stdout(predicates::str::contains("2 crates (+1)"));
+
+    Ok(())
+}
+
+#[test]
+fn fails_to_show_trend_when_database_missing() -> Result<()> {
+    let package = Package::builder().build()?;
+
+    CargoAbout::new(&package)?
+        .trend()
+        .arg("--db")
+        .arg("missing.db")
+        .assert()
+        .failure()
+        .stderr(predicates::str::contains("does not exist"));
+
+    Ok(())
+}
@@ -0,0 +1,63 @@
+use crate::utils::*;
+
+use anyhow::Result;
+
+#[test]
+fn reports_mistakes_in_config() -> Result<()> {
+    let valid = Package::builder()
+        .license(Some("MIT"))
+        .accepted(&["MIT"])
+        .build()?;
+
+    CargoAbout::new(&valid)?
+        .validate_config()
+        .assert()
+        .success();
+
+    let package = Package::builder()
+        .license(Some("MIT"))
+        .file(
+            "about.toml",
+            r#"
+accepted = ["MIT"]
+workarounds = ["ring", "not-a-workaround"]
+exceptions = [{ name = "not-an-exception", reason = "misspelled" }]
+
+[not-a-crate]
+accepted = ["ISC"]
+
+[package.clarify]
+license = "MIT"
+
+[[package.clarify.files]]
+path = "MISSING"
+checksum = "41c8b86392181b7a6ff6510880a63c8463a91ab14a60a70d698ea7412f684a4b"
+"#,
+        )
+        .build()?;
+
+    CargoAbout::new(&package)?
+        .validate_config()
+        .assert()
+        .failure()
+        .stderr(predicates::str::contains(
+            "configuration for 'not-a-crate' does not apply to any crate in the graph",
+        ))
+        .stderr(predicates::str::contains(
+            "clarification file 'MISSING' does not exist in crate",
+        ))
+        .stderr(predicates::str::contains(
+            "workaround 'ring' does not apply to any crate in the graph",
+        ))
+        .stderr(predicates::str::contains(
+            "exception for 'not-an-exception' does not apply to any crate in the graph",
+        ))
+        .stderr(predicates::str::contains(
+            "no workaround registered for the 'not-a-workaround' crate",
+        ))
+        .stderr(predicates::str::contains(
+            "encountered 2 errors validating the configuration",
+        ));
+
+    Ok(())
+}
@@ -21,14 +21,66 @@ impl CargoAbout {
         self
     }
 
+    pub fn env(&mut self, key: &str, value: impl AsRef<std::ffi::OsStr>) -> &mut Self {
+        self.cmd.env(key, value);
+        self
+    }
+
+    pub fn baseline(&mut self) -> &mut Self {
+        self.arg("baseline")
+    }
+
     pub fn init(&mut self) -> &mut Self {
         self.arg("init")
     }
 
+    pub fn clarify(&mut self) -> &mut Self {
+        self.arg("clarify")
+    }
+
     pub fn generate(&mut self) -> &mut Self {
         self.arg("generate")
     }
 
+    #[cfg(feature = "sqlite")]
+    pub fn record(&mut self) -> &mut Self {
+        self.arg("record")
+    }
+
+    #[cfg(feature = "sqlite")]
+    pub fn trend(&mut self) -> &mut Self {
+        self.arg("trend")
+    }
+
+    pub fn licenses(&mut self) -> &mut Self {
+        self.arg("licenses")
+    }
+
+    pub fn validate_config(&mut self) -> &mut Self {
+        self.arg("validate-config")
+    }
+
+    pub fn dist(&mut self) -> &mut Self {
+        self.arg("dist")
+    }
+
+    pub fn fix(&mut self) -> &mut Self {
+        self.arg("fix")
+    }
+
+    pub fn cache(&mut self) -> &mut Self {
+        self.arg("cache")
+    }
+
+    pub fn changelog(&mut self) -> &mut Self {
+        self.arg("changelog")
+    }
+
+    pub fn stdin(&mut self, path: &std::path::Path) -> Result<&mut Self> {
+        self.cmd.stdin(std::fs::File::open(path)?);
+        Ok(self)
+    }
+
     pub fn template(&mut self, template: &str) -> &mut Self {
         self.arg(template)
     }